@@ -0,0 +1,608 @@
+//! Several OpenSubtitles accounts signed in at once, for workstations shared
+//! by a team.
+//!
+//! Each account keeps its XML-RPC token, REST session and the last
+//! `GetUserInfo` answer. One account is active: the shared REST client
+//! carries its session and new uploads are queued under it. Inactive REST
+//! sessions are detached from the client and put back on switching, so
+//! every account stays signed in. Passwords are held in memory only, to log
+//! in again when a token expires; the saved login of [`Credentials`] is the
+//! only one kept between runs.
+
+use crate::api::rest::{RestClient, RestSession};
+use crate::api::xmlrpc::{UserInfo, XmlRpcClient, XmlRpcError};
+use crate::credentials::{CredentialError, Credentials, SignedIn};
+use crate::error::impl_serialize_error;
+use crate::upload::queue::UploadQueue;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::async_runtime::Mutex;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AccountError {
+    #[error("{0} is not signed in")]
+    NotSignedIn(String),
+    #[error("login failed: {0}")]
+    Login(#[from] XmlRpcError),
+    #[error(transparent)]
+    Credentials(#[from] CredentialError),
+}
+
+impl AccountError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotSignedIn(_) => "notSignedIn",
+            Self::Login(_) => "login",
+            Self::Credentials(e) => e.kind(),
+        }
+    }
+}
+
+impl_serialize_error!(AccountError);
+
+struct Account {
+    username: String,
+    password: String,
+    token: String,
+    user: Option<UserInfo>,
+    /// The REST session while the account is inactive; the active one's
+    /// lives on the client.
+    rest: Option<RestSession>,
+    has_rest: bool,
+}
+
+/// What the UI learns about a signed-in account.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    pub username: String,
+    pub active: bool,
+    /// Rank and upload counts from the last `GetUserInfo`.
+    pub user: Option<UserInfo>,
+    /// Whether a REST session is held as well; it needs an API key.
+    pub rest: bool,
+    /// Queued uploads that will be sent from this account.
+    pub queued: usize,
+}
+
+#[derive(Default)]
+struct State {
+    accounts: Vec<Account>,
+    active: Option<String>,
+}
+
+impl State {
+    fn find(&self, username: &str) -> Option<usize> {
+        self.accounts.iter().position(|a| a.username == username)
+    }
+}
+
+/// Shared account handle; cheap to clone.
+#[derive(Clone)]
+pub struct Accounts {
+    state: Arc<Mutex<State>>,
+    xmlrpc: XmlRpcClient,
+    rest: RestClient,
+    credentials: Option<Credentials>,
+}
+
+impl Accounts {
+    pub fn new(xmlrpc: XmlRpcClient, rest: RestClient) -> Self {
+        Self {
+            state: Arc::default(),
+            xmlrpc,
+            rest,
+            credentials: None,
+        }
+    }
+
+    /// Signs in the saved account on [`resume`](Self::resume), and logs it in
+    /// again when its token was set by the UI rather than through here.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Moves the client's REST session to the active account, if any.
+    async fn stash_rest(&self, state: &mut State) {
+        let session = self.rest.detach_session().await;
+        if let Some(i) = state.active.as_deref().and_then(|name| state.find(name)) {
+            state.accounts[i].rest = session;
+        }
+    }
+
+    /// Adds `account`, replacing an earlier sign-in with the same name, and
+    /// makes it active. Its REST session must already be on the client.
+    async fn insert(&self, state: &mut State, mut account: Account) {
+        account.has_rest = self.rest.is_logged_in().await;
+        state.active = Some(account.username.clone());
+        match state.find(&account.username) {
+            Some(i) => state.accounts[i] = account,
+            None => state.accounts.push(account),
+        }
+    }
+
+    /// Signs in another account and makes it the active one.
+    pub async fn log_in(&self, username: &str, password: &str) -> Result<(), AccountError> {
+        let login = self.xmlrpc.log_in(username, password, "en").await?;
+        let user = match login.user {
+            Some(user) => Some(user),
+            None => self.xmlrpc.get_user_info(&login.token).await.ok(),
+        };
+        let mut state = self.state.lock().await;
+        self.stash_rest(&mut state).await;
+        // Without an API key there is no REST session to have.
+        let _ = self.rest.log_in(username, password).await;
+        let account = Account {
+            username: username.into(),
+            password: password.into(),
+            token: login.token,
+            user,
+            rest: None,
+            has_rest: false,
+        };
+        self.insert(&mut state, account).await;
+        Ok(())
+    }
+
+    /// Signs in the saved account, which becomes the active one.
+    pub async fn resume(&self) -> Result<SignedIn, AccountError> {
+        let credentials = self.credentials.as_ref().ok_or(CredentialError::NotSaved)?;
+        let mut state = self.state.lock().await;
+        self.stash_rest(&mut state).await;
+        let resumed = credentials
+            .resume(&self.xmlrpc, &self.rest)
+            .await
+            .and_then(|signed_in| {
+                let (_, password) = credentials.login()?.ok_or(CredentialError::NotSaved)?;
+                Ok((signed_in, password))
+            });
+        let (signed_in, password) = match resumed {
+            Ok(resumed) => resumed,
+            Err(e) => {
+                // Put the previous account's session back.
+                let active = state.active.as_deref().and_then(|name| state.find(name));
+                if let Some(i) = active {
+                    self.rest
+                        .attach_session(state.accounts[i].rest.take())
+                        .await;
+                }
+                return Err(e.into());
+            }
+        };
+        let account = Account {
+            username: signed_in.username.clone(),
+            password,
+            token: signed_in.token.clone(),
+            user: signed_in.user.clone(),
+            rest: None,
+            has_rest: false,
+        };
+        self.insert(&mut state, account).await;
+        Ok(signed_in)
+    }
+
+    /// Makes `username` the active account.
+    pub async fn switch(&self, username: &str) -> Result<(), AccountError> {
+        let mut state = self.state.lock().await;
+        let i = state
+            .find(username)
+            .ok_or_else(|| AccountError::NotSignedIn(username.into()))?;
+        if state.active.as_deref() == Some(username) {
+            return Ok(());
+        }
+        self.stash_rest(&mut state).await;
+        self.rest
+            .attach_session(state.accounts[i].rest.take())
+            .await;
+        state.active = Some(username.into());
+        Ok(())
+    }
+
+    /// Signs `username` out. When it was active, the first remaining account
+    /// takes over, or none if it was the last.
+    pub async fn log_out(&self, username: &str) -> Result<(), AccountError> {
+        let mut state = self.state.lock().await;
+        let i = state
+            .find(username)
+            .ok_or_else(|| AccountError::NotSignedIn(username.into()))?;
+        let account = state.accounts.remove(i);
+        // The local state goes either way; a failed LogOut only leaves a
+        // session on the server to expire.
+        let _ = self.xmlrpc.log_out(&account.token).await;
+        if state.active.as_deref() == Some(username) {
+            let _ = self.rest.log_out().await;
+            state.active = state.accounts.first().map(|a| a.username.clone());
+            if let Some(next) = state.accounts.first_mut() {
+                self.rest.attach_session(next.rest.take()).await;
+            }
+        } else if let Some(session) = account.rest {
+            // Log the inactive REST session out with the client, then put
+            // the active one back.
+            let active = self.rest.detach_session().await;
+            self.rest.attach_session(Some(session)).await;
+            let _ = self.rest.log_out().await;
+            self.rest.attach_session(active).await;
+        }
+        Ok(())
+    }
+
+    /// The signed-in accounts with their uploads waiting in `queue`,
+    /// fetching fresh user info first if `refresh`.
+    pub async fn list(&self, queue: &UploadQueue, refresh: bool) -> Vec<AccountInfo> {
+        let mut state = self.state.lock().await;
+        if refresh {
+            for account in &mut state.accounts {
+                match self.xmlrpc.get_user_info(&account.token).await {
+                    Ok(user) => account.user = Some(user),
+                    Err(e) if e.is_session_error() => {
+                        let login = self
+                            .xmlrpc
+                            .log_in(&account.username, &account.password, "en")
+                            .await;
+                        if let Ok(login) = login {
+                            account.token = login.token;
+                            account.user = login.user.or(account.user.take());
+                        }
+                    }
+                    // Offline: keep what we have.
+                    Err(_) => {}
+                }
+            }
+        }
+        let active = state.active.clone();
+        let queued = queue.queued_by_account();
+        state
+            .accounts
+            .iter()
+            .map(|account| AccountInfo {
+                username: account.username.clone(),
+                active: active.as_deref() == Some(account.username.as_str()),
+                user: account.user.clone(),
+                rest: account.has_rest,
+                queued: queued.get(&account.username).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    /// Logs in again after `stale` was rejected for `account` (`None` for a
+    /// session the UI set without naming it), unless another caller already
+    /// did. Returns the new token and the account name.
+    pub async fn relogin(
+        &self,
+        account: Option<&str>,
+        stale: &str,
+    ) -> Result<(String, String), AccountError> {
+        {
+            let mut state = self.state.lock().await;
+            if let Some(i) = account.and_then(|name| state.find(name)) {
+                let account = &mut state.accounts[i];
+                if account.token != stale {
+                    return Ok((account.token.clone(), account.username.clone()));
+                }
+                let login = self
+                    .xmlrpc
+                    .log_in(&account.username, &account.password, "en")
+                    .await?;
+                account.token = login.token.clone();
+                account.user = login.user.or(account.user.take());
+                return Ok((login.token, account.username.clone()));
+            }
+        }
+        let credentials = self.credentials.as_ref().ok_or(CredentialError::NotSaved)?;
+        // Only the saved account can be logged in again without a password.
+        let saved = credentials.saved()?.ok_or(CredentialError::NotSaved)?;
+        if let Some(name) = account.filter(|name| *name != saved.username) {
+            return Err(AccountError::NotSignedIn(name.into()));
+        }
+        Ok(credentials.relogin(&self.xmlrpc, stale).await?)
+    }
+
+    /// Hands every account's token to `queue`, and the active account's as
+    /// the one new uploads are tagged with.
+    pub async fn sync(&self, queue: &UploadQueue) {
+        let state = self.state.lock().await;
+        let tokens = state
+            .accounts
+            .iter()
+            .map(|a| (a.username.clone(), a.token.clone()))
+            .collect();
+        queue.set_accounts(tokens, state.active.clone());
+    }
+}
+
+/// Brings the queue up to date after a change and lists the accounts.
+async fn changed(accounts: &Accounts, queue: &UploadQueue) -> Vec<AccountInfo> {
+    accounts.sync(queue).await;
+    accounts.list(queue, false).await
+}
+
+#[tauri::command]
+pub async fn list_accounts(
+    accounts: tauri::State<'_, Accounts>,
+    queue: tauri::State<'_, UploadQueue>,
+    refresh: Option<bool>,
+) -> Result<Vec<AccountInfo>, AccountError> {
+    let refresh = refresh.unwrap_or(false);
+    let list = accounts.list(&queue, refresh).await;
+    if refresh {
+        // Expired tokens may have been replaced.
+        accounts.sync(&queue).await;
+    }
+    Ok(list)
+}
+
+/// Signs in another account and makes it active.
+#[tauri::command]
+pub async fn add_account(
+    accounts: tauri::State<'_, Accounts>,
+    queue: tauri::State<'_, UploadQueue>,
+    username: String,
+    password: String,
+) -> Result<Vec<AccountInfo>, AccountError> {
+    accounts.log_in(&username, &password).await?;
+    Ok(changed(&accounts, &queue).await)
+}
+
+#[tauri::command]
+pub async fn switch_account(
+    accounts: tauri::State<'_, Accounts>,
+    queue: tauri::State<'_, UploadQueue>,
+    username: String,
+) -> Result<Vec<AccountInfo>, AccountError> {
+    accounts.switch(&username).await?;
+    Ok(changed(&accounts, &queue).await)
+}
+
+/// Signs an account out; its queued uploads wait until it signs in again.
+#[tauri::command]
+pub async fn logout(
+    accounts: tauri::State<'_, Accounts>,
+    queue: tauri::State<'_, UploadQueue>,
+    username: String,
+) -> Result<Vec<AccountInfo>, AccountError> {
+    accounts.log_out(&username).await?;
+    Ok(changed(&accounts, &queue).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::middleware::Http;
+    use crate::api::stand_in as http;
+    use crate::api::xmlrpc::stand_in::{status, StandIn};
+    use crate::api::xmlrpc::{UploadFile, UploadInfo, Value};
+    use crate::credentials::memory::Memory;
+    use crate::upload::queue::NewUpload;
+    use std::collections::HashMap;
+    use std::sync::Mutex as SyncMutex;
+    use tauri::async_runtime::block_on;
+
+    fn user(name: &str) -> Value {
+        Value::Struct([("UserNickName".into(), name.into())].into())
+    }
+
+    /// Logs anyone in whose password is `secret`, handing out `<name>-<n>`
+    /// on their n-th login.
+    fn xmlrpc() -> StandIn {
+        let logins = SyncMutex::new(HashMap::<String, u32>::new());
+        StandIn::start(
+            move |call| match (call.method.as_str(), call.params.as_slice()) {
+                ("LogIn", [Value::String(name), Value::String(password), ..]) => {
+                    if password != "secret" {
+                        return Ok(status("401 Unauthorized", []));
+                    }
+                    let mut logins = logins.lock().unwrap();
+                    let n = logins.entry(name.clone()).or_default();
+                    *n += 1;
+                    let token = format!("{name}-{n}");
+                    Ok(status(
+                        "200 OK",
+                        [("token", token.into()), ("data", user(name))],
+                    ))
+                }
+                ("GetUserInfo", _) => Ok(status("200 OK", [("data", user("tester"))])),
+                ("LogOut", _) => Ok(status("200 OK", [])),
+                _ => Err(500),
+            },
+        )
+    }
+
+    /// Hands out `rest-<name>` tokens.
+    fn rest() -> http::StandIn {
+        http::StandIn::start(|request| match request.path.as_str() {
+            "/login" => {
+                let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+                let token = format!("rest-{}", body["username"].as_str().unwrap());
+                http::Response::json(200, serde_json::json!({ "token": token }))
+            }
+            _ => http::Response::json(200, serde_json::json!({})),
+        })
+    }
+
+    fn accounts(xmlrpc: &StandIn, rest: &http::StandIn) -> Accounts {
+        let rest = RestClient::with_http(
+            Http::new(reqwest::Client::new()),
+            &rest.url,
+            Some("test-key".into()),
+        );
+        Accounts::new(xmlrpc.client(), rest)
+    }
+
+    fn queue(name: &str, xmlrpc: &StandIn) -> UploadQueue {
+        let path = std::env::temp_dir().join(format!("{}-{name}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        UploadQueue::open(path, xmlrpc.client()).unwrap()
+    }
+
+    /// Usernames, marking the active one with `*`.
+    async fn names(accounts: &Accounts, queue: &UploadQueue) -> Vec<String> {
+        let list = accounts.list(queue, false).await;
+        list.into_iter()
+            .map(|a| match a.active {
+                true => format!("*{}", a.username),
+                false => a.username,
+            })
+            .collect()
+    }
+
+    /// The REST token the shared client currently sends.
+    async fn rest_token(accounts: &Accounts) -> Option<String> {
+        accounts.rest.saved_session().await.map(|s| s.token)
+    }
+
+    fn calls(server: &StandIn) -> Vec<String> {
+        let calls = server.calls().into_iter();
+        calls
+            .map(|call| match call.params.first() {
+                Some(Value::String(first)) => format!("{} {first}", call.method),
+                _ => call.method,
+            })
+            .collect()
+    }
+
+    fn rest_logouts(server: &http::StandIn) -> Vec<String> {
+        let requests = server.requests().into_iter();
+        requests
+            .filter(|r| r.method == "DELETE" && r.path == "/logout")
+            .filter_map(|r| r.header("Authorization").map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn adds_switches_and_logs_out_accounts() {
+        let (xmlrpc, rest) = (xmlrpc(), rest());
+        let accounts = accounts(&xmlrpc, &rest);
+        let queue = queue("accounts-switch", &xmlrpc);
+        block_on(async {
+            accounts.log_in("alice", "secret").await.unwrap();
+            accounts.log_in("bob", "secret").await.unwrap();
+            assert_eq!(names(&accounts, &queue).await, ["alice", "*bob"]);
+            assert!(accounts.list(&queue, false).await.iter().all(|a| a.rest));
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-bob"));
+
+            let error = accounts.log_in("mallory", "wrong").await.unwrap_err();
+            assert_eq!(error.kind(), "login");
+            let error = accounts.switch("mallory").await.unwrap_err();
+            assert_eq!(error.kind(), "notSignedIn");
+            assert_eq!(names(&accounts, &queue).await, ["alice", "*bob"]);
+
+            // Each switch puts the account's own REST session back.
+            accounts.switch("alice").await.unwrap();
+            assert_eq!(names(&accounts, &queue).await, ["*alice", "bob"]);
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-alice"));
+            accounts.switch("bob").await.unwrap();
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-bob"));
+
+            accounts.log_out("bob").await.unwrap();
+            assert_eq!(names(&accounts, &queue).await, ["*alice"]);
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-alice"));
+
+            accounts.log_out("alice").await.unwrap();
+            assert!(names(&accounts, &queue).await.is_empty());
+            assert_eq!(rest_token(&accounts).await, None);
+        });
+        assert_eq!(
+            calls(&xmlrpc),
+            [
+                "LogIn alice",
+                "LogIn bob",
+                "LogIn mallory",
+                "LogOut bob-1",
+                "LogOut alice-1"
+            ]
+        );
+        assert_eq!(
+            rest_logouts(&rest),
+            ["Bearer rest-bob", "Bearer rest-alice"]
+        );
+    }
+
+    #[test]
+    fn logging_out_an_inactive_account_keeps_the_active_session() {
+        let (xmlrpc, rest) = (xmlrpc(), rest());
+        let accounts = accounts(&xmlrpc, &rest);
+        let queue = queue("accounts-inactive", &xmlrpc);
+        block_on(async {
+            accounts.log_in("alice", "secret").await.unwrap();
+            accounts.log_in("bob", "secret").await.unwrap();
+            accounts.log_out("alice").await.unwrap();
+            assert_eq!(names(&accounts, &queue).await, ["*bob"]);
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-bob"));
+
+            // Signing in again replaces the earlier sign-in.
+            accounts.log_in("bob", "secret").await.unwrap();
+            assert_eq!(names(&accounts, &queue).await, ["*bob"]);
+        });
+        assert_eq!(rest_logouts(&rest), ["Bearer rest-alice"]);
+    }
+
+    #[test]
+    fn logs_in_again_once_per_stale_token() {
+        let (xmlrpc, rest) = (xmlrpc(), rest());
+        let accounts = accounts(&xmlrpc, &rest);
+        block_on(async {
+            accounts.log_in("alice", "secret").await.unwrap();
+            let renewed = accounts.relogin(Some("alice"), "alice-1").await.unwrap();
+            assert_eq!(renewed, ("alice-2".into(), "alice".into()));
+            // A second caller holding the same stale token gets the new one.
+            let renewed = accounts.relogin(Some("alice"), "alice-1").await.unwrap();
+            assert_eq!(renewed.0, "alice-2");
+
+            let error = accounts.relogin(Some("bob"), "bob-1").await.unwrap_err();
+            assert_eq!(error.kind(), "notSaved");
+        });
+        assert_eq!(calls(&xmlrpc), ["LogIn alice", "LogIn alice"]);
+    }
+
+    #[test]
+    fn resumes_the_saved_account_next_to_others() {
+        let (xmlrpc, rest) = (xmlrpc(), rest());
+        let credentials = Credentials::with_store(Memory::default());
+        credentials.save("carol", "secret").unwrap();
+        let accounts = accounts(&xmlrpc, &rest).with_credentials(credentials);
+        let queue = queue("accounts-resume", &xmlrpc);
+        block_on(async {
+            accounts.log_in("alice", "secret").await.unwrap();
+            let signed_in = accounts.resume().await.unwrap();
+            assert_eq!(signed_in.username, "carol");
+            assert!(signed_in.rest);
+            assert_eq!(names(&accounts, &queue).await, ["alice", "*carol"]);
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-carol"));
+            accounts.switch("alice").await.unwrap();
+            assert_eq!(rest_token(&accounts).await.as_deref(), Some("rest-alice"));
+
+            // Only the saved account can log in again without a password.
+            let error = accounts.relogin(Some("dave"), "dave-1").await.unwrap_err();
+            assert_eq!(error.kind(), "notSignedIn");
+        });
+    }
+
+    #[test]
+    fn lists_queued_uploads_per_account() {
+        let (xmlrpc, rest) = (xmlrpc(), rest());
+        let accounts = accounts(&xmlrpc, &rest);
+        let queue = queue("accounts-queued", &xmlrpc);
+        let upload = |account: Option<&str>| NewUpload {
+            info: UploadInfo::default(),
+            file: UploadFile::default(),
+            paused: true,
+            account: account.map(str::to_string),
+        };
+        block_on(async {
+            accounts.log_in("alice", "secret").await.unwrap();
+            accounts.log_in("bob", "secret").await.unwrap();
+            accounts.sync(&queue).await;
+            queue
+                .add(vec![upload(None), upload(None), upload(Some("alice"))])
+                .unwrap();
+            let queued: Vec<_> = accounts
+                .list(&queue, false)
+                .await
+                .into_iter()
+                .map(|a| (a.username, a.queued))
+                .collect();
+            assert_eq!(queued, [("alice".into(), 1), ("bob".into(), 2)]);
+        });
+    }
+}
@@ -0,0 +1,561 @@
+//! HTTP layer shared by every backend API call.
+//!
+//! [`Http`] wraps the connection pool and adds what each JS service used to do
+//! on its own:
+//!
+//! - identical in-flight reads (method, URL, credentials and body) are
+//!   coalesced into one network round trip whose response every caller gets;
+//!   only `GET`, `HEAD` and read-only XML-RPC methods count as reads, so two
+//!   uploads or logins always reach the server separately;
+//! - each endpoint draws from its own token bucket, sized after the
+//!   published OpenSubtitles limits;
+//! - `429 Too Many Requests` pauses the endpoint for `Retry-After` (or an
+//!   exponential backoff) and retries a few times before giving up;
+//! - rate-limit headers are recorded and forwarded to a quota listener, which
+//!   the app turns into an event for the UI.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Retries after a `429` before the response is handed back to the caller.
+const MAX_RETRIES: u32 = 3;
+
+/// Pause after a `429` without a usable `Retry-After`, doubled per retry.
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Longest pause honoured from a `Retry-After` header.
+const MAX_BACKOFF: Duration = Duration::from_secs(120);
+
+/// Name of the event carrying [`QuotaUpdate`]s to the UI.
+pub const QUOTA_EVENT: &str = "api-quota";
+
+/// Transport failure, shareable between coalesced callers.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{0}")]
+pub struct HttpError(Arc<reqwest::Error>);
+
+impl From<reqwest::Error> for HttpError {
+    fn from(error: reqwest::Error) -> Self {
+        Self(Arc::new(error))
+    }
+}
+
+/// Fully buffered response.
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// `Retry-After` in seconds; HTTP-date values are not used by the APIs.
+    pub fn retry_after(&self) -> Option<u64> {
+        header_u64(&self.headers, &["retry-after"])
+    }
+}
+
+fn header_u64(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names.iter().find_map(|name| {
+        headers
+            .get(*name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|v| v.max(0.0).ceil() as u64)
+    })
+}
+
+/// Token-bucket parameters for one endpoint.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    /// Limits per endpoint name. XML-RPC allows 40 requests per 10 seconds,
+    /// REST 5 per second, and REST `/login` 1 per second.
+    pub fn for_endpoint(endpoint: &str) -> Self {
+        match endpoint {
+            "xmlrpc" => Self {
+                burst: 10,
+                per_second: 4.0,
+            },
+            "rest/login" => Self {
+                burst: 1,
+                per_second: 1.0,
+            },
+            _ => Self {
+                burst: 5,
+                per_second: 5.0,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+    /// Set after a `429` or an exhausted quota; nothing is sent before it.
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            refilled_at: Instant::now(),
+            blocked_until: None,
+        }
+    }
+
+    /// Takes a token, or returns how long to wait before trying again.
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(until) = self.blocked_until {
+            if now < until {
+                return Err(until - now);
+            }
+            self.blocked_until = None;
+        }
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.limit.per_second).min(f64::from(self.limit.burst));
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.limit.per_second,
+            ))
+        }
+    }
+
+    fn block_for(&mut self, pause: Duration) {
+        let until = Instant::now() + pause;
+        self.blocked_until = Some(
+            self.blocked_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+}
+
+/// Remaining quota reported by the server for one endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaUpdate {
+    pub endpoint: String,
+    pub remaining: u64,
+    pub limit: Option<u64>,
+    /// Seconds until the quota resets, when the server says.
+    pub reset_secs: Option<u64>,
+}
+
+type QuotaListener = Arc<dyn Fn(&QuotaUpdate) + Send + Sync>;
+type Shared = Arc<OnceCell<Result<Arc<HttpResponse>, HttpError>>>;
+
+#[derive(Default)]
+struct State {
+    buckets: HashMap<String, Bucket>,
+    in_flight: HashMap<[u8; 16], Shared>,
+    quotas: HashMap<String, QuotaUpdate>,
+}
+
+/// Shared HTTP handle; cheap to clone.
+#[derive(Clone)]
+pub struct Http {
+    client: reqwest::Client,
+    state: Arc<Mutex<State>>,
+    on_quota: Option<QuotaListener>,
+}
+
+impl Http {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            state: Arc::default(),
+            on_quota: None,
+        }
+    }
+
+    /// Calls `listener` whenever a response carries rate-limit headers.
+    pub fn on_quota(mut self, listener: impl Fn(&QuotaUpdate) + Send + Sync + 'static) -> Self {
+        self.on_quota = Some(Arc::new(listener));
+        self
+    }
+
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Last quota reported per endpoint.
+    pub fn quotas(&self) -> Vec<QuotaUpdate> {
+        let mut quotas: Vec<_> = self
+            .state
+            .lock()
+            .unwrap()
+            .quotas
+            .values()
+            .cloned()
+            .collect();
+        quotas.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        quotas
+    }
+
+    /// Sends `request` through the `endpoint` bucket, joining an identical
+    /// read already in flight instead of repeating it.
+    pub async fn send(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+    ) -> Result<Arc<HttpResponse>, HttpError> {
+        let request = request.build()?;
+        let Some(key) = coalesce_key(&request) else {
+            return self.execute(endpoint, request).await;
+        };
+        let shared = self
+            .state
+            .lock()
+            .unwrap()
+            .in_flight
+            .entry(key)
+            .or_default()
+            .clone();
+        // Whoever gets to run the initializer performs the request; if that
+        // caller is cancelled, the next waiter takes over.
+        let result = shared
+            .get_or_init(|| self.execute(endpoint, request))
+            .await
+            .clone();
+        let mut state = self.state.lock().unwrap();
+        if state
+            .in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &shared))
+        {
+            state.in_flight.remove(&key);
+        }
+        result
+    }
+
+    async fn execute(
+        &self,
+        endpoint: &str,
+        request: reqwest::Request,
+    ) -> Result<Arc<HttpResponse>, HttpError> {
+        // Streaming bodies cannot be replayed; those get a single attempt.
+        if request.try_clone().is_none() {
+            self.acquire(endpoint).await;
+            return self.fetch(endpoint, request).await.map(Arc::new);
+        }
+        let mut attempt = 0;
+        loop {
+            self.acquire(endpoint).await;
+            let copy = request.try_clone().expect("body is cloneable");
+            let response = self.fetch(endpoint, copy).await?;
+            if response.status != StatusCode::TOO_MANY_REQUESTS || attempt == MAX_RETRIES {
+                return Ok(Arc::new(response));
+            }
+            let pause = response
+                .retry_after()
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_BACKOFF * 2u32.pow(attempt))
+                .min(MAX_BACKOFF);
+            self.bucket(endpoint, |bucket| bucket.block_for(pause));
+            attempt += 1;
+        }
+    }
+
+    async fn fetch(
+        &self,
+        endpoint: &str,
+        request: reqwest::Request,
+    ) -> Result<HttpResponse, HttpError> {
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+        self.record_quota(endpoint, &headers);
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    fn bucket<T>(&self, endpoint: &str, f: impl FnOnce(&mut Bucket) -> T) -> T {
+        let mut state = self.state.lock().unwrap();
+        let bucket = state
+            .buckets
+            .entry(endpoint.to_string())
+            .or_insert_with(|| Bucket::new(RateLimit::for_endpoint(endpoint)));
+        f(bucket)
+    }
+
+    /// Waits until the endpoint bucket hands out a token.
+    async fn acquire(&self, endpoint: &str) {
+        while let Err(wait) = self.bucket(endpoint, |bucket| bucket.try_take(Instant::now())) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn record_quota(&self, endpoint: &str, headers: &HeaderMap) {
+        let Some(remaining) =
+            header_u64(headers, &["x-ratelimit-remaining", "ratelimit-remaining"])
+        else {
+            return;
+        };
+        let update = QuotaUpdate {
+            endpoint: endpoint.to_string(),
+            remaining,
+            limit: header_u64(headers, &["x-ratelimit-limit", "ratelimit-limit"]),
+            reset_secs: header_u64(headers, &["x-ratelimit-reset", "ratelimit-reset"]),
+        };
+        if remaining == 0 {
+            // Out of quota: hold the endpoint until the window resets rather
+            // than collecting 429s.
+            let pause = Duration::from_secs(update.reset_secs.unwrap_or(1)).min(MAX_BACKOFF);
+            self.bucket(endpoint, |bucket| bucket.block_for(pause));
+        }
+        self.state
+            .lock()
+            .unwrap()
+            .quotas
+            .insert(endpoint.to_string(), update.clone());
+        if let Some(listener) = &self.on_quota {
+            listener(&update);
+        }
+    }
+}
+
+/// Headers that tell otherwise identical requests apart: the session, the
+/// REST consumer key and the body encoding.
+const COALESCE_HEADERS: [&str; 3] = ["authorization", "api-key", "content-type"];
+
+/// XML-RPC methods without side effects, whose identical calls may share
+/// one response.
+const READ_ONLY_METHODS: [&str; 11] = [
+    "ServerInfo",
+    "GetUserInfo",
+    "GetSubLanguages",
+    "CheckMovieHash",
+    "CheckMovieHash2",
+    "CheckSubHash",
+    "SearchSubtitles",
+    "SearchMoviesOnIMDB",
+    "GetIMDBMovieDetails",
+    "GuessMovieFromString",
+    "DetectLanguage",
+];
+
+/// The `methodName` of an XML-RPC call body.
+fn xmlrpc_method(body: &[u8]) -> Option<&str> {
+    let body = std::str::from_utf8(body).ok()?;
+    let (_, rest) = body.split_once("<methodName>")?;
+    Some(rest.split_once("</methodName>")?.0.trim())
+}
+
+/// Digest of everything that makes two requests interchangeable, or `None`
+/// for requests that must not share a response: streaming bodies, which
+/// cannot be compared, and anything that may change state on the server.
+fn coalesce_key(request: &reqwest::Request) -> Option<[u8; 16]> {
+    let body = match request.body() {
+        Some(body) => body.as_bytes()?,
+        None => &[],
+    };
+    let read = match *request.method() {
+        Method::GET | Method::HEAD => true,
+        Method::POST => xmlrpc_method(body).is_some_and(|m| READ_ONLY_METHODS.contains(&m)),
+        _ => false,
+    };
+    if !read {
+        return None;
+    }
+    let mut context = md5::Context::new();
+    context.consume(request.method().as_str());
+    context.consume([0]);
+    context.consume(request.url().as_str());
+    context.consume([0]);
+    for name in COALESCE_HEADERS {
+        if let Some(value) = request.headers().get(name) {
+            context.consume(value.as_bytes());
+        }
+        context.consume([0]);
+    }
+    context.consume(body);
+    Some(context.compute().0)
+}
+
+#[tauri::command]
+pub fn api_quota(http: tauri::State<'_, Http>) -> Vec<QuotaUpdate> {
+    http.quotas()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::stand_in::{Response, StandIn};
+    use crate::api::xmlrpc::encode_call;
+    use tauri::async_runtime::{block_on, spawn};
+
+    fn http() -> Http {
+        Http::new(reqwest::Client::new())
+    }
+
+    /// Sends two copies of a request built by `build` at the same time.
+    fn send_twice(
+        http: &Http,
+        build: impl Fn(&Http) -> RequestBuilder,
+    ) -> [Result<Arc<HttpResponse>, HttpError>; 2] {
+        let requests = [build(http), build(http)];
+        block_on(async {
+            let [first, second] = requests.map(|request| {
+                let http = http.clone();
+                spawn(async move { http.send("test", request).await })
+            });
+            [first.await.unwrap(), second.await.unwrap()]
+        })
+    }
+
+    fn slow_server() -> StandIn {
+        StandIn::start(|_| {
+            std::thread::sleep(Duration::from_millis(200));
+            Response::new(200, "ok")
+        })
+    }
+
+    #[test]
+    fn coalesces_concurrent_identical_gets() {
+        let server = slow_server();
+        let url = format!("{}/features", server.url);
+        let http = http();
+        let [first, second] = send_twice(&http, |http| http.request(Method::GET, &url));
+        assert_eq!(first.unwrap().body, "ok");
+        assert_eq!(second.unwrap().body, "ok");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn coalesces_only_read_only_calls() {
+        let server = slow_server();
+        let http = http();
+        for (method, round_trips) in [("CheckSubHash", 1), ("UploadSubtitles", 2), ("LogIn", 2)] {
+            let before = server.requests().len();
+            let body = encode_call(method, &["token".into()]);
+            let results = send_twice(&http, |http| {
+                http.request(Method::POST, &server.url)
+                    .header("Content-Type", "text/xml")
+                    .body(body.clone())
+            });
+            assert!(results.iter().all(Result::is_ok));
+            assert_eq!(server.requests().len() - before, round_trips, "{method}");
+        }
+
+        let post = |path: &str| {
+            http.request(Method::POST, &format!("{}{path}", server.url))
+                .body("{}")
+                .build()
+                .unwrap()
+        };
+        assert!(coalesce_key(&post("/download")).is_none());
+    }
+
+    #[test]
+    fn token_bucket_refills_at_its_rate() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(RateLimit {
+            burst: 2,
+            per_second: 4.0,
+        });
+        assert!(bucket.try_take(now).is_ok());
+        assert!(bucket.try_take(now).is_ok());
+        let wait = bucket.try_take(now).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(250));
+        assert!(bucket.try_take(now + wait).is_ok());
+        assert!(bucket.try_take(now + wait).is_err());
+    }
+
+    #[test]
+    fn retries_after_too_many_requests() {
+        let server = StandIn::start({
+            let calls = Mutex::new(0);
+            move |_| {
+                let mut calls = calls.lock().unwrap();
+                *calls += 1;
+                match *calls {
+                    1 => Response::new(429, "").header("Retry-After", "1"),
+                    _ => Response::new(200, "ok"),
+                }
+            }
+        });
+        let http = http();
+        let started = Instant::now();
+        let response = block_on(http.send("test", http.request(Method::GET, &server.url))).unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn gives_up_after_repeated_too_many_requests() {
+        let server = StandIn::start(|_| Response::new(429, "").header("Retry-After", "0"));
+        let http = http();
+        let response = block_on(http.send("test", http.request(Method::GET, &server.url))).unwrap();
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.requests().len(), MAX_RETRIES as usize + 1);
+    }
+
+    #[test]
+    fn an_exhausted_quota_holds_the_endpoint() {
+        let server = StandIn::start(|_| {
+            Response::new(200, "ok")
+                .header("X-RateLimit-Remaining", "0")
+                .header("X-RateLimit-Limit", "20")
+                .header("X-RateLimit-Reset", "1")
+        });
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let http = http().on_quota({
+            let updates = updates.clone();
+            move |update| updates.lock().unwrap().push(update.remaining)
+        });
+        let get = |path: &str| {
+            http.send(
+                "test",
+                http.request(Method::GET, &format!("{}{path}", server.url)),
+            )
+        };
+        block_on(get("/first")).unwrap();
+        let started = Instant::now();
+        block_on(get("/second")).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(900));
+        assert_eq!(*updates.lock().unwrap(), [0, 0]);
+        let quota = &http.quotas()[0];
+        assert_eq!((quota.limit, quota.reset_secs), (Some(20), Some(1)));
+    }
+
+    #[test]
+    fn coalesce_key_covers_the_api_key() {
+        let client = reqwest::Client::new();
+        let key = |api_key: &str| {
+            let request = client
+                .get("https://api.example.com/api/v1/features")
+                .header("Api-Key", api_key)
+                .build()
+                .unwrap();
+            coalesce_key(&request).unwrap()
+        };
+        assert_eq!(key("first"), key("first"));
+        assert_ne!(key("first"), key("second"));
+    }
+}
@@ -0,0 +1,27 @@
+//! Clients for the OpenSubtitles APIs.
+//!
+//! Requests go out from the backend instead of the webview, so credentials and
+//! keys stay out of the frontend bundle and every call can share one
+//! connection pool.
+
+pub mod middleware;
+pub mod rest;
+#[cfg(test)]
+pub(crate) mod stand_in;
+pub mod xmlrpc;
+
+use std::time::Duration;
+
+/// User agent registered for the uploader, sent to both APIs.
+pub const USER_AGENT: &str = concat!("OpenSubtitles Uploader PRO v", env!("CARGO_PKG_VERSION"));
+
+/// Builds the connection pool shared by every API client; wrap it in
+/// [`middleware::Http`] to get rate limiting and request coalescing.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(15))
+        .timeout(Duration::from_secs(120))
+        .build()
+        .expect("static reqwest configuration is valid")
+}
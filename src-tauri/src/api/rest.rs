@@ -0,0 +1,1077 @@
+//! REST client for the `api.opensubtitles.com` v1 API.
+//!
+//! The API key is resolved in the backend (runtime environment first, then the
+//! value baked in at compile time) and never reaches the webview. Logging in
+//! stores the JWT together with the credentials so an expired or rejected
+//! token is renewed transparently; the API has no dedicated refresh endpoint,
+//! a fresh `/login` is the refresh.
+
+use super::middleware::{Http, HttpError};
+use super::USER_AGENT;
+use crate::cache::{ApiCache, Namespace};
+use crate::error::impl_serialize_error;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::RwLock;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.opensubtitles.com/api/v1";
+
+/// Environment variable holding the consumer API key.
+pub const API_KEY_VAR: &str = "OPENSUBTITLES_API_KEY";
+
+/// Tokens are renewed this long before their `exp` claim.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Tokens without a readable `exp` claim are assumed to live this long.
+const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestError {
+    #[error("no OpenSubtitles API key configured (set api.key in settings.toml or {API_KEY_VAR})")]
+    MissingApiKey,
+    #[error("not logged in")]
+    NotLoggedIn,
+    #[error("401 Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("403 Forbidden: {0}")]
+    Forbidden(String),
+    #[error("404 Not found: {0}")]
+    NotFound(String),
+    #[error("429 Too many requests")]
+    TooManyRequests { retry_after: Option<u64> },
+    #[error("HTTP {status}: {message}")]
+    Status { status: u16, message: String },
+    #[error("malformed response: {0}")]
+    Decode(String),
+    #[error("could not read {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("HTTP error: {0}")]
+    Http(#[from] HttpError),
+    #[error("background task failed: {0}")]
+    Task(String),
+}
+
+impl RestError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::MissingApiKey => "missingApiKey",
+            Self::NotLoggedIn => "notLoggedIn",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::NotFound(_) => "notFound",
+            Self::TooManyRequests { .. } => "tooManyRequests",
+            Self::Status { .. } => "status",
+            Self::Decode(_) => "decode",
+            Self::Io { .. } => "io",
+            Self::Http(_) => "http",
+            Self::Task(_) => "task",
+        }
+    }
+
+    fn from_response(status: reqwest::StatusCode, retry_after: Option<u64>, body: &str) -> Self {
+        let message = error_message(body);
+        match status.as_u16() {
+            401 => Self::Unauthorized(message),
+            403 => Self::Forbidden(message),
+            404 => Self::NotFound(message),
+            429 => Self::TooManyRequests { retry_after },
+            status => Self::Status { status, message },
+        }
+    }
+}
+
+impl_serialize_error!(RestError);
+
+/// Pulls a human-readable message out of an error body; the API uses both
+/// `{"message": ...}` and `{"errors": [...]}`.
+fn error_message(body: &str) -> String {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().chars().take(200).collect();
+    };
+    if let Some(message) = json.get("message").and_then(|m| m.as_str()) {
+        return message.to_string();
+    }
+    if let Some(errors) = json.get("errors").and_then(|e| e.as_array()) {
+        let messages: Vec<_> = errors.iter().filter_map(|e| e.as_str()).collect();
+        if !messages.is_empty() {
+            return messages.join("; ");
+        }
+    }
+    json.to_string()
+}
+
+/// Resolves the API key: runtime environment first, then the build-time value.
+pub fn api_key_from_env() -> Option<String> {
+    std::env::var(API_KEY_VAR)
+        .ok()
+        .or_else(|| option_env!("OPENSUBTITLES_API_KEY").map(str::to_string))
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// Accepts a JSON string or number; the API is not consistent about years
+/// and IDs.
+fn string_or_number<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::String(s)) if !s.is_empty() => Some(s),
+            Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct RestUser {
+    pub user_id: Option<u64>,
+    pub level: Option<String>,
+    pub allowed_downloads: Option<i64>,
+    pub allowed_translations: Option<i64>,
+    #[serde(default)]
+    pub vip: bool,
+    #[serde(default)]
+    pub ext_installed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct LoginResponse {
+    pub user: Option<RestUser>,
+    /// Host the account should talk to, e.g. `vip-api.opensubtitles.com`.
+    pub base_url: Option<String>,
+    #[serde(skip_serializing)]
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Language {
+    pub language_code: String,
+    pub language_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageDetection {
+    /// ISO 639-1 code, or the raw label when the service returns something else.
+    pub language_code: Option<String>,
+    #[serde(rename = "iso639_2")]
+    pub iso639_2: Option<String>,
+    pub name: Option<String>,
+    pub confidence: Option<f64>,
+    pub raw: serde_json::Value,
+}
+
+impl LanguageDetection {
+    fn from_value(raw: serde_json::Value) -> Self {
+        // The language may be a bare code or an object with ISO fields.
+        let language = raw.get("language").unwrap_or(&raw);
+        let text = |value: &serde_json::Value, keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| value.get(*key).and_then(|v| v.as_str()))
+                .map(str::to_string)
+        };
+        let language_code = language
+            .as_str()
+            .map(str::to_string)
+            .or_else(|| text(language, &["ISO639", "iso639", "language_code", "code"]));
+        Self {
+            language_code,
+            iso639_2: text(language, &["ISO639_2", "iso639_2"]),
+            name: text(language, &["name", "language_name"]),
+            confidence: ["confidence", "probability", "score"]
+                .iter()
+                .find_map(|key| raw.get(*key).or_else(|| language.get(*key)))
+                .and_then(|v| v.as_f64()),
+            raw,
+        }
+    }
+}
+
+/// Query for `/features`; unset fields are left out of the request.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureQuery {
+    pub feature_id: Option<u64>,
+    pub imdb_id: Option<u64>,
+    pub tmdb_id: Option<u64>,
+    pub query: Option<String>,
+    /// `movie`, `tvshow` or `episode`.
+    #[serde(rename = "type")]
+    pub feature_type: Option<String>,
+    pub year: Option<u32>,
+}
+
+impl FeatureQuery {
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        let mut push = |key, value: Option<String>| {
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                params.push((key, value));
+            }
+        };
+        push("feature_id", self.feature_id.map(|v| v.to_string()));
+        push("imdb_id", self.imdb_id.map(|v| v.to_string()));
+        push("tmdb_id", self.tmdb_id.map(|v| v.to_string()));
+        push("query", self.query.clone());
+        push("type", self.feature_type.clone());
+        push("year", self.year.map(|v| v.to_string()));
+        // The API wants parameters sorted, and redirects otherwise.
+        params.sort();
+        params
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Feature {
+    #[serde(deserialize_with = "string_or_number", default)]
+    pub id: Option<String>,
+    #[serde(rename(deserialize = "type"))]
+    pub kind: Option<String>,
+    pub attributes: FeatureAttributes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct FeatureAttributes {
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    #[serde(deserialize_with = "string_or_number", default)]
+    pub year: Option<String>,
+    /// `Movie`, `Tvshow` or `Episode`.
+    pub feature_type: Option<String>,
+    pub imdb_id: Option<u64>,
+    pub tmdb_id: Option<u64>,
+    pub parent_title: Option<String>,
+    pub parent_imdb_id: Option<u64>,
+    pub season_number: Option<u32>,
+    pub episode_number: Option<u32>,
+    pub subtitles_count: Option<u64>,
+    #[serde(default)]
+    pub subtitles_counts: BTreeMap<String, u64>,
+    pub url: Option<String>,
+    pub img_url: Option<String>,
+}
+
+/// `/utilities/guessit` result. Keys keep the API's snake_case names and
+/// absent keys stay absent because the frontend consumes this shape directly;
+/// fields that guessit may return as either a scalar or a list are left as
+/// raw JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuessitResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode_title: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle_language: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streaming_service: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_codec: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_codec: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_channels: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_profile: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edition: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_group: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+struct Session {
+    token: String,
+    /// Unix seconds after which the token should be renewed.
+    refresh_at: u64,
+    /// Per-account API root from the login response.
+    base_url: Option<String>,
+    username: String,
+    password: String,
+}
+
+/// A logged-in session taken off the client with
+/// [`RestClient::detach_session`].
+#[derive(Debug, Clone)]
+pub struct RestSession(Session);
+
+/// The part of a REST session worth keeping between runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSession {
+    pub token: String,
+    pub base_url: Option<String>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Reads the `exp` claim of a JWT without verifying it; the server does that.
+fn jwt_expiry(token: &str) -> Option<u64> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    claims.get("exp")?.as_u64()
+}
+
+/// Hand-rolled `multipart/form-data` body with a single file field, which
+/// spares the extra dependencies of reqwest's `multipart` feature.
+fn multipart_file(field: &str, filename: &str, contents: &[u8]) -> (String, Vec<u8>) {
+    let boundary = format!(
+        "----OpenSubtitlesUploader{:x}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    );
+    let filename = filename.replace(['"', '\r', '\n'], "_");
+    let mut body = Vec::with_capacity(contents.len() + 256);
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{field}\"; filename=\"{filename}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(contents);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    (format!("multipart/form-data; boundary={boundary}"), body)
+}
+
+/// Shared REST client; cheap to clone, the session is shared between clones.
+#[derive(Clone)]
+pub struct RestClient {
+    http: Http,
+    base_url: String,
+    /// Shared between clones so a key changed in the settings applies to all.
+    api_key: Arc<std::sync::RwLock<Option<String>>>,
+    session: Arc<RwLock<Option<Session>>>,
+    cache: Option<ApiCache>,
+}
+
+impl RestClient {
+    /// Uses an existing HTTP layer, e.g. the one shared with the XML-RPC client.
+    pub fn with_http(http: Http, base_url: impl Into<String>, api_key: Option<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: Arc::new(std::sync::RwLock::new(api_key)),
+            session: Arc::new(RwLock::new(None)),
+            cache: None,
+        }
+    }
+
+    /// Serves language detection, guessit and features lookups from `cache`
+    /// when possible.
+    pub fn with_cache(mut self, cache: ApiCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Replaces the consumer API key for subsequent requests.
+    pub fn set_api_key(&self, api_key: Option<String>) {
+        *self.api_key.write().unwrap() = api_key;
+    }
+
+    pub async fn is_logged_in(&self) -> bool {
+        self.session.read().await.is_some()
+    }
+
+    /// The current session, for storing with the account's credentials.
+    pub async fn saved_session(&self) -> Option<SavedSession> {
+        self.session
+            .read()
+            .await
+            .as_ref()
+            .map(|session| SavedSession {
+                token: session.token.clone(),
+                base_url: session.base_url.clone(),
+            })
+    }
+
+    /// Removes the session, keeping it (and its ability to renew itself) for
+    /// [`attach_session`](Self::attach_session) while another account uses
+    /// the client.
+    pub async fn detach_session(&self) -> Option<RestSession> {
+        self.session.write().await.take().map(RestSession)
+    }
+
+    /// Makes `session` the current one, replacing any other.
+    pub async fn attach_session(&self, session: Option<RestSession>) {
+        *self.session.write().await = session.map(|s| s.0);
+    }
+
+    /// Picks up a session saved by an earlier run. Returns `false`, leaving
+    /// the client logged out, when the token is due for renewal anyway.
+    pub async fn restore_session(
+        &self,
+        saved: &SavedSession,
+        username: &str,
+        password: &str,
+    ) -> bool {
+        let expires_at = jwt_expiry(&saved.token)
+            .unwrap_or_else(|| unix_now() + DEFAULT_TOKEN_LIFETIME.as_secs());
+        let refresh_at = expires_at.saturating_sub(REFRESH_MARGIN.as_secs());
+        if refresh_at <= unix_now() {
+            return false;
+        }
+        *self.session.write().await = Some(Session {
+            token: saved.token.clone(),
+            refresh_at,
+            base_url: saved.base_url.clone().filter(|u| !u.is_empty()),
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+        true
+    }
+
+    /// API root for the current session: the account's host from `/login`
+    /// when talking to the production API, the configured URL otherwise.
+    fn root(&self, session: Option<&Session>) -> String {
+        match session.and_then(|s| s.base_url.as_deref()) {
+            Some(host) if self.base_url == DEFAULT_BASE_URL => {
+                let host = host.trim_start_matches("https://").trim_end_matches('/');
+                format!("https://{host}/api/v1")
+            }
+            _ => self.base_url.clone(),
+        }
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        token: Option<&str>,
+    ) -> Result<reqwest::RequestBuilder, RestError> {
+        let api_key = self
+            .api_key
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or(RestError::MissingApiKey)?;
+        let mut request = self
+            .http
+            .request(method, url)
+            .header("Api-Key", api_key)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::ACCEPT, "application/json");
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        Ok(request)
+    }
+
+    /// Sends one request through the `rest<path>` endpoint bucket.
+    async fn send_once(
+        &self,
+        path: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<String, RestError> {
+        let response = self.http.send(&format!("rest{path}"), request).await?;
+        if response.status.is_success() {
+            Ok(response.body.clone())
+        } else {
+            Err(RestError::from_response(
+                response.status,
+                response.retry_after(),
+                &response.body,
+            ))
+        }
+    }
+
+    /// Sends a request built by `build`, renewing the session first if the
+    /// token is about to expire and once more if the server rejects it.
+    async fn send<T, F>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        build: F,
+    ) -> Result<T, RestError>
+    where
+        T: DeserializeOwned,
+        F: Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        // Bound first: a guard in the `if let` would live through `relogin`,
+        // which needs the lock for writing.
+        let current = self.session.read().await.clone();
+        if let Some(session) = current {
+            if unix_now() >= session.refresh_at {
+                self.relogin(&session).await?;
+            }
+        }
+        let mut retried = false;
+        loop {
+            let session = self.session.read().await.clone();
+            let url = format!("{}{path}", self.root(session.as_ref()));
+            let token = session.as_ref().map(|s| s.token.as_str());
+            let request = build(self.request(method.clone(), &url, token)?);
+            match self.send_once(path, request).await {
+                Err(RestError::Unauthorized(_)) if !retried && session.is_some() => {
+                    retried = true;
+                    self.relogin(session.as_ref().unwrap()).await?;
+                }
+                result => {
+                    let body = result?;
+                    return serde_json::from_str(&body)
+                        .map_err(|e| RestError::Decode(e.to_string()));
+                }
+            }
+        }
+    }
+
+    /// Like [`Self::send`], but answers from the cache when it holds `key` and
+    /// stores the raw response otherwise. Raw JSON is cached rather than `T`
+    /// so entries decode exactly like a fresh response.
+    async fn send_cached<T, F>(
+        &self,
+        namespace: Namespace,
+        key: &str,
+        method: reqwest::Method,
+        path: &str,
+        build: F,
+    ) -> Result<T, RestError>
+    where
+        T: DeserializeOwned,
+        F: Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        let cached = match &self.cache {
+            Some(cache) => cache.get::<serde_json::Value>(namespace, key).await,
+            None => None,
+        }
+        .and_then(|raw| serde_json::from_value(raw).ok());
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+        let raw: serde_json::Value = self.send(method, path, build).await?;
+        let value =
+            serde_json::from_value(raw.clone()).map_err(|e| RestError::Decode(e.to_string()))?;
+        if let Some(cache) = &self.cache {
+            // A cache write failure only costs a future request.
+            let _ = cache.put(namespace, key, &raw).await;
+        }
+        Ok(value)
+    }
+
+    /// Replaces `stale` with a fresh session unless another task already did.
+    async fn relogin(&self, stale: &Session) -> Result<(), RestError> {
+        let mut guard = self.session.write().await;
+        match guard.as_ref() {
+            Some(current) if current.token != stale.token => return Ok(()),
+            None => return Err(RestError::NotLoggedIn),
+            _ => {}
+        }
+        match self.authenticate(&stale.username, &stale.password).await {
+            Ok((_, session)) => {
+                *guard = Some(session);
+                Ok(())
+            }
+            Err(error) => {
+                // The stored credentials stopped working; force a manual login.
+                if matches!(error, RestError::Unauthorized(_)) {
+                    *guard = None;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<(LoginResponse, Session), RestError> {
+        let url = format!("{}/login", self.base_url);
+        let request = self
+            .request(reqwest::Method::POST, &url, None)?
+            .json(&serde_json::json!({ "username": username, "password": password }));
+        let body = self.send_once("/login", request).await?;
+        let response: LoginResponse =
+            serde_json::from_str(&body).map_err(|e| RestError::Decode(e.to_string()))?;
+        let expires_at = jwt_expiry(&response.token)
+            .unwrap_or_else(|| unix_now() + DEFAULT_TOKEN_LIFETIME.as_secs());
+        let session = Session {
+            token: response.token.clone(),
+            refresh_at: expires_at.saturating_sub(REFRESH_MARGIN.as_secs()),
+            base_url: response.base_url.clone().filter(|u| !u.is_empty()),
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        Ok((response, session))
+    }
+
+    pub async fn log_in(&self, username: &str, password: &str) -> Result<LoginResponse, RestError> {
+        let (response, session) = self.authenticate(username, password).await?;
+        *self.session.write().await = Some(session);
+        Ok(response)
+    }
+
+    /// Ends the session; the local state is dropped even if the server call fails.
+    pub async fn log_out(&self) -> Result<(), RestError> {
+        let Some(session) = self.session.write().await.take() else {
+            return Ok(());
+        };
+        let url = format!("{}/logout", self.root(Some(&session)));
+        let request = self.request(reqwest::Method::DELETE, &url, Some(&session.token))?;
+        match self.send_once("/logout", request).await {
+            Ok(_) | Err(RestError::Unauthorized(_)) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Detects the language of a subtitle file's text; cached by content MD5.
+    pub async fn detect_language(&self, path: &Path) -> Result<LanguageDetection, RestError> {
+        let contents = read_file(path).await?;
+        let key = format!("{:x}", md5::compute(&contents));
+        let filename = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "subtitle.srt".into());
+        let (content_type, body) = multipart_file("file", &filename, &contents);
+        let response: Data<serde_json::Value> = self
+            .send_cached(
+                Namespace::LanguageDetection,
+                &key,
+                reqwest::Method::POST,
+                "/utilities/fasttext/language/detect/file",
+                |r| {
+                    r.header(reqwest::header::CONTENT_TYPE, content_type.as_str())
+                        .body(body.clone())
+                },
+            )
+            .await?;
+        Ok(LanguageDetection::from_value(response.data))
+    }
+
+    pub async fn features(&self, query: &FeatureQuery) -> Result<Vec<Feature>, RestError> {
+        let params = query.params();
+        let key = params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let response: Data<Vec<Feature>> = self
+            .send_cached(
+                Namespace::Features,
+                &key,
+                reqwest::Method::GET,
+                "/features",
+                |r| r.query(&params),
+            )
+            .await?;
+        Ok(response.data)
+    }
+
+    pub async fn guessit(&self, filename: &str) -> Result<GuessitResult, RestError> {
+        self.send_cached(
+            Namespace::MovieGuess,
+            &format!("guessit:{filename}"),
+            reqwest::Method::GET,
+            "/utilities/guessit",
+            |r| r.query(&[("filename", filename)]),
+        )
+        .await
+    }
+
+    pub async fn languages(&self) -> Result<Vec<Language>, RestError> {
+        let response: Data<Vec<Language>> = self
+            .send(reqwest::Method::GET, "/infos/languages", |r| r)
+            .await?;
+        Ok(response.data)
+    }
+}
+
+async fn read_file(path: &Path) -> Result<Vec<u8>, RestError> {
+    let owned = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || std::fs::read(&owned))
+        .await
+        .map_err(|e| RestError::Task(e.to_string()))?
+        .map_err(|source| RestError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+impl Default for RestClient {
+    fn default() -> Self {
+        Self::with_http(
+            Http::new(super::http_client()),
+            DEFAULT_BASE_URL,
+            api_key_from_env(),
+        )
+    }
+}
+
+#[tauri::command]
+pub async fn rest_log_in(
+    client: tauri::State<'_, RestClient>,
+    username: String,
+    password: String,
+) -> Result<LoginResponse, RestError> {
+    client.log_in(&username, &password).await
+}
+
+#[tauri::command]
+pub async fn rest_log_out(client: tauri::State<'_, RestClient>) -> Result<(), RestError> {
+    client.log_out().await
+}
+
+#[tauri::command]
+pub async fn rest_detect_language(
+    client: tauri::State<'_, RestClient>,
+    path: String,
+) -> Result<LanguageDetection, RestError> {
+    client.detect_language(Path::new(&path)).await
+}
+
+#[tauri::command]
+pub async fn rest_features(
+    client: tauri::State<'_, RestClient>,
+    query: FeatureQuery,
+) -> Result<Vec<Feature>, RestError> {
+    client.features(&query).await
+}
+
+#[tauri::command]
+pub async fn rest_guessit(
+    client: tauri::State<'_, RestClient>,
+    filename: String,
+) -> Result<GuessitResult, RestError> {
+    client.guessit(&filename).await
+}
+
+#[tauri::command]
+pub async fn rest_languages(
+    client: tauri::State<'_, RestClient>,
+) -> Result<Vec<Language>, RestError> {
+    client.languages().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::stand_in::{Request, Response, StandIn};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tauri::async_runtime::block_on;
+
+    fn client(server: &StandIn) -> RestClient {
+        RestClient::with_http(
+            Http::new(reqwest::Client::new()),
+            &server.url,
+            Some("test-key".into()),
+        )
+    }
+
+    /// An unsigned JWT expiring at `exp`.
+    fn jwt(name: &str, exp: u64) -> String {
+        let claims = URL_SAFE_NO_PAD.encode(json!({ "sub": name, "exp": exp }).to_string());
+        format!("e30.{claims}.sig")
+    }
+
+    fn login(token: &str) -> Response {
+        Response::json(
+            200,
+            json!({
+                "user": { "user_id": 7, "level": "Sub leecher", "allowed_downloads": 20, "vip": false },
+                "base_url": "api.opensubtitles.com",
+                "token": token,
+                "status": 200
+            }),
+        )
+    }
+
+    fn languages() -> Response {
+        Response::json(
+            200,
+            json!({ "data": [{ "language_code": "en", "language_name": "English" }] }),
+        )
+    }
+
+    fn paths(server: &StandIn) -> Vec<String> {
+        server.requests().into_iter().map(|r| r.path).collect()
+    }
+
+    #[test]
+    fn logs_in_and_sends_the_key_and_token() {
+        let token = jwt("first", unix_now() + 3600);
+        let answer = token.clone();
+        let server = StandIn::start(move |request| match request.path.as_str() {
+            "/login" => login(&answer),
+            _ => languages(),
+        });
+        let client = client(&server);
+        block_on(async {
+            let response = client.log_in("alice", "secret").await.unwrap();
+            assert_eq!(response.user.unwrap().user_id, Some(7));
+            assert_eq!(response.token, token);
+            assert!(client.is_logged_in().await);
+            let languages = client.languages().await.unwrap();
+            assert_eq!(languages[0].language_code, "en");
+        });
+
+        let requests = server.requests();
+        assert_eq!(paths(&server), ["/login", "/infos/languages"]);
+        for request in &requests {
+            assert_eq!(request.header("Api-Key"), Some("test-key"));
+            assert_eq!(request.header("Accept"), Some("application/json"));
+        }
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body, json!({ "username": "alice", "password": "secret" }));
+        assert_eq!(requests[0].header("Authorization"), None);
+        let bearer = format!("Bearer {token}");
+        assert_eq!(requests[1].header("Authorization"), Some(bearer.as_str()));
+    }
+
+    #[test]
+    fn refuses_to_send_without_an_api_key() {
+        let server = StandIn::start(|_| languages());
+        let client = RestClient::with_http(Http::new(reqwest::Client::new()), &server.url, None);
+        let result = block_on(client.languages());
+        assert!(
+            matches!(result, Err(RestError::MissingApiKey)),
+            "{result:?}"
+        );
+        assert!(server.requests().is_empty());
+    }
+
+    /// Answers `/login` with `first`, then `second`, and other paths with
+    /// the languages list for `second` only.
+    fn renewing_server(first: String, second: String) -> StandIn {
+        let logins = AtomicU32::new(0);
+        StandIn::start(move |request: &Request| {
+            if request.path == "/login" {
+                return match logins.fetch_add(1, Ordering::SeqCst) {
+                    0 => login(&first),
+                    _ => login(&second),
+                };
+            }
+            match request.header("Authorization") {
+                Some(bearer) if bearer == format!("Bearer {second}") => languages(),
+                _ => Response::json(401, json!({ "message": "invalid token" })),
+            }
+        })
+    }
+
+    #[test]
+    fn logs_in_again_when_the_token_is_rejected() {
+        let later = unix_now() + 3600;
+        let (first, second) = (jwt("first", later), jwt("second", later));
+        let server = renewing_server(first, second.clone());
+        let client = client(&server);
+        block_on(async {
+            client.log_in("alice", "secret").await.unwrap();
+            client.languages().await.unwrap();
+            assert_eq!(client.saved_session().await.unwrap().token, second);
+        });
+        assert_eq!(
+            paths(&server),
+            ["/login", "/infos/languages", "/login", "/infos/languages"]
+        );
+    }
+
+    #[test]
+    fn renews_a_token_about_to_expire_before_using_it() {
+        let (first, second) = (
+            jwt("first", unix_now() + 60),
+            jwt("second", unix_now() + 3600),
+        );
+        let server = renewing_server(first, second);
+        let client = client(&server);
+        block_on(async {
+            client.log_in("alice", "secret").await.unwrap();
+            client.languages().await.unwrap();
+        });
+        assert_eq!(paths(&server), ["/login", "/login", "/infos/languages"]);
+    }
+
+    #[test]
+    fn drops_the_session_when_the_credentials_stop_working() {
+        let token = jwt("first", unix_now() + 3600);
+        let logins = AtomicU32::new(0);
+        let server = StandIn::start(move |request| {
+            match (request.path.as_str(), logins.load(Ordering::SeqCst)) {
+                ("/login", 0) => {
+                    logins.fetch_add(1, Ordering::SeqCst);
+                    login(&token)
+                }
+                _ => Response::json(401, json!({ "message": "invalid credentials" })),
+            }
+        });
+        let client = client(&server);
+        block_on(async {
+            client.log_in("alice", "secret").await.unwrap();
+            let result = client.languages().await;
+            assert!(
+                matches!(&result, Err(RestError::Unauthorized(m)) if m == "invalid credentials"),
+                "{result:?}"
+            );
+            assert!(!client.is_logged_in().await);
+        });
+    }
+
+    #[test]
+    fn parses_features() {
+        let server = StandIn::start(|_| {
+            Response::json(
+                200,
+                json!({
+                    "total_count": 1,
+                    "data": [{
+                        "id": "646",
+                        "type": "feature",
+                        "attributes": {
+                            "title": "The Matrix",
+                            "original_title": "The Matrix",
+                            "year": 1999,
+                            "feature_type": "Movie",
+                            "imdb_id": 133093,
+                            "tmdb_id": 603,
+                            "subtitles_count": 1200,
+                            "subtitles_counts": { "en": 300, "pt-BR": 90 },
+                            "url": "https://www.opensubtitles.com/en/movies/1999-the-matrix",
+                            "img_url": null
+                        }
+                    }]
+                }),
+            )
+        });
+        let client = client(&server);
+        let query = FeatureQuery {
+            query: Some("matrix".into()),
+            feature_type: Some("movie".into()),
+            year: Some(1999),
+            ..FeatureQuery::default()
+        };
+        let features = block_on(client.features(&query)).unwrap();
+        assert_eq!(features.len(), 1);
+        let feature = &features[0];
+        assert_eq!(feature.id.as_deref(), Some("646"));
+        assert_eq!(feature.attributes.year.as_deref(), Some("1999"));
+        assert_eq!(feature.attributes.imdb_id, Some(133093));
+        assert_eq!(feature.attributes.subtitles_counts["pt-BR"], 90);
+        assert_eq!(feature.attributes.img_url, None);
+        // Parameters go out sorted, as the API asks.
+        assert_eq!(
+            paths(&server),
+            ["/features?query=matrix&type=movie&year=1999"]
+        );
+    }
+
+    #[test]
+    fn parses_guessit_results() {
+        let server = StandIn::start(|_| {
+            Response::json(
+                200,
+                json!({
+                    "title": "Show",
+                    "season": 1,
+                    "episode": [1, 2],
+                    "type": "episode",
+                    "screen_size": "1080p",
+                    "release_group": "GRP",
+                    "container": "mkv"
+                }),
+            )
+        });
+        let guess = block_on(client(&server).guessit("Show.S01E01E02.1080p-GRP.mkv")).unwrap();
+        assert_eq!(guess.title.as_deref(), Some("Show"));
+        assert_eq!(guess.episode, Some(json!([1, 2])));
+        assert_eq!(guess.kind.as_deref(), Some("episode"));
+        assert_eq!(guess.release_group.as_deref(), Some("GRP"));
+        assert_eq!(guess.extra["container"], "mkv");
+        assert_eq!(
+            paths(&server),
+            ["/utilities/guessit?filename=Show.S01E01E02.1080p-GRP.mkv"]
+        );
+    }
+
+    #[test]
+    fn maps_error_statuses() {
+        let cases = [
+            (
+                Response::json(401, json!({ "message": "token expired" })),
+                "unauthorized",
+                "401 Unauthorized: token expired",
+            ),
+            (
+                Response::json(403, json!({ "message": "you cannot consume this service" })),
+                "forbidden",
+                "403 Forbidden: you cannot consume this service",
+            ),
+            (
+                Response::json(404, json!({ "errors": ["not found"] })),
+                "notFound",
+                "404 Not found: not found",
+            ),
+            (
+                Response::json(406, json!({ "errors": ["invalid file_id", "try again"] })),
+                "status",
+                "HTTP 406: invalid file_id; try again",
+            ),
+            (
+                Response::new(429, "").header("Retry-After", "0"),
+                "tooManyRequests",
+                "429 Too many requests",
+            ),
+            (
+                Response::new(502, "<html>Bad gateway</html>\n"),
+                "status",
+                "HTTP 502: <html>Bad gateway</html>",
+            ),
+        ];
+        for (response, kind, message) in cases {
+            let server = StandIn::start(move |_| response.clone());
+            let error = block_on(client(&server).languages()).unwrap_err();
+            assert_eq!((error.kind(), error.to_string().as_str()), (kind, message));
+            if let RestError::TooManyRequests { retry_after } = error {
+                assert_eq!(retry_after, Some(0));
+            }
+        }
+    }
+}
@@ -0,0 +1,1381 @@
+//! XML-RPC client for the legacy `api.opensubtitles.org` API.
+//!
+//! Every method returns a struct with a textual `status` such as
+//! `"200 OK"` or `"402 Subtitles has invalid format"`; those are mapped onto
+//! [`XmlRpcError`] so callers can match on the failure instead of parsing
+//! strings. The endpoint is configurable, which lets the client run against a
+//! local stand-in server.
+
+use super::middleware::{Http, HttpError};
+use super::USER_AGENT;
+use crate::cache::{ApiCache, Namespace};
+use crate::error::impl_serialize_error;
+use crate::subtitle::encoding;
+use crate::subtitle::timing::Resyncs;
+use crate::upload::history::{HistoryRecord, UploadHistory};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+pub const DEFAULT_ENDPOINT: &str = "https://api.opensubtitles.org/xml-rpc";
+
+#[derive(Debug, thiserror::Error)]
+pub enum XmlRpcError {
+    #[error("401 Unauthorized")]
+    Unauthorized,
+    #[error("402 Subtitles has invalid format")]
+    InvalidSubtitleFormat,
+    #[error("403 SubHashes (content and sent subhash) are not same")]
+    SubHashMismatch,
+    #[error("404 Subtitles has invalid language")]
+    InvalidLanguage,
+    #[error("405 Not all mandatory parameters were specified")]
+    MissingParameters,
+    #[error("406 No session")]
+    NoSession,
+    #[error("407 Download limit reached")]
+    DownloadLimitReached,
+    #[error("408 Invalid parameters")]
+    InvalidParameters,
+    #[error("409 Method not found")]
+    MethodNotFound,
+    #[error("410 Other or unknown error")]
+    UnknownError,
+    #[error("411 Empty or invalid user agent")]
+    InvalidUserAgent,
+    #[error("{0}")]
+    InvalidFormat(String),
+    #[error("413 Invalid IMDb ID")]
+    InvalidImdbId,
+    #[error("414 Unknown user agent")]
+    UnknownUserAgent,
+    #[error("415 Disabled user agent")]
+    DisabledUserAgent,
+    #[error("416 Internal subtitle validation failed")]
+    ValidationFailed,
+    #[error("429 Too many requests")]
+    TooManyRequests,
+    #[error("503 Service unavailable")]
+    ServiceUnavailable,
+    #[error("506 Server under maintenance")]
+    Maintenance,
+    #[error("unexpected status: {0}")]
+    UnexpectedStatus(String),
+    #[error("XML-RPC fault {code}: {message}")]
+    Fault { code: i64, message: String },
+    #[error("malformed XML-RPC response: {0}")]
+    Malformed(String),
+    #[error("could not read {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("HTTP error: {0}")]
+    Http(#[from] HttpError),
+}
+
+impl XmlRpcError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Unauthorized => "unauthorized",
+            Self::InvalidSubtitleFormat => "invalidSubtitleFormat",
+            Self::SubHashMismatch => "subHashMismatch",
+            Self::InvalidLanguage => "invalidLanguage",
+            Self::MissingParameters => "missingParameters",
+            Self::NoSession => "noSession",
+            Self::DownloadLimitReached => "downloadLimitReached",
+            Self::InvalidParameters => "invalidParameters",
+            Self::MethodNotFound => "methodNotFound",
+            Self::UnknownError => "unknownError",
+            Self::InvalidUserAgent => "invalidUserAgent",
+            Self::InvalidFormat(_) => "invalidFormat",
+            Self::InvalidImdbId => "invalidImdbId",
+            Self::UnknownUserAgent => "unknownUserAgent",
+            Self::DisabledUserAgent => "disabledUserAgent",
+            Self::ValidationFailed => "validationFailed",
+            Self::TooManyRequests => "tooManyRequests",
+            Self::ServiceUnavailable => "serviceUnavailable",
+            Self::Maintenance => "maintenance",
+            Self::UnexpectedStatus(_) => "unexpectedStatus",
+            Self::Fault { .. } => "fault",
+            Self::Malformed(_) => "malformed",
+            Self::Io { .. } => "io",
+            Self::Http(_) => "http",
+        }
+    }
+
+    /// Maps a textual API status to a result; 2xx statuses are success.
+    pub fn check_status(status: &str) -> Result<(), Self> {
+        let code: u16 = status
+            .split_whitespace()
+            .next()
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| Self::UnexpectedStatus(status.to_string()))?;
+        Err(match code {
+            200..=299 => return Ok(()),
+            401 => Self::Unauthorized,
+            402 => Self::InvalidSubtitleFormat,
+            403 => Self::SubHashMismatch,
+            404 => Self::InvalidLanguage,
+            405 => Self::MissingParameters,
+            406 => Self::NoSession,
+            407 => Self::DownloadLimitReached,
+            408 => Self::InvalidParameters,
+            409 => Self::MethodNotFound,
+            410 => Self::UnknownError,
+            411 => Self::InvalidUserAgent,
+            412 => Self::InvalidFormat(status.to_string()),
+            413 => Self::InvalidImdbId,
+            414 => Self::UnknownUserAgent,
+            415 => Self::DisabledUserAgent,
+            416 => Self::ValidationFailed,
+            429 => Self::TooManyRequests,
+            503 => Self::ServiceUnavailable,
+            506 => Self::Maintenance,
+            _ => Self::UnexpectedStatus(status.to_string()),
+        })
+    }
+
+    /// Whether the session token is no longer valid and a fresh login may help.
+    pub fn is_session_error(&self) -> bool {
+        matches!(self, Self::Unauthorized | Self::NoSession)
+    }
+
+    /// Whether the same call may succeed later without changing anything.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::TooManyRequests
+                | Self::ServiceUnavailable
+                | Self::Maintenance
+                | Self::UnknownError
+                | Self::UnexpectedStatus(_)
+                | Self::Malformed(_)
+                | Self::Http(_)
+        )
+    }
+}
+
+impl_serialize_error!(XmlRpcError);
+
+/// An XML-RPC value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+    DateTime(String),
+    Base64(Vec<u8>),
+    Array(Vec<Value>),
+    Struct(BTreeMap<String, Value>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Struct(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    /// String view; numbers are formatted since the API mixes the two freely.
+    pub fn as_string(&self) -> Option<String> {
+        match self {
+            Self::String(s) | Self::DateTime(s) => Some(s.clone()),
+            Self::Int(i) => Some(i.to_string()),
+            Self::Double(d) => Some(d.to_string()),
+            Self::Bool(b) => Some(if *b { "1" } else { "0" }.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Integer view, accepting numeric strings.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            Self::Bool(b) => Some(i64::from(*b)),
+            Self::Double(d) => Some(*d as i64),
+            Self::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> bool {
+        self.as_i64().is_some_and(|i| i != 0)
+    }
+
+    fn encode(&self, out: &mut String) {
+        out.push_str("<value>");
+        match self {
+            Self::Nil => out.push_str("<nil/>"),
+            Self::Bool(b) => {
+                let _ = write!(out, "<boolean>{}</boolean>", u8::from(*b));
+            }
+            Self::Int(i) => {
+                let _ = write!(out, "<int>{i}</int>");
+            }
+            Self::Double(d) => {
+                let _ = write!(out, "<double>{d}</double>");
+            }
+            Self::String(s) => {
+                let _ = write!(out, "<string>{}</string>", escape(s));
+            }
+            Self::DateTime(s) => {
+                let _ = write!(out, "<dateTime.iso8601>{}</dateTime.iso8601>", escape(s));
+            }
+            Self::Base64(bytes) => {
+                let _ = write!(out, "<base64>{}</base64>", BASE64.encode(bytes));
+            }
+            Self::Array(items) => {
+                out.push_str("<array><data>");
+                for item in items {
+                    item.encode(out);
+                }
+                out.push_str("</data></array>");
+            }
+            Self::Struct(members) => {
+                out.push_str("<struct>");
+                for (name, value) in members {
+                    let _ = write!(out, "<member><name>{}</name>", escape(name));
+                    value.encode(out);
+                    out.push_str("</member>");
+                }
+                out.push_str("</struct>");
+            }
+        }
+        out.push_str("</value>");
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::Int(i)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Self::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Nil => serde_json::Value::Null,
+            Value::Bool(b) => (*b).into(),
+            Value::Int(i) => (*i).into(),
+            Value::Double(d) => (*d).into(),
+            Value::String(s) | Value::DateTime(s) => s.clone().into(),
+            Value::Base64(bytes) => BASE64.encode(bytes).into(),
+            Value::Array(items) => items.iter().map(serde_json::Value::from).collect(),
+            Value::Struct(members) => members
+                .iter()
+                .map(|(k, v)| (k.clone(), serde_json::Value::from(v)))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+        }
+    }
+}
+
+/// Builds a struct value from `(name, value)` pairs, skipping `None`s.
+fn members<const N: usize>(pairs: [(&str, Option<Value>); N]) -> Value {
+    Value::Struct(
+        pairs
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect(),
+    )
+}
+
+/// Escapes text for an element, dropping the characters XML 1.0 does not
+/// allow at all (control characters other than tab and line breaks), which
+/// subtitle comments and release names sometimes carry and which would
+/// make the server reject the whole call. Carriage returns are written as
+/// references, since parsers turn a literal one into a line feed.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#13;"),
+            '\t' | '\n' => out.push(c),
+            '\0'..='\x1f' | '\u{fffe}' | '\u{ffff}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn encode_call(method: &str, params: &[Value]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\"?><methodCall>");
+    let _ = write!(out, "<methodName>{}</methodName><params>", escape(method));
+    for param in params {
+        out.push_str("<param>");
+        param.encode(&mut out);
+        out.push_str("</param>");
+    }
+    out.push_str("</params></methodCall>");
+    out
+}
+
+/// Parses a `methodResponse`, turning `<fault>` into [`XmlRpcError::Fault`].
+pub fn decode_response(xml: &str) -> Result<Value, XmlRpcError> {
+    let mut parser = Parser {
+        reader: Reader::from_str(xml),
+    };
+    let mut fault = false;
+    loop {
+        match parser.next()? {
+            Event::Start(e) if e.name().as_ref() == b"fault" => fault = true,
+            Event::Start(e) if e.name().as_ref() == b"value" => {
+                let value = parser.value()?;
+                if !fault {
+                    return Ok(value);
+                }
+                return Err(XmlRpcError::Fault {
+                    code: value.get("faultCode").and_then(Value::as_i64).unwrap_or(0),
+                    message: value
+                        .get("faultString")
+                        .and_then(Value::as_string)
+                        .unwrap_or_default(),
+                });
+            }
+            Event::Eof => return Err(XmlRpcError::Malformed("no value in response".into())),
+            _ => {}
+        }
+    }
+}
+
+struct Parser<'a> {
+    reader: Reader<&'a [u8]>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<Event<'a>, XmlRpcError> {
+        self.reader
+            .read_event()
+            .map_err(|e| XmlRpcError::Malformed(e.to_string()))
+    }
+
+    /// Text content up to the closing tag of the current element.
+    fn text(&mut self) -> Result<String, XmlRpcError> {
+        let mut text = String::new();
+        loop {
+            match self.next()? {
+                Event::Text(t) => text.push_str(
+                    &t.unescape()
+                        .map_err(|e| XmlRpcError::Malformed(e.to_string()))?,
+                ),
+                Event::CData(c) => text.push_str(&String::from_utf8_lossy(&c)),
+                Event::End(_) => return Ok(text),
+                Event::Eof => return Err(XmlRpcError::Malformed("unexpected end".into())),
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses the content of a `<value>` whose start tag was just consumed.
+    fn value(&mut self) -> Result<Value, XmlRpcError> {
+        let mut implicit = String::new();
+        loop {
+            match self.next()? {
+                Event::Text(t) => implicit.push_str(
+                    &t.unescape()
+                        .map_err(|e| XmlRpcError::Malformed(e.to_string()))?,
+                ),
+                Event::End(_) => return Ok(Value::String(implicit)),
+                Event::Empty(e) => {
+                    let value = match e.name().as_ref() {
+                        b"nil" => Value::Nil,
+                        b"array" => Value::Array(Vec::new()),
+                        b"struct" => Value::Struct(BTreeMap::new()),
+                        _ => Value::String(String::new()),
+                    };
+                    self.close()?;
+                    return Ok(value);
+                }
+                Event::Start(e) => {
+                    let value = match e.name().as_ref() {
+                        b"string" => Value::String(self.text()?),
+                        b"int" | b"i4" | b"i8" => {
+                            let text = self.text()?;
+                            Value::Int(text.trim().parse().map_err(|_| {
+                                XmlRpcError::Malformed(format!("bad integer {text:?}"))
+                            })?)
+                        }
+                        b"boolean" => Value::Bool(self.text()?.trim() == "1"),
+                        b"double" => {
+                            let text = self.text()?;
+                            Value::Double(text.trim().parse().map_err(|_| {
+                                XmlRpcError::Malformed(format!("bad double {text:?}"))
+                            })?)
+                        }
+                        b"dateTime.iso8601" => Value::DateTime(self.text()?),
+                        b"base64" => {
+                            let text: String = self.text()?.split_whitespace().collect();
+                            Value::Base64(
+                                BASE64
+                                    .decode(text)
+                                    .map_err(|e| XmlRpcError::Malformed(e.to_string()))?,
+                            )
+                        }
+                        b"nil" => {
+                            self.text()?;
+                            Value::Nil
+                        }
+                        b"array" => self.array()?,
+                        b"struct" => self.structure()?,
+                        other => {
+                            return Err(XmlRpcError::Malformed(format!(
+                                "unknown type <{}>",
+                                String::from_utf8_lossy(other)
+                            )))
+                        }
+                    };
+                    self.close()?;
+                    return Ok(value);
+                }
+                Event::Eof => return Err(XmlRpcError::Malformed("unexpected end".into())),
+                _ => {}
+            }
+        }
+    }
+
+    /// Consumes events up to and including the next end tag.
+    fn close(&mut self) -> Result<(), XmlRpcError> {
+        loop {
+            match self.next()? {
+                Event::End(_) => return Ok(()),
+                Event::Eof => return Err(XmlRpcError::Malformed("unexpected end".into())),
+                _ => {}
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, XmlRpcError> {
+        let mut items = Vec::new();
+        loop {
+            match self.next()? {
+                Event::Start(e) if e.name().as_ref() == b"value" => items.push(self.value()?),
+                Event::End(e) if e.name().as_ref() == b"array" => return Ok(Value::Array(items)),
+                Event::Eof => return Err(XmlRpcError::Malformed("unterminated array".into())),
+                _ => {}
+            }
+        }
+    }
+
+    fn structure(&mut self) -> Result<Value, XmlRpcError> {
+        let mut members = BTreeMap::new();
+        let mut name = None;
+        loop {
+            match self.next()? {
+                Event::Start(e) if e.name().as_ref() == b"name" => name = Some(self.text()?),
+                Event::Start(e) if e.name().as_ref() == b"value" => {
+                    let value = self.value()?;
+                    members.insert(name.take().unwrap_or_default(), value);
+                }
+                Event::End(e) if e.name().as_ref() == b"struct" => {
+                    return Ok(Value::Struct(members))
+                }
+                Event::Eof => return Err(XmlRpcError::Malformed("unterminated struct".into())),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    pub id_user: Option<String>,
+    pub nickname: Option<String>,
+    pub rank: Option<String>,
+    pub upload_count: Option<i64>,
+    pub download_count: Option<i64>,
+    pub preferred_languages: Option<String>,
+    pub web_language: Option<String>,
+}
+
+impl UserInfo {
+    fn from_value(data: &Value) -> Self {
+        let text = |key| data.get(key).and_then(Value::as_string).filter(|s| !s.is_empty());
+        let int = |key| data.get(key).and_then(Value::as_i64);
+        Self {
+            id_user: text("IDUser"),
+            nickname: text("UserNickName"),
+            rank: text("UserRank"),
+            upload_count: int("UploadCnt"),
+            download_count: int("DownloadCnt"),
+            preferred_languages: text("UserPreferedLanguages"),
+            web_language: text("UserWebLanguage"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResult {
+    pub token: String,
+    pub user: Option<UserInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BestGuess {
+    pub imdb_id: String,
+    pub movie_name: Option<String>,
+    pub movie_year: Option<String>,
+    pub movie_kind: Option<String>,
+    pub season: Option<String>,
+    pub episode: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovieGuess {
+    pub best_guess: Option<BestGuess>,
+    /// Full per-title response (`GuessIt`, `GetIMDBSuggest`, ...) for the UI.
+    pub raw: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubLanguage {
+    pub sub_language_id: String,
+    pub language_name: String,
+    pub iso639: String,
+}
+
+/// Movie-side fields shared by `TryUploadSubtitles` and `UploadSubtitles`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadFile {
+    pub subtitle_path: PathBuf,
+    /// Defaults to the file name of `subtitle_path`.
+    pub subtitle_filename: Option<String>,
+    pub movie_hash: Option<String>,
+    pub movie_byte_size: Option<u64>,
+    pub movie_filename: Option<String>,
+    pub movie_fps: Option<f64>,
+    pub movie_frames: Option<u64>,
+    pub movie_time_ms: Option<u64>,
+    /// Character encoding of the subtitle; detected on upload when unset.
+    pub subtitle_encoding: Option<String>,
+}
+
+/// `baseinfo` of an `UploadSubtitles` call.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadInfo {
+    pub imdb_id: String,
+    pub sub_language_id: String,
+    pub release_name: Option<String>,
+    pub movie_aka: Option<String>,
+    pub comment: Option<String>,
+    pub translator: Option<String>,
+    #[serde(default)]
+    pub hearing_impaired: bool,
+    #[serde(default)]
+    pub high_definition: bool,
+    #[serde(default)]
+    pub automatic_translation: bool,
+    #[serde(default)]
+    pub foreign_parts_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TryUploadResult {
+    pub already_in_db: bool,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadResult {
+    /// Subtitle page URL returned in `data`.
+    pub url: String,
+    pub subtitle_md5: String,
+    /// The `subencoding` sent along.
+    pub subtitle_encoding: Option<String>,
+}
+
+/// Subtitle bytes prepared for upload.
+pub struct EncodedSubtitle {
+    /// MD5 of the raw file, the `subhash` the server verifies.
+    pub md5: String,
+    /// Gzip-compressed, base64-encoded content for `subcontent`.
+    pub content: String,
+    /// Detected character encoding, for `subencoding`.
+    pub encoding: Option<String>,
+}
+
+/// Computes the `subhash` and the gzip+base64 `subcontent` for `bytes`, and
+/// detects their encoding with `language` as a hint.
+pub fn encode_subtitle(bytes: &[u8], language: Option<&str>) -> EncodedSubtitle {
+    let mut gz = GzEncoder::new(Vec::with_capacity(bytes.len() / 3), Compression::best());
+    gz.write_all(bytes).expect("writing to a Vec cannot fail");
+    let compressed = gz.finish().expect("writing to a Vec cannot fail");
+    EncodedSubtitle {
+        md5: format!("{:x}", md5::compute(bytes)),
+        content: BASE64.encode(compressed),
+        encoding: encoding::detect(bytes, language)
+            .ok()
+            .map(|detection| detection.encoding.to_string()),
+    }
+}
+
+impl UploadFile {
+    fn read(&self) -> Result<Vec<u8>, XmlRpcError> {
+        std::fs::read(&self.subtitle_path).map_err(|source| XmlRpcError::Io {
+            path: self.subtitle_path.clone(),
+            source,
+        })
+    }
+
+    pub(crate) fn filename(&self) -> String {
+        self.subtitle_filename.clone().unwrap_or_else(|| {
+            self.subtitle_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+    }
+
+    /// The encoding given, or else the detected one.
+    fn encoding(&self, encoded: &EncodedSubtitle) -> Option<String> {
+        self.subtitle_encoding
+            .clone()
+            .or_else(|| encoded.encoding.clone())
+    }
+
+    fn to_value(&self, encoded: &EncodedSubtitle, with_content: bool) -> Value {
+        members([
+            ("subhash", Some(encoded.md5.clone().into())),
+            ("subfilename", Some(self.filename().into())),
+            ("moviehash", self.movie_hash.clone().map(Value::from)),
+            ("moviebytesize", self.movie_byte_size.map(|s| s.to_string().into())),
+            ("moviefilename", self.movie_filename.clone().map(Value::from)),
+            ("moviefps", self.movie_fps.map(|f| format!("{f:.3}").into())),
+            ("movieframes", self.movie_frames.map(|f| f.to_string().into())),
+            ("movietimems", self.movie_time_ms.map(|t| t.to_string().into())),
+            ("subencoding", self.encoding(encoded).map(Value::from)),
+            ("subcontent", with_content.then(|| encoded.content.clone().into())),
+        ])
+    }
+}
+
+impl UploadInfo {
+    fn to_value(&self) -> Value {
+        let flag = |b: bool| Some(Value::from(if b { "1" } else { "0" }));
+        members([
+            ("idmovieimdb", Some(self.imdb_id.trim_start_matches("tt").into())),
+            ("sublanguageid", Some(self.sub_language_id.clone().into())),
+            ("moviereleasename", self.release_name.clone().map(Value::from)),
+            ("movieaka", self.movie_aka.clone().map(Value::from)),
+            ("subauthorcomment", self.comment.clone().map(Value::from)),
+            ("subtranslator", self.translator.clone().map(Value::from)),
+            ("hearingimpaired", flag(self.hearing_impaired)),
+            ("highdefinition", flag(self.high_definition)),
+            ("automatictranslation", flag(self.automatic_translation)),
+            ("foreignpartsonly", flag(self.foreign_parts_only)),
+        ])
+    }
+}
+
+/// Shared XML-RPC client; cheap to clone.
+#[derive(Clone)]
+pub struct XmlRpcClient {
+    http: Http,
+    endpoint: String,
+    cache: Option<ApiCache>,
+}
+
+impl XmlRpcClient {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self::with_http(Http::new(super::http_client()), endpoint)
+    }
+
+    /// Uses an existing HTTP layer, e.g. the one shared with the REST client.
+    pub fn with_http(http: Http, endpoint: impl Into<String>) -> Self {
+        Self {
+            http,
+            endpoint: endpoint.into(),
+            cache: None,
+        }
+    }
+
+    /// Serves `GuessMovieFromString` and `CheckSubHash` from `cache` when possible.
+    pub fn with_cache(mut self, cache: ApiCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    async fn cached<T: serde::de::DeserializeOwned + Send + 'static>(
+        &self,
+        namespace: Namespace,
+        key: &str,
+    ) -> Option<T> {
+        self.cache.as_ref()?.get(namespace, key).await
+    }
+
+    async fn store<T: Serialize>(&self, namespace: Namespace, key: &str, value: &T) {
+        if let Some(cache) = &self.cache {
+            // A cache write failure only costs a future request.
+            let _ = cache.put(namespace, key, value).await;
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Performs a call and returns the response struct after checking `status`.
+    pub async fn call(&self, method: &str, params: &[Value]) -> Result<Value, XmlRpcError> {
+        let body = encode_call(method, params);
+        let request = self
+            .http
+            .request(reqwest::Method::POST, &self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "text/xml")
+            .body(body);
+        let response = self.http.send("xmlrpc", request).await?;
+        match response.status {
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(XmlRpcError::TooManyRequests),
+            reqwest::StatusCode::SERVICE_UNAVAILABLE => {
+                return Err(XmlRpcError::ServiceUnavailable)
+            }
+            status if !status.is_success() => {
+                return Err(XmlRpcError::UnexpectedStatus(format!("HTTP {status}")))
+            }
+            _ => {}
+        }
+        let value = decode_response(&response.body)?;
+        if let Some(status) = value.get("status").and_then(Value::as_string) {
+            XmlRpcError::check_status(&status)?;
+        }
+        Ok(value)
+    }
+
+    pub async fn log_in(
+        &self,
+        username: &str,
+        password: &str,
+        language: &str,
+    ) -> Result<LoginResult, XmlRpcError> {
+        let response = self
+            .call(
+                "LogIn",
+                &[username.into(), password.into(), language.into(), USER_AGENT.into()],
+            )
+            .await?;
+        let token = response
+            .get("token")
+            .and_then(Value::as_string)
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| XmlRpcError::Malformed("LogIn returned no token".into()))?;
+        Ok(LoginResult {
+            token,
+            user: response.get("data").map(UserInfo::from_value),
+        })
+    }
+
+    /// `ServerInfo` needs no session, which makes it a cheap reachability probe.
+    pub async fn server_info(&self) -> Result<serde_json::Value, XmlRpcError> {
+        self.call("ServerInfo", &[]).await.map(|info| (&info).into())
+    }
+
+    pub async fn log_out(&self, token: &str) -> Result<(), XmlRpcError> {
+        self.call("LogOut", &[token.into()]).await.map(|_| ())
+    }
+
+    pub async fn get_user_info(&self, token: &str) -> Result<UserInfo, XmlRpcError> {
+        let response = self.call("GetUserInfo", &[token.into()]).await?;
+        let data = response
+            .get("data")
+            .ok_or_else(|| XmlRpcError::Malformed("GetUserInfo returned no data".into()))?;
+        Ok(UserInfo::from_value(data))
+    }
+
+    /// Guesses movies for release names; keyed by the submitted string.
+    /// Cached titles are answered locally and only the rest are sent.
+    pub async fn guess_movie_from_string(
+        &self,
+        token: &str,
+        titles: &[String],
+    ) -> Result<BTreeMap<String, MovieGuess>, XmlRpcError> {
+        let mut guesses = BTreeMap::new();
+        let mut missing = Vec::new();
+        for title in titles {
+            let key = format!("xmlrpc:{title}");
+            match self.cached(Namespace::MovieGuess, &key).await {
+                Some(guess) => {
+                    guesses.insert(title.clone(), guess);
+                }
+                None => missing.push(title.clone()),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(guesses);
+        }
+        let response = self
+            .call("GuessMovieFromString", &[token.into(), missing.into()])
+            .await?;
+        if let Some(Value::Struct(data)) = response.get("data") {
+            for (title, entry) in data {
+                let best_guess = entry.get("BestGuess").and_then(|best| {
+                    let text = |key| best.get(key).and_then(Value::as_string);
+                    Some(BestGuess {
+                        imdb_id: text("IDMovieIMDB")?,
+                        movie_name: text("MovieName"),
+                        movie_year: text("MovieYear"),
+                        movie_kind: text("MovieKind"),
+                        season: text("SeriesSeason"),
+                        episode: text("SeriesEpisode"),
+                    })
+                });
+                let guess = MovieGuess {
+                    best_guess,
+                    raw: entry.into(),
+                };
+                let key = format!("xmlrpc:{title}");
+                self.store(Namespace::MovieGuess, &key, &guess).await;
+                guesses.insert(title.clone(), guess);
+            }
+        }
+        Ok(guesses)
+    }
+
+    pub async fn get_sub_languages(&self, language: &str) -> Result<Vec<SubLanguage>, XmlRpcError> {
+        let response = self.call("GetSubLanguages", &[language.into()]).await?;
+        let Some(Value::Array(items)) = response.get("data") else {
+            return Err(XmlRpcError::Malformed("GetSubLanguages returned no data".into()));
+        };
+        Ok(items
+            .iter()
+            .filter_map(|item| {
+                let text = |key| item.get(key).and_then(Value::as_string);
+                Some(SubLanguage {
+                    sub_language_id: text("SubLanguageID")?,
+                    language_name: text("LanguageName").unwrap_or_default(),
+                    iso639: text("ISO639").unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    /// Maps each subtitle MD5 to its `IDSubtitleFile`, or `None` if unknown.
+    pub async fn check_sub_hash(
+        &self,
+        token: &str,
+        hashes: &[String],
+    ) -> Result<BTreeMap<String, Option<String>>, XmlRpcError> {
+        let mut found = BTreeMap::new();
+        let mut missing = Vec::new();
+        for hash in hashes {
+            match self.cached(Namespace::CheckSubHash, hash).await {
+                Some(id) => {
+                    found.insert(hash.clone(), Some(id));
+                }
+                None => missing.push(hash.clone()),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(found);
+        }
+        let response = self
+            .call("CheckSubHash", &[token.into(), missing.into()])
+            .await?;
+        if let Some(Value::Struct(data)) = response.get("data") {
+            for (hash, id) in data {
+                let id = id.as_string().filter(|id| !id.is_empty() && id != "0");
+                // Only known hashes are cached; an upload may add the others.
+                if let Some(id) = &id {
+                    self.store(Namespace::CheckSubHash, hash, id).await;
+                }
+                found.insert(hash.clone(), id);
+            }
+        }
+        Ok(found)
+    }
+
+    pub async fn try_upload_subtitles(
+        &self,
+        token: &str,
+        file: &UploadFile,
+    ) -> Result<TryUploadResult, XmlRpcError> {
+        let encoded = encode_subtitle(&file.read()?, None);
+        let cds = members([("cd1", Some(file.to_value(&encoded, false)))]);
+        let response = self.call("TryUploadSubtitles", &[token.into(), cds]).await?;
+        Ok(TryUploadResult {
+            already_in_db: response.get("alreadyindb").is_some_and(Value::as_bool),
+            data: response.get("data").map(Into::into).unwrap_or_default(),
+        })
+    }
+
+    pub async fn upload_subtitles(
+        &self,
+        token: &str,
+        info: &UploadInfo,
+        file: &UploadFile,
+    ) -> Result<UploadResult, XmlRpcError> {
+        let encoded = encode_subtitle(&file.read()?, Some(&info.sub_language_id));
+        let request = members([
+            ("baseinfo", Some(info.to_value())),
+            ("cd1", Some(file.to_value(&encoded, true))),
+        ]);
+        let response = self.call("UploadSubtitles", &[token.into(), request]).await?;
+        Ok(UploadResult {
+            url: response
+                .get("data")
+                .and_then(Value::as_string)
+                .unwrap_or_default(),
+            subtitle_encoding: file.encoding(&encoded),
+            subtitle_md5: encoded.md5,
+        })
+    }
+}
+
+impl Default for XmlRpcClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_ENDPOINT)
+    }
+}
+
+#[tauri::command]
+pub async fn xmlrpc_log_in(
+    client: tauri::State<'_, XmlRpcClient>,
+    username: String,
+    password: String,
+    language: Option<String>,
+) -> Result<LoginResult, XmlRpcError> {
+    client
+        .log_in(&username, &password, language.as_deref().unwrap_or("en"))
+        .await
+}
+
+#[tauri::command]
+pub async fn xmlrpc_get_user_info(
+    client: tauri::State<'_, XmlRpcClient>,
+    token: String,
+) -> Result<UserInfo, XmlRpcError> {
+    client.get_user_info(&token).await
+}
+
+#[tauri::command]
+pub async fn xmlrpc_guess_movie_from_string(
+    client: tauri::State<'_, XmlRpcClient>,
+    token: String,
+    titles: Vec<String>,
+) -> Result<BTreeMap<String, MovieGuess>, XmlRpcError> {
+    client.guess_movie_from_string(&token, &titles).await
+}
+
+#[tauri::command]
+pub async fn xmlrpc_get_sub_languages(
+    client: tauri::State<'_, XmlRpcClient>,
+    language: Option<String>,
+) -> Result<Vec<SubLanguage>, XmlRpcError> {
+    client
+        .get_sub_languages(language.as_deref().unwrap_or("en"))
+        .await
+}
+
+#[tauri::command]
+pub async fn xmlrpc_check_sub_hash(
+    client: tauri::State<'_, XmlRpcClient>,
+    token: String,
+    hashes: Vec<String>,
+) -> Result<BTreeMap<String, Option<String>>, XmlRpcError> {
+    client.check_sub_hash(&token, &hashes).await
+}
+
+#[tauri::command]
+pub async fn xmlrpc_try_upload_subtitles(
+    client: tauri::State<'_, XmlRpcClient>,
+    token: String,
+    file: UploadFile,
+) -> Result<TryUploadResult, XmlRpcError> {
+    client.try_upload_subtitles(&token, &file).await
+}
+
+#[tauri::command]
+pub async fn xmlrpc_upload_subtitles(
+    client: tauri::State<'_, XmlRpcClient>,
+    history: tauri::State<'_, UploadHistory>,
+    resyncs: tauri::State<'_, Resyncs>,
+    token: String,
+    mut info: UploadInfo,
+    file: UploadFile,
+    account: Option<String>,
+) -> Result<UploadResult, XmlRpcError> {
+    resyncs.annotate(&mut info, &file.subtitle_path);
+    let result = client.upload_subtitles(&token, &info, &file).await?;
+    // Best effort: the subtitle is on the server either way.
+    let _ = history.record(HistoryRecord::new(&info, &file, &result, account.as_deref()));
+    Ok(result)
+}
+
+/// A stand-in for the XML-RPC server on a local port, for tests.
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use super::*;
+    use crate::api::stand_in as http;
+
+    #[derive(Debug, Clone)]
+    pub(crate) struct Call {
+        pub method: String,
+        pub params: Vec<Value>,
+    }
+
+    /// What to answer a call with: a response value, or an HTTP error status.
+    pub(crate) type Reply = Result<Value, u16>;
+
+    pub(crate) struct StandIn {
+        pub endpoint: String,
+        server: http::StandIn,
+    }
+
+    impl StandIn {
+        /// Answers every call with `respond` until the test ends.
+        pub fn start(respond: impl Fn(&Call) -> Reply + Send + 'static) -> Self {
+            let server = http::StandIn::start(move |request| match decode_call(&request.body) {
+                Some(call) => response(respond(&call)),
+                None => http::Response::new(400, ""),
+            });
+            let endpoint = format!("{}/xml-rpc", server.url);
+            Self { endpoint, server }
+        }
+
+        pub fn client(&self) -> XmlRpcClient {
+            XmlRpcClient::new(&self.endpoint)
+        }
+
+        /// Calls received so far, oldest first.
+        pub fn calls(&self) -> Vec<Call> {
+            self.server
+                .requests()
+                .iter()
+                .filter_map(|request| decode_call(&request.body))
+                .collect()
+        }
+    }
+
+    /// A response struct with `status` and the other members given.
+    pub(crate) fn status<const N: usize>(status: &str, members: [(&str, Value); N]) -> Value {
+        let mut value = BTreeMap::from([("status".to_string(), Value::from(status))]);
+        value.extend(members.into_iter().map(|(k, v)| (k.to_string(), v)));
+        Value::Struct(value)
+    }
+
+    fn decode_call(xml: &str) -> Option<Call> {
+        let mut parser = Parser {
+            reader: Reader::from_str(xml),
+        };
+        let mut call = Call {
+            method: String::new(),
+            params: Vec::new(),
+        };
+        loop {
+            match parser.next().ok()? {
+                Event::Start(e) if e.name().as_ref() == b"methodName" => {
+                    call.method = parser.text().ok()?
+                }
+                Event::Start(e) if e.name().as_ref() == b"value" => {
+                    call.params.push(parser.value().ok()?)
+                }
+                Event::Eof => return Some(call),
+                _ => {}
+            }
+        }
+    }
+
+    fn response(reply: Reply) -> http::Response {
+        match reply {
+            Ok(value) => {
+                let mut body =
+                    String::from("<?xml version=\"1.0\"?><methodResponse><params><param>");
+                value.encode(&mut body);
+                body.push_str("</param></params></methodResponse>");
+                http::Response::new(200, body).header("Content-Type", "text/xml")
+            }
+            Err(code) => http::Response::new(code, ""),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stand_in::{status, StandIn};
+    use super::*;
+    use tauri::async_runtime::block_on;
+
+    fn subtitle_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn log_in() {
+        let server = StandIn::start(|call| match call.method.as_str() {
+            "LogIn" => Ok(status(
+                "200 OK",
+                [
+                    ("token", "abc123".into()),
+                    (
+                        "data",
+                        Value::Struct(BTreeMap::from([
+                            ("IDUser".into(), "42".into()),
+                            ("UserNickName".into(), "tester".into()),
+                            ("UploadCnt".into(), Value::Int(7)),
+                        ])),
+                    ),
+                ],
+            )),
+            _ => Err(500),
+        });
+        let login = block_on(server.client().log_in("tester", "secret", "en")).unwrap();
+        assert_eq!(login.token, "abc123");
+        let user = login.user.unwrap();
+        assert_eq!(user.nickname.as_deref(), Some("tester"));
+        assert_eq!(user.upload_count, Some(7));
+
+        let calls = server.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(
+            calls[0].params,
+            vec![
+                "tester".into(),
+                "secret".into(),
+                "en".into(),
+                USER_AGENT.into()
+            ]
+        );
+    }
+
+    #[test]
+    fn log_in_rejected() {
+        let server = StandIn::start(|_| Ok(status("401 Unauthorized", [])));
+        let error = block_on(server.client().log_in("tester", "wrong", "en")).unwrap_err();
+        assert!(matches!(error, XmlRpcError::Unauthorized), "{error:?}");
+        assert!(error.is_session_error());
+    }
+
+    #[test]
+    fn log_in_without_token() {
+        let server = StandIn::start(|_| Ok(status("200 OK", [("token", "".into())])));
+        let error = block_on(server.client().log_in("tester", "secret", "en")).unwrap_err();
+        assert!(matches!(error, XmlRpcError::Malformed(_)), "{error:?}");
+    }
+
+    #[test]
+    fn session_errors() {
+        let server = StandIn::start(|call| match call.params.first() {
+            Some(Value::String(token)) if token == "expired" => Ok(status("406 No session", [])),
+            _ => Ok(status("401 Unauthorized", [])),
+        });
+        let client = server.client();
+        let error = block_on(client.get_user_info("expired")).unwrap_err();
+        assert!(matches!(error, XmlRpcError::NoSession), "{error:?}");
+        assert!(error.is_session_error());
+        let error = block_on(client.get_user_info("forged")).unwrap_err();
+        assert!(matches!(error, XmlRpcError::Unauthorized), "{error:?}");
+        assert!(error.is_session_error() && !error.is_transient());
+    }
+
+    #[test]
+    fn server_unavailable() {
+        let server = StandIn::start(|_| Err(503));
+        let error = block_on(server.client().get_user_info("abc123")).unwrap_err();
+        assert!(
+            matches!(error, XmlRpcError::ServiceUnavailable),
+            "{error:?}"
+        );
+        assert!(error.is_transient() && !error.is_session_error());
+    }
+
+    #[test]
+    fn check_sub_hash() {
+        let server = StandIn::start(|call| {
+            let Some(Value::Array(hashes)) = call.params.get(1) else {
+                return Err(400);
+            };
+            let ids = hashes.iter().map(|hash| {
+                let hash = hash.as_string().unwrap();
+                let id = if hash == "known" { "12345" } else { "0" };
+                (hash, Value::from(id))
+            });
+            Ok(status("200 OK", [("data", Value::Struct(ids.collect()))]))
+        });
+        let hashes = vec!["known".to_string(), "unknown".to_string()];
+        let found = block_on(server.client().check_sub_hash("abc123", &hashes)).unwrap();
+        assert_eq!(found["known"].as_deref(), Some("12345"));
+        assert_eq!(found["unknown"], None);
+        assert_eq!(server.calls()[0].method, "CheckSubHash");
+        assert_eq!(server.calls()[0].params[0], "abc123".into());
+    }
+
+    #[test]
+    fn check_sub_hash_caches_known_hashes_only() {
+        let server = StandIn::start(|call| {
+            let Some(Value::Array(hashes)) = call.params.get(1) else {
+                return Err(400);
+            };
+            let ids = hashes.iter().map(|hash| {
+                let hash = hash.as_string().unwrap();
+                let id = if hash == "known" { "12345" } else { "0" };
+                (hash, Value::from(id))
+            });
+            Ok(status("200 OK", [("data", Value::Struct(ids.collect()))]))
+        });
+        let dir = std::env::temp_dir().join(format!("{}-hash-cache", std::process::id()));
+        let cache = ApiCache::open(&dir, crate::cache::DEFAULT_MAX_BYTES).unwrap();
+        let client = server.client().with_cache(cache);
+        let hashes = vec!["known".to_string(), "unknown".to_string()];
+        block_on(client.check_sub_hash("abc123", &hashes)).unwrap();
+        let found = block_on(client.check_sub_hash("abc123", &hashes)).unwrap();
+        assert_eq!(found["known"].as_deref(), Some("12345"));
+        assert_eq!(found["unknown"], None);
+        let calls = server.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].params[1], vec![Value::from("unknown")].into());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn try_upload_subtitles() {
+        let server = StandIn::start(|_| {
+            Ok(status(
+                "200 OK",
+                [
+                    ("alreadyindb", Value::Int(1)),
+                    ("data", vec![Value::Struct(BTreeMap::new())].into()),
+                ],
+            ))
+        });
+        let contents = "1\r\n00:00:01,000 --> 00:00:02,000\r\nHello\r\n";
+        let file = UploadFile {
+            subtitle_path: subtitle_file("try.srt", contents),
+            movie_hash: Some("0123456789abcdef".into()),
+            movie_byte_size: Some(1_000_000),
+            ..UploadFile::default()
+        };
+        let result = block_on(server.client().try_upload_subtitles("abc123", &file)).unwrap();
+        assert!(result.already_in_db);
+
+        let call = &server.calls()[0];
+        assert_eq!(call.method, "TryUploadSubtitles");
+        let cd1 = call.params[1].get("cd1").unwrap();
+        let text = |key| cd1.get(key).and_then(Value::as_string);
+        assert_eq!(
+            text("subhash"),
+            Some(format!("{:x}", md5::compute(contents)))
+        );
+        assert_eq!(text("subfilename"), Some(file.filename()));
+        assert_eq!(text("moviebytesize").as_deref(), Some("1000000"));
+        // Content is only sent with the upload itself.
+        assert_eq!(cd1.get("subcontent"), None);
+        let _ = std::fs::remove_file(&file.subtitle_path);
+    }
+
+    #[test]
+    fn try_upload_subtitles_new() {
+        let server = StandIn::start(|_| Ok(status("200 OK", [("alreadyindb", Value::Int(0))])));
+        let file = UploadFile {
+            subtitle_path: subtitle_file("new.srt", "1\n00:00:01,000 --> 00:00:02,000\nNew\n"),
+            ..UploadFile::default()
+        };
+        let result = block_on(server.client().try_upload_subtitles("abc123", &file)).unwrap();
+        assert!(!result.already_in_db);
+        let _ = std::fs::remove_file(&file.subtitle_path);
+    }
+
+    #[test]
+    fn try_upload_subtitles_expired_session() {
+        let server = StandIn::start(|_| Ok(status("406 No session", [])));
+        let file = UploadFile {
+            subtitle_path: subtitle_file("expired.srt", "1\n00:00:01,000 --> 00:00:02,000\nHi\n"),
+            ..UploadFile::default()
+        };
+        let error = block_on(server.client().try_upload_subtitles("stale", &file)).unwrap_err();
+        assert!(error.is_session_error(), "{error:?}");
+        let _ = std::fs::remove_file(&file.subtitle_path);
+    }
+
+    #[test]
+    fn upload_subtitles() {
+        let server = StandIn::start(|_| {
+            Ok(status(
+                "200 OK",
+                [("data", "https://www.opensubtitles.org/subtitles/1".into())],
+            ))
+        });
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+        let file = UploadFile {
+            subtitle_path: subtitle_file("upload.srt", contents),
+            ..UploadFile::default()
+        };
+        let info = UploadInfo {
+            imdb_id: "tt0133093".into(),
+            sub_language_id: "eng".into(),
+            hearing_impaired: true,
+            ..UploadInfo::default()
+        };
+        let result = block_on(server.client().upload_subtitles("abc123", &info, &file)).unwrap();
+        assert_eq!(result.url, "https://www.opensubtitles.org/subtitles/1");
+        assert_eq!(result.subtitle_md5, format!("{:x}", md5::compute(contents)));
+
+        let request = &server.calls()[0].params[1];
+        let base = request.get("baseinfo").unwrap();
+        assert_eq!(base.get("idmovieimdb"), Some(&"0133093".into()));
+        assert_eq!(base.get("hearingimpaired"), Some(&"1".into()));
+        assert!(request.get("cd1").unwrap().get("subcontent").is_some());
+        let _ = std::fs::remove_file(&file.subtitle_path);
+    }
+
+    #[test]
+    fn escape_drops_characters_xml_forbids() {
+        let call = encode_call(
+            "Test",
+            &[Value::String("a\u{1}b\u{1b}[0m\tc\r\n<&>\u{ffff}".into())],
+        );
+        assert!(call.contains("<string>ab[0m\tc&#13;\n&lt;&amp;&gt;</string>"));
+
+        let mut response = String::from("<methodResponse><params><param>");
+        Value::String("line\r\nnext".into()).encode(&mut response);
+        response.push_str("</param></params></methodResponse>");
+        let value = decode_response(&response).unwrap();
+        assert_eq!(value.as_string().as_deref(), Some("line\r\nnext"));
+    }
+}
@@ -0,0 +1,799 @@
+//! Subtitle packs: listing and extracting ZIP, RAR, 7z and tar (plain, gzip
+//! or xz) archives, replacing `archive-wasm` in the webview.
+//!
+//! Only subtitles and nested archives are extracted; nested archives are
+//! unpacked in turn, up to [`Limits::max_depth`]. Members whose names would
+//! land outside the extraction directory (`../x.srt`, `/etc/x`, `C:\x`) are
+//! skipped and reported. Sizes are counted as data is written rather than
+//! trusted from headers, and extraction stops with
+//! [`ArchiveError::LimitExceeded`] once a member, the whole pack or the
+//! compression ratio goes past its limit.
+//!
+//! Extracted files live in a temporary directory owned by an [`Extraction`]
+//! and removed with it.
+
+mod rar;
+mod sevenz;
+mod tar;
+mod zip;
+
+use crate::error::impl_serialize_error;
+use crate::pairing::is_subtitle;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("could not read {}: {source}", .path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("could not write {}: {source}", .path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("{} is not a supported archive", .0.display())]
+    Unsupported(PathBuf),
+    #[error("malformed archive: {0}")]
+    Malformed(String),
+    #[error("archive limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("no extracted archive with id {0}")]
+    NotFound(String),
+    #[error("extraction task failed: {0}")]
+    Task(String),
+}
+
+impl ArchiveError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Write { .. } => "write",
+            Self::Unsupported(_) => "unsupported",
+            Self::Malformed(_) => "malformed",
+            Self::LimitExceeded(_) => "limitExceeded",
+            Self::NotFound(_) => "notFound",
+            Self::Task(_) => "task",
+        }
+    }
+
+    /// Attributes a read error to `path`, treating bad data as malformed
+    /// input and passing limit errors raised inside readers through.
+    pub(crate) fn io(path: &Path, err: io::Error) -> Self {
+        if err.get_ref().is_some_and(|e| e.is::<LimitError>()) {
+            return Self::LimitExceeded(err.to_string());
+        }
+        match err.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => {
+                Self::Malformed(err.to_string())
+            }
+            io::ErrorKind::UnexpectedEof => Self::Malformed("unexpected end of file".into()),
+            _ => Self::Io {
+                path: path.to_path_buf(),
+                source: err,
+            },
+        }
+    }
+}
+
+impl_serialize_error!(ArchiveError);
+
+/// Raised inside readers so a limit hit mid-stream is not mistaken for bad
+/// data by the decompressor wrapping it.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct LimitError(String);
+
+fn limit_error(message: String) -> io::Error {
+    io::Error::other(LimitError(message))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Format {
+    Zip,
+    Rar,
+    SevenZip,
+    Tar,
+    TarGz,
+    TarXz,
+}
+
+const EXTENSIONS: &[(&str, Format)] = &[
+    (".zip", Format::Zip),
+    (".rar", Format::Rar),
+    (".7z", Format::SevenZip),
+    (".tar", Format::Tar),
+    (".tar.gz", Format::TarGz),
+    (".tgz", Format::TarGz),
+    (".tar.xz", Format::TarXz),
+    (".txz", Format::TarXz),
+];
+
+/// Whether `path` is named like an archive we can open.
+pub fn is_archive(path: &Path) -> bool {
+    format_from_name(path).is_some()
+}
+
+fn format_from_name(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    EXTENSIONS
+        .iter()
+        .filter(|(ext, _)| name.ends_with(ext))
+        .max_by_key(|(ext, _)| ext.len())
+        .map(|&(_, format)| format)
+}
+
+/// Identifies an archive by its signature, falling back to the file name
+/// for formats without one (plain tar with a pre-POSIX header).
+pub fn detect(path: &Path) -> Result<Format, ArchiveError> {
+    let mut head = [0u8; 512];
+    let mut file = File::open(path).map_err(|e| ArchiveError::io(path, e))?;
+    let mut len = 0;
+    while len < head.len() {
+        match file.read(&mut head[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) => return Err(ArchiveError::io(path, e)),
+        }
+    }
+    let head = &head[..len];
+    let format = if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        Some(Format::Zip)
+    } else if head.starts_with(b"Rar!\x1a\x07") {
+        Some(Format::Rar)
+    } else if head.starts_with(b"7z\xbc\xaf\x27\x1c") {
+        Some(Format::SevenZip)
+    } else if head.starts_with(b"\x1f\x8b") {
+        Some(Format::TarGz)
+    } else if head.starts_with(b"\xfd7zXZ\x00") {
+        Some(Format::TarXz)
+    } else if head.get(257..262) == Some(b"ustar") {
+        Some(Format::Tar)
+    } else {
+        format_from_name(path).filter(|f| *f == Format::Tar)
+    };
+    format.ok_or_else(|| ArchiveError::Unsupported(path.to_path_buf()))
+}
+
+/// Caps applied while extracting, across nested archives.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_entries: usize,
+    /// Largest single member written, nested archives included.
+    pub max_entry_bytes: u64,
+    /// Total bytes written for one top-level archive.
+    pub max_total_bytes: u64,
+    /// Uncompressed to compressed size, checked once a member or stream has
+    /// produced more than [`Limits::ratio_floor`] bytes.
+    pub max_ratio: u64,
+    pub ratio_floor: u64,
+    pub max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            max_entry_bytes: 256 * 1024 * 1024,
+            max_total_bytes: 1024 * 1024 * 1024,
+            max_ratio: 100,
+            ratio_floor: 1024 * 1024,
+            max_depth: 3,
+        }
+    }
+}
+
+/// One member as described by the archive's own headers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Member {
+    pub name: String,
+    /// Uncompressed size as declared; not trusted when extracting.
+    pub size: u64,
+    /// Stored size, for formats that record it per member.
+    pub compressed_size: Option<u64>,
+    pub is_dir: bool,
+    pub encrypted: bool,
+    /// Symlinks, hard links and device nodes, which are never extracted.
+    pub special: bool,
+}
+
+/// Receives members from a format reader. `read` is only called for members
+/// `wants` accepted, with the member's decompressed data, and `skip` for
+/// accepted members the reader cannot unpack.
+pub(crate) trait Visitor {
+    fn wants(&mut self, member: &Member) -> Result<bool, ArchiveError>;
+    fn read(&mut self, member: &Member, data: &mut dyn Read) -> Result<(), ArchiveError>;
+    fn skip(&mut self, member: &Member, reason: &str);
+}
+
+fn visit(
+    path: &Path,
+    format: Format,
+    limits: &Limits,
+    visitor: &mut dyn Visitor,
+) -> Result<(), ArchiveError> {
+    match format {
+        Format::Zip => zip::visit(path, visitor),
+        Format::Rar => rar::visit(path, limits, visitor),
+        Format::SevenZip => sevenz::visit(path, limits, visitor),
+        Format::Tar | Format::TarGz | Format::TarXz => tar::visit(path, format, limits, visitor),
+    }
+}
+
+/// Lists the members of the archive at `path`, without nested archives.
+pub fn list(path: &Path) -> Result<Vec<Member>, ArchiveError> {
+    struct Lister(Vec<Member>);
+    impl Visitor for Lister {
+        fn wants(&mut self, member: &Member) -> Result<bool, ArchiveError> {
+            self.0.push(member.clone());
+            Ok(false)
+        }
+        fn read(&mut self, _: &Member, _: &mut dyn Read) -> Result<(), ArchiveError> {
+            Ok(())
+        }
+        fn skip(&mut self, _: &Member, _: &str) {}
+    }
+    let mut lister = Lister(Vec::new());
+    visit(path, detect(path)?, &Limits::default(), &mut lister)?;
+    Ok(lister.0)
+}
+
+/// Counts bytes coming out of a decompressor and fails once they pass
+/// `limit`. Wraps whole streams (tar.gz) so members that are skipped rather
+/// than extracted still count.
+pub(crate) struct Counted<R> {
+    inner: R,
+    read: u64,
+    limit: u64,
+}
+
+impl<R: Read> Counted<R> {
+    pub(crate) fn new(inner: R, limit: u64) -> Self {
+        Self {
+            inner,
+            read: 0,
+            limit,
+        }
+    }
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if self.read > self.limit {
+            return Err(limit_error(format!(
+                "decompressed stream passed {} bytes",
+                self.limit
+            )));
+        }
+        Ok(n)
+    }
+}
+
+/// Highest number of bytes `compressed` bytes may expand to.
+pub(crate) fn ratio_limit(limits: &Limits, compressed: u64) -> u64 {
+    compressed
+        .saturating_mul(limits.max_ratio)
+        .max(limits.ratio_floor)
+}
+
+/// A member name turned into a relative path that stays inside the
+/// extraction directory, or `None` for anything that would escape it.
+fn safe_path(name: &str) -> Option<PathBuf> {
+    let name = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for part in name.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            // `C:` and other prefixes.
+            part if part.contains(':') => return None,
+            part => {
+                if Path::new(part)
+                    .components()
+                    .any(|c| !matches!(c, Component::Normal(_)))
+                {
+                    return None;
+                }
+                path.push(part);
+            }
+        }
+    }
+    (!name.starts_with('/') && path.components().next().is_some()).then_some(path)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedFile {
+    pub path: PathBuf,
+    /// Where it came from: `inner.rar/Movie.srt` for nested archives.
+    pub member: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedMember {
+    pub member: String,
+    pub reason: String,
+}
+
+/// A temporary directory of extracted subtitles, removed on drop.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extraction {
+    pub archive: PathBuf,
+    pub dir: PathBuf,
+    pub subtitles: Vec<ExtractedFile>,
+    pub skipped: Vec<SkippedMember>,
+}
+
+impl Drop for Extraction {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Creates a fresh directory under `parent`.
+fn unique_dir(parent: &Path) -> Result<PathBuf, ArchiveError> {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    fs::create_dir_all(parent).map_err(|source| ArchiveError::Write {
+        path: parent.to_path_buf(),
+        source,
+    })?;
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = parent.join(format!("{:x}-{nanos:x}-{n:x}", std::process::id()));
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(source) => return Err(ArchiveError::Write { path: dir, source }),
+        }
+    }
+}
+
+/// What an extraction has produced so far, shared across nesting levels.
+#[derive(Default)]
+struct Progress {
+    entries: usize,
+    written: u64,
+    subtitles: Vec<ExtractedFile>,
+    skipped: Vec<SkippedMember>,
+}
+
+/// Writes wanted members below `dir`, collecting nested archives.
+struct Extractor<'a> {
+    limits: &'a Limits,
+    dir: &'a Path,
+    /// Prefix for member names in reports, `outer.zip/` when nested.
+    prefix: &'a str,
+    depth: usize,
+    progress: &'a mut Progress,
+    /// Nested archives written out, unpacked once this archive is done.
+    nested: Vec<(PathBuf, String)>,
+}
+
+impl Visitor for Extractor<'_> {
+    fn wants(&mut self, member: &Member) -> Result<bool, ArchiveError> {
+        self.progress.entries += 1;
+        if self.progress.entries > self.limits.max_entries {
+            return Err(ArchiveError::LimitExceeded(format!(
+                "more than {} members",
+                self.limits.max_entries
+            )));
+        }
+        if member.is_dir {
+            return Ok(false);
+        }
+        let name = Path::new(&member.name);
+        let nested = is_archive(name);
+        if !is_subtitle(name) && !nested {
+            return Ok(false);
+        }
+        if member.special {
+            self.skip(member, "link or special file");
+            return Ok(false);
+        }
+        if member.encrypted {
+            self.skip(member, "encrypted");
+            return Ok(false);
+        }
+        if safe_path(&member.name).is_none() {
+            self.skip(member, "path escapes the archive");
+            return Ok(false);
+        }
+        if nested && self.depth >= self.limits.max_depth {
+            self.skip(member, "archive nested too deep");
+            return Ok(false);
+        }
+        if member.size > self.limits.max_entry_bytes {
+            return Err(ArchiveError::LimitExceeded(format!(
+                "{}{} declares {} bytes",
+                self.prefix, member.name, member.size
+            )));
+        }
+        Ok(true)
+    }
+
+    fn read(&mut self, member: &Member, data: &mut dyn Read) -> Result<(), ArchiveError> {
+        let relative = safe_path(&member.name).expect("checked in wants");
+        let path = self.dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| ArchiveError::Write {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        let mut cap = self.limits.max_entry_bytes;
+        if let Some(compressed) = member.compressed_size {
+            cap = cap.min(ratio_limit(self.limits, compressed));
+        }
+        cap = cap.min(
+            self.limits
+                .max_total_bytes
+                .saturating_sub(self.progress.written),
+        );
+
+        let write_error = |source| ArchiveError::Write {
+            path: path.clone(),
+            source,
+        };
+        let mut out = File::create(&path).map_err(write_error)?;
+        let mut buf = [0u8; 64 * 1024];
+        let mut total = 0u64;
+        loop {
+            let n = data
+                .read(&mut buf)
+                .map_err(|e| ArchiveError::io(&path, e))?;
+            if n == 0 {
+                break;
+            }
+            total += n as u64;
+            if total > cap {
+                drop(out);
+                let _ = fs::remove_file(&path);
+                return Err(ArchiveError::LimitExceeded(format!(
+                    "{}{} expands past {cap} bytes",
+                    self.prefix, member.name
+                )));
+            }
+            out.write_all(&buf[..n]).map_err(write_error)?;
+        }
+        self.progress.written += total;
+
+        let label = format!("{}{}", self.prefix, member.name);
+        if is_archive(&relative) {
+            self.nested.push((path, label));
+        } else {
+            self.progress.subtitles.push(ExtractedFile {
+                path,
+                member: label,
+            });
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, member: &Member, reason: &str) {
+        self.progress.skipped.push(SkippedMember {
+            member: format!("{}{}", self.prefix, member.name),
+            reason: reason.into(),
+        });
+    }
+}
+
+fn extract_into(
+    path: &Path,
+    dir: &Path,
+    prefix: &str,
+    depth: usize,
+    limits: &Limits,
+    progress: &mut Progress,
+) -> Result<(), ArchiveError> {
+    let mut extractor = Extractor {
+        limits,
+        dir,
+        prefix,
+        depth,
+        progress,
+        nested: Vec::new(),
+    };
+    visit(path, detect(path)?, limits, &mut extractor)?;
+    let nested = extractor.nested;
+    for (archive, label) in nested {
+        let inner = unique_dir(dir)?;
+        let result = extract_into(
+            &archive,
+            &inner,
+            &format!("{label}/"),
+            depth + 1,
+            limits,
+            progress,
+        );
+        let _ = fs::remove_file(&archive);
+        match result {
+            Ok(()) => {}
+            // A broken inner archive should not cost the rest of the pack.
+            Err(ArchiveError::Unsupported(_) | ArchiveError::Malformed(_)) => {
+                progress.skipped.push(SkippedMember {
+                    member: label,
+                    reason: "not a readable archive".into(),
+                })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the subtitles in the archive at `path` (and in archives inside
+/// it) into a new directory under `temp_root`.
+pub fn extract(path: &Path, temp_root: &Path, limits: &Limits) -> Result<Extraction, ArchiveError> {
+    let mut extraction = Extraction {
+        archive: path.to_path_buf(),
+        dir: unique_dir(temp_root)?,
+        subtitles: Vec::new(),
+        skipped: Vec::new(),
+    };
+    // On error the half-written directory goes with `extraction`.
+    let mut progress = Progress::default();
+    extract_into(path, &extraction.dir, "", 0, limits, &mut progress)?;
+    extraction.subtitles = progress.subtitles;
+    extraction.skipped = progress.skipped;
+    Ok(extraction)
+}
+
+/// Default parent for extraction directories outside the app.
+pub fn temp_root() -> PathBuf {
+    std::env::temp_dir().join("opensubtitles-uploader-archives")
+}
+
+/// Extractions the UI is still working with, by id.
+#[derive(Clone)]
+pub struct ArchiveStore {
+    root: PathBuf,
+    open: Arc<Mutex<HashMap<String, Extraction>>>,
+}
+
+impl ArchiveStore {
+    /// Uses `root` for extraction directories, clearing whatever an earlier
+    /// run left there.
+    pub fn new(root: PathBuf) -> Self {
+        let _ = fs::remove_dir_all(&root);
+        Self {
+            root,
+            open: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedArchive {
+    /// Pass to `archive_release` once the subtitles are no longer needed.
+    pub id: String,
+    pub dir: PathBuf,
+    pub subtitles: Vec<ExtractedFile>,
+    pub skipped: Vec<SkippedMember>,
+}
+
+#[tauri::command]
+pub async fn archive_list(path: PathBuf) -> Result<Vec<Member>, ArchiveError> {
+    tauri::async_runtime::spawn_blocking(move || list(&path))
+        .await
+        .map_err(|e| ArchiveError::Task(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn archive_extract(
+    store: tauri::State<'_, ArchiveStore>,
+    path: PathBuf,
+) -> Result<ExtractedArchive, ArchiveError> {
+    let root = store.root.clone();
+    let extraction =
+        tauri::async_runtime::spawn_blocking(move || extract(&path, &root, &Limits::default()))
+            .await
+            .map_err(|e| ArchiveError::Task(e.to_string()))??;
+    let id = extraction
+        .dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let result = ExtractedArchive {
+        id: id.clone(),
+        dir: extraction.dir.clone(),
+        subtitles: extraction.subtitles.clone(),
+        skipped: extraction.skipped.clone(),
+    };
+    store.open.lock().unwrap().insert(id, extraction);
+    Ok(result)
+}
+
+/// Deletes an extraction directory.
+#[tauri::command]
+pub fn archive_release(
+    store: tauri::State<'_, ArchiveStore>,
+    id: String,
+) -> Result<(), ArchiveError> {
+    store
+        .open
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .map(drop)
+        .ok_or(ArchiveError::NotFound(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn safe_path_keeps_members_inside() {
+        let cases = [
+            ("Movie.srt", Some("Movie.srt")),
+            ("Subs/2_English.srt", Some("Subs/2_English.srt")),
+            ("Subs\\2_English.srt", Some("Subs/2_English.srt")),
+            ("./Subs//./x.srt", Some("Subs/x.srt")),
+            ("../x.srt", None),
+            ("Subs/../../x.srt", None),
+            ("..\\x.srt", None),
+            ("Subs\\..\\..\\x.srt", None),
+            ("/etc/x.srt", None),
+            ("\\x.srt", None),
+            ("\\\\server\\share\\x.srt", None),
+            ("C:\\x.srt", None),
+            ("C:x.srt", None),
+            ("Subs/c:/x.srt", None),
+            ("", None),
+            ("./", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(safe_path(name), expected.map(PathBuf::from), "{name:?}");
+        }
+    }
+
+    fn member(name: &str, size: u64, compressed_size: Option<u64>) -> Member {
+        Member {
+            name: name.into(),
+            size,
+            compressed_size,
+            is_dir: false,
+            encrypted: false,
+            special: false,
+        }
+    }
+
+    /// Runs `member` with `data` through an extractor writing to a fresh
+    /// directory, which is returned for inspection.
+    fn extract_member(
+        name: &str,
+        limits: &Limits,
+        member: &Member,
+        data: &[u8],
+    ) -> (PathBuf, Result<Progress, ArchiveError>) {
+        let dir = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut progress = Progress::default();
+        let mut extractor = Extractor {
+            limits,
+            dir: &dir,
+            prefix: "",
+            depth: 0,
+            progress: &mut progress,
+            nested: Vec::new(),
+        };
+        let result = extractor
+            .wants(member)
+            .and_then(|wanted| {
+                assert!(wanted);
+                extractor.read(member, &mut Cursor::new(data))
+            })
+            .map(|()| progress);
+        (dir, result)
+    }
+
+    #[test]
+    fn extracts_a_member_within_limits() {
+        let (dir, result) = extract_member(
+            "archive-within",
+            &Limits::default(),
+            &member("Subs/Movie.srt", 5, Some(5)),
+            b"Hello",
+        );
+        let progress = result.unwrap();
+        assert_eq!(progress.written, 5);
+        assert_eq!(progress.subtitles[0].member, "Subs/Movie.srt");
+        assert_eq!(fs::read(dir.join("Subs/Movie.srt")).unwrap(), b"Hello");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_member_declared_over_the_size_limit() {
+        let limits = Limits {
+            max_entry_bytes: 8,
+            ..Limits::default()
+        };
+        let (dir, result) =
+            extract_member("archive-declared", &limits, &member("x.srt", 9, None), b"");
+        assert!(
+            matches!(result, Err(ArchiveError::LimitExceeded(_))),
+            "{:?}",
+            result.map(|p| p.written)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stops_a_member_that_grows_past_the_size_limit() {
+        let limits = Limits {
+            max_entry_bytes: 8,
+            ..Limits::default()
+        };
+        // Declared small, as a hostile header would.
+        let (dir, result) = extract_member(
+            "archive-oversized",
+            &limits,
+            &member("x.srt", 1, None),
+            &[b'a'; 9],
+        );
+        match result {
+            Err(ArchiveError::LimitExceeded(message)) => {
+                assert_eq!(message, "x.srt expands past 8 bytes")
+            }
+            other => panic!("{:?}", other.map(|p| p.written)),
+        }
+        assert!(!dir.join("x.srt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stops_a_member_past_the_compression_ratio() {
+        let limits = Limits {
+            max_ratio: 10,
+            ratio_floor: 0,
+            ..Limits::default()
+        };
+        let (_, result) = extract_member(
+            "archive-ratio",
+            &limits,
+            &member("x.srt", 1, Some(2)),
+            &[0; 21],
+        );
+        assert!(
+            matches!(result, Err(ArchiveError::LimitExceeded(_))),
+            "{:?}",
+            result.map(|p| p.written)
+        );
+        // Exactly at the ratio is fine.
+        let (dir, result) = extract_member(
+            "archive-ratio",
+            &limits,
+            &member("x.srt", 1, Some(2)),
+            &[0; 20],
+        );
+        assert_eq!(result.unwrap().written, 20);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn counted_streams_fail_as_limit_errors() {
+        let mut counted = Counted::new(Cursor::new([0u8; 16]), 15);
+        let err = io::copy(&mut counted, &mut io::sink()).unwrap_err();
+        assert!(matches!(
+            ArchiveError::io(Path::new("x"), err),
+            ArchiveError::LimitExceeded(_)
+        ));
+
+        let mut counted = Counted::new(Cursor::new([0u8; 16]), 16);
+        assert_eq!(io::copy(&mut counted, &mut io::sink()).unwrap(), 16);
+    }
+}
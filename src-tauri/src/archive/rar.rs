@@ -0,0 +1,352 @@
+//! RAR 4 and 5 through the bundled UnRAR library's C interface. Headers give
+//! each member's packed size, so the ratio limit applies as for ZIP. UnRAR
+//! hands unpacked data to a callback in chunks; a worker thread runs the
+//! unpacking and passes the chunks on as they come, so members are counted
+//! and capped while they are unpacked rather than after.
+
+use super::{ratio_limit, ArchiveError, Counted, Limits, Member, Visitor};
+use std::ffi::c_int;
+use std::io::{self, Read};
+use std::path::Path;
+use std::ptr::{self, NonNull};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use unrar_sys as native;
+
+/// Unpacked chunks queued between UnRAR and the visitor.
+const QUEUED_CHUNKS: usize = 4;
+
+/// A chunk of unpacked data, or the error code unpacking ended with.
+type Chunk = Result<Vec<u8>, c_int>;
+
+fn describe(code: c_int) -> &'static str {
+    match code {
+        native::ERAR_NO_MEMORY => "out of memory",
+        native::ERAR_BAD_DATA => "corrupt data",
+        native::ERAR_BAD_ARCHIVE => "not a RAR archive",
+        native::ERAR_UNKNOWN_FORMAT => "unknown format",
+        native::ERAR_EOPEN => "could not open a volume",
+        native::ERAR_EREAD => "read error",
+        native::ERAR_MISSING_PASSWORD | native::ERAR_BAD_PASSWORD => "encrypted headers",
+        _ => "unpacking failed",
+    }
+}
+
+fn malformed(code: c_int) -> ArchiveError {
+    ArchiveError::Malformed(format!("rar: {}", describe(code)))
+}
+
+/// Member name from the wide-character header field.
+fn name(wide: &[native::WCHAR]) -> String {
+    let units = wide.iter().take_while(|&&c| c != 0);
+    #[cfg(windows)]
+    let name = char::decode_utf16(units.map(|&c| c as u16))
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    #[cfg(not(windows))]
+    let name = units
+        .map(|&c| char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    name
+}
+
+fn size(low: u32, high: u32) -> u64 {
+    (high as u64) << 32 | low as u64
+}
+
+/// Receives UnRAR's messages. `user_data` is the sender of the member being
+/// unpacked, or 0 while reading headers.
+extern "C" fn callback(
+    message: native::UINT,
+    user_data: native::LPARAM,
+    p1: native::LPARAM,
+    p2: native::LPARAM,
+) -> c_int {
+    match message {
+        native::UCM_PROCESSDATA if user_data != 0 => {
+            // SAFETY: `Lent::test` registers a sender that outlives the
+            // `RARProcessFile` call this message comes from, and p1 and p2
+            // describe UnRAR's buffer for the duration of the message.
+            let (sender, data) = unsafe {
+                (
+                    &*(user_data as *const SyncSender<Chunk>),
+                    std::slice::from_raw_parts(p1 as *const u8, p2 as usize),
+                )
+            };
+            // Stops unpacking once the visitor is gone.
+            match sender.send(Ok(data.to_vec())) {
+                Ok(()) => 0,
+                Err(_) => -1,
+            }
+        }
+        native::UCM_CHANGEVOLUME | native::UCM_CHANGEVOLUMEW if p2 == native::RAR_VOL_ASK => -1,
+        native::UCM_NEEDPASSWORD | native::UCM_NEEDPASSWORDW => -1,
+        _ => 0,
+    }
+}
+
+/// An archive opened for unpacking, closed on drop.
+struct Archive(NonNull<native::Handle>);
+
+impl Archive {
+    fn open(path: &Path) -> Result<Self, ArchiveError> {
+        let unsupported = || ArchiveError::Unsupported(path.to_path_buf());
+        #[cfg(any(target_os = "linux", target_os = "netbsd"))]
+        let name = std::ffi::CString::new(path.as_os_str().as_encoded_bytes())
+            .map_err(|_| unsupported())?;
+        #[cfg(windows)]
+        let name: Vec<native::WCHAR> = {
+            use std::os::windows::ffi::OsStrExt;
+            path.as_os_str().encode_wide().chain([0]).collect()
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "netbsd", windows)))]
+        let name: Vec<native::WCHAR> = path
+            .to_str()
+            .ok_or_else(unsupported)?
+            .chars()
+            .map(|c| c as native::WCHAR)
+            .chain([0])
+            .collect();
+        let mut data = native::OpenArchiveDataEx::new(name.as_ptr().cast(), native::RAR_OM_EXTRACT);
+        data.callback = Some(callback);
+        // SAFETY: `data` and the name it points to outlive the call.
+        let handle = unsafe { native::RAROpenArchiveEx(&data) };
+        let archive = NonNull::new(handle.cast_mut()).map(Self);
+        match archive {
+            Some(archive) if data.open_result == 0 => Ok(archive),
+            _ => Err(malformed(data.open_result as c_int)),
+        }
+    }
+
+    /// The next member's header, or `None` at the end of the archive.
+    fn next(&mut self) -> Result<Option<Box<native::HeaderDataEx>>, ArchiveError> {
+        let mut header = Box::<native::HeaderDataEx>::default();
+        // SAFETY: the handle is open and `header` is writable; the binding
+        // declares it `*const`, but UnRAR fills it in.
+        match unsafe { native::RARReadHeaderEx(self.0.as_ptr(), &raw mut *header) } {
+            native::ERAR_SUCCESS => Ok(Some(header)),
+            native::ERAR_END_ARCHIVE => Ok(None),
+            code => Err(malformed(code)),
+        }
+    }
+
+    /// Moves past the member whose header was just read.
+    fn skip(&mut self) -> Result<(), ArchiveError> {
+        // SAFETY: the handle is open and a header was just read.
+        match unsafe {
+            native::RARProcessFile(self.0.as_ptr(), native::RAR_SKIP, ptr::null(), ptr::null())
+        } {
+            native::ERAR_SUCCESS => Ok(()),
+            code => Err(malformed(code)),
+        }
+    }
+
+    /// Unpacks the member whose header was just read on a worker thread,
+    /// handing `read` its data as it comes. Whatever `read` leaves unread is
+    /// thrown away, and unpacking stops early once it returns.
+    fn unpack<T>(&mut self, read: impl FnOnce(&mut dyn Read) -> T) -> T {
+        let lent = Lent(self.0);
+        let (sender, receiver) = mpsc::sync_channel(QUEUED_CHUNKS);
+        std::thread::scope(|scope| {
+            scope.spawn(move || lent.test(sender));
+            let mut chunks = Chunks {
+                receiver,
+                chunk: Vec::new(),
+                at: 0,
+            };
+            read(&mut chunks)
+        })
+    }
+}
+
+impl Drop for Archive {
+    fn drop(&mut self) {
+        // SAFETY: the handle is open and not used again.
+        unsafe { native::RARCloseArchive(self.0.as_ptr()) };
+    }
+}
+
+/// The handle lent to the unpacking thread.
+struct Lent(NonNull<native::Handle>);
+
+// SAFETY: UnRAR handles are not tied to a thread, and `Archive::unpack`
+// waits for the worker before the handle is used again.
+unsafe impl Send for Lent {}
+
+impl Lent {
+    fn test(self, sender: SyncSender<Chunk>) {
+        let handle = self.0.as_ptr();
+        // SAFETY: the handle is open and a header was just read; `sender`
+        // outlives the call, and the callback is reset before it is dropped.
+        let code = unsafe {
+            native::RARSetCallback(
+                handle,
+                Some(callback),
+                &sender as *const _ as native::LPARAM,
+            );
+            let code = native::RARProcessFile(handle, native::RAR_TEST, ptr::null(), ptr::null());
+            native::RARSetCallback(handle, Some(callback), 0);
+            code
+        };
+        if code != native::ERAR_SUCCESS {
+            let _ = sender.send(Err(code));
+        }
+    }
+}
+
+/// Reads the chunks the worker sends, failing with the code unpacking
+/// ended with.
+struct Chunks {
+    receiver: Receiver<Chunk>,
+    chunk: Vec<u8>,
+    at: usize,
+}
+
+impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.at == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.at = 0;
+                }
+                Ok(Err(code)) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("rar: {}", describe(code)),
+                    ))
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.at);
+        buf[..n].copy_from_slice(&self.chunk[self.at..self.at + n]);
+        self.at += n;
+        Ok(n)
+    }
+}
+
+pub(super) fn visit(
+    path: &Path,
+    limits: &Limits,
+    visitor: &mut dyn Visitor,
+) -> Result<(), ArchiveError> {
+    let compressed = std::fs::metadata(path)
+        .map_err(|e| ArchiveError::io(path, e))?
+        .len();
+    // Solid archives unpack skipped members too, but only wanted ones come
+    // out; the budget covers what is handed to the visitor.
+    let mut budget = ratio_limit(limits, compressed).min(limits.max_total_bytes.saturating_mul(2));
+    let mut archive = Archive::open(path)?;
+    while let Some(header) = archive.next()? {
+        let member = Member {
+            name: name(&header.filename_w),
+            size: size(header.unp_size, header.unp_size_high),
+            compressed_size: Some(size(header.pack_size, header.pack_size_high)),
+            is_dir: header.flags & native::RHDF_DIRECTORY != 0,
+            encrypted: header.flags & native::RHDF_ENCRYPTED != 0,
+            special: false,
+        };
+        if !visitor.wants(&member)? {
+            archive.skip()?;
+            continue;
+        }
+        archive.unpack(|data| {
+            let mut data = Counted::new(data, budget);
+            let read = visitor.read(&member, &mut data).and_then(|()| {
+                io::copy(&mut data, &mut io::sink())
+                    .map(drop)
+                    .map_err(|e| ArchiveError::io(path, e))
+            });
+            budget = budget.saturating_sub(data.read);
+            read
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::{extract, list, ArchiveError, Limits};
+    use std::path::PathBuf;
+
+    /// A RAR 4 block: the header CRC is the low half of the CRC-32 of
+    /// everything after it.
+    fn block(kind: u8, flags: u16, fields: &[u8]) -> Vec<u8> {
+        let mut header = vec![kind];
+        header.extend_from_slice(&flags.to_le_bytes());
+        header.extend_from_slice(&(fields.len() as u16 + 7).to_le_bytes());
+        header.extend_from_slice(fields);
+        let mut crc = flate2::Crc::new();
+        crc.update(&header);
+        let mut out = (crc.sum() as u16).to_le_bytes().to_vec();
+        out.extend_from_slice(&header);
+        out
+    }
+
+    /// A RAR 4 archive of stored members.
+    fn rar(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = b"Rar!\x1a\x07\x00".to_vec();
+        out.extend_from_slice(&block(0x73, 0, &[0; 6]));
+        for &(name, data) in members {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let mut fields = Vec::new();
+            fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            fields.push(0);
+            fields.extend_from_slice(&crc.sum().to_le_bytes());
+            fields.extend_from_slice(&0x5a21_0000u32.to_le_bytes());
+            fields.extend_from_slice(&[29, 0x30]);
+            fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fields.extend_from_slice(&0x20u32.to_le_bytes());
+            fields.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&block(0x74, 0x8000, &fields));
+            out.extend_from_slice(data);
+        }
+        out.extend_from_slice(&block(0x7b, 0x4000, &[]));
+        out
+    }
+
+    const SRT: &[u8] = b"1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+    fn write(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pack.rar");
+        std::fs::write(&path, rar(&[("readme.txt", b"hi"), ("Movie.srt", SRT)])).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn streams_members() {
+        let (dir, path) = write("rar-stream");
+        let members = list(&path).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[1].name, "Movie.srt");
+        assert_eq!(members[1].size, SRT.len() as u64);
+        assert_eq!(members[1].compressed_size, Some(SRT.len() as u64));
+
+        let extraction = extract(&path, &dir, &Limits::default()).unwrap();
+        assert_eq!(extraction.subtitles.len(), 1);
+        assert_eq!(extraction.subtitles[0].member, "Movie.srt");
+        assert_eq!(std::fs::read(&extraction.subtitles[0].path).unwrap(), SRT);
+        drop(extraction);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn applies_the_ratio_limit() {
+        let (dir, path) = write("rar-ratio");
+        let limits = Limits {
+            max_ratio: 0,
+            ratio_floor: 8,
+            ..Limits::default()
+        };
+        let result = extract(&path, &dir, &limits);
+        assert!(
+            matches!(result, Err(ArchiveError::LimitExceeded(_))),
+            "{result:?}"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+//! 7z through `sevenz-rust`. Members in a solid block can only be reached
+//! by decompressing the ones before them, so unwanted members are drained
+//! under the same stream limit as tarballs.
+
+use super::{ratio_limit, ArchiveError, Counted, Limits, Member, Visitor};
+use sevenz_rust::{Password, SevenZReader};
+use std::io;
+use std::path::Path;
+
+fn malformed(err: sevenz_rust::Error) -> ArchiveError {
+    ArchiveError::Malformed(format!("7z: {err}"))
+}
+
+pub(super) fn visit(
+    path: &Path,
+    limits: &Limits,
+    visitor: &mut dyn Visitor,
+) -> Result<(), ArchiveError> {
+    let compressed = std::fs::metadata(path)
+        .map_err(|e| ArchiveError::io(path, e))?
+        .len();
+    let mut budget = ratio_limit(limits, compressed).min(limits.max_total_bytes.saturating_mul(2));
+    let mut reader = SevenZReader::open(path, Password::empty()).map_err(malformed)?;
+    // The callback can only fail with the library's error type, so ours is
+    // kept aside and iteration stopped.
+    let mut failure = None;
+    let result = reader.for_each_entries(|entry, data| {
+        let member = Member {
+            name: entry.name().to_string(),
+            size: entry.size(),
+            compressed_size: None,
+            is_dir: entry.is_directory(),
+            encrypted: false,
+            special: entry.is_anti_item,
+        };
+        let mut data = Counted::new(data, budget);
+        let outcome = visitor.wants(&member).and_then(|wanted| {
+            if wanted {
+                visitor.read(&member, &mut data)?;
+            }
+            io::copy(&mut data, &mut io::sink()).map_err(|e| ArchiveError::io(path, e))?;
+            Ok(())
+        });
+        budget = budget.saturating_sub(data.read);
+        match outcome {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                failure = Some(e);
+                Ok(false)
+            }
+        }
+    });
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    result.map_err(malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::{extract, list, ArchiveError, Limits};
+    use sevenz_rust::{SevenZArchiveEntry, SevenZWriter};
+    use std::path::PathBuf;
+
+    const SRT: &[u8] = b"1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+    fn write(name: &str, members: &[(&str, &[u8])]) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pack.7z");
+        let mut writer = SevenZWriter::create(&path).unwrap();
+        for &(name, data) in members {
+            let mut entry = SevenZArchiveEntry::new();
+            entry.name = name.into();
+            writer.push_archive_entry(entry, Some(data)).unwrap();
+        }
+        writer.finish().unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn extracts_subtitles() {
+        let (dir, path) = write(
+            "7z-extract",
+            &[
+                ("readme.txt", b"hi"),
+                ("Subs/2_English.srt", SRT),
+                ("../escape.srt", SRT),
+            ],
+        );
+        let members = list(&path).unwrap();
+        assert_eq!(members.len(), 3);
+        assert_eq!(members[1].name, "Subs/2_English.srt");
+        assert_eq!(members[1].size, SRT.len() as u64);
+
+        let extraction = extract(&path, &dir, &Limits::default()).unwrap();
+        assert_eq!(extraction.subtitles.len(), 1);
+        assert_eq!(extraction.subtitles[0].member, "Subs/2_English.srt");
+        assert_eq!(std::fs::read(&extraction.subtitles[0].path).unwrap(), SRT);
+        assert_eq!(extraction.skipped.len(), 1);
+        assert_eq!(extraction.skipped[0].member, "../escape.srt");
+        drop(extraction);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn counts_skipped_members_against_the_stream_limit() {
+        let (dir, path) = write(
+            "7z-bomb",
+            &[("padding.bin", &[0; 512 * 1024]), ("Movie.srt", SRT)],
+        );
+        let limits = Limits {
+            ratio_floor: 64 * 1024,
+            ..Limits::default()
+        };
+        let result = extract(&path, &dir, &limits);
+        assert!(
+            matches!(result, Err(ArchiveError::LimitExceeded(_))),
+            "{result:?}"
+        );
+        assert!(extract(&path, &dir, &Limits::default()).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+//! Plain, gzip and xz tarballs. The compressed stream is counted as a whole
+//! because tar members can only be skipped by decompressing them.
+
+use super::{ratio_limit, ArchiveError, Counted, Format, Limits, Member, Visitor};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use tar::EntryType;
+use xz2::read::XzDecoder;
+
+pub(super) fn visit(
+    path: &Path,
+    format: Format,
+    limits: &Limits,
+    visitor: &mut dyn Visitor,
+) -> Result<(), ArchiveError> {
+    let file = File::open(path).map_err(|e| ArchiveError::io(path, e))?;
+    let compressed = file
+        .metadata()
+        .map_err(|e| ArchiveError::io(path, e))?
+        .len();
+    let file = BufReader::new(file);
+    let stream: Box<dyn Read> = match format {
+        Format::TarGz => Box::new(GzDecoder::new(file)),
+        Format::TarXz => Box::new(XzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    let limit = ratio_limit(limits, compressed).min(limits.max_total_bytes.saturating_mul(2));
+    let mut archive = tar::Archive::new(Counted::new(stream, limit));
+    for entry in archive.entries().map_err(|e| ArchiveError::io(path, e))? {
+        let mut entry = entry.map_err(|e| ArchiveError::io(path, e))?;
+        let kind = entry.header().entry_type();
+        let member = Member {
+            name: String::from_utf8_lossy(&entry.path_bytes()).into_owned(),
+            size: entry.size(),
+            compressed_size: None,
+            is_dir: kind.is_dir(),
+            encrypted: false,
+            special: !matches!(
+                kind,
+                EntryType::Regular | EntryType::Continuous | EntryType::Directory
+            ),
+        };
+        if visitor.wants(&member)? {
+            visitor.read(&member, &mut entry)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::{extract, list, ArchiveError, Format, Limits};
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use tar::{EntryType, Header};
+    use xz2::write::XzEncoder;
+
+    const SRT: &[u8] = b"1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+    /// A tarball of `members`, with names written raw so escaping ones
+    /// survive the builder's own checks.
+    fn tarball(members: &[(&str, EntryType, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for &(name, kind, data) in members {
+            let mut header = Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(kind);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            if kind == EntryType::Symlink {
+                header.set_link_name("/etc/passwd").unwrap();
+            }
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn compress(format: Format, tar: &[u8]) -> Vec<u8> {
+        match format {
+            Format::TarGz => {
+                let mut out = GzEncoder::new(Vec::new(), flate2::Compression::best());
+                out.write_all(tar).unwrap();
+                out.finish().unwrap()
+            }
+            Format::TarXz => {
+                let mut out = XzEncoder::new(Vec::new(), 6);
+                out.write_all(tar).unwrap();
+                out.finish().unwrap()
+            }
+            _ => tar.to_vec(),
+        }
+    }
+
+    fn write(name: &str, file: &str, data: &[u8]) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(file);
+        std::fs::write(&path, data).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn extracts_subtitles_from_every_flavour() {
+        let tar = tarball(&[
+            ("Subs", EntryType::Directory, b""),
+            ("Subs/2_English.srt", EntryType::Regular, SRT),
+            ("readme.txt", EntryType::Regular, b"hi"),
+            ("../escape.srt", EntryType::Regular, SRT),
+            ("link.srt", EntryType::Symlink, b""),
+        ]);
+        for (format, file) in [
+            (Format::Tar, "pack.tar"),
+            (Format::TarGz, "pack.tar.gz"),
+            (Format::TarXz, "pack.txz"),
+        ] {
+            let (dir, path) = write("tar-flavours", file, &compress(format, &tar));
+            assert_eq!(list(&path).unwrap().len(), 5, "{file}");
+
+            let extraction = extract(&path, &dir, &Limits::default()).unwrap();
+            assert_eq!(extraction.subtitles.len(), 1, "{file}");
+            assert_eq!(extraction.subtitles[0].member, "Subs/2_English.srt");
+            assert_eq!(std::fs::read(&extraction.subtitles[0].path).unwrap(), SRT);
+            let skipped: Vec<_> = extraction
+                .skipped
+                .iter()
+                .map(|s| (s.member.as_str(), s.reason.as_str()))
+                .collect();
+            assert_eq!(
+                skipped,
+                [
+                    ("../escape.srt", "path escapes the archive"),
+                    ("link.srt", "link or special file"),
+                ],
+                "{file}"
+            );
+            drop(extraction);
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn counts_skipped_members_against_the_stream_limit() {
+        // Only the padding is ever read, and it still counts.
+        let tar = tarball(&[
+            ("padding.bin", EntryType::Regular, &[0; 512 * 1024]),
+            ("Movie.srt", EntryType::Regular, SRT),
+        ]);
+        let (dir, path) = write("tar-bomb", "pack.tar.gz", &compress(Format::TarGz, &tar));
+        let limits = Limits {
+            ratio_floor: 64 * 1024,
+            ..Limits::default()
+        };
+        let result = extract(&path, &dir, &limits);
+        assert!(
+            matches!(result, Err(ArchiveError::LimitExceeded(_))),
+            "{result:?}"
+        );
+        assert!(extract(&path, &dir, &Limits::default()).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+//! ZIP reading from the central directory, including zip64. Stored and
+//! deflated members are supported, which covers every subtitle pack we
+//! have seen; members using anything else are skipped and reported.
+
+use super::{ArchiveError, Member, Visitor};
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_END_OF_DIRECTORY: u32 = 0x0606_4b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const FLAG_ENCRYPTED: u16 = 1;
+const FLAG_UTF8: u16 = 1 << 11;
+
+/// End-of-directory record plus the largest comment it may be followed by.
+const MAX_TAIL: u64 = 22 + u16::MAX as u64;
+
+struct Entry {
+    member: Member,
+    method: u16,
+    crc: u32,
+    offset: u64,
+}
+
+fn malformed(message: &str) -> ArchiveError {
+    ArchiveError::Malformed(format!("zip: {message}"))
+}
+
+fn u16_at(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([buf[at], buf[at + 1]])
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn u64_at(buf: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+fn read_at(file: &mut File, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)
+}
+
+/// Finds the central directory: `(offset, size, entries)`.
+fn directory(file: &mut File) -> Result<(u64, u64, u64), io::Error> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(MAX_TAIL);
+    let mut tail = vec![0u8; tail_len as usize];
+    read_at(file, len - tail_len, &mut tail)?;
+    let at = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(&tail, i) == END_OF_DIRECTORY)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no end of central directory"))?;
+    let record = &tail[at..];
+    let mut entries = u16_at(record, 10) as u64;
+    let mut size = u32_at(record, 12) as u64;
+    let mut offset = u32_at(record, 16) as u64;
+
+    let eocd = len - tail_len + at as u64;
+    if eocd >= 20 {
+        let mut locator = [0u8; 20];
+        read_at(file, eocd - 20, &mut locator)?;
+        if u32_at(&locator, 0) == ZIP64_LOCATOR {
+            let mut record = [0u8; 56];
+            read_at(file, u64_at(&locator, 8), &mut record)?;
+            if u32_at(&record, 0) != ZIP64_END_OF_DIRECTORY {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "bad zip64 end of central directory",
+                ));
+            }
+            entries = u64_at(&record, 32);
+            size = u64_at(&record, 40);
+            offset = u64_at(&record, 48);
+        }
+    }
+    if offset.saturating_add(size) > len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "central directory past end of file",
+        ));
+    }
+    Ok((offset, size, entries))
+}
+
+/// Replaces 0xFFFFFFFF sizes and offsets with their zip64 extra values,
+/// which appear in that order and only for the fields that overflowed.
+fn apply_zip64(extra: &[u8], size: &mut u64, compressed: &mut u64, offset: &mut u64) {
+    let mut at = 0;
+    while at + 4 <= extra.len() {
+        let id = u16_at(extra, at);
+        let len = u16_at(extra, at + 2) as usize;
+        let data = &extra[(at + 4).min(extra.len())..(at + 4 + len).min(extra.len())];
+        if id == 0x0001 {
+            let mut fields = data.chunks_exact(8).map(|c| u64_at(c, 0));
+            for field in [size, compressed, offset] {
+                if *field == u32::MAX as u64 {
+                    match fields.next() {
+                        Some(value) => *field = value,
+                        None => break,
+                    }
+                }
+            }
+            return;
+        }
+        at += 4 + len;
+    }
+}
+
+fn entries(file: &mut File) -> Result<Vec<Entry>, ArchiveError> {
+    let (offset, size, count) =
+        directory(file).map_err(|e| ArchiveError::Malformed(format!("zip: {e}")))?;
+    let mut directory = vec![0u8; size as usize];
+    read_at(file, offset, &mut directory).map_err(|_| malformed("truncated central directory"))?;
+
+    let mut entries = Vec::new();
+    let mut at = 0;
+    while at + 46 <= directory.len() && u32_at(&directory, at) == CENTRAL_HEADER {
+        let header = &directory[at..];
+        let flags = u16_at(header, 8);
+        let method = u16_at(header, 10);
+        let crc = u32_at(header, 16);
+        let mut compressed = u32_at(header, 20) as u64;
+        let mut size = u32_at(header, 24) as u64;
+        let name_len = u16_at(header, 28) as usize;
+        let extra_len = u16_at(header, 30) as usize;
+        let comment_len = u16_at(header, 32) as usize;
+        let external = u32_at(header, 38);
+        let mut offset = u32_at(header, 42) as u64;
+        let end = 46 + name_len + extra_len;
+        if end > header.len() {
+            return Err(malformed("truncated central directory entry"));
+        }
+        let raw = &header[46..46 + name_len];
+        // Names without the UTF-8 flag are CP437 in theory; in practice they
+        // are whatever the packer's locale was, so take them as UTF-8 when
+        // they decode and byte-for-byte otherwise.
+        let name = match std::str::from_utf8(raw) {
+            Ok(name) => name.to_string(),
+            Err(_) if flags & FLAG_UTF8 == 0 => raw.iter().map(|&b| b as char).collect(),
+            Err(_) => return Err(malformed("invalid UTF-8 member name")),
+        };
+        apply_zip64(
+            &header[46 + name_len..end],
+            &mut size,
+            &mut compressed,
+            &mut offset,
+        );
+        // Unix mode in the high half of the external attributes.
+        let mode = external >> 16;
+        let special =
+            mode & 0o170000 != 0 && mode & 0o170000 != 0o100000 && mode & 0o170000 != 0o040000;
+        entries.push(Entry {
+            member: Member {
+                is_dir: name.ends_with('/') || name.ends_with('\\'),
+                name,
+                size,
+                compressed_size: Some(compressed),
+                encrypted: flags & FLAG_ENCRYPTED != 0,
+                special,
+            },
+            method,
+            crc,
+            offset,
+        });
+        at += end + comment_len;
+    }
+    if (entries.len() as u64) < count.min(u16::MAX as u64) {
+        return Err(malformed(
+            "central directory lists fewer entries than declared",
+        ));
+    }
+    Ok(entries)
+}
+
+/// Checks the CRC once the member has been read to the end.
+struct Checked<R> {
+    inner: R,
+    crc: Crc,
+    expected: u32,
+}
+
+impl<R: Read> Read for Checked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 && !buf.is_empty() && self.crc.sum() != self.expected {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "CRC mismatch"));
+        }
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+pub(super) fn visit(path: &Path, visitor: &mut dyn Visitor) -> Result<(), ArchiveError> {
+    let mut file = File::open(path).map_err(|e| ArchiveError::io(path, e))?;
+    for entry in entries(&mut file)? {
+        if !visitor.wants(&entry.member)? {
+            continue;
+        }
+        let mut local = [0u8; 30];
+        read_at(&mut file, entry.offset, &mut local).map_err(|e| ArchiveError::io(path, e))?;
+        if u32_at(&local, 0) != LOCAL_HEADER {
+            return Err(malformed("bad local header"));
+        }
+        let start = entry.offset + 30 + u16_at(&local, 26) as u64 + u16_at(&local, 28) as u64;
+        file.seek(SeekFrom::Start(start))
+            .map_err(|e| ArchiveError::io(path, e))?;
+        let compressed = entry.member.compressed_size.unwrap_or_default();
+        let raw = BufReader::new((&mut file).take(compressed));
+        let data: Box<dyn Read + '_> = match entry.method {
+            STORED => Box::new(raw),
+            DEFLATED => Box::new(DeflateDecoder::new(raw)),
+            method => {
+                visitor.skip(
+                    &entry.member,
+                    &format!("unsupported compression method {method}"),
+                );
+                continue;
+            }
+        };
+        let mut checked = Checked {
+            inner: data,
+            crc: Crc::new(),
+            expected: entry.crc,
+        };
+        visitor.read(&entry.member, &mut checked)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::{extract, Limits};
+
+    /// A ZIP of stored members, each with the method it claims.
+    fn zip(members: &[(&str, u16, &[u8])]) -> Vec<u8> {
+        let (mut out, mut directory) = (Vec::new(), Vec::new());
+        for &(name, method, data) in members {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let mut common = Vec::new();
+            common.extend_from_slice(&20u16.to_le_bytes());
+            common.extend_from_slice(&0u16.to_le_bytes());
+            common.extend_from_slice(&method.to_le_bytes());
+            common.extend_from_slice(&[0; 4]);
+            common.extend_from_slice(&crc.sum().to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            common.extend_from_slice(&0u16.to_le_bytes());
+
+            directory.extend_from_slice(&super::CENTRAL_HEADER.to_le_bytes());
+            directory.extend_from_slice(&20u16.to_le_bytes());
+            directory.extend_from_slice(&common);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&(out.len() as u32).to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+
+            out.extend_from_slice(&super::LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&common);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+        }
+        let offset = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&super::END_OF_DIRECTORY.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&[0; 2]);
+        out
+    }
+
+    #[test]
+    fn skips_unsupported_methods() {
+        let srt = b"1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+        let dir = std::env::temp_dir().join(format!("{}-zip-methods", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pack.zip");
+        std::fs::write(&path, zip(&[("a.srt", 14, srt), ("b.srt", 0, srt)])).unwrap();
+
+        let extraction = extract(&path, &dir, &Limits::default()).unwrap();
+        assert_eq!(extraction.subtitles.len(), 1);
+        assert_eq!(extraction.subtitles[0].member, "b.srt");
+        assert_eq!(std::fs::read(&extraction.subtitles[0].path).unwrap(), srt);
+        assert_eq!(extraction.skipped.len(), 1);
+        assert_eq!(extraction.skipped[0].member, "a.srt");
+        assert_eq!(
+            extraction.skipped[0].reason,
+            "unsupported compression method 14"
+        );
+        drop(extraction);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
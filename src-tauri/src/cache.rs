@@ -0,0 +1,515 @@
+//! Persistent cache for API responses.
+//!
+//! Entries live under `<app data>/cache/<namespace>/` as one file each: a small
+//! header (magic, expiry, full key) followed by the gzip-compressed JSON value.
+//! File names are the MD5 of the key, and the stored key guards against
+//! collisions. Access order for LRU eviction is kept in the file mtime, which
+//! is bumped on every hit, so it survives restarts without a separate index
+//! file. An in-memory index mirrors the directory for size accounting.
+//!
+//! [`ApiCache::get`] and [`ApiCache::put`] run their file I/O on the blocking
+//! pool; the `_blocking` variants are for code already off the async runtime.
+
+use crate::error::impl_serialize_error;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 4] = b"OSC1";
+
+/// Default cap for the whole cache directory.
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Values larger than this (after compression) are not worth caching.
+const MAX_ENTRY_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Namespace {
+    LanguageDetection,
+    MovieGuess,
+    CheckSubHash,
+    Features,
+}
+
+impl Namespace {
+    pub const ALL: [Namespace; 4] = [
+        Self::LanguageDetection,
+        Self::MovieGuess,
+        Self::CheckSubHash,
+        Self::Features,
+    ];
+
+    pub fn ttl(self) -> Duration {
+        const HOUR: u64 = 60 * 60;
+        Duration::from_secs(match self {
+            Self::LanguageDetection | Self::MovieGuess | Self::Features => 72 * HOUR,
+            // Uploads change the answer, so hash checks go stale sooner.
+            Self::CheckSubHash => 24 * HOUR,
+        })
+    }
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::LanguageDetection => "language-detection",
+            Self::MovieGuess => "movie-guess",
+            Self::CheckSubHash => "check-sub-hash",
+            Self::Features => "features",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("cache I/O error on {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not encode cache value: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("value of {size} bytes exceeds the {MAX_ENTRY_BYTES}-byte entry limit")]
+    TooLarge { size: u64 },
+    #[error("background task failed: {0}")]
+    Task(String),
+}
+
+impl CacheError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Encode(_) => "encode",
+            Self::TooLarge { .. } => "tooLarge",
+            Self::Task(_) => "task",
+        }
+    }
+
+    fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl_serialize_error!(CacheError);
+
+#[derive(Debug, Clone, Copy)]
+struct EntryMeta {
+    size: u64,
+    expires_at: u64,
+    last_access: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<(Namespace, String), EntryMeta>,
+    counters: HashMap<Namespace, Counters>,
+    total_bytes: u64,
+}
+
+impl Index {
+    fn insert(&mut self, key: (Namespace, String), meta: EntryMeta) {
+        if let Some(old) = self.entries.insert(key, meta) {
+            self.total_bytes -= old.size;
+        }
+        self.total_bytes += meta.size;
+    }
+
+    fn remove(&mut self, key: &(Namespace, String)) {
+        if let Some(old) = self.entries.remove(key) {
+            self.total_bytes -= old.size;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceStats {
+    pub namespace: Namespace,
+    pub entries: u64,
+    pub bytes: u64,
+    pub expired: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub directory: PathBuf,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+    pub namespaces: Vec<NamespaceStats>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parses an entry file into `(expiry, key, compressed payload)`.
+fn decode_entry(data: &[u8]) -> Option<(u64, &str, &[u8])> {
+    let rest = data.strip_prefix(MAGIC)?;
+    let expires_at = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+    let key_len = u32::from_le_bytes(rest.get(8..12)?.try_into().ok()?) as usize;
+    let key = std::str::from_utf8(rest.get(12..12 + key_len)?).ok()?;
+    Some((expires_at, key, &rest[12 + key_len..]))
+}
+
+struct Inner {
+    root: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+}
+
+/// Shared cache handle; cheap to clone.
+#[derive(Clone)]
+pub struct ApiCache {
+    inner: Arc<Inner>,
+}
+
+impl ApiCache {
+    /// Opens (creating if needed) the cache under `root` and indexes what is
+    /// already on disk, dropping expired and unreadable entries.
+    pub fn open(root: impl Into<PathBuf>, max_bytes: u64) -> Result<Self, CacheError> {
+        let root = root.into();
+        let mut index = Index::default();
+        let now = unix_now();
+        for namespace in Namespace::ALL {
+            let dir = root.join(namespace.dir_name());
+            fs::create_dir_all(&dir).map_err(|e| CacheError::io(&dir, e))?;
+            let listing = fs::read_dir(&dir).map_err(|e| CacheError::io(&dir, e))?;
+            for entry in listing.flatten() {
+                let path = entry.path();
+                // Anything else is a temporary file left by an interrupted put.
+                if path.extension().is_none_or(|ext| ext != "bin") {
+                    let _ = fs::remove_file(&path);
+                    continue;
+                }
+                let indexed = fs::read(&path).ok().and_then(|data| {
+                    let (expires_at, key, _) = decode_entry(&data)?;
+                    let modified = entry.metadata().ok()?.modified().ok()?;
+                    let meta = EntryMeta {
+                        size: data.len() as u64,
+                        expires_at,
+                        last_access: unix_secs(modified),
+                    };
+                    (expires_at > now).then(|| (key.to_string(), meta))
+                });
+                match indexed {
+                    Some((key, meta)) => index.insert((namespace, key), meta),
+                    None => {
+                        let _ = fs::remove_file(&path);
+                    }
+                }
+            }
+        }
+        let cache = Self {
+            inner: Arc::new(Inner {
+                root,
+                max_bytes,
+                index: Mutex::new(index),
+            }),
+        };
+        cache.evict(&mut cache.inner.index.lock().unwrap());
+        Ok(cache)
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.inner.root
+    }
+
+    fn entry_path(&self, namespace: Namespace, key: &str) -> PathBuf {
+        let digest = md5::compute(key.as_bytes());
+        self.inner
+            .root
+            .join(namespace.dir_name())
+            .join(format!("{digest:x}.bin"))
+    }
+
+    /// Returns the cached value, or `None` when missing, expired or unreadable.
+    pub async fn get<T: DeserializeOwned + Send + 'static>(
+        &self,
+        namespace: Namespace,
+        key: &str,
+    ) -> Option<T> {
+        let (cache, key) = (self.clone(), key.to_string());
+        tauri::async_runtime::spawn_blocking(move || cache.get_blocking(namespace, &key))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub fn get_blocking<T: DeserializeOwned>(&self, namespace: Namespace, key: &str) -> Option<T> {
+        let value = self.read(namespace, key);
+        let mut index = self.inner.index.lock().unwrap();
+        let counters = index.counters.entry(namespace).or_default();
+        match value {
+            Some(_) => counters.hits += 1,
+            None => counters.misses += 1,
+        }
+        value
+    }
+
+    fn read<T: DeserializeOwned>(&self, namespace: Namespace, key: &str) -> Option<T> {
+        let id = (namespace, key.to_string());
+        let now = unix_now();
+        let expired = {
+            let index = self.inner.index.lock().unwrap();
+            index.entries.get(&id)?.expires_at <= now
+        };
+        let path = self.entry_path(namespace, key);
+        let decoded = (!expired)
+            .then(|| fs::read(&path).ok())
+            .flatten()
+            .and_then(|data| {
+                let (expires_at, stored_key, payload) = decode_entry(&data)?;
+                if stored_key != key || expires_at <= now {
+                    return None;
+                }
+                let mut json = Vec::new();
+                GzDecoder::new(payload).read_to_end(&mut json).ok()?;
+                serde_json::from_slice(&json).ok()
+            });
+        let mut index = self.inner.index.lock().unwrap();
+        match decoded {
+            Some(value) => {
+                if let Some(meta) = index.entries.get_mut(&id) {
+                    meta.last_access = now;
+                }
+                drop(index);
+                // Best effort: the mtime only orders eviction after a restart.
+                if let Ok(file) = File::options().write(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+                Some(value)
+            }
+            None => {
+                index.remove(&id);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Stores `value` with the namespace TTL, evicting least recently used
+    /// entries if the cache grows past its size limit.
+    pub async fn put<T: Serialize + ?Sized>(
+        &self,
+        namespace: Namespace,
+        key: &str,
+        value: &T,
+    ) -> Result<(), CacheError> {
+        let value = serde_json::to_value(value)?;
+        let (cache, key) = (self.clone(), key.to_string());
+        tauri::async_runtime::spawn_blocking(move || cache.put_blocking(namespace, &key, &value))
+            .await
+            .map_err(|e| CacheError::Task(e.to_string()))?
+    }
+
+    pub fn put_blocking<T: Serialize + ?Sized>(
+        &self,
+        namespace: Namespace,
+        key: &str,
+        value: &T,
+    ) -> Result<(), CacheError> {
+        let json = serde_json::to_vec(value)?;
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let path = self.entry_path(namespace, key);
+        encoder
+            .write_all(&json)
+            .map_err(|e| CacheError::io(&path, e))?;
+        let payload = encoder.finish().map_err(|e| CacheError::io(&path, e))?;
+
+        let now = unix_now();
+        let expires_at = now + namespace.ttl().as_secs();
+        let mut data = Vec::with_capacity(16 + key.len() + payload.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&expires_at.to_le_bytes());
+        data.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        data.extend_from_slice(key.as_bytes());
+        data.extend_from_slice(&payload);
+        let size = data.len() as u64;
+        if size > MAX_ENTRY_BYTES.min(self.inner.max_bytes) {
+            return Err(CacheError::TooLarge { size });
+        }
+
+        // Write-then-rename so a crash never leaves a half-written entry. The
+        // temporary name is unique so concurrent puts of one key, from this
+        // process or another, do not write into the same file.
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp = path.with_extension(format!("{:x}-{n:x}.tmp", std::process::id()));
+        fs::write(&tmp, &data).map_err(|e| CacheError::io(&tmp, e))?;
+        fs::rename(&tmp, &path).map_err(|e| CacheError::io(&path, e))?;
+
+        let mut index = self.inner.index.lock().unwrap();
+        index.insert(
+            (namespace, key.to_string()),
+            EntryMeta {
+                size,
+                expires_at,
+                last_access: now,
+            },
+        );
+        self.evict(&mut index);
+        Ok(())
+    }
+
+    /// Drops expired entries, then least recently used ones until the cache
+    /// fits in `max_bytes`.
+    fn evict(&self, index: &mut Index) {
+        let now = unix_now();
+        let mut victims: Vec<_> = index
+            .entries
+            .iter()
+            .map(|(id, meta)| (meta.expires_at > now, meta.last_access, id.clone()))
+            .collect();
+        // Expired first (false < true), then oldest access.
+        victims.sort_by_key(|(live, last_access, _)| (*live, *last_access));
+        for (live, _, id) in victims {
+            if live && index.total_bytes <= self.inner.max_bytes {
+                break;
+            }
+            let _ = fs::remove_file(self.entry_path(id.0, &id.1));
+            index.remove(&id);
+        }
+    }
+
+    /// Removes every entry of `namespace`, or of all namespaces; returns the
+    /// number of entries removed.
+    pub fn clear(&self, namespace: Option<Namespace>) -> Result<u64, CacheError> {
+        let mut index = self.inner.index.lock().unwrap();
+        let mut removed = 0;
+        for ns in Namespace::ALL
+            .into_iter()
+            .filter(|ns| namespace.is_none_or(|only| only == *ns))
+        {
+            let dir = self.inner.root.join(ns.dir_name());
+            let listing = fs::read_dir(&dir).map_err(|e| CacheError::io(&dir, e))?;
+            for entry in listing.flatten() {
+                let path = entry.path();
+                fs::remove_file(&path).map_err(|e| CacheError::io(&path, e))?;
+            }
+            let keys: Vec<_> = index
+                .entries
+                .keys()
+                .filter(|(entry_ns, _)| *entry_ns == ns)
+                .cloned()
+                .collect();
+            removed += keys.len() as u64;
+            for key in keys {
+                index.remove(&key);
+            }
+            index.counters.remove(&ns);
+        }
+        Ok(removed)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let index = self.inner.index.lock().unwrap();
+        let now = unix_now();
+        let namespaces = Namespace::ALL
+            .into_iter()
+            .map(|namespace| {
+                let mut stats = NamespaceStats {
+                    namespace,
+                    entries: 0,
+                    bytes: 0,
+                    expired: 0,
+                    hits: 0,
+                    misses: 0,
+                    ttl_secs: namespace.ttl().as_secs(),
+                };
+                for ((ns, _), meta) in &index.entries {
+                    if *ns == namespace {
+                        stats.entries += 1;
+                        stats.bytes += meta.size;
+                        stats.expired += u64::from(meta.expires_at <= now);
+                    }
+                }
+                if let Some(counters) = index.counters.get(&namespace) {
+                    stats.hits = counters.hits;
+                    stats.misses = counters.misses;
+                }
+                stats
+            })
+            .collect();
+        CacheStats {
+            directory: self.inner.root.clone(),
+            total_bytes: index.total_bytes,
+            max_bytes: self.inner.max_bytes,
+            namespaces,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn cache_stats(cache: tauri::State<'_, ApiCache>) -> CacheStats {
+    cache.stats()
+}
+
+#[tauri::command]
+pub async fn cache_clear(
+    cache: tauri::State<'_, ApiCache>,
+    namespace: Option<Namespace>,
+) -> Result<u64, CacheError> {
+    let cache = cache.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || cache.clear(namespace))
+        .await
+        .map_err(|e| CacheError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::async_runtime::block_on;
+
+    #[test]
+    fn put_get_and_reopen() {
+        let dir = std::env::temp_dir().join(format!("{}-api-cache", std::process::id()));
+        let cache = ApiCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+        block_on(cache.put(Namespace::Features, "key", &vec![1, 2, 3])).unwrap();
+        let value: Option<Vec<u32>> = block_on(cache.get(Namespace::Features, "key"));
+        assert_eq!(value, Some(vec![1, 2, 3]));
+        assert_eq!(
+            block_on(cache.get::<Vec<u32>>(Namespace::Features, "other")),
+            None
+        );
+
+        // A write cut short leaves its temporary file behind.
+        let leftover = dir.join("features").join("0123.1-0.tmp");
+        fs::write(&leftover, b"partial").unwrap();
+        let cache = ApiCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+        assert!(!leftover.exists());
+        assert_eq!(
+            cache.get_blocking::<Vec<u32>>(Namespace::Features, "key"),
+            Some(vec![1, 2, 3])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
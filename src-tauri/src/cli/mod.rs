@@ -0,0 +1,379 @@
+//! Headless mode: `opensubtitles-uploader-pro <command> ...` runs a command
+//! without creating a window, so uploads can be scripted on servers.
+//!
+//! Progress goes to stderr and the JSON result to stdout (or `--output`).
+//! Exit codes: 0 success, 1 some files failed, 2 usage error, 3 login
+//! failed, 4 server unreachable, 5 local I/O error.
+
+pub(crate) mod pipeline;
+mod scan;
+mod upload;
+
+use crate::api::rest::RestClient;
+use crate::api::xmlrpc::{XmlRpcClient, XmlRpcError};
+use crate::api::{self as api, middleware::Http};
+use crate::cache::ApiCache;
+use crate::credentials::Credentials;
+use crate::settings::SettingsStore;
+use crate::upload::history::UploadHistory;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_LOGIN: i32 = 3;
+pub const EXIT_UNAVAILABLE: i32 = 4;
+pub const EXIT_IO: i32 = 5;
+
+/// Account used when `--username` is not given.
+const USERNAME_VAR: &str = "OPENSUBTITLES_USERNAME";
+const PASSWORD_VAR: &str = "OPENSUBTITLES_PASSWORD";
+
+const USAGE: &str = "\
+Usage: opensubtitles-uploader-pro <command> [options]
+
+Commands:
+  upload <paths>...   Scan files or folders, identify and upload subtitles
+  scan <paths>...     Identify subtitles and write a report, upload nothing
+  help                Show this message
+
+Run without a command to start the desktop app.
+
+Common options:
+  --lang <code>       Subtitle language for every file (e.g. eng), skips detection
+  --imdb <id>         IMDb ID for every file (e.g. tt0133093), skips guessing
+  --username <name>   Account to log in with (default: $OPENSUBTITLES_USERNAME,
+                      then the account saved in the desktop app)
+  --password <pass>   Password (default: $OPENSUBTITLES_PASSWORD)
+  --output <file>     Write the result to a file instead of stdout
+  --quiet             Do not print progress
+
+Upload options:
+  --dry-run           Identify and check for duplicates, but do not upload
+  --report <file>     Upload the subtitles listed in a scan report
+  --min-confidence <n>
+                      Skip subtitles identified with less confidence (0 to 1)
+  --anonymous         Upload without an account when none is given or saved
+
+Scan options:
+  --format <fmt>      json (default), or ndjson for one record per line
+";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("login failed: {0}")]
+    Login(XmlRpcError),
+    #[error("server unavailable: {0}")]
+    Unavailable(XmlRpcError),
+    #[error("{}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not open app data: {0}")]
+    Storage(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => EXIT_USAGE,
+            Self::Login(_) => EXIT_LOGIN,
+            Self::Unavailable(_) => EXIT_UNAVAILABLE,
+            Self::Io { .. } | Self::Storage(_) => EXIT_IO,
+        }
+    }
+
+    pub(crate) fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+/// Runs the command named in `args` and returns its exit code, or `None`
+/// when there is no command and the app should start normally.
+pub fn run(args: Vec<OsString>, identifier: &str) -> Option<i32> {
+    let mut args = Args(args.into_iter().skip(1));
+    let command = args.0.next()?;
+    let result = match command.to_str()? {
+        "upload" => {
+            attach_console();
+            upload::Options::parse(args).and_then(|options| {
+                tauri::async_runtime::block_on(upload::run(options, identifier))
+            })
+        }
+        "scan" => {
+            attach_console();
+            scan::Options::parse(args)
+                .and_then(|options| tauri::async_runtime::block_on(scan::run(options, identifier)))
+        }
+        "help" | "--help" | "-h" => {
+            attach_console();
+            print!("{USAGE}");
+            Ok(EXIT_OK)
+        }
+        "--version" | "-V" => {
+            attach_console();
+            println!("{}", api::USER_AGENT);
+            Ok(EXIT_OK)
+        }
+        // Anything else (including arguments the OS passes to GUI apps) is
+        // left to the desktop app.
+        _ => return None,
+    };
+    Some(result.unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        if let CliError::Usage(_) = error {
+            eprintln!("\n{USAGE}");
+        }
+        error.exit_code()
+    }))
+}
+
+/// Release builds use the Windows GUI subsystem, which starts without a
+/// console; borrow the one of the shell that launched us.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // SAFETY: plain Win32 call without pointers; failure (no parent console)
+    // just leaves output unattached.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Validates an IMDb ID and strips the `tt` prefix.
+fn imdb_id(id: &str) -> Result<String, CliError> {
+    let digits = id.trim_start_matches("tt");
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(CliError::Usage(format!("invalid IMDb ID {id:?}")));
+    }
+    Ok(digits.to_string())
+}
+
+/// Remaining command-line arguments; accepts `--name value` and `--name=value`.
+pub(crate) struct Args(std::iter::Skip<std::vec::IntoIter<OsString>>);
+
+pub(crate) enum Arg {
+    Flag(String, Option<OsString>),
+    Positional(OsString),
+}
+
+impl Args {
+    fn next(&mut self) -> Option<Arg> {
+        let arg = self.0.next()?;
+        Some(match arg.to_str() {
+            Some(flag) if flag.starts_with("--") => match flag.split_once('=') {
+                Some((name, value)) => Arg::Flag(name.to_string(), Some(value.into())),
+                None => Arg::Flag(flag.to_string(), None),
+            },
+            _ => Arg::Positional(arg),
+        })
+    }
+
+    /// Value of `flag`, taken inline or from the next argument.
+    fn value(&mut self, flag: &str, inline: Option<OsString>) -> Result<OsString, CliError> {
+        inline
+            .or_else(|| self.0.next())
+            .ok_or_else(|| CliError::Usage(format!("{flag} needs a value")))
+    }
+
+    fn string(&mut self, flag: &str, inline: Option<OsString>) -> Result<String, CliError> {
+        self.value(flag, inline)?
+            .into_string()
+            .map_err(|_| CliError::Usage(format!("{flag} must be valid UTF-8")))
+    }
+}
+
+/// API clients and stores, set up like the app does in `setup` but without
+/// the event emitters.
+pub(crate) struct Backend {
+    pub xmlrpc: XmlRpcClient,
+    pub rest: RestClient,
+    pub history: UploadHistory,
+    credentials: Credentials,
+}
+
+impl Backend {
+    pub fn open(identifier: &str) -> Result<Self, CliError> {
+        // Same directory as `app.path().app_data_dir()` in the desktop app.
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| CliError::Storage("no data directory on this system".into()))?
+            .join(identifier);
+        // Same file the desktop app reads from `app_config_dir()`.
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| CliError::Storage("no config directory on this system".into()))?
+            .join(identifier);
+        let settings = SettingsStore::open(&config_dir)
+            .map_err(|e| CliError::Storage(e.to_string()))?
+            .current();
+        let cache = ApiCache::open(data_dir.join("cache"), settings.cache_max_bytes())
+            .map_err(|e| CliError::Storage(e.to_string()))?;
+        let history = UploadHistory::open(data_dir.join("upload-history.jsonl"))
+            .map_err(|e| CliError::Storage(e.to_string()))?;
+        let credentials = Credentials::open(identifier, &data_dir, settings.credentials.store);
+        let http = Http::new(api::http_client());
+        Ok(Self {
+            xmlrpc: XmlRpcClient::with_http(http.clone(), &settings.api.xmlrpc_endpoint)
+                .with_cache(cache.clone()),
+            rest: RestClient::with_http(http, &settings.api.rest_base_url, settings.api_key())
+                .with_cache(cache),
+            history,
+            credentials,
+        })
+    }
+
+    /// Logs in with the given account or the saved one. Without either, logs
+    /// in anonymously if `anonymous` allows it and fails with a usage error
+    /// otherwise, so a missing variable on a server never uploads unattributed.
+    pub async fn log_in(
+        &self,
+        username: Option<String>,
+        password: Option<String>,
+        anonymous: bool,
+    ) -> Result<(String, Option<String>), CliError> {
+        let mut username = username.or_else(|| std::env::var(USERNAME_VAR).ok());
+        let mut password = password.or_else(|| std::env::var(PASSWORD_VAR).ok());
+        if username.is_none() && password.is_none() {
+            let saved = self.credentials.login().ok().flatten();
+            if let Some((name, secret)) = saved {
+                (username, password) = (Some(name), Some(secret));
+            }
+        }
+        if !anonymous && username.as_deref().is_none_or(str::is_empty) {
+            return Err(CliError::Usage(format!(
+                "no account to log in with; pass --username and --password, set \
+                 ${USERNAME_VAR} and ${PASSWORD_VAR}, save an account in the desktop \
+                 app, or pass --anonymous"
+            )));
+        }
+        let login = self
+            .xmlrpc
+            .log_in(
+                username.as_deref().unwrap_or_default(),
+                password.as_deref().unwrap_or_default(),
+                "en",
+            )
+            .await
+            .map_err(|e| match e {
+                XmlRpcError::Http(_) | XmlRpcError::ServiceUnavailable => CliError::Unavailable(e),
+                e => CliError::Login(e),
+            })?;
+        Ok((login.token, username.filter(|u| !u.is_empty())))
+    }
+}
+
+/// Progress lines on stderr, unless `--quiet`.
+pub(crate) struct Progress {
+    quiet: bool,
+    total: usize,
+}
+
+impl Progress {
+    pub fn new(quiet: bool, total: usize) -> Self {
+        Self { quiet, total }
+    }
+
+    pub fn step(&self, index: usize, path: &Path, message: &str) {
+        if !self.quiet {
+            eprintln!(
+                "[{}/{}] {}: {message}",
+                index + 1,
+                self.total,
+                path.display()
+            );
+        }
+    }
+
+    pub fn note(&self, message: &str) {
+        if !self.quiet {
+            eprintln!("{message}");
+        }
+    }
+}
+
+/// Writes `value` as pretty JSON to `output`, or to stdout.
+pub(crate) fn write_json(value: &impl Serialize, output: Option<&Path>) -> Result<(), CliError> {
+    let mut json = serde_json::to_vec_pretty(value).expect("report serializes");
+    json.push(b'\n');
+    match output {
+        Some(path) => std::fs::write(path, json).map_err(|e| CliError::io(path, e)),
+        None => std::io::stdout()
+            .write_all(&json)
+            .map_err(|e| CliError::io(Path::new("<stdout>"), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_errors_to_exit_codes() {
+        let io = || std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let cases = [
+            (CliError::Usage("unknown option --x".into()), EXIT_USAGE),
+            (CliError::Login(XmlRpcError::Unauthorized), EXIT_LOGIN),
+            (
+                CliError::Unavailable(XmlRpcError::ServiceUnavailable),
+                EXIT_UNAVAILABLE,
+            ),
+            (CliError::io(Path::new("report.json"), io()), EXIT_IO),
+            (CliError::Storage("no data directory".into()), EXIT_IO),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.exit_code(), code, "{error}");
+        }
+        // Each failure has its own code, apart from success and partial failure.
+        assert_eq!(
+            [
+                EXIT_OK,
+                EXIT_FAILED,
+                EXIT_USAGE,
+                EXIT_LOGIN,
+                EXIT_UNAVAILABLE,
+                EXIT_IO
+            ],
+            [0, 1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_commands_to_the_app() {
+        let run = |args: &[&str]| {
+            let args = ["opensubtitles-uploader-pro"].iter().chain(args);
+            run(args.map(Into::into).collect(), "test.identifier")
+        };
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&["-psn_0_12345"]), None);
+        assert_eq!(run(&["help"]), Some(EXIT_OK));
+        // Usage errors come before anything is opened or sent.
+        assert_eq!(run(&["scan"]), Some(EXIT_USAGE));
+        assert_eq!(
+            run(&["upload", "--imdb", "nope", "a.srt"]),
+            Some(EXIT_USAGE)
+        );
+    }
+
+    #[test]
+    fn checks_imdb_ids() {
+        assert_eq!(imdb_id("tt0133093").unwrap(), "0133093");
+        assert_eq!(imdb_id("133093").unwrap(), "133093");
+        for id in ["", "tt", "tt01330x3", "nm0000206"] {
+            assert_eq!(imdb_id(id).unwrap_err().exit_code(), EXIT_USAGE, "{id}");
+        }
+    }
+}
@@ -0,0 +1,609 @@
+//! Scan → identify steps shared by the headless commands.
+
+use super::CliError;
+use crate::api::rest::RestClient;
+use crate::api::xmlrpc::{UploadFile, UploadInfo, XmlRpcClient};
+use crate::archive::{self, is_archive, Extraction, Limits};
+use crate::languages::Language;
+use crate::media::probe;
+use crate::movie_hash;
+use crate::pairing::{self, is_subtitle, is_subtitle_folder, is_video};
+use crate::scan::{self, FileKind, ScanError, ScanEvent, ScanOptions};
+use crate::subtitle::encoding::{self, LOW_CONFIDENCE};
+use crate::subtitle::language::{decide, identify_subtitle, Decision};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A subtitle and the video it belongs to, if one was found near it.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// For subtitles from an archive, the archive path followed by the
+    /// member name (`Pack.zip/Movie.srt`); reports show this path.
+    pub subtitle: PathBuf,
+    /// Where a subtitle from an archive was extracted to.
+    pub extracted: Option<PathBuf>,
+    pub video: Option<PathBuf>,
+    /// How sure the pairing is, 0.0 when there is no video.
+    pub pairing: f64,
+    /// Language named by the subtitle file name (`Movie.en.srt`).
+    pub language: Option<&'static Language>,
+}
+
+impl Candidate {
+    /// The file to read the subtitle from.
+    pub fn source(&self) -> &Path {
+        self.extracted.as_deref().unwrap_or(&self.subtitle)
+    }
+}
+
+/// What [`discover`] found. Subtitles from archives are read from
+/// `extractions`, which must be kept until they have been uploaded.
+pub struct Discovery {
+    pub candidates: Vec<Candidate>,
+    pub extractions: Vec<Extraction>,
+    /// Archives that could not be read and members left out of them.
+    pub notes: Vec<String>,
+}
+
+/// Collects the subtitles under `paths` (files or directories, walked
+/// recursively, archives unpacked) and pairs each with a video from its own
+/// directory or one just above it.
+pub fn discover(paths: &[PathBuf]) -> Result<Discovery, CliError> {
+    let mut subtitles = Vec::new();
+    let mut archives = Vec::new();
+    // Subtitles pulled in by a video argument, kept only if they pair with it.
+    let mut wanted: HashMap<PathBuf, PathBuf> = HashMap::new();
+    for path in paths {
+        let meta = fs::metadata(path).map_err(|e| CliError::io(path, e))?;
+        if meta.is_dir() {
+            walk(path, &mut subtitles, &mut archives)?;
+        } else if is_subtitle(path) {
+            subtitles.push(path.clone());
+        } else if is_archive(path) {
+            archives.push(path.clone());
+        } else if is_video(path) {
+            // A video on the command line stands for the subtitles beside it
+            // and in the subtitle folders below it.
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let (mut found, mut packs) = (Vec::new(), Vec::new());
+            for entry in list_dir(dir)? {
+                if is_subtitle(&entry) {
+                    found.push(entry);
+                } else if is_archive(&entry) {
+                    packs.push(entry);
+                } else if entry.is_dir() && is_subtitle_folder(&entry) {
+                    walk(&entry, &mut found, &mut packs)?;
+                }
+            }
+            for subtitle in found {
+                wanted.insert(subtitle.clone(), path.clone());
+                subtitles.push(subtitle);
+            }
+            for pack in packs {
+                wanted.insert(pack.clone(), path.clone());
+                archives.push(pack);
+            }
+        }
+    }
+    subtitles.sort();
+    subtitles.dedup();
+    archives.sort();
+    archives.dedup();
+
+    // Archive members are paired as if extracted next to the archive, so
+    // `Movie/Subs.zip` pairs with `Movie/Movie.mkv` like `Movie/Subs/*.srt`.
+    let mut extractions = Vec::new();
+    let mut notes = Vec::new();
+    let mut members: HashMap<PathBuf, (PathBuf, PathBuf)> = HashMap::new();
+    for path in &archives {
+        let extraction = match archive::extract(path, &archive::temp_root(), &Limits::default()) {
+            Ok(extraction) => extraction,
+            Err(e) => {
+                notes.push(format!("{}: {e}", path.display()));
+                continue;
+            }
+        };
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for skipped in &extraction.skipped {
+            notes.push(format!(
+                "{}: skipped {}: {}",
+                path.display(),
+                skipped.member,
+                skipped.reason
+            ));
+        }
+        for file in &extraction.subtitles {
+            let member = file.member.replace('\\', "/");
+            let placed = dir.join(&member);
+            if subtitles.contains(&placed) || members.contains_key(&placed) {
+                continue;
+            }
+            if let Some(video) = wanted.get(path).cloned() {
+                wanted.insert(placed.clone(), video);
+            }
+            members.insert(placed.clone(), (path.join(&member), file.path.clone()));
+            subtitles.push(placed);
+        }
+        extractions.push(extraction);
+    }
+
+    let mut files = subtitles.clone();
+    let mut listed = HashSet::new();
+    for subtitle in &subtitles {
+        for dir in subtitle
+            .ancestors()
+            .skip(1)
+            .take(pairing::MAX_LEVELS_UP + 1)
+        {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            if !listed.insert(dir.to_path_buf()) {
+                continue;
+            }
+            // Folders above the ones given may well be unreadable, and those
+            // inside archives do not exist.
+            let Ok(entries) = list_dir(dir) else { continue };
+            files.extend(entries.into_iter().filter(|p| is_video(p)));
+        }
+    }
+
+    let candidates = pairing::pair(&files)
+        .pairs
+        .into_iter()
+        .filter(|pair| {
+            wanted
+                .get(&pair.subtitle)
+                .is_none_or(|video| pair.video.as_ref() == Some(video))
+        })
+        .map(|pair| {
+            let (subtitle, extracted) = match members.remove(&pair.subtitle) {
+                Some((shown, extracted)) => (shown, Some(extracted)),
+                None => (pair.subtitle, None),
+            };
+            Candidate {
+                subtitle,
+                extracted,
+                pairing: if pair.video.is_some() {
+                    pair.confidence
+                } else {
+                    0.0
+                },
+                video: pair.video,
+                language: pair.language,
+            }
+        })
+        .collect();
+    Ok(Discovery {
+        candidates,
+        extractions,
+        notes,
+    })
+}
+
+/// Extracts again the archives holding subtitles that a scan report lists
+/// by their in-archive path, pointing those candidates at the new copies.
+pub fn unpack_reported<'a>(
+    candidates: impl IntoIterator<Item = &'a mut Candidate>,
+) -> (Vec<Extraction>, Vec<String>) {
+    let mut extractions: Vec<Extraction> = Vec::new();
+    let mut notes = Vec::new();
+    let mut failed = HashSet::new();
+    for candidate in candidates {
+        if candidate.extracted.is_some() || candidate.subtitle.exists() {
+            continue;
+        }
+        let Some(path) = candidate
+            .subtitle
+            .ancestors()
+            .skip(1)
+            .find(|a| is_archive(a) && a.is_file())
+            .map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        if failed.contains(&path) {
+            continue;
+        }
+        let index = match extractions.iter().position(|e| e.archive == path) {
+            Some(index) => index,
+            None => match archive::extract(&path, &archive::temp_root(), &Limits::default()) {
+                Ok(extraction) => {
+                    extractions.push(extraction);
+                    extractions.len() - 1
+                }
+                Err(e) => {
+                    notes.push(format!("{}: {e}", path.display()));
+                    failed.insert(path);
+                    continue;
+                }
+            },
+        };
+        let member = candidate
+            .subtitle
+            .strip_prefix(&path)
+            .unwrap_or(Path::new(""));
+        candidate.extracted = extractions[index]
+            .subtitles
+            .iter()
+            .find(|file| Path::new(&file.member.replace('\\', "/")) == member)
+            .map(|file| file.path.clone());
+    }
+    (extractions, notes)
+}
+
+/// Collects the subtitles and archives under `dir` with the library
+/// scanner's default ignore rules, so samples, extras and hidden entries are
+/// left out.
+fn walk(
+    dir: &Path,
+    subtitles: &mut Vec<PathBuf>,
+    archives: &mut Vec<PathBuf>,
+) -> Result<(), CliError> {
+    let found = Mutex::new(Vec::new());
+    scan::scan(dir, &ScanOptions::default(), &|event| {
+        if let ScanEvent::Files(files) = event {
+            found
+                .lock()
+                .expect("scan results poisoned")
+                .extend(files.into_iter().filter(|f| f.kind != FileKind::Video));
+        }
+    })
+    .map_err(|e| match e {
+        ScanError::Io { path, source } => CliError::Io { path, source },
+        other => CliError::Storage(other.to_string()),
+    })?;
+    for file in found.into_inner().expect("scan results poisoned") {
+        match file.kind {
+            FileKind::Archive => archives.push(file.path),
+            _ => subtitles.push(file.path),
+        }
+    }
+    Ok(())
+}
+
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, CliError> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| CliError::io(dir, e))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| CliError::io(dir, e))?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Where an identified value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+    /// Given on the command line.
+    Argument,
+    /// Taken from a scan report passed to `upload --report`.
+    Report,
+    /// Read off the subtitle file name, e.g. `Movie.en.srt`.
+    Filename,
+    /// `GuessMovieFromString` / language detection on the server.
+    Server,
+    /// Identified offline from the subtitle text.
+    Local,
+}
+
+/// Per-field confidence in `0.0..=1.0`; values given by the user count as 1.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Confidence {
+    /// Subtitle ↔ video pairing, `None` without a video.
+    pub pairing: Option<f64>,
+    pub imdb: f64,
+    pub language: f64,
+    /// Product of the above: the chance the upload lands on the right entry.
+    pub overall: f64,
+}
+
+impl Confidence {
+    fn new(pairing: Option<f64>, imdb: f64, language: f64) -> Self {
+        let round = |v: f64| (v * 100.0).round() / 100.0;
+        Self {
+            pairing: pairing.map(round),
+            imdb: round(imdb),
+            language: round(language),
+            overall: round(pairing.unwrap_or(1.0) * imdb * language),
+        }
+    }
+}
+
+/// Server guesses get less weight when they only had the subtitle name.
+const GUESS_FROM_VIDEO: f64 = 0.8;
+const GUESS_FROM_SUBTITLE: f64 = 0.6;
+
+/// A language tag in the file name is usually right, but is sometimes left
+/// over from the release the subtitle was made for.
+const LANGUAGE_FROM_FILENAME: f64 = 0.9;
+
+/// Everything the upload call needs to know about one subtitle.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identified {
+    /// Left to the report embedding this, which knows the paths even when
+    /// identification fails.
+    #[serde(skip)]
+    pub subtitle: PathBuf,
+    #[serde(skip)]
+    pub video: Option<PathBuf>,
+    pub subtitle_md5: String,
+    pub movie_hash: Option<String>,
+    pub movie_byte_size: Option<u64>,
+    pub imdb_id: Option<String>,
+    pub imdb_source: Option<Source>,
+    pub movie_name: Option<String>,
+    pub language: Option<String>,
+    pub language_source: Option<Source>,
+    /// Character encoding of the subtitle, sent as `subencoding`.
+    pub encoding: Option<String>,
+    pub confidence: Confidence,
+    /// Problems that did not stop identification, e.g. an unreadable video.
+    pub warnings: Vec<String>,
+    /// A server lookup failed, so a missing movie or language may still be
+    /// found on another try.
+    #[serde(skip)]
+    pub lookup_failed: bool,
+    #[serde(skip)]
+    pub file: UploadFile,
+}
+
+impl Identified {
+    /// What `UploadSubtitles` needs besides the file, once both the movie and
+    /// the language are known. The release name is the video's file name.
+    pub fn upload_info(&self) -> Option<UploadInfo> {
+        Some(UploadInfo {
+            imdb_id: self.imdb_id.clone()?,
+            sub_language_id: self.language.clone()?,
+            release_name: self
+                .video
+                .as_ref()
+                .and_then(|video| video.file_stem())
+                .map(|stem| stem.to_string_lossy().into_owned()),
+            ..Default::default()
+        })
+    }
+}
+
+/// Values used as given instead of being looked up.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub imdb_id: Option<(String, Source)>,
+    pub language: Option<(String, Source)>,
+}
+
+/// Hashes the pair, probes the video and looks up whatever `overrides`
+/// leaves open.
+pub async fn identify(
+    candidate: &Candidate,
+    overrides: &Overrides,
+    xmlrpc: &XmlRpcClient,
+    token: &str,
+    rest: &RestClient,
+) -> Result<Identified, CliError> {
+    let source = candidate.source();
+    let bytes = fs::read(source).map_err(|e| CliError::io(source, e))?;
+    let mut warnings = Vec::new();
+    let mut lookup_failed = false;
+    let mut file = UploadFile {
+        subtitle_path: source.to_path_buf(),
+        ..Default::default()
+    };
+    if let Some(video) = &candidate.video {
+        file.movie_filename = video.file_name().map(|n| n.to_string_lossy().into_owned());
+        match movie_hash::compute(video) {
+            Ok(hash) => {
+                file.movie_hash = Some(hash.hash);
+                file.movie_byte_size = Some(hash.byte_size);
+            }
+            Err(e) => warnings.push(e.to_string()),
+        }
+        if let Ok(probe) = probe::probe(video) {
+            file.movie_fps = probe.fps;
+            file.movie_frames = probe.frame_count;
+            file.movie_time_ms = probe.duration_ms;
+        }
+    }
+
+    let (mut imdb_id, mut imdb_source, mut movie_name) = (None, None, None);
+    let mut imdb_confidence = 0.0;
+    if let Some((id, source)) = &overrides.imdb_id {
+        imdb_id = Some(id.trim_start_matches("tt").to_string());
+        imdb_source = Some(*source);
+        imdb_confidence = 1.0;
+    } else {
+        let title = file
+            .movie_filename
+            .clone()
+            .unwrap_or_else(|| file.filename());
+        match xmlrpc
+            .guess_movie_from_string(token, std::slice::from_ref(&title))
+            .await
+        {
+            Ok(mut guesses) => {
+                if let Some(best) = guesses.remove(&title).and_then(|g| g.best_guess) {
+                    imdb_id = Some(best.imdb_id);
+                    imdb_source = Some(Source::Server);
+                    movie_name = best.movie_name;
+                    imdb_confidence = if candidate.video.is_some() {
+                        GUESS_FROM_VIDEO
+                    } else {
+                        GUESS_FROM_SUBTITLE
+                    };
+                }
+            }
+            Err(e) => {
+                warnings.push(format!("movie guess failed: {e}"));
+                lookup_failed = true;
+            }
+        }
+    }
+
+    let (language, language_source, language_confidence) =
+        if let Some((lang, source)) = &overrides.language {
+            (Some(lang.clone()), Some(*source), 1.0)
+        } else if let Some(tagged) = candidate.language {
+            (
+                Some(tagged.code.to_string()),
+                Some(Source::Filename),
+                LANGUAGE_FROM_FILENAME,
+            )
+        } else {
+            let decision = decide(rest, source, identify_subtitle(&bytes)).await;
+            if let Some(e) = &decision.server_error {
+                warnings.push(format!("language detection failed: {e}"));
+                lookup_failed = true;
+            }
+            let decided_by = decision.source.map(|decided_by| match decided_by {
+                Decision::Server => Source::Server,
+                Decision::Local | Decision::LocalFallback => Source::Local,
+            });
+            (decision.language, decided_by, decision.confidence)
+        };
+
+    match encoding::detect(&bytes, language.as_deref()) {
+        Ok(detection) => {
+            if detection.confidence < LOW_CONFIDENCE {
+                warnings.push(format!(
+                    "encoding guessed as {} with low confidence ({:.0}%)",
+                    detection.encoding,
+                    detection.confidence * 100.0
+                ));
+            }
+            if let Some(first) = detection.undecodable.first() {
+                warnings.push(format!(
+                    "{} byte sequence(s) not valid {}, the first on line {}",
+                    detection.undecodable_count, detection.encoding, first.line
+                ));
+            }
+            file.subtitle_encoding = Some(detection.encoding.to_string());
+        }
+        Err(e) => warnings.push(e.to_string()),
+    }
+
+    Ok(Identified {
+        subtitle: candidate.subtitle.clone(),
+        video: candidate.video.clone(),
+        subtitle_md5: format!("{:x}", md5::compute(&bytes)),
+        movie_hash: file.movie_hash.clone(),
+        movie_byte_size: file.movie_byte_size,
+        imdb_id,
+        imdb_source,
+        movie_name,
+        language,
+        language_source,
+        encoding: file.subtitle_encoding.clone(),
+        confidence: Confidence::new(
+            candidate.video.as_ref().map(|_| candidate.pairing),
+            imdb_confidence,
+            language_confidence,
+        ),
+        warnings,
+        lookup_failed,
+        file,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confidence_is_rounded_and_multiplied() {
+        let paired = Confidence::new(Some(0.856), 0.8, LANGUAGE_FROM_FILENAME);
+        assert_eq!(
+            (paired.pairing, paired.imdb, paired.language, paired.overall),
+            (Some(0.86), 0.8, 0.9, 0.62)
+        );
+        // Without a video, the pairing does not count against the rest.
+        let alone = Confidence::new(None, GUESS_FROM_SUBTITLE, 1.0);
+        assert_eq!((alone.pairing, alone.overall), (None, 0.6));
+    }
+
+    #[test]
+    fn upload_info_needs_the_movie_and_the_language() {
+        let mut identified = Identified {
+            subtitle: "Movie/Movie.en.srt".into(),
+            video: Some("Movie/Movie.2019.1080p.mkv".into()),
+            subtitle_md5: String::new(),
+            movie_hash: None,
+            movie_byte_size: None,
+            imdb_id: Some("133093".into()),
+            imdb_source: Some(Source::Server),
+            movie_name: None,
+            language: None,
+            language_source: None,
+            encoding: None,
+            confidence: Confidence::default(),
+            warnings: Vec::new(),
+            lookup_failed: false,
+            file: UploadFile::default(),
+        };
+        assert!(identified.upload_info().is_none());
+        identified.language = Some("eng".into());
+        let info = identified.upload_info().unwrap();
+        assert_eq!(
+            (info.imdb_id.as_str(), info.sub_language_id.as_str()),
+            ("133093", "eng")
+        );
+        assert_eq!(info.release_name.as_deref(), Some("Movie.2019.1080p"));
+        identified.imdb_id = None;
+        assert!(identified.upload_info().is_none());
+    }
+
+    #[test]
+    fn discovers_and_pairs_subtitles() {
+        let root = std::env::temp_dir().join(format!("{}-discover", std::process::id()));
+        let matrix = root.join("The.Matrix.1999");
+        let other = root.join("Other.Film.2001");
+        let files = [
+            matrix.join("The.Matrix.1999.1080p.mkv"),
+            matrix.join("The.Matrix.1999.1080p.en.srt"),
+            matrix.join("Subs").join("2_English.srt"),
+            matrix.join("Extras").join("notes.txt"),
+            other.join("Other.Film.2001.mkv"),
+            other.join("Other.Film.2001.srt"),
+        ];
+        for file in &files {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "").unwrap();
+        }
+        let found = |paths: &[PathBuf]| {
+            let discovery = discover(paths).unwrap();
+            assert!(discovery.notes.is_empty() && discovery.extractions.is_empty());
+            let candidates = discovery.candidates.into_iter();
+            candidates
+                .map(|c| (c.subtitle, c.video, c.language.map(|l| l.code)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut everything = found(std::slice::from_ref(&root));
+        everything.sort();
+        assert_eq!(
+            everything,
+            [
+                (files[5].clone(), Some(files[4].clone()), None),
+                (files[2].clone(), Some(files[0].clone()), Some("eng")),
+                (files[1].clone(), Some(files[0].clone()), Some("eng")),
+            ]
+        );
+        // A video stands for the subtitles beside it and in its Subs folder.
+        let mut beside = found(std::slice::from_ref(&files[0]));
+        beside.sort();
+        assert_eq!(beside, everything[1..]);
+        // Naming a subtitle and its folder lists it once.
+        assert_eq!(found(&[files[5].clone(), other.clone()]).len(), 1);
+
+        let missing = discover(&[root.join("missing")]).err().unwrap();
+        assert_eq!(missing.exit_code(), super::super::EXIT_IO);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+//! `scan`: the identify half of `upload`, written out as a report instead of
+//! acted on. Reports can be reviewed, edited and fed to `upload --report`.
+
+use super::pipeline::{self, Candidate, Confidence, Identified, Overrides, Source};
+use super::{Arg, Args, Backend, CliError, Progress, EXIT_FAILED, EXIT_OK};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Format {
+    /// One JSON array, written when the scan is complete.
+    #[default]
+    Json,
+    /// One record per line, written as soon as it is known.
+    Ndjson,
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    paths: Vec<PathBuf>,
+    overrides: Overrides,
+    format: Format,
+    username: Option<String>,
+    password: Option<String>,
+    output: Option<PathBuf>,
+    quiet: bool,
+}
+
+impl Options {
+    pub fn parse(mut args: Args) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg {
+                Arg::Flag(flag, inline) => match flag.as_str() {
+                    "--lang" => {
+                        options.overrides.language =
+                            Some((args.string(&flag, inline)?, Source::Argument))
+                    }
+                    "--imdb" => {
+                        options.overrides.imdb_id = Some((
+                            super::imdb_id(&args.string(&flag, inline)?)?,
+                            Source::Argument,
+                        ))
+                    }
+                    "--format" => {
+                        options.format = match args.string(&flag, inline)?.as_str() {
+                            "json" => Format::Json,
+                            "ndjson" => Format::Ndjson,
+                            other => {
+                                return Err(CliError::Usage(format!("unknown format {other:?}")))
+                            }
+                        }
+                    }
+                    "--username" => options.username = Some(args.string(&flag, inline)?),
+                    "--password" => options.password = Some(args.string(&flag, inline)?),
+                    "--output" => options.output = Some(args.value(&flag, inline)?.into()),
+                    "--quiet" => options.quiet = true,
+                    _ => return Err(CliError::Usage(format!("unknown option {flag}"))),
+                },
+                Arg::Positional(path) => options.paths.push(path.into()),
+            }
+        }
+        if options.paths.is_empty() {
+            return Err(CliError::Usage(
+                "scan needs at least one file or folder".into(),
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// One line of the report.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    subtitle: PathBuf,
+    video: Option<PathBuf>,
+    #[serde(flatten)]
+    identified: Option<Identified>,
+    /// Whether `CheckSubHash` knows the subtitle already.
+    already_in_database: Option<bool>,
+    error: Option<String>,
+}
+
+/// The part of a report record `upload --report` reads back; everything else
+/// is recomputed.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportEntry {
+    pub subtitle: PathBuf,
+    pub video: Option<PathBuf>,
+    pub imdb_id: Option<String>,
+    pub language: Option<String>,
+    pub confidence: Option<Confidence>,
+}
+
+impl ReportEntry {
+    pub fn candidate(&self) -> Candidate {
+        Candidate {
+            subtitle: self.subtitle.clone(),
+            extracted: None,
+            video: self.video.clone(),
+            pairing: self
+                .confidence
+                .and_then(|c| c.pairing)
+                .unwrap_or(if self.video.is_some() { 1.0 } else { 0.0 }),
+            language: None,
+        }
+    }
+}
+
+/// Reads a report written by `scan` in either format.
+pub fn read_report(path: &Path) -> Result<Vec<ReportEntry>, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CliError::io(path, e))?;
+    let invalid =
+        |e: serde_json::Error| CliError::Usage(format!("{}: invalid report: {e}", path.display()));
+    if text.trim_start().starts_with('[') {
+        return serde_json::from_str(&text).map_err(invalid);
+    }
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(invalid))
+        .collect()
+}
+
+/// Where records go: NDJSON lines are flushed one by one, JSON is buffered.
+struct Sink {
+    out: Box<dyn Write>,
+    path: PathBuf,
+    format: Format,
+    records: Vec<Record>,
+}
+
+impl Sink {
+    fn open(output: Option<&Path>, format: Format) -> Result<Self, CliError> {
+        let (out, path): (Box<dyn Write>, _) = match output {
+            Some(path) => (
+                Box::new(BufWriter::new(
+                    File::create(path).map_err(|e| CliError::io(path, e))?,
+                )),
+                path.to_path_buf(),
+            ),
+            None => (Box::new(io::stdout()), PathBuf::from("<stdout>")),
+        };
+        Ok(Self {
+            out,
+            path,
+            format,
+            records: Vec::new(),
+        })
+    }
+
+    fn push(&mut self, record: Record) -> Result<(), CliError> {
+        match self.format {
+            Format::Json => self.records.push(record),
+            Format::Ndjson => {
+                let mut line = serde_json::to_vec(&record).expect("record serializes");
+                line.push(b'\n');
+                self.out
+                    .write_all(&line)
+                    .and_then(|()| self.out.flush())
+                    .map_err(|e| CliError::io(&self.path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), CliError> {
+        if self.format == Format::Json {
+            let mut json = serde_json::to_vec_pretty(&self.records).expect("report serializes");
+            json.push(b'\n');
+            self.out
+                .write_all(&json)
+                .map_err(|e| CliError::io(&self.path, e))?;
+        }
+        self.out.flush().map_err(|e| CliError::io(&self.path, e))
+    }
+}
+
+pub async fn run(options: Options, identifier: &str) -> Result<i32, CliError> {
+    let discovery = pipeline::discover(&options.paths)?;
+    let candidates = &discovery.candidates;
+    let progress = Progress::new(options.quiet, candidates.len());
+    for note in &discovery.notes {
+        progress.note(note);
+    }
+    progress.note(&format!("found {} subtitle file(s)", candidates.len()));
+
+    let backend = Backend::open(identifier)?;
+    let (token, _) = backend
+        .log_in(options.username.clone(), options.password.clone(), true)
+        .await?;
+
+    let mut sink = Sink::open(options.output.as_deref(), options.format)?;
+    let mut failed = 0;
+    for (index, candidate) in candidates.iter().enumerate() {
+        progress.step(index, &candidate.subtitle, "identifying");
+        let mut record = Record {
+            subtitle: candidate.subtitle.clone(),
+            video: candidate.video.clone(),
+            identified: None,
+            already_in_database: None,
+            error: None,
+        };
+        match pipeline::identify(
+            candidate,
+            &options.overrides,
+            &backend.xmlrpc,
+            &token,
+            &backend.rest,
+        )
+        .await
+        {
+            Ok(mut identified) => {
+                let md5 = identified.subtitle_md5.clone();
+                match backend
+                    .xmlrpc
+                    .check_sub_hash(&token, std::slice::from_ref(&md5))
+                    .await
+                {
+                    Ok(found) => {
+                        record.already_in_database =
+                            Some(found.get(&md5).is_some_and(Option::is_some))
+                    }
+                    Err(e) => identified
+                        .warnings
+                        .push(format!("duplicate check failed: {e}")),
+                }
+                progress.step(
+                    index,
+                    &candidate.subtitle,
+                    &format!("confidence {:.2}", identified.confidence.overall),
+                );
+                record.identified = Some(identified);
+            }
+            Err(e) => {
+                failed += 1;
+                progress.step(index, &candidate.subtitle, &format!("failed: {e}"));
+                record.error = Some(e.to_string());
+            }
+        }
+        sink.push(record)?;
+    }
+    let _ = backend.xmlrpc.log_out(&token).await;
+    sink.finish()?;
+    Ok(if failed > 0 { EXIT_FAILED } else { EXIT_OK })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        // As left by `run`, which has already taken the command name.
+        let args: Vec<_> = ["scan"].iter().chain(args).map(Into::into).collect();
+        Options::parse(Args(args.into_iter().skip(1)))
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&[
+            "--format=ndjson",
+            "--lang",
+            "eng",
+            "--imdb=tt0133093",
+            "--output",
+            "report.ndjson",
+            "--quiet",
+            "Movies",
+        ])
+        .unwrap();
+        assert_eq!(options.format, Format::Ndjson);
+        assert_eq!(
+            options.overrides.language,
+            Some(("eng".into(), Source::Argument))
+        );
+        assert_eq!(
+            options.overrides.imdb_id,
+            Some(("0133093".into(), Source::Argument))
+        );
+        assert_eq!(options.output, Some(PathBuf::from("report.ndjson")));
+        assert!(options.quiet);
+        assert_eq!(options.paths, [PathBuf::from("Movies")]);
+        assert_eq!(parse(&["Movies"]).unwrap().format, Format::Json);
+    }
+
+    #[test]
+    fn rejects_unknown_and_incomplete_options() {
+        let cases = [
+            (&["--dry-run", "Movies"][..], "unknown option --dry-run"),
+            (&["--recursive=yes", "Movies"], "unknown option --recursive"),
+            (&["--format", "csv", "Movies"], "unknown format \"csv\""),
+            (&["Movies", "--lang"], "--lang needs a value"),
+            (&["--quiet"], "scan needs at least one file or folder"),
+        ];
+        for (args, message) in cases {
+            let error = parse(args).unwrap_err();
+            assert!(matches!(error, CliError::Usage(_)), "{args:?}");
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    fn report(name: &str, text: &str) -> Result<Vec<ReportEntry>, CliError> {
+        let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let entries = read_report(&path);
+        std::fs::remove_file(&path).unwrap();
+        entries
+    }
+
+    #[test]
+    fn reads_json_and_ndjson_reports() {
+        // As `scan` writes them, with fields `upload --report` ignores.
+        let records = [
+            r#"{"subtitle":"Movie/Movie.en.srt","video":"Movie/Movie.mkv","subtitleMd5":"0123","imdbId":"133093","imdbSource":"server","language":"eng","confidence":{"pairing":0.85,"imdb":0.8,"language":0.9,"overall":0.61},"warnings":[],"alreadyInDatabase":false,"error":null}"#,
+            r#"{"subtitle":"Lost.srt","video":null,"alreadyInDatabase":null,"error":"no movie found"}"#,
+        ];
+        let json = format!("[\n  {},\n  {}\n]\n", records[0], records[1]);
+        let ndjson = format!("{}\n\n{}\n", records[0], records[1]);
+        for (name, text) in [("report.json", json), ("report.ndjson", ndjson)] {
+            let entries = report(name, &text).unwrap();
+            assert_eq!(entries.len(), 2, "{name}");
+            let movie = &entries[0];
+            assert_eq!(movie.subtitle, Path::new("Movie/Movie.en.srt"));
+            assert_eq!(movie.video.as_deref(), Some(Path::new("Movie/Movie.mkv")));
+            assert_eq!(movie.imdb_id.as_deref(), Some("133093"));
+            assert_eq!(movie.language.as_deref(), Some("eng"));
+            assert_eq!(movie.confidence.and_then(|c| c.pairing), Some(0.85));
+            let lost = &entries[1];
+            assert_eq!(lost.subtitle, Path::new("Lost.srt"));
+            assert!(lost.video.is_none() && lost.imdb_id.is_none() && lost.confidence.is_none());
+        }
+        assert!(report("report-empty.ndjson", "").unwrap().is_empty());
+        assert!(report("report-empty.json", "[]").unwrap().is_empty());
+
+        for (name, text) in [
+            ("report-bad.json", "[{\"video\":null}]"),
+            (
+                "report-bad.ndjson",
+                "{\"subtitle\":\"a.srt\"}\n{\"subtitle\":",
+            ),
+        ] {
+            let error = report(name, text).unwrap_err();
+            assert_eq!(error.exit_code(), super::super::EXIT_USAGE, "{name}");
+            assert!(error.to_string().contains("invalid report"), "{error}");
+        }
+        let missing = std::env::temp_dir().join(format!("{}-no-report", std::process::id()));
+        assert_eq!(
+            read_report(&missing).unwrap_err().exit_code(),
+            super::super::EXIT_IO
+        );
+    }
+
+    #[test]
+    fn candidates_default_the_pairing_confidence() {
+        let entry = |video: Option<&str>, confidence: Option<&str>| {
+            let json = format!(
+                r#"{{"subtitle":"a.srt","video":{},"confidence":{}}}"#,
+                video.map_or("null".into(), |v| format!("{v:?}")),
+                confidence.unwrap_or("null")
+            );
+            serde_json::from_str::<ReportEntry>(&json)
+                .unwrap()
+                .candidate()
+        };
+        // From the report when it says.
+        let reported = entry(Some("a.mkv"), Some(r#"{"pairing":0.55}"#));
+        assert_eq!(reported.pairing, 0.55);
+        assert_eq!(reported.video.as_deref(), Some(Path::new("a.mkv")));
+        assert!(reported.extracted.is_none() && reported.language.is_none());
+        // A video put in the report by hand is taken as right.
+        assert_eq!(entry(Some("a.mkv"), None).pairing, 1.0);
+        assert_eq!(entry(Some("a.mkv"), Some(r#"{"imdb":1.0}"#)).pairing, 1.0);
+        // No video, nothing to be sure of.
+        assert_eq!(entry(None, None).pairing, 0.0);
+        assert_eq!(entry(None, Some(r#"{"pairing":null}"#)).pairing, 0.0);
+    }
+}
@@ -0,0 +1,353 @@
+//! `upload`: scan → identify → upload without a window.
+
+use super::pipeline::{self, Identified, Overrides, Source};
+use super::{scan, write_json, Arg, Args, Backend, CliError, Progress, EXIT_FAILED, EXIT_OK};
+use crate::api::xmlrpc::XmlRpcError;
+use crate::upload::history::HistoryRecord;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Default)]
+pub struct Options {
+    paths: Vec<PathBuf>,
+    report: Option<PathBuf>,
+    min_confidence: f64,
+    overrides: Overrides,
+    dry_run: bool,
+    username: Option<String>,
+    password: Option<String>,
+    anonymous: bool,
+    output: Option<PathBuf>,
+    quiet: bool,
+}
+
+impl Options {
+    pub fn parse(mut args: Args) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg {
+                Arg::Flag(flag, inline) => match flag.as_str() {
+                    "--lang" => {
+                        options.overrides.language =
+                            Some((args.string(&flag, inline)?, Source::Argument))
+                    }
+                    "--imdb" => {
+                        options.overrides.imdb_id = Some((
+                            super::imdb_id(&args.string(&flag, inline)?)?,
+                            Source::Argument,
+                        ))
+                    }
+                    "--report" => options.report = Some(args.value(&flag, inline)?.into()),
+                    "--min-confidence" => {
+                        let value = args.string(&flag, inline)?;
+                        options.min_confidence = value
+                            .parse()
+                            .ok()
+                            .filter(|v| (0.0..=1.0).contains(v))
+                            .ok_or_else(|| {
+                                CliError::Usage(format!("{flag} takes 0 to 1, got {value:?}"))
+                            })?;
+                    }
+                    "--username" => options.username = Some(args.string(&flag, inline)?),
+                    "--password" => options.password = Some(args.string(&flag, inline)?),
+                    "--output" => options.output = Some(args.value(&flag, inline)?.into()),
+                    "--dry-run" => options.dry_run = true,
+                    "--anonymous" => options.anonymous = true,
+                    "--quiet" => options.quiet = true,
+                    _ => return Err(CliError::Usage(format!("unknown option {flag}"))),
+                },
+                Arg::Positional(path) => options.paths.push(path.into()),
+            }
+        }
+        match (options.paths.is_empty(), options.report.is_some()) {
+            (true, false) => Err(CliError::Usage(
+                "upload needs at least one file or folder, or --report".into(),
+            )),
+            (false, true) => Err(CliError::Usage(
+                "--report replaces the file list; pass one or the other".into(),
+            )),
+            _ => Ok(options),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum Status {
+    Uploaded,
+    Duplicate,
+    /// `--dry-run`: everything checked out and the upload was skipped.
+    WouldUpload,
+    /// Below `--min-confidence`; left for a human to check.
+    Skipped,
+    Failed,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Uploaded => "uploaded",
+            Self::Duplicate => "already in database",
+            Self::WouldUpload => "would upload",
+            Self::Skipped => "skipped, low confidence",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemResult {
+    status: Status,
+    subtitle: PathBuf,
+    video: Option<PathBuf>,
+    #[serde(flatten)]
+    identified: Option<Identified>,
+    url: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    uploaded: usize,
+    duplicate: usize,
+    would_upload: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    dry_run: bool,
+    account: Option<String>,
+    summary: Summary,
+    items: Vec<ItemResult>,
+}
+
+pub async fn run(options: Options, identifier: &str) -> Result<i32, CliError> {
+    // Values given on the command line win over those in a reviewed report.
+    let (candidates, _extractions, notes) = match &options.report {
+        Some(report) => {
+            let mut candidates: Vec<_> = scan::read_report(report)?
+                .into_iter()
+                .map(|entry| {
+                    let overrides = Overrides {
+                        imdb_id: options
+                            .overrides
+                            .imdb_id
+                            .clone()
+                            .or_else(|| Some((entry.imdb_id.clone()?, Source::Report))),
+                        language: options
+                            .overrides
+                            .language
+                            .clone()
+                            .or_else(|| Some((entry.language.clone()?, Source::Report))),
+                    };
+                    (entry.candidate(), overrides)
+                })
+                .collect();
+            let (extractions, notes) =
+                pipeline::unpack_reported(candidates.iter_mut().map(|(candidate, _)| candidate));
+            (candidates, extractions, notes)
+        }
+        None => {
+            let discovery = pipeline::discover(&options.paths)?;
+            let candidates = discovery
+                .candidates
+                .into_iter()
+                .map(|candidate| (candidate, options.overrides.clone()))
+                .collect();
+            (candidates, discovery.extractions, discovery.notes)
+        }
+    };
+    let progress = Progress::new(options.quiet, candidates.len());
+    for note in &notes {
+        progress.note(note);
+    }
+    progress.note(&format!("found {} subtitle file(s)", candidates.len()));
+
+    let backend = Backend::open(identifier)?;
+    let (token, account) = backend
+        .log_in(
+            options.username.clone(),
+            options.password.clone(),
+            options.anonymous,
+        )
+        .await?;
+    progress.note(&format!(
+        "logged in {}",
+        account
+            .as_deref()
+            .map_or("anonymously".into(), |a| format!("as {a}"))
+    ));
+
+    let mut summary = Summary::default();
+    let mut items = Vec::with_capacity(candidates.len());
+    for (index, (candidate, overrides)) in candidates.iter().enumerate() {
+        progress.step(index, &candidate.subtitle, "identifying");
+        let item =
+            match pipeline::identify(candidate, overrides, &backend.xmlrpc, &token, &backend.rest)
+                .await
+            {
+                Ok(identified) if identified.confidence.overall < options.min_confidence => {
+                    ItemResult {
+                        status: Status::Skipped,
+                        subtitle: candidate.subtitle.clone(),
+                        video: candidate.video.clone(),
+                        identified: Some(identified),
+                        url: None,
+                        error: None,
+                    }
+                }
+                Ok(identified) => {
+                    upload(
+                        &backend,
+                        &token,
+                        account.as_deref(),
+                        identified,
+                        options.dry_run,
+                    )
+                    .await
+                }
+                Err(e) => ItemResult {
+                    status: Status::Failed,
+                    subtitle: candidate.subtitle.clone(),
+                    video: candidate.video.clone(),
+                    identified: None,
+                    url: None,
+                    error: Some(e.to_string()),
+                },
+            };
+        match item.status {
+            Status::Uploaded => summary.uploaded += 1,
+            Status::Duplicate => summary.duplicate += 1,
+            Status::WouldUpload => summary.would_upload += 1,
+            Status::Skipped => summary.skipped += 1,
+            Status::Failed => summary.failed += 1,
+        }
+        let message = match (&item.url, &item.error) {
+            (_, Some(error)) => format!("failed: {error}"),
+            (Some(url), None) => format!("{} {url}", item.status.label()),
+            (None, None) => item.status.label().to_string(),
+        };
+        progress.step(index, &candidate.subtitle, &message);
+        items.push(item);
+    }
+    let _ = backend.xmlrpc.log_out(&token).await;
+
+    let failed = summary.failed;
+    write_json(
+        &Report {
+            dry_run: options.dry_run,
+            account,
+            summary,
+            items,
+        },
+        options.output.as_deref(),
+    )?;
+    Ok(if failed > 0 { EXIT_FAILED } else { EXIT_OK })
+}
+
+async fn upload(
+    backend: &Backend,
+    token: &str,
+    account: Option<&str>,
+    identified: Identified,
+    dry_run: bool,
+) -> ItemResult {
+    let mut item = ItemResult {
+        status: Status::Failed,
+        subtitle: identified.subtitle.clone(),
+        video: identified.video.clone(),
+        identified: None,
+        url: None,
+        error: None,
+    };
+    let Some(info) = identified.upload_info() else {
+        item.error = Some(
+            match identified.imdb_id {
+                None => "movie not identified; pass --imdb",
+                Some(_) => "language not detected; pass --lang",
+            }
+            .into(),
+        );
+        item.identified = Some(identified);
+        return item;
+    };
+    let file = &identified.file;
+    let outcome = async {
+        let check = backend.xmlrpc.try_upload_subtitles(token, file).await?;
+        if check.already_in_db {
+            return Ok((Status::Duplicate, None));
+        }
+        if dry_run {
+            return Ok((Status::WouldUpload, None));
+        }
+        let result = backend.xmlrpc.upload_subtitles(token, &info, file).await?;
+        // The subtitle is on the server either way; history is best effort.
+        let _ = backend
+            .history
+            .record(HistoryRecord::new(&info, file, &result, account));
+        Ok::<_, XmlRpcError>((Status::Uploaded, Some(result.url)))
+    }
+    .await;
+    match outcome {
+        Ok((status, url)) => {
+            item.status = status;
+            item.url = url;
+        }
+        Err(e) => item.error = Some(e.to_string()),
+    }
+    item.identified = Some(identified);
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        // As left by `run`, which has already taken the command name.
+        let args: Vec<_> = ["upload"].iter().chain(args).map(Into::into).collect();
+        Options::parse(Args(args.into_iter().skip(1)))
+    }
+
+    #[test]
+    fn anonymous_is_opt_in() {
+        assert!(!parse(&["movie.srt"]).unwrap().anonymous);
+        assert!(parse(&["--anonymous", "movie.srt"]).unwrap().anonymous);
+    }
+
+    #[test]
+    fn rejects_unknown_and_conflicting_options() {
+        let cases = [
+            (
+                &["--format", "json", "a.srt"][..],
+                "unknown option --format",
+            ),
+            (&["--dryrun", "a.srt"], "unknown option --dryrun"),
+            (
+                &["--min-confidence=1.5", "a.srt"],
+                "--min-confidence takes 0 to 1, got \"1.5\"",
+            ),
+            (
+                &["--dry-run"],
+                "upload needs at least one file or folder, or --report",
+            ),
+            (
+                &["--report", "scan.json", "a.srt"],
+                "--report replaces the file list; pass one or the other",
+            ),
+        ];
+        for (args, message) in cases {
+            let error = parse(args).unwrap_err();
+            assert!(matches!(error, CliError::Usage(_)), "{args:?}");
+            assert_eq!(error.to_string(), message);
+        }
+        let options = parse(&["--report=scan.json", "--min-confidence", "0.8"]).unwrap();
+        assert_eq!(options.report, Some(PathBuf::from("scan.json")));
+        assert_eq!(options.min_confidence, 0.8);
+    }
+}
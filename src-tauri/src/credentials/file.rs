@@ -0,0 +1,161 @@
+//! Fallback secret store: a JSON map sealed with ChaCha20-Poly1305 under a
+//! random key in a neighbouring `.key` file, both readable by the owner only.
+
+use super::{CredentialError, SecretStore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MAGIC: &[u8] = b"OSUC1";
+const KEY_LEN: usize = 32;
+
+pub struct EncryptedFile {
+    path: PathBuf,
+    key_path: PathBuf,
+    /// Held across read-modify-write cycles.
+    lock: Mutex<()>,
+}
+
+impl EncryptedFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            key_path: path.with_extension("key"),
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn key(&self, create: bool) -> Result<Option<LessSafeKey>, CredentialError> {
+        let bytes = match fs::read(&self.key_path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && create => {
+                let mut bytes = vec![0u8; KEY_LEN];
+                SystemRandom::new()
+                    .fill(&mut bytes)
+                    .map_err(|_| CredentialError::Backend("no system randomness".into()))?;
+                write_private(&self.key_path, &bytes)?;
+                bytes
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(CredentialError::io(&self.key_path, e)),
+        };
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+            .map_err(|_| CredentialError::Corrupt("bad key file".into()))?;
+        Ok(Some(LessSafeKey::new(key)))
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, String>, CredentialError> {
+        let sealed = match fs::read(&self.path) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(CredentialError::io(&self.path, e)),
+        };
+        let corrupt =
+            || CredentialError::Corrupt(format!("{} cannot be decrypted", self.path.display()));
+        let Some(key) = self.key(false)? else {
+            return Err(corrupt());
+        };
+        let rest = sealed.strip_prefix(MAGIC).ok_or_else(corrupt)?;
+        if rest.len() < NONCE_LEN {
+            return Err(corrupt());
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| corrupt())?;
+        let mut buf = ciphertext.to_vec();
+        let plain = key
+            .open_in_place(nonce, Aad::from(MAGIC), &mut buf)
+            .map_err(|_| corrupt())?;
+        serde_json::from_slice(plain).map_err(|e| CredentialError::Corrupt(e.to_string()))
+    }
+
+    /// Like `load`, but starts over when the file cannot be decrypted (e.g.
+    /// the key file was lost), so saving and forgetting still work.
+    fn load_or_reset(&self) -> Result<BTreeMap<String, String>, CredentialError> {
+        match self.load() {
+            Err(CredentialError::Corrupt(_)) => Ok(BTreeMap::new()),
+            result => result,
+        }
+    }
+
+    fn store(&self, secrets: &BTreeMap<String, String>) -> Result<(), CredentialError> {
+        if secrets.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(CredentialError::io(&self.path, e)),
+            };
+        }
+        let key = self.key(true)?.expect("created above");
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| CredentialError::Backend("no system randomness".into()))?;
+        let mut buf = serde_json::to_vec(secrets).expect("secrets serialize");
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(MAGIC),
+            &mut buf,
+        )
+        .map_err(|_| CredentialError::Backend("encryption failed".into()))?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + buf.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&buf);
+        write_private(&self.path, &sealed)
+    }
+}
+
+/// Writes `bytes` atomically into a file only the current user can read.
+fn write_private(path: &Path, bytes: &[u8]) -> Result<(), CredentialError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| CredentialError::io(dir, e))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let write = |file: &mut File| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    };
+    options
+        .open(&tmp)
+        .and_then(|mut file| write(&mut file))
+        .and_then(|()| fs::rename(&tmp, path))
+        .map_err(|e| CredentialError::io(path, e))
+}
+
+impl SecretStore for EncryptedFile {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, CredentialError> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(self.load()?.remove(key))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), CredentialError> {
+        let _lock = self.lock.lock().unwrap();
+        let mut secrets = self.load_or_reset()?;
+        secrets.insert(key.into(), value.into());
+        self.store(&secrets)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), CredentialError> {
+        let _lock = self.lock.lock().unwrap();
+        let mut secrets = self.load_or_reset()?;
+        secrets.remove(key);
+        self.store(&secrets)
+    }
+}
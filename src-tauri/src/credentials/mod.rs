@@ -0,0 +1,512 @@
+//! Saved login: the OpenSubtitles username and password, the XML-RPC session
+//! token and the REST JWT, kept in the OS secret store rather than in the
+//! webview's `?sid=` parameter or `PHPSESSID` cookie.
+//!
+//! The OS keyring (Secret Service on Linux, Keychain on macOS, Credential
+//! Manager on Windows) is used when it answers. Headless Linux boxes often
+//! have no Secret Service running, so the fallback is a file encrypted with
+//! a key kept beside it, which keeps the password out of backups of the data
+//! file but not away from someone who can read the whole directory.
+//! `credentials.store` in the settings picks a backend.
+
+mod file;
+mod os;
+
+use crate::accounts::{AccountError, Accounts};
+use crate::api::rest::{RestClient, SavedSession};
+use crate::api::xmlrpc::{UserInfo, XmlRpcClient, XmlRpcError};
+use crate::error::impl_serialize_error;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub use file::EncryptedFile;
+pub use os::OsKeyring;
+
+/// Which [`SecretStore`] to use; `credentials.store` in the settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StoreKind {
+    #[default]
+    Auto,
+    Keyring,
+    File,
+}
+
+impl std::str::FromStr for StoreKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "auto" => Ok(Self::Auto),
+            "keyring" => Ok(Self::Keyring),
+            "file" => Ok(Self::File),
+            other => Err(format!(
+                "unknown credential store {other:?}; use auto, keyring or file"
+            )),
+        }
+    }
+}
+
+/// Entry holding the saved account.
+const ACCOUNT_KEY: &str = "account";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("secret store unavailable: {0}")]
+    Backend(String),
+    #[error("could not access {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("saved credentials are unreadable: {0}")]
+    Corrupt(String),
+    #[error("no saved credentials")]
+    NotSaved,
+    #[error("login failed: {0}")]
+    Login(#[from] XmlRpcError),
+}
+
+impl CredentialError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Backend(_) => "backend",
+            Self::Io { .. } => "io",
+            Self::Corrupt(_) => "corrupt",
+            Self::NotSaved => "notSaved",
+            Self::Login(_) => "login",
+        }
+    }
+
+    fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl_serialize_error!(CredentialError);
+
+/// A place to keep small secrets by name.
+pub trait SecretStore: Send + Sync {
+    /// Short name reported to the UI, e.g. `keyring`.
+    fn name(&self) -> &'static str;
+    fn get(&self, key: &str) -> Result<Option<String>, CredentialError>;
+    fn set(&self, key: &str, value: &str) -> Result<(), CredentialError>;
+    /// Removes `key`; removing a missing key is not an error.
+    fn delete(&self, key: &str) -> Result<(), CredentialError>;
+}
+
+/// Everything saved for the account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Account {
+    username: String,
+    password: String,
+    /// Last XML-RPC session token.
+    token: Option<String>,
+    rest: Option<SavedSession>,
+}
+
+/// What the UI learns about the saved account; never the password.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedCredentials {
+    pub username: String,
+    pub store: &'static str,
+}
+
+/// A live session restored from or created with saved credentials.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedIn {
+    pub username: String,
+    /// XML-RPC token, used where the UI used the `sid` parameter.
+    pub token: String,
+    /// `None` when the saved token was kept without checking it, e.g.
+    /// because the server could not be reached.
+    pub user: Option<UserInfo>,
+    /// Whether the REST client has a session too; it needs an API key.
+    pub rest: bool,
+}
+
+/// Shared credential handle; cheap to clone.
+#[derive(Clone)]
+pub struct Credentials {
+    store: Arc<dyn SecretStore>,
+    /// Serializes logins so parallel expiries log in once.
+    login: Arc<tauri::async_runtime::Mutex<()>>,
+}
+
+impl Credentials {
+    pub fn with_store(store: impl SecretStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            login: Arc::default(),
+        }
+    }
+
+    /// Opens the backend `kind` names; [`StoreKind::Auto`] is the OS
+    /// keyring when it answers and the encrypted file in `data_dir`
+    /// otherwise.
+    pub fn open(service: &str, data_dir: &Path, kind: StoreKind) -> Self {
+        let file = || EncryptedFile::new(data_dir.join("credentials.bin"));
+        match kind {
+            StoreKind::Keyring => Self::with_store(OsKeyring::new(service)),
+            StoreKind::File => Self::with_store(file()),
+            StoreKind::Auto => {
+                let keyring = OsKeyring::new(service);
+                if keyring.available() {
+                    Self::with_store(keyring)
+                } else {
+                    Self::with_store(file())
+                }
+            }
+        }
+    }
+
+    pub fn store_name(&self) -> &'static str {
+        self.store.name()
+    }
+
+    fn account(&self) -> Result<Option<Account>, CredentialError> {
+        self.store
+            .get(ACCOUNT_KEY)?
+            .map(|json| {
+                serde_json::from_str(&json).map_err(|e| CredentialError::Corrupt(e.to_string()))
+            })
+            .transpose()
+    }
+
+    fn put(&self, account: &Account) -> Result<(), CredentialError> {
+        let json = serde_json::to_string(account).expect("account serializes");
+        self.store.set(ACCOUNT_KEY, &json)
+    }
+
+    /// Saves a username and password, dropping tokens of another account.
+    pub fn save(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<SavedCredentials, CredentialError> {
+        // An unreadable entry is simply replaced.
+        let previous = self
+            .account()
+            .ok()
+            .flatten()
+            .filter(|a| a.username == username);
+        self.put(&Account {
+            username: username.into(),
+            password: password.into(),
+            token: previous.as_ref().and_then(|a| a.token.clone()),
+            rest: previous.and_then(|a| a.rest),
+        })?;
+        Ok(SavedCredentials {
+            username: username.into(),
+            store: self.store_name(),
+        })
+    }
+
+    pub fn saved(&self) -> Result<Option<SavedCredentials>, CredentialError> {
+        Ok(self.account()?.map(|account| SavedCredentials {
+            username: account.username,
+            store: self.store_name(),
+        }))
+    }
+
+    /// The saved username and password, for logging in elsewhere (the CLI).
+    pub fn login(&self) -> Result<Option<(String, String)>, CredentialError> {
+        Ok(self.account()?.map(|a| (a.username, a.password)))
+    }
+
+    pub fn forget(&self) -> Result<(), CredentialError> {
+        self.store.delete(ACCOUNT_KEY)
+    }
+
+    /// Restores the saved sessions, checking the XML-RPC token with
+    /// `GetUserInfo` and logging in again when it has expired.
+    pub async fn resume(
+        &self,
+        xmlrpc: &XmlRpcClient,
+        rest: &RestClient,
+    ) -> Result<SignedIn, CredentialError> {
+        let _login = self.login.lock().await;
+        let mut account = self.account()?.ok_or(CredentialError::NotSaved)?;
+        let mut signed_in = None;
+        if let Some(token) = account.token.clone() {
+            match xmlrpc.get_user_info(&token).await {
+                Ok(user) => signed_in = Some((token, Some(user))),
+                Err(e) if e.is_session_error() => {}
+                // Offline: keep the token, uploads will log in again if it
+                // turns out to be stale.
+                Err(_) => signed_in = Some((token, None)),
+            }
+        }
+        let (token, user) = match signed_in {
+            Some(signed_in) => signed_in,
+            None => {
+                let login = xmlrpc
+                    .log_in(&account.username, &account.password, "en")
+                    .await?;
+                account.token = Some(login.token.clone());
+                (login.token, login.user)
+            }
+        };
+
+        let restored = match &account.rest {
+            Some(saved) => {
+                rest.restore_session(saved, &account.username, &account.password)
+                    .await
+            }
+            None => false,
+        };
+        if !restored {
+            // Without an API key there is no REST session to have.
+            let _ = rest.log_in(&account.username, &account.password).await;
+        }
+        account.rest = rest.saved_session().await;
+        self.put(&account)?;
+        Ok(SignedIn {
+            username: account.username,
+            token,
+            user,
+            rest: account.rest.is_some(),
+        })
+    }
+
+    /// Logs in again after `stale` was rejected, unless another caller has
+    /// already replaced it. Returns the new token and the account name.
+    pub async fn relogin(
+        &self,
+        xmlrpc: &XmlRpcClient,
+        stale: &str,
+    ) -> Result<(String, String), CredentialError> {
+        let _login = self.login.lock().await;
+        let mut account = self.account()?.ok_or(CredentialError::NotSaved)?;
+        if let Some(token) = account.token.as_ref().filter(|t| *t != stale) {
+            return Ok((token.clone(), account.username));
+        }
+        let login = xmlrpc
+            .log_in(&account.username, &account.password, "en")
+            .await?;
+        account.token = Some(login.token.clone());
+        self.put(&account)?;
+        Ok((login.token, account.username))
+    }
+}
+
+#[tauri::command]
+pub fn credentials_save(
+    credentials: tauri::State<'_, Credentials>,
+    username: String,
+    password: String,
+) -> Result<SavedCredentials, CredentialError> {
+    credentials.save(&username, &password)
+}
+
+/// Signs in with the saved account, which joins the signed-in accounts as
+/// the active one, or returns `None` when there is none.
+#[tauri::command]
+pub async fn credentials_load(
+    accounts: tauri::State<'_, Accounts>,
+    queue: tauri::State<'_, crate::upload::queue::UploadQueue>,
+) -> Result<Option<SignedIn>, AccountError> {
+    match accounts.resume().await {
+        Ok(signed_in) => {
+            accounts.sync(&queue).await;
+            Ok(Some(signed_in))
+        }
+        Err(AccountError::Credentials(CredentialError::NotSaved)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[tauri::command]
+pub fn credentials_saved(
+    credentials: tauri::State<'_, Credentials>,
+) -> Result<Option<SavedCredentials>, CredentialError> {
+    credentials.saved()
+}
+
+#[tauri::command]
+pub fn credentials_forget(
+    credentials: tauri::State<'_, Credentials>,
+) -> Result<(), CredentialError> {
+    credentials.forget()
+}
+
+#[cfg(test)]
+pub(crate) mod memory {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Keeps secrets in memory only.
+    #[derive(Default)]
+    pub(crate) struct Memory(Mutex<HashMap<String, String>>);
+
+    impl SecretStore for Memory {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        fn get(&self, key: &str) -> Result<Option<String>, CredentialError> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<(), CredentialError> {
+            self.0.lock().unwrap().insert(key.into(), value.into());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), CredentialError> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::memory::Memory;
+    use super::*;
+    use crate::api::middleware::Http;
+    use crate::api::xmlrpc::stand_in::{status, StandIn};
+    use crate::api::xmlrpc::Value;
+    use tauri::async_runtime::block_on;
+
+    fn with_token(credentials: &Credentials, token: &str) {
+        let mut account = credentials.account().unwrap().unwrap();
+        account.token = Some(token.into());
+        credentials.put(&account).unwrap();
+    }
+
+    fn token(credentials: &Credentials) -> Option<String> {
+        credentials.account().unwrap().unwrap().token
+    }
+
+    /// Answers `GetUserInfo` for `fresh` only and hands out `fresh` on login.
+    fn server(fresh: &'static str) -> StandIn {
+        StandIn::start(move |call| match call.method.as_str() {
+            "LogIn" => Ok(status("200 OK", [("token", fresh.into())])),
+            "GetUserInfo" => match call.params.first() {
+                Some(Value::String(token)) if token == fresh => Ok(status(
+                    "200 OK",
+                    [(
+                        "data",
+                        Value::Struct([("UserNickName".into(), "tester".into())].into()),
+                    )],
+                )),
+                _ => Ok(status("406 No session", [])),
+            },
+            _ => Err(500),
+        })
+    }
+
+    /// A REST client without an API key, which never gets a session.
+    fn rest() -> RestClient {
+        RestClient::with_http(
+            Http::new(reqwest::Client::new()),
+            "http://127.0.0.1:9",
+            None,
+        )
+    }
+
+    #[test]
+    fn save_load_and_forget() {
+        let credentials = Credentials::with_store(Memory::default());
+        assert!(credentials.saved().unwrap().is_none());
+        assert!(credentials.login().unwrap().is_none());
+
+        let saved = credentials.save("tester", "secret").unwrap();
+        assert_eq!((saved.username.as_str(), saved.store), ("tester", "memory"));
+        assert_eq!(credentials.saved().unwrap().unwrap().username, "tester");
+        assert_eq!(
+            credentials.login().unwrap(),
+            Some(("tester".into(), "secret".into()))
+        );
+
+        // A new password keeps the token; another account drops it.
+        with_token(&credentials, "abc123");
+        credentials.save("tester", "changed").unwrap();
+        assert_eq!(token(&credentials).as_deref(), Some("abc123"));
+        credentials.save("other", "secret").unwrap();
+        assert_eq!(token(&credentials), None);
+
+        credentials.forget().unwrap();
+        assert!(credentials.saved().unwrap().is_none());
+        credentials.forget().unwrap();
+    }
+
+    #[test]
+    fn resume_logs_in_again_on_an_expired_session() {
+        let server = server("fresh");
+        let credentials = Credentials::with_store(Memory::default());
+        credentials.save("tester", "secret").unwrap();
+        with_token(&credentials, "expired");
+
+        let signed_in = block_on(credentials.resume(&server.client(), &rest())).unwrap();
+        assert_eq!(signed_in.token, "fresh");
+        assert!(!signed_in.rest);
+        assert_eq!(token(&credentials).as_deref(), Some("fresh"));
+        let methods: Vec<_> = server.calls().into_iter().map(|c| c.method).collect();
+        assert_eq!(methods, ["GetUserInfo", "LogIn"]);
+
+        // A live token is kept as it is.
+        let signed_in = block_on(credentials.resume(&server.client(), &rest())).unwrap();
+        assert_eq!(signed_in.token, "fresh");
+        assert_eq!(
+            signed_in.user.and_then(|u| u.nickname).as_deref(),
+            Some("tester")
+        );
+        assert_eq!(server.calls().len(), 3);
+    }
+
+    #[test]
+    fn relogin_replaces_a_stale_token_once() {
+        let server = server("fresh");
+        let client = server.client();
+        let credentials = Credentials::with_store(Memory::default());
+        assert!(matches!(
+            block_on(credentials.relogin(&client, "expired")),
+            Err(CredentialError::NotSaved)
+        ));
+
+        credentials.save("tester", "secret").unwrap();
+        with_token(&credentials, "expired");
+        let (token, username) = block_on(credentials.relogin(&client, "expired")).unwrap();
+        assert_eq!((token.as_str(), username.as_str()), ("fresh", "tester"));
+        // A second caller holding the same stale token gets the new one.
+        let (token, _) = block_on(credentials.relogin(&client, "expired")).unwrap();
+        assert_eq!(token, "fresh");
+        assert_eq!(server.calls().len(), 1);
+    }
+
+    #[test]
+    fn corrupt_file_store() {
+        let path = std::env::temp_dir().join(format!("{}-credentials.bin", std::process::id()));
+        let credentials = Credentials::with_store(EncryptedFile::new(path.clone()));
+        credentials.save("tester", "secret").unwrap();
+        assert_eq!(
+            credentials.login().unwrap(),
+            Some(("tester".into(), "secret".into()))
+        );
+
+        std::fs::write(&path, b"OSUC1 not what was written").unwrap();
+        let error = credentials.saved().unwrap_err();
+        assert_eq!(error.kind(), "corrupt");
+
+        // Saving again starts over rather than failing for good.
+        credentials.save("tester", "secret").unwrap();
+        assert_eq!(credentials.saved().unwrap().unwrap().username, "tester");
+        std::fs::remove_file(path.with_extension("key")).unwrap();
+        assert_eq!(credentials.saved().unwrap_err().kind(), "corrupt");
+        credentials.forget().unwrap();
+        assert!(credentials.saved().unwrap().is_none());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("key"));
+    }
+}
@@ -0,0 +1,57 @@
+//! The platform secret store through the `keyring` crate.
+
+use super::{CredentialError, SecretStore};
+
+pub struct OsKeyring {
+    service: String,
+}
+
+impl OsKeyring {
+    /// Entries are filed under `service`, the app identifier.
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+
+    fn entry(&self, key: &str) -> Result<keyring::Entry, CredentialError> {
+        keyring::Entry::new(&self.service, key).map_err(backend)
+    }
+
+    /// Whether the store answers at all; a missing entry is an answer.
+    pub fn available(&self) -> bool {
+        self.get("probe").is_ok()
+    }
+}
+
+fn backend(err: keyring::Error) -> CredentialError {
+    CredentialError::Backend(err.to_string())
+}
+
+impl SecretStore for OsKeyring {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, CredentialError> {
+        match self.entry(key)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(keyring::Error::BadEncoding(_)) => {
+                Err(CredentialError::Corrupt(format!("{key} is not UTF-8")))
+            }
+            Err(e) => Err(backend(e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), CredentialError> {
+        self.entry(key)?.set_password(value).map_err(backend)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), CredentialError> {
+        match self.entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(backend(e)),
+        }
+    }
+}
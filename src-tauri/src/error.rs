@@ -0,0 +1,31 @@
+//! Shared plumbing for the error types returned from Tauri commands.
+
+use serde::ser::{SerializeStruct, Serializer};
+use std::fmt::Display;
+
+/// Serializes a command error as `{ "kind": ..., "message": ... }` so the
+/// webview can branch on `kind` while still showing the readable message.
+pub(crate) fn serialize_error<S: Serializer>(
+    serializer: S,
+    kind: &str,
+    error: &impl Display,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("Error", 2)?;
+    state.serialize_field("kind", kind)?;
+    state.serialize_field("message", &error.to_string())?;
+    state.end()
+}
+
+/// Implements `serde::Serialize` for an error enum that exposes
+/// `fn kind(&self) -> &'static str`.
+macro_rules! impl_serialize_error {
+    ($ty:ty) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::error::serialize_error(serializer, self.kind(), self)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_serialize_error;
@@ -0,0 +1,170 @@
+//! Subtitle languages as OpenSubtitles names them, and the spellings they
+//! turn up under in file and folder names (`Movie.en.srt`, `2_English.srt`,
+//! `Subs/Spa.srt`, `Movie.pt-BR.srt`).
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Language {
+    /// `SubLanguageID` in the XML-RPC API (ISO 639-2/B, plus a few
+    /// OpenSubtitles-specific codes such as `pob`).
+    pub code: &'static str,
+    #[serde(rename = "iso639_1")]
+    pub iso639_1: &'static str,
+    pub name: &'static str,
+    #[serde(skip)]
+    aliases: &'static [&'static str],
+}
+
+macro_rules! languages {
+    ($(($code:literal, $iso:literal, $name:literal, [$($alias:literal),*])),* $(,)?) => {
+        pub const LANGUAGES: &[Language] = &[
+            $(Language { code: $code, iso639_1: $iso, name: $name, aliases: &[$($alias),*] }),*
+        ];
+    };
+}
+
+languages![
+    ("eng", "en", "English", ["english"]),
+    (
+        "spa",
+        "es",
+        "Spanish",
+        ["spanish", "espanol", "castellano", "esp"]
+    ),
+    ("spl", "ea", "Spanish (LA)", ["latino", "latam", "es-419"]),
+    ("fre", "fr", "French", ["fra", "french", "francais"]),
+    ("ger", "de", "German", ["deu", "german", "deutsch"]),
+    ("ita", "it", "Italian", ["italian", "italiano"]),
+    ("por", "pt", "Portuguese", ["portuguese", "portugues"]),
+    (
+        "pob",
+        "pb",
+        "Portuguese (BR)",
+        ["brazilian", "ptbr", "pt-br"]
+    ),
+    ("dut", "nl", "Dutch", ["nld", "dutch", "nederlands"]),
+    ("swe", "sv", "Swedish", ["swedish", "svenska"]),
+    ("nor", "no", "Norwegian", ["nob", "norwegian", "norsk"]),
+    ("dan", "da", "Danish", ["danish", "dansk"]),
+    ("fin", "fi", "Finnish", ["finnish", "suomi"]),
+    ("ice", "is", "Icelandic", ["isl", "icelandic"]),
+    ("pol", "pl", "Polish", ["polish", "polski"]),
+    ("cze", "cs", "Czech", ["ces", "czech", "cesky"]),
+    ("slo", "sk", "Slovak", ["slk", "slovak"]),
+    ("slv", "sl", "Slovenian", ["slovenian", "slovene"]),
+    ("hun", "hu", "Hungarian", ["hungarian", "magyar"]),
+    ("rum", "ro", "Romanian", ["ron", "romanian"]),
+    ("bul", "bg", "Bulgarian", ["bulgarian"]),
+    ("hrv", "hr", "Croatian", ["croatian", "hrvatski"]),
+    ("scc", "sr", "Serbian", ["srp", "serbian"]),
+    ("bos", "bs", "Bosnian", ["bosnian"]),
+    ("mac", "mk", "Macedonian", ["mkd", "macedonian"]),
+    ("alb", "sq", "Albanian", ["sqi", "albanian"]),
+    ("ell", "el", "Greek", ["gre", "greek"]),
+    ("tur", "tr", "Turkish", ["turkish"]),
+    ("rus", "ru", "Russian", ["russian"]),
+    ("ukr", "uk", "Ukrainian", ["ukrainian"]),
+    ("est", "et", "Estonian", ["estonian"]),
+    ("lav", "lv", "Latvian", ["latvian"]),
+    ("lit", "lt", "Lithuanian", ["lithuanian"]),
+    ("cat", "ca", "Catalan", ["catalan"]),
+    ("baq", "eu", "Basque", ["eus", "basque"]),
+    ("glg", "gl", "Galician", ["galician"]),
+    ("ara", "ar", "Arabic", ["arabic"]),
+    ("heb", "he", "Hebrew", ["hebrew"]),
+    ("per", "fa", "Persian", ["fas", "persian", "farsi"]),
+    ("hin", "hi", "Hindi", ["hindi"]),
+    ("ben", "bn", "Bengali", ["bengali"]),
+    ("tam", "ta", "Tamil", ["tamil"]),
+    ("tel", "te", "Telugu", ["telugu"]),
+    (
+        "chi",
+        "zh",
+        "Chinese (simplified)",
+        ["zho", "chs", "chinese"]
+    ),
+    ("zht", "zt", "Chinese (traditional)", ["cht"]),
+    ("jpn", "ja", "Japanese", ["japanese"]),
+    ("kor", "ko", "Korean", ["korean"]),
+    ("tha", "th", "Thai", ["thai"]),
+    ("vie", "vi", "Vietnamese", ["vietnamese"]),
+    ("ind", "id", "Indonesian", ["indonesian"]),
+    ("may", "ms", "Malay", ["msa", "malay"]),
+    ("afr", "af", "Afrikaans", ["afrikaans"]),
+    ("epo", "eo", "Esperanto", ["esperanto"]),
+    ("tgl", "tl", "Tagalog", ["tagalog", "filipino"]),
+    ("swa", "sw", "Swahili", ["swahili"]),
+    ("wel", "cy", "Welsh", ["cym", "welsh"]),
+    ("aze", "az", "Azerbaijani", ["azerbaijani"]),
+    ("bel", "be", "Belarusian", ["belarusian"]),
+    ("urd", "ur", "Urdu", ["urdu"]),
+    ("arm", "hy", "Armenian", ["hye", "armenian"]),
+    ("geo", "ka", "Georgian", ["kat", "georgian"]),
+    ("kan", "kn", "Kannada", ["kannada"]),
+    ("mal", "ml", "Malayalam", ["malayalam"]),
+    ("khm", "km", "Khmer", ["khmer"]),
+    ("bur", "my", "Burmese", ["mya", "burmese"]),
+    ("spn", "sp", "Spanish (EU)", []),
+    ("pom", "pm", "Portuguese (MZ)", []),
+    ("zhc", "zc", "Chinese (Cantonese)", ["cantonese"]),
+    ("zhe", "ze", "Chinese bilingual", []),
+    ("mne", "me", "Montenegrin", ["montenegrin"]),
+    ("ast", "at", "Asturian", ["asturian"]),
+    ("ext", "ex", "Extremaduran", ["extremaduran"]),
+    ("arg", "an", "Aragonese", ["aragonese"]),
+    ("oci", "oc", "Occitan", ["occitan"]),
+    ("bre", "br", "Breton", ["breton"]),
+    ("gle", "ga", "Irish", ["irish"]),
+    ("gla", "gd", "Gaelic", ["gaelic"]),
+    ("ltz", "lb", "Luxembourgish", ["luxembourgish"]),
+    ("sme", "se", "Northern Sami", ["sami"]),
+    ("ina", "ia", "Interlingua", ["interlingua"]),
+    ("tok", "tp", "Toki Pona", ["tokipona"]),
+    ("tet", "tm", "Tetum", ["tetum"]),
+    ("som", "so", "Somali", ["somali"]),
+    ("ibo", "ig", "Igbo", ["igbo"]),
+    ("amh", "am", "Amharic", ["amharic"]),
+    ("nav", "nv", "Navajo", ["navajo"]),
+    ("kaz", "kk", "Kazakh", ["kazakh"]),
+    ("tat", "tt", "Tatar", ["tatar"]),
+    ("tuk", "tk", "Turkmen", ["turkmen"]),
+    ("mon", "mn", "Mongolian", ["mongolian"]),
+    ("kur", "ku", "Kurdish", ["kurdish"]),
+    ("prs", "pr", "Dari", ["dari"]),
+    ("pus", "ps", "Pushto", ["pashto", "pushto"]),
+    ("snd", "sd", "Sindhi", ["sindhi"]),
+    ("uig", "ug", "Uyghur", ["uighur", "uyghur"]),
+    ("syr", "sy", "Syriac", ["syriac"]),
+    ("mar", "mr", "Marathi", ["marathi"]),
+    ("nep", "ne", "Nepali", ["nepali"]),
+    ("asm", "as", "Assamese", ["assamese"]),
+    ("mni", "ma", "Manipuri", ["manipuri"]),
+    ("sat", "sx", "Santali", ["santali"]),
+    ("ori", "or", "Odia", ["oriya"]),
+    ("sin", "si", "Sinhalese", ["sinhala"]),
+];
+
+/// Looks up a language by code, alias or English name, case-insensitively.
+///
+/// Two-letter codes are only matched when `allow_short` is set: on their own
+/// they collide with ordinary words (`it`, `no`) and release tags (`hi`).
+pub fn lookup(token: &str, allow_short: bool) -> Option<&'static Language> {
+    let token = token.to_ascii_lowercase();
+    if token.len() < 2 || (token.len() == 2 && !allow_short) {
+        return None;
+    }
+    LANGUAGES.iter().find(|language| {
+        language.code == token
+            || language.iso639_1 == token
+            || language.name.eq_ignore_ascii_case(&token)
+            || language.aliases.contains(&token.as_str())
+    })
+}
+
+/// Maps an ISO 639-1 or 639-2 code, as returned by language detection, to a
+/// `SubLanguageID`.
+pub fn sub_language_id(code: &str) -> Option<&'static str> {
+    lookup(code, true).map(|language| language.code)
+}
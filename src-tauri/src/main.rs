@@ -1,16 +1,181 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::Manager;
+mod accounts;
+mod api;
+mod archive;
+mod cache;
+mod cli;
+mod credentials;
+mod error;
+mod languages;
+mod media;
+mod movie_hash;
+mod pairing;
+mod release;
+mod scan;
+mod settings;
+mod subtitle;
+mod upload;
+mod watch;
+
+use tauri::{Emitter, Manager};
 
 fn main() {
+    let context = tauri::generate_context!();
+    if let Some(code) = cli::run(std::env::args_os().collect(), &context.config().identifier) {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .invoke_handler(tauri::generate_handler![
+            movie_hash::compute_movie_hash,
+            media::matroska::list_mkv_subtitle_tracks,
+            media::matroska::extract_mkv_subtitles,
+            media::mp4::list_mp4_subtitle_tracks,
+            media::mp4::extract_mp4_subtitles,
+            media::probe::probe_video,
+            api::xmlrpc::xmlrpc_log_in,
+            api::xmlrpc::xmlrpc_get_user_info,
+            api::xmlrpc::xmlrpc_guess_movie_from_string,
+            api::xmlrpc::xmlrpc_get_sub_languages,
+            api::xmlrpc::xmlrpc_check_sub_hash,
+            api::xmlrpc::xmlrpc_try_upload_subtitles,
+            api::xmlrpc::xmlrpc_upload_subtitles,
+            api::rest::rest_log_in,
+            api::rest::rest_log_out,
+            api::rest::rest_detect_language,
+            api::rest::rest_features,
+            api::rest::rest_guessit,
+            api::rest::rest_languages,
+            cache::cache_stats,
+            cache::cache_clear,
+            api::middleware::api_quota,
+            upload::queue::upload_queue_list,
+            upload::queue::upload_queue_set_session,
+            upload::queue::upload_queue_add,
+            upload::queue::upload_queue_pause,
+            upload::queue::upload_queue_resume,
+            upload::queue::upload_queue_reorder,
+            upload::queue::upload_queue_cancel,
+            upload::history::upload_history_query,
+            upload::history::upload_history_export,
+            pairing::pair_files,
+            release::parse_release_name,
+            scan::scan_directory,
+            watch::watch_get_config,
+            watch::watch_set_config,
+            watch::watch_reviews,
+            archive::archive_list,
+            archive::archive_extract,
+            archive::archive_release,
+            credentials::credentials_save,
+            credentials::credentials_load,
+            credentials::credentials_saved,
+            credentials::credentials_forget,
+            settings::get_settings,
+            settings::update_settings,
+            accounts::list_accounts,
+            accounts::add_account,
+            accounts::switch_account,
+            accounts::logout,
+            subtitle::encoding::detect_subtitle_encoding,
+            subtitle::language::detect_subtitle_language,
+            subtitle::encoding::transcode_subtitle,
+            subtitle::parse::parse_subtitle,
+            subtitle::convert::convert_subtitle,
+            subtitle::timing::shift_subtitle,
+            subtitle::timing::stretch_subtitle,
+            subtitle::timing::convert_subtitle_fps,
+        ])
         .setup(|app| {
+            // A broken settings file should not keep the app from starting;
+            // the settings screen shows what was wrong with it.
+            let settings_store = settings::SettingsStore::open_or_default(&app.path().app_config_dir()?);
+            let current = settings_store.current();
+
+            // API clients share one connection pool and the on-disk cache.
+            let data_dir = app.path().app_data_dir()?;
+            let cache = cache::ApiCache::open(data_dir.join("cache"), current.cache_max_bytes())?;
+            let handle = app.handle().clone();
+            let http = api::middleware::Http::new(api::http_client()).on_quota(move |quota| {
+                let _ = handle.emit(api::middleware::QUOTA_EVENT, quota);
+            });
+            app.manage(http.clone());
+            let xmlrpc =
+                api::xmlrpc::XmlRpcClient::with_http(http.clone(), &current.api.xmlrpc_endpoint)
+                    .with_cache(cache.clone());
+            app.manage(xmlrpc.clone());
+            let rest = api::rest::RestClient::with_http(
+                http,
+                &current.api.rest_base_url,
+                current.api_key(),
+            )
+            .with_cache(cache.clone());
+            app.manage(rest.clone());
+            app.manage(cache);
+
+            // A new API key applies at once; everything else on restart.
+            let handle = app.handle().clone();
+            let rest_key = rest.clone();
+            app.manage(settings_store.on_change(move |view| {
+                rest_key.set_api_key(view.settings.api_key());
+                let _ = handle.emit(settings::SETTINGS_EVENT, view);
+            }));
+
+            // The saved login lives in the OS keyring, or an encrypted file
+            // where there is none, and renews expired upload sessions.
+            let credentials = credentials::Credentials::open(
+                &app.config().identifier,
+                &data_dir,
+                current.credentials.store,
+            );
+            app.manage(credentials.clone());
+
+            // Several accounts can be signed in; the active one's REST
+            // session is on the shared client.
+            let accounts = accounts::Accounts::new(xmlrpc.clone(), rest.clone())
+                .with_credentials(credentials);
+            app.manage(accounts.clone());
+
+            // Queued uploads survive restarts and resume once a session is set.
+            let history = upload::history::UploadHistory::open(data_dir.join("upload-history.jsonl"))?;
+            app.manage(history.clone());
+            let handle = app.handle().clone();
+            let queue = upload::queue::UploadQueue::open(data_dir.join("upload-queue.jsonl"), xmlrpc.clone())?
+                .with_history(history)
+                .with_accounts(accounts)
+                .on_change(move |snapshot| {
+                    let _ = handle.emit(upload::queue::QUEUE_EVENT, snapshot);
+                });
+            queue.start();
+            app.manage(queue.clone());
+
+            // Watch folders feed the queue; polling starts even with no
+            // folders configured so settings changes apply without a restart.
+            let handle = app.handle().clone();
+            let watcher = watch::Watcher::open(&data_dir, queue, xmlrpc, rest)?.on_report(
+                move |report| {
+                    let _ = handle.emit(watch::WATCH_EVENT, report);
+                },
+            );
+            watcher.start();
+            app.manage(watcher);
+
+            // Subtitle packs are unpacked here; leftovers from a crash are
+            // cleared on startup.
+            app.manage(archive::ArchiveStore::new(
+                app.path().app_cache_dir()?.join("archives"),
+            ));
+
+            // Retimed files, so their uploads can say what was done.
+            app.manage(subtitle::timing::Resyncs::open(data_dir.join("resyncs.json")));
+
             #[cfg(debug_assertions)] // only include this code on debug builds
             {
                 let window = app.get_webview_window("main").unwrap();
@@ -27,6 +192,6 @@ fn main() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
\ No newline at end of file
@@ -0,0 +1,165 @@
+//! AVI (RIFF) header probing.
+//!
+//! Only the `hdrl` list is read: `avih` for the frame size, each stream's
+//! `strh`/`strf` for codec and rate, and OpenDML `dmlh` for the real frame
+//! count of files larger than 1 GB.
+
+use super::probe::{codec_name, AudioTrack, Container, VideoProbe};
+use super::MediaError;
+use crate::movie_hash::read_exact_at;
+use std::path::Path;
+
+/// `hdrl` is a few KB in practice; anything bigger is not a real header list.
+const MAX_HDRL: u32 = 16 * 1024 * 1024;
+
+/// Iterates `(fourcc, list type, payload)` over RIFF chunks; `list type` is
+/// set for `LIST` chunks and the payload then excludes it.
+fn chunks(data: &[u8]) -> impl Iterator<Item = ([u8; 4], Option<[u8; 4]>, &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || {
+        if data.len().saturating_sub(pos) < 8 {
+            return None;
+        }
+        let id: [u8; 4] = data[pos..pos + 4].try_into().unwrap();
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let start = pos + 8;
+        let end = start.saturating_add(size).min(data.len());
+        pos = end + (size & 1);
+        if &id == b"LIST" && end - start >= 4 {
+            let list: [u8; 4] = data[start..start + 4].try_into().unwrap();
+            Some((id, Some(list), &data[start + 4..end]))
+        } else {
+            Some((id, None, &data[start..end]))
+        }
+    })
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+pub fn probe(path: &Path, byte_size: u64) -> Result<VideoProbe, MediaError> {
+    let file = super::open(path)?;
+    let read = |buf: &mut [u8], offset| {
+        read_exact_at(&file, buf, offset).map_err(|e| MediaError::io(path, e))
+    };
+
+    // RIFF header, then chunks until LIST hdrl (always the first list).
+    let mut pos = 12u64;
+    let hdrl = loop {
+        if pos + 12 > byte_size {
+            return Err(MediaError::Malformed("AVI without hdrl list".into()));
+        }
+        let mut header = [0u8; 12];
+        read(&mut header, pos)?;
+        let size = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if &header[..4] == b"LIST" && &header[8..12] == b"hdrl" {
+            if size > MAX_HDRL {
+                return Err(MediaError::Malformed("oversized AVI hdrl list".into()));
+            }
+            let mut data = vec![0u8; size.saturating_sub(4) as usize];
+            read(&mut data, pos + 12)?;
+            break data;
+        }
+        pos += 8 + u64::from(size) + u64::from(size & 1);
+    };
+
+    let mut probe = VideoProbe::new(Container::Avi, byte_size);
+    let mut avih_frames = None;
+    let mut odml_frames = None;
+    for (id, list, data) in chunks(&hdrl) {
+        match (&id, list.as_ref()) {
+            (b"avih", None) => {
+                let micros_per_frame = le_u32(data, 0).unwrap_or(0);
+                if micros_per_frame > 0 {
+                    probe.fps = Some(1_000_000.0 / f64::from(micros_per_frame));
+                }
+                avih_frames = le_u32(data, 16).map(u64::from);
+                probe.width = le_u32(data, 32).filter(|w| *w > 0);
+                probe.height = le_u32(data, 36).filter(|h| *h > 0);
+            }
+            (b"LIST", Some(b"strl")) => read_stream(data, &mut probe),
+            (b"LIST", Some(b"odml")) => {
+                odml_frames = chunks(data)
+                    .find(|(id, _, _)| id == b"dmlh")
+                    .and_then(|(_, _, dmlh)| le_u32(dmlh, 0))
+                    .map(u64::from);
+            }
+            _ => {}
+        }
+    }
+    // dwTotalFrames in avih only counts the first RIFF segment of OpenDML files.
+    probe.frame_count = odml_frames.or(probe.frame_count).or(avih_frames);
+    if let (Some(frames), Some(fps)) = (probe.frame_count, probe.fps) {
+        probe.duration_ms = Some((frames as f64 * 1000.0 / fps).round() as u64);
+    }
+    Ok(probe)
+}
+
+/// Applies one `strl` list (stream header + format) to the probe.
+fn read_stream(strl: &[u8], probe: &mut VideoProbe) {
+    let mut strh = None;
+    let mut strf = None;
+    for (id, _, data) in chunks(strl) {
+        match &id {
+            b"strh" => strh = Some(data),
+            b"strf" => strf = Some(data),
+            _ => {}
+        }
+    }
+    let Some(strh) = strh else { return };
+    let kind = &strh[..4.min(strh.len())];
+    let handler = strh.get(4..8).map(fourcc).unwrap_or_default();
+    let scale = le_u32(strh, 20).unwrap_or(0);
+    let rate = le_u32(strh, 24).unwrap_or(0);
+    let length = le_u32(strh, 32).unwrap_or(0);
+
+    match kind {
+        b"vids" if probe.video_codec.is_none() => {
+            // BITMAPINFOHEADER.biCompression is more reliable than fccHandler.
+            let compression = strf.and_then(|f| f.get(16..20)).map(fourcc);
+            let raw = compression.filter(|c| !c.is_empty()).unwrap_or(handler);
+            probe.video_codec = Some(codec_name(&raw));
+            if scale > 0 && rate > 0 {
+                probe.fps = Some(f64::from(rate) / f64::from(scale));
+            }
+            if length > 0 {
+                probe.frame_count = Some(u64::from(length));
+            }
+        }
+        b"auds" => {
+            let format = strf.and_then(|f| le_u16(f, 0)).unwrap_or(0);
+            probe.audio_tracks.push(AudioTrack {
+                codec: wave_format_name(format),
+                language: None,
+                channels: strf.and_then(|f| le_u16(f, 2)).map(u32::from),
+                sample_rate: strf.and_then(|f| le_u32(f, 4)),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn fourcc(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+fn wave_format_name(tag: u16) -> String {
+    match tag {
+        0x0001 => "pcm".into(),
+        0x0050 => "mp2".into(),
+        0x0055 => "mp3".into(),
+        0x00FF | 0x1610 | 0x706D => "aac".into(),
+        0x0161..=0x0163 => "wma".into(),
+        0x2000 => "ac3".into(),
+        0x2001 => "dts".into(),
+        0x674F..=0x6751 => "vorbis".into(),
+        other => format!("0x{other:04x}"),
+    }
+}
@@ -0,0 +1,167 @@
+//! Minimal EBML element reader.
+//!
+//! Only element headers are decoded eagerly; payloads are read on demand or
+//! skipped with a seek, so walking a large Matroska file touches very little
+//! of it.
+
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Header {
+    pub id: u32,
+    /// `None` for elements written with the "unknown size" marker.
+    pub size: Option<u64>,
+    /// Absolute offset of the first payload byte.
+    pub data_start: u64,
+    pub header_len: u64,
+}
+
+impl Header {
+    /// Absolute end offset of the payload, if the size is known.
+    pub fn end(&self) -> Option<u64> {
+        // A corrupt size saturates, and lands past any parent or file end.
+        self.size.map(|size| self.data_start.saturating_add(size))
+    }
+
+    /// Absolute offset of the header itself.
+    pub fn start(&self) -> u64 {
+        self.data_start - self.header_len
+    }
+}
+
+pub struct EbmlReader<R> {
+    inner: BufReader<R>,
+    pos: u64,
+    len: u64,
+}
+
+impl<R: Read + Seek> EbmlReader<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+        Ok(Self {
+            inner: BufReader::with_capacity(64 * 1024, inner),
+            pos: 0,
+            len,
+        })
+    }
+
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn file_len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn seek(&mut self, pos: u64) -> io::Result<()> {
+        if pos != self.pos {
+            // Relative seeks keep the buffer when the target is already in it.
+            self.inner.seek_relative(pos as i64 - self.pos as i64)?;
+            self.pos = pos;
+        }
+        Ok(())
+    }
+
+    /// Reads the next element header, or `None` at end of file.
+    pub fn read_header(&mut self) -> io::Result<Option<Header>> {
+        if self.pos >= self.len {
+            return Ok(None);
+        }
+        let start = self.pos;
+        let (id, _) = self.read_vint(4, false)?;
+        let (size, size_len) = self.read_vint(8, true)?;
+        // All value bits set means "unknown size".
+        let size = (size != (1u64 << (7 * size_len)) - 1).then_some(size);
+        Ok(Some(Header {
+            id: id as u32,
+            size,
+            data_start: self.pos,
+            header_len: self.pos - start,
+        }))
+    }
+
+    /// Reads a size-style vint (marker bit stripped), as used for block track numbers.
+    pub fn read_size_vint(&mut self) -> io::Result<(u64, u32)> {
+        self.read_vint(8, true)
+    }
+
+    /// Reads a variable-length integer. IDs keep their length marker bit,
+    /// sizes have it masked off. Returns the value and its encoded length.
+    fn read_vint(&mut self, max_len: usize, strip_marker: bool) -> io::Result<(u64, u32)> {
+        let first = self.read_u8()?;
+        let len = first.leading_zeros() + 1;
+        if len as usize > max_len {
+            return Err(invalid("invalid EBML variable-length integer"));
+        }
+        let mut value = if strip_marker {
+            u64::from(first) & (0xFF >> len)
+        } else {
+            u64::from(first)
+        };
+        for _ in 1..len {
+            value = (value << 8) | u64::from(self.read_u8()?);
+        }
+        Ok((value, len))
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8; 1];
+        self.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.pos += buf.len() as u64;
+        Ok(())
+    }
+
+    pub fn read_bytes(&mut self, header: &Header) -> io::Result<Vec<u8>> {
+        let size = header
+            .size
+            .ok_or_else(|| invalid("unexpected unknown-size element"))?;
+        if size > self.len.saturating_sub(self.pos) {
+            return Err(invalid("element extends past end of file"));
+        }
+        let mut buf = vec![0u8; size as usize];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn read_uint(&mut self, header: &Header) -> io::Result<u64> {
+        let bytes = self.read_bytes(header)?;
+        if bytes.len() > 8 {
+            return Err(invalid("unsigned integer element longer than 8 bytes"));
+        }
+        Ok(bytes.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+    }
+
+    pub fn read_float(&mut self, header: &Header) -> io::Result<f64> {
+        let bytes = self.read_bytes(header)?;
+        match bytes.len() {
+            0 => Ok(0.0),
+            4 => Ok(f64::from(f32::from_be_bytes(bytes.try_into().unwrap()))),
+            8 => Ok(f64::from_be_bytes(bytes.try_into().unwrap())),
+            _ => Err(invalid("float element must be 4 or 8 bytes")),
+        }
+    }
+
+    pub fn read_string(&mut self, header: &Header) -> io::Result<String> {
+        let bytes = self.read_bytes(header)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    /// Moves past the payload of `header`.
+    pub fn skip(&mut self, header: &Header) -> io::Result<()> {
+        let end = header
+            .end()
+            .ok_or_else(|| invalid("cannot skip unknown-size element"))?;
+        self.seek(end)
+    }
+}
+
+pub(crate) fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
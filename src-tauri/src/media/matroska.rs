@@ -0,0 +1,1031 @@
+//! Matroska / WebM subtitle tracks.
+//!
+//! The segment is parsed up to the first cluster (following SeekHead entries
+//! for anything stored after the clusters), then only the clusters that Cues
+//! point at for the requested tracks are visited. Files without usable cues
+//! fall back to a header-only walk over every cluster, skipping block payloads
+//! of other tracks.
+
+use super::ebml::{self, EbmlReader, Header};
+use super::{ssa_timestamp, ExtractedSubtitle, MediaError, SubtitleTrack, TextCue, TextFormat};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, Read as _};
+use std::path::{Path, PathBuf};
+
+pub mod ids {
+    pub const EBML: u32 = 0x1A45DFA3;
+    pub const DOC_TYPE: u32 = 0x4282;
+    pub const SEGMENT: u32 = 0x18538067;
+
+    pub const SEEK_HEAD: u32 = 0x114D9B74;
+    pub const SEEK: u32 = 0x4DBB;
+    pub const SEEK_ID: u32 = 0x53AB;
+    pub const SEEK_POSITION: u32 = 0x53AC;
+
+    pub const INFO: u32 = 0x1549A966;
+    pub const TIMESTAMP_SCALE: u32 = 0x2AD7B1;
+    pub const DURATION: u32 = 0x4489;
+
+    pub const TRACKS: u32 = 0x1654AE6B;
+    pub const TRACK_ENTRY: u32 = 0xAE;
+    pub const TRACK_NUMBER: u32 = 0xD7;
+    pub const TRACK_TYPE: u32 = 0x83;
+    pub const FLAG_ENABLED: u32 = 0xB9;
+    pub const FLAG_DEFAULT: u32 = 0x88;
+    pub const FLAG_FORCED: u32 = 0x55AA;
+    pub const FLAG_HEARING_IMPAIRED: u32 = 0x55AB;
+    pub const DEFAULT_DURATION: u32 = 0x23E383;
+    pub const NAME: u32 = 0x536E;
+    pub const LANGUAGE: u32 = 0x22B59C;
+    pub const LANGUAGE_BCP47: u32 = 0x22B59D;
+    pub const CODEC_ID: u32 = 0x86;
+    pub const CODEC_PRIVATE: u32 = 0x63A2;
+    pub const VIDEO: u32 = 0xE0;
+    pub const PIXEL_WIDTH: u32 = 0xB0;
+    pub const PIXEL_HEIGHT: u32 = 0xBA;
+    pub const AUDIO: u32 = 0xE1;
+    pub const SAMPLING_FREQUENCY: u32 = 0xB5;
+    pub const CHANNELS: u32 = 0x9F;
+    pub const CONTENT_ENCODINGS: u32 = 0x6D80;
+    pub const CONTENT_ENCODING: u32 = 0x6240;
+    pub const CONTENT_COMPRESSION: u32 = 0x5034;
+    pub const CONTENT_COMP_ALGO: u32 = 0x4254;
+    pub const CONTENT_COMP_SETTINGS: u32 = 0x4255;
+
+    pub const CUES: u32 = 0x1C53BB6B;
+    pub const CUE_POINT: u32 = 0xBB;
+    pub const CUE_TRACK_POSITIONS: u32 = 0xB7;
+    pub const CUE_TRACK: u32 = 0xF7;
+    pub const CUE_CLUSTER_POSITION: u32 = 0xF1;
+
+    pub const CLUSTER: u32 = 0x1F43B675;
+    pub const TIMESTAMP: u32 = 0xE7;
+    pub const SIMPLE_BLOCK: u32 = 0xA3;
+    pub const BLOCK_GROUP: u32 = 0xA0;
+    pub const BLOCK: u32 = 0xA1;
+    pub const BLOCK_DURATION: u32 = 0x9B;
+    pub const BLOCK_ADDITIONS: u32 = 0x75A1;
+    pub const BLOCK_MORE: u32 = 0xA6;
+    pub const BLOCK_ADDITIONAL: u32 = 0xA5;
+
+    pub const CHAPTERS: u32 = 0x1043A770;
+    pub const TAGS: u32 = 0x1254C367;
+    pub const ATTACHMENTS: u32 = 0x1941A469;
+
+    /// Children of Segment; seeing one of these ends an unknown-size cluster.
+    pub const TOP_LEVEL: [u32; 8] = [
+        SEEK_HEAD,
+        INFO,
+        TRACKS,
+        CUES,
+        CLUSTER,
+        CHAPTERS,
+        TAGS,
+        ATTACHMENTS,
+    ];
+}
+
+pub const TRACK_TYPE_VIDEO: u64 = 0x01;
+pub const TRACK_TYPE_AUDIO: u64 = 0x02;
+pub const TRACK_TYPE_SUBTITLE: u64 = 0x11;
+
+#[derive(Debug, Clone)]
+pub enum Compression {
+    Zlib,
+    HeaderStripping(Vec<u8>),
+    Other(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackEntry {
+    pub number: u64,
+    pub track_type: u64,
+    pub codec_id: String,
+    pub codec_private: Vec<u8>,
+    /// ISO 639-2 code; Matroska defaults this to `eng` when absent.
+    pub language: String,
+    pub language_bcp47: Option<String>,
+    pub name: Option<String>,
+    pub enabled: bool,
+    pub default: bool,
+    pub forced: bool,
+    pub hearing_impaired: bool,
+    /// Nanoseconds.
+    pub default_duration: Option<u64>,
+    pub compression: Option<Compression>,
+    pub pixel_width: Option<u64>,
+    pub pixel_height: Option<u64>,
+    pub sampling_frequency: Option<f64>,
+    pub channels: Option<u64>,
+}
+
+impl Default for TrackEntry {
+    fn default() -> Self {
+        Self {
+            number: 0,
+            track_type: 0,
+            codec_id: String::new(),
+            codec_private: Vec::new(),
+            language: "eng".into(),
+            language_bcp47: None,
+            name: None,
+            enabled: true,
+            default: true,
+            forced: false,
+            hearing_impaired: false,
+            default_duration: None,
+            compression: None,
+            pixel_width: None,
+            pixel_height: None,
+            sampling_frequency: None,
+            channels: None,
+        }
+    }
+}
+
+impl TrackEntry {
+    /// Output format for text codecs we can export, `None` for bitmap codecs.
+    pub fn text_format(&self) -> Option<TextFormat> {
+        match self.codec_id.as_str() {
+            "S_TEXT/UTF8" | "S_TEXT/ASCII" => Some(TextFormat::Srt),
+            "S_TEXT/ASS" | "S_ASS" => Some(TextFormat::Ass),
+            "S_TEXT/SSA" | "S_SSA" => Some(TextFormat::Ssa),
+            "S_TEXT/WEBVTT" | "D_WEBVTT/SUBTITLES" => Some(TextFormat::Vtt),
+            _ => None,
+        }
+    }
+}
+
+impl From<&TrackEntry> for SubtitleTrack {
+    fn from(track: &TrackEntry) -> Self {
+        Self {
+            track_number: track.number,
+            codec_id: track.codec_id.clone(),
+            language: track.language.clone(),
+            language_bcp47: track.language_bcp47.clone(),
+            name: track.name.clone(),
+            default: track.default,
+            forced: track.forced,
+            hearing_impaired: track.hearing_impaired,
+            extractable: track.text_format().is_some(),
+        }
+    }
+}
+
+/// A parsed block belonging to one of the requested tracks.
+struct Block {
+    track: u64,
+    /// Absolute timestamp in segment timestamp units.
+    timestamp: i64,
+    duration: Option<u64>,
+    data: Vec<u8>,
+    additional: Option<Vec<u8>>,
+}
+
+impl Block {
+    /// Timestamp `duration` units after the start, `None` when a crafted
+    /// duration puts it out of range.
+    fn end(&self, duration: u64) -> Option<i64> {
+        self.timestamp.checked_add(i64::try_from(duration).ok()?)
+    }
+}
+
+pub struct MatroskaFile {
+    path: PathBuf,
+    reader: EbmlReader<File>,
+    pub doc_type: String,
+    segment_start: u64,
+    segment_end: u64,
+    /// Nanoseconds per timestamp unit.
+    pub timestamp_scale: u64,
+    /// In timestamp units.
+    pub duration: Option<f64>,
+    pub tracks: Vec<TrackEntry>,
+    /// `(track, absolute cluster offset)` for every cue entry.
+    cue_positions: Vec<(u64, u64)>,
+    first_cluster: Option<u64>,
+}
+
+impl MatroskaFile {
+    pub fn open(path: &Path) -> Result<Self, MediaError> {
+        let reader = EbmlReader::new(super::open(path)?).map_err(|e| MediaError::io(path, e))?;
+        let mut file = Self {
+            path: path.to_path_buf(),
+            reader,
+            doc_type: String::new(),
+            segment_start: 0,
+            segment_end: 0,
+            timestamp_scale: 1_000_000,
+            duration: None,
+            tracks: Vec::new(),
+            cue_positions: Vec::new(),
+            first_cluster: None,
+        };
+        file.parse_headers().map_err(|e| MediaError::io(path, e))?;
+        if file.doc_type.is_empty() {
+            return Err(MediaError::UnsupportedContainer {
+                path: path.to_path_buf(),
+                expected: "Matroska",
+            });
+        }
+        Ok(file)
+    }
+
+    pub fn subtitle_tracks(&self) -> Vec<SubtitleTrack> {
+        self.tracks
+            .iter()
+            .filter(|t| t.track_type == TRACK_TYPE_SUBTITLE)
+            .map(SubtitleTrack::from)
+            .collect()
+    }
+
+    /// Duration in milliseconds, if the segment declares one.
+    pub fn duration_ms(&self) -> Option<f64> {
+        self.duration
+            .map(|d| d * self.timestamp_scale as f64 / 1_000_000.0)
+    }
+
+    fn parse_headers(&mut self) -> io::Result<()> {
+        let Some(header) = self.reader.read_header()? else {
+            return Ok(());
+        };
+        if header.id != ids::EBML {
+            return Ok(());
+        }
+        let end = header
+            .end()
+            .ok_or_else(|| ebml::invalid("unknown-size EBML header"))?;
+        let mut doc_type = None;
+        while self.reader.position() < end {
+            let Some(child) = self.reader.read_header()? else {
+                break;
+            };
+            match child.id {
+                ids::DOC_TYPE => doc_type = Some(self.reader.read_string(&child)?),
+                _ => self.reader.skip(&child)?,
+            }
+        }
+        let doc_type = doc_type.unwrap_or_else(|| "matroska".into());
+        if doc_type != "matroska" && doc_type != "webm" {
+            return Ok(());
+        }
+        self.doc_type = doc_type;
+
+        let segment = loop {
+            match self.reader.read_header()? {
+                Some(h) if h.id == ids::SEGMENT => break h,
+                Some(h) => self.reader.skip(&h)?,
+                None => return Err(ebml::invalid("no Segment element")),
+            }
+        };
+        self.segment_start = segment.data_start;
+        self.segment_end = segment.end().map_or(self.reader.file_len(), |end| {
+            end.min(self.reader.file_len())
+        });
+
+        let mut seek_heads = Vec::new();
+        let mut seen: HashSet<u32> = HashSet::new();
+        while self.reader.position() < self.segment_end {
+            let Some(child) = self.reader.read_header()? else {
+                break;
+            };
+            if child.id == ids::CLUSTER {
+                self.first_cluster = Some(child.start());
+                break;
+            }
+            seen.insert(child.id);
+            match child.id {
+                ids::SEEK_HEAD => seek_heads.extend(self.parse_seek_head(&child)?),
+                ids::INFO => self.parse_info(&child)?,
+                ids::TRACKS => self.parse_tracks(&child)?,
+                ids::CUES => self.parse_cues(&child)?,
+                _ if child.size.is_none() => break,
+                _ => self.reader.skip(&child)?,
+            }
+        }
+
+        // Anything not found before the first cluster (typically Cues) is
+        // reached through the SeekHead, including chained SeekHeads.
+        let mut index = 0;
+        while index < seek_heads.len() {
+            let (id, pos) = seek_heads[index];
+            index += 1;
+            if !seen.insert(id) || pos >= self.segment_end {
+                continue;
+            }
+            self.reader.seek(pos)?;
+            let Some(child) = self.reader.read_header()? else {
+                continue;
+            };
+            if child.id != id {
+                continue;
+            }
+            match id {
+                ids::SEEK_HEAD => seek_heads.extend(self.parse_seek_head(&child)?),
+                ids::INFO => self.parse_info(&child)?,
+                ids::TRACKS => self.parse_tracks(&child)?,
+                ids::CUES => self.parse_cues(&child)?,
+                ids::CLUSTER if self.first_cluster.is_none() => {
+                    self.first_cluster = Some(child.start())
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn children(&mut self, parent: &Header) -> io::Result<Vec<Header>> {
+        let end = parent
+            .end()
+            .ok_or_else(|| ebml::invalid("unknown-size master element"))?;
+        let mut out = Vec::new();
+        while self.reader.position() < end {
+            let Some(child) = self.reader.read_header()? else {
+                break;
+            };
+            out.push(child);
+            self.reader.skip(&child)?;
+        }
+        Ok(out)
+    }
+
+    fn parse_seek_head(&mut self, header: &Header) -> io::Result<Vec<(u32, u64)>> {
+        let mut entries = Vec::new();
+        for seek in self.children(header)? {
+            if seek.id != ids::SEEK {
+                continue;
+            }
+            self.reader.seek(seek.data_start)?;
+            let (mut id, mut pos) = (None, None);
+            for field in self.children(&seek)? {
+                self.reader.seek(field.data_start)?;
+                match field.id {
+                    ids::SEEK_ID => id = Some(self.reader.read_uint(&field)? as u32),
+                    ids::SEEK_POSITION => pos = Some(self.reader.read_uint(&field)?),
+                    _ => {}
+                }
+            }
+            if let (Some(id), Some(pos)) = (id, pos) {
+                entries.push((id, self.segment_start + pos));
+            }
+        }
+        self.reader.skip(header)?;
+        Ok(entries)
+    }
+
+    fn parse_info(&mut self, header: &Header) -> io::Result<()> {
+        for field in self.children(header)? {
+            self.reader.seek(field.data_start)?;
+            match field.id {
+                ids::TIMESTAMP_SCALE => {
+                    self.timestamp_scale = self.reader.read_uint(&field)?.max(1)
+                }
+                ids::DURATION => self.duration = Some(self.reader.read_float(&field)?),
+                _ => {}
+            }
+        }
+        self.reader.skip(header)
+    }
+
+    fn parse_tracks(&mut self, header: &Header) -> io::Result<()> {
+        for entry in self.children(header)? {
+            if entry.id == ids::TRACK_ENTRY {
+                self.reader.seek(entry.data_start)?;
+                let track = self.parse_track_entry(&entry)?;
+                self.tracks.push(track);
+            }
+        }
+        self.reader.skip(header)
+    }
+
+    fn parse_track_entry(&mut self, header: &Header) -> io::Result<TrackEntry> {
+        let mut track = TrackEntry::default();
+        for field in self.children(header)? {
+            self.reader.seek(field.data_start)?;
+            match field.id {
+                ids::TRACK_NUMBER => track.number = self.reader.read_uint(&field)?,
+                ids::TRACK_TYPE => track.track_type = self.reader.read_uint(&field)?,
+                ids::CODEC_ID => track.codec_id = self.reader.read_string(&field)?,
+                ids::CODEC_PRIVATE => track.codec_private = self.reader.read_bytes(&field)?,
+                ids::LANGUAGE => track.language = self.reader.read_string(&field)?,
+                ids::LANGUAGE_BCP47 => {
+                    track.language_bcp47 = Some(self.reader.read_string(&field)?)
+                }
+                ids::NAME => track.name = Some(self.reader.read_string(&field)?),
+                ids::FLAG_ENABLED => track.enabled = self.reader.read_uint(&field)? != 0,
+                ids::FLAG_DEFAULT => track.default = self.reader.read_uint(&field)? != 0,
+                ids::FLAG_FORCED => track.forced = self.reader.read_uint(&field)? != 0,
+                ids::FLAG_HEARING_IMPAIRED => {
+                    track.hearing_impaired = self.reader.read_uint(&field)? != 0
+                }
+                ids::DEFAULT_DURATION => {
+                    track.default_duration = Some(self.reader.read_uint(&field)?)
+                }
+                ids::CONTENT_ENCODINGS => track.compression = self.parse_encodings(&field)?,
+                ids::VIDEO | ids::AUDIO => self.parse_media_settings(&field, &mut track)?,
+                _ => {}
+            }
+        }
+        self.reader.skip(header)?;
+        Ok(track)
+    }
+
+    /// Reads the Video / Audio sub-elements of a TrackEntry.
+    fn parse_media_settings(&mut self, header: &Header, track: &mut TrackEntry) -> io::Result<()> {
+        for field in self.children(header)? {
+            self.reader.seek(field.data_start)?;
+            match field.id {
+                ids::PIXEL_WIDTH => track.pixel_width = Some(self.reader.read_uint(&field)?),
+                ids::PIXEL_HEIGHT => track.pixel_height = Some(self.reader.read_uint(&field)?),
+                ids::SAMPLING_FREQUENCY => {
+                    track.sampling_frequency = Some(self.reader.read_float(&field)?)
+                }
+                ids::CHANNELS => track.channels = Some(self.reader.read_uint(&field)?),
+                _ => {}
+            }
+        }
+        self.reader.skip(header)
+    }
+
+    fn parse_encodings(&mut self, header: &Header) -> io::Result<Option<Compression>> {
+        let mut compression = None;
+        for encoding in self.children(header)? {
+            if encoding.id != ids::CONTENT_ENCODING {
+                continue;
+            }
+            self.reader.seek(encoding.data_start)?;
+            for part in self.children(&encoding)? {
+                if part.id != ids::CONTENT_COMPRESSION {
+                    continue;
+                }
+                self.reader.seek(part.data_start)?;
+                let (mut algo, mut settings) = (0, Vec::new());
+                for field in self.children(&part)? {
+                    self.reader.seek(field.data_start)?;
+                    match field.id {
+                        ids::CONTENT_COMP_ALGO => algo = self.reader.read_uint(&field)?,
+                        ids::CONTENT_COMP_SETTINGS => settings = self.reader.read_bytes(&field)?,
+                        _ => {}
+                    }
+                }
+                compression = Some(match algo {
+                    0 => Compression::Zlib,
+                    3 => Compression::HeaderStripping(settings),
+                    other => Compression::Other(other),
+                });
+            }
+        }
+        self.reader.skip(header)?;
+        Ok(compression)
+    }
+
+    fn parse_cues(&mut self, header: &Header) -> io::Result<()> {
+        for point in self.children(header)? {
+            if point.id != ids::CUE_POINT {
+                continue;
+            }
+            self.reader.seek(point.data_start)?;
+            for positions in self.children(&point)? {
+                if positions.id != ids::CUE_TRACK_POSITIONS {
+                    continue;
+                }
+                self.reader.seek(positions.data_start)?;
+                let (mut track, mut cluster) = (None, None);
+                for field in self.children(&positions)? {
+                    self.reader.seek(field.data_start)?;
+                    match field.id {
+                        ids::CUE_TRACK => track = Some(self.reader.read_uint(&field)?),
+                        ids::CUE_CLUSTER_POSITION => cluster = Some(self.reader.read_uint(&field)?),
+                        _ => {}
+                    }
+                }
+                if let (Some(track), Some(cluster)) = (track, cluster) {
+                    self.cue_positions
+                        .push((track, self.segment_start + cluster));
+                }
+            }
+        }
+        self.reader.skip(header)
+    }
+
+    /// Reads every block of `tracks`, visiting only cued clusters when each
+    /// requested track has cue entries.
+    fn read_blocks(&mut self, tracks: &HashSet<u64>) -> io::Result<Vec<Block>> {
+        let cued: HashSet<u64> = self.cue_positions.iter().map(|(t, _)| *t).collect();
+        let mut blocks = Vec::new();
+        if tracks.iter().all(|t| cued.contains(t)) {
+            let clusters: BTreeSet<u64> = self
+                .cue_positions
+                .iter()
+                .filter(|(t, _)| tracks.contains(t))
+                .map(|(_, pos)| *pos)
+                .collect();
+            for pos in clusters {
+                self.reader.seek(pos)?;
+                match self.reader.read_header()? {
+                    Some(h) if h.id == ids::CLUSTER => {
+                        self.read_cluster(&h, tracks, &mut blocks)?;
+                    }
+                    _ => return Err(ebml::invalid("cue does not point at a cluster")),
+                }
+            }
+            return Ok(blocks);
+        }
+
+        let Some(mut pos) = self.first_cluster else {
+            return Ok(blocks);
+        };
+        while pos < self.segment_end {
+            self.reader.seek(pos)?;
+            let Some(header) = self.reader.read_header()? else {
+                break;
+            };
+            pos = match header.id {
+                ids::CLUSTER => self.read_cluster(&header, tracks, &mut blocks)?,
+                _ => match header.end() {
+                    Some(end) => end,
+                    None => break,
+                },
+            };
+        }
+        Ok(blocks)
+    }
+
+    /// Collects blocks of `tracks` from one cluster and returns its end offset.
+    fn read_cluster(
+        &mut self,
+        cluster: &Header,
+        tracks: &HashSet<u64>,
+        blocks: &mut Vec<Block>,
+    ) -> io::Result<u64> {
+        let end = cluster.end().unwrap_or(self.segment_end);
+        let mut cluster_ts: i64 = 0;
+        while self.reader.position() < end {
+            let Some(child) = self.reader.read_header()? else {
+                break;
+            };
+            if cluster.size.is_none() && ids::TOP_LEVEL.contains(&child.id) {
+                return Ok(child.start());
+            }
+            match child.id {
+                ids::TIMESTAMP => {
+                    cluster_ts = i64::try_from(self.reader.read_uint(&child)?).unwrap_or(i64::MAX)
+                }
+                ids::SIMPLE_BLOCK => {
+                    if let Some(block) = self.read_block(&child, end, tracks, cluster_ts)? {
+                        blocks.push(block);
+                    }
+                }
+                ids::BLOCK_GROUP => {
+                    if let Some(block) = self.read_block_group(&child, tracks, cluster_ts)? {
+                        blocks.push(block);
+                    }
+                }
+                _ => self.reader.skip(&child)?,
+            }
+        }
+        Ok(self.reader.position())
+    }
+
+    fn read_block_group(
+        &mut self,
+        group: &Header,
+        tracks: &HashSet<u64>,
+        cluster_ts: i64,
+    ) -> io::Result<Option<Block>> {
+        let end = group
+            .end()
+            .ok_or_else(|| ebml::invalid("unknown-size BlockGroup"))?;
+        let (mut block, mut duration, mut additional) = (None, None, None);
+        while self.reader.position() < end {
+            let Some(child) = self.reader.read_header()? else {
+                break;
+            };
+            match child.id {
+                ids::BLOCK => {
+                    block = self.read_block(&child, end, tracks, cluster_ts)?;
+                    if block.is_none() {
+                        // Not one of ours; the rest of the group is irrelevant.
+                        self.reader.seek(end)?;
+                        return Ok(None);
+                    }
+                }
+                ids::BLOCK_DURATION => duration = Some(self.reader.read_uint(&child)?),
+                ids::BLOCK_ADDITIONS => additional = self.read_block_additional(&child)?,
+                _ => self.reader.skip(&child)?,
+            }
+        }
+        Ok(block.map(|mut b| {
+            b.duration = duration;
+            b.additional = additional;
+            b
+        }))
+    }
+
+    fn read_block_additional(&mut self, header: &Header) -> io::Result<Option<Vec<u8>>> {
+        let mut additional = None;
+        for more in self.children(header)? {
+            if more.id != ids::BLOCK_MORE {
+                continue;
+            }
+            self.reader.seek(more.data_start)?;
+            for field in self.children(&more)? {
+                if field.id == ids::BLOCK_ADDITIONAL {
+                    self.reader.seek(field.data_start)?;
+                    additional = Some(self.reader.read_bytes(&field)?);
+                }
+            }
+        }
+        self.reader.skip(header)?;
+        Ok(additional)
+    }
+
+    /// Parses a Block/SimpleBlock, reading the payload only for wanted tracks.
+    /// The block must fit inside its parent, which ends at `parent_end`, and
+    /// inside the file before anything is allocated for it.
+    fn read_block(
+        &mut self,
+        header: &Header,
+        parent_end: u64,
+        tracks: &HashSet<u64>,
+        cluster_ts: i64,
+    ) -> io::Result<Option<Block>> {
+        let end = header
+            .end()
+            .ok_or_else(|| ebml::invalid("unknown-size block"))?;
+        if end > parent_end.min(self.reader.file_len()) {
+            return Err(ebml::invalid("block extends past its parent element"));
+        }
+        let (track, _) = self.reader.read_size_vint()?;
+        if !tracks.contains(&track) {
+            self.reader.seek(end)?;
+            return Ok(None);
+        }
+        let mut fixed = [0u8; 3];
+        self.reader.read_exact(&mut fixed)?;
+        if fixed[2] & 0x06 != 0 {
+            return Err(ebml::invalid("laced subtitle blocks are not supported"));
+        }
+        let relative = i16::from_be_bytes([fixed[0], fixed[1]]);
+        // A block whose time cannot be represented has nowhere to go.
+        let Some(timestamp) = cluster_ts.checked_add(i64::from(relative)) else {
+            self.reader.seek(end)?;
+            return Ok(None);
+        };
+        let len = end
+            .checked_sub(self.reader.position())
+            .ok_or_else(|| ebml::invalid("block header overruns block"))?;
+        let mut data = vec![0u8; len as usize];
+        self.reader.read_exact(&mut data)?;
+        Ok(Some(Block {
+            track,
+            timestamp,
+            duration: None,
+            data,
+            additional: None,
+        }))
+    }
+
+    /// Exports the given text tracks (all extractable subtitle tracks when
+    /// `numbers` is empty) into `output_dir`, or next to the source file.
+    pub fn extract(
+        &mut self,
+        numbers: &[u64],
+        output_dir: Option<&Path>,
+    ) -> Result<Vec<ExtractedSubtitle>, MediaError> {
+        let selected: Vec<TrackEntry> = if numbers.is_empty() {
+            self.tracks
+                .iter()
+                .filter(|t| t.track_type == TRACK_TYPE_SUBTITLE && t.text_format().is_some())
+                .cloned()
+                .collect()
+        } else {
+            numbers
+                .iter()
+                .map(|&n| {
+                    let track = self
+                        .tracks
+                        .iter()
+                        .find(|t| t.number == n && t.track_type == TRACK_TYPE_SUBTITLE)
+                        .ok_or(MediaError::TrackNotFound(n))?;
+                    match track.text_format() {
+                        Some(_) => Ok(track.clone()),
+                        None => Err(MediaError::UnsupportedCodec {
+                            track: n,
+                            codec: track.codec_id.clone(),
+                        }),
+                    }
+                })
+                .collect::<Result<_, _>>()?
+        };
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let wanted: HashSet<u64> = selected.iter().map(|t| t.number).collect();
+        let path = self.path.clone();
+        let blocks = self
+            .read_blocks(&wanted)
+            .map_err(|e| MediaError::io(&path, e))?;
+        let mut by_track: HashMap<u64, Vec<Block>> = HashMap::new();
+        for block in blocks {
+            by_track.entry(block.track).or_default().push(block);
+        }
+
+        let mut extracted = Vec::new();
+        for track in &selected {
+            let mut blocks = by_track.remove(&track.number).unwrap_or_default();
+            for block in &mut blocks {
+                block.data = decode(track, std::mem::take(&mut block.data))
+                    .map_err(|e| MediaError::io(&path, e))?;
+            }
+            let format = track.text_format().expect("selected tracks are text");
+            let (contents, cue_count) = match format {
+                TextFormat::Srt => {
+                    let cues = self.to_cues(track, &blocks);
+                    (super::render_srt(&cues), cues.len())
+                }
+                TextFormat::Vtt => {
+                    let cues = self.to_cues(track, &blocks);
+                    let header = String::from_utf8_lossy(&track.codec_private).into_owned();
+                    (super::render_vtt(&cues, Some(&header)), cues.len())
+                }
+                TextFormat::Ass | TextFormat::Ssa => self.render_ssa(track, &blocks, format),
+            };
+            let language = track.language_bcp47.as_deref().unwrap_or(&track.language);
+            let out = super::output_path(
+                &path,
+                output_dir,
+                track.number,
+                Some(language),
+                track.forced,
+                format,
+            );
+            super::write_file(&out, &contents)?;
+            extracted.push(ExtractedSubtitle {
+                track_number: track.number,
+                path: out,
+                format,
+                language: Some(track.language.clone()),
+                forced: track.forced,
+                cue_count,
+            });
+        }
+        Ok(extracted)
+    }
+
+    fn to_ms(&self, timestamp: i64) -> u64 {
+        let ns = timestamp.max(0) as u128 * u128::from(self.timestamp_scale);
+        u64::try_from(ns / 1_000_000).unwrap_or(u64::MAX)
+    }
+
+    /// Converts blocks to timed cues, inferring missing durations from the
+    /// track default or the next block.
+    fn to_cues(&self, track: &TrackEntry, blocks: &[Block]) -> Vec<TextCue> {
+        let mut sorted: Vec<&Block> = blocks.iter().collect();
+        sorted.sort_by_key(|b| b.timestamp);
+        let mut cues = Vec::with_capacity(sorted.len());
+        for (i, block) in sorted.iter().enumerate() {
+            let start_ms = self.to_ms(block.timestamp);
+            let end_ms = match (block.duration, track.default_duration) {
+                (Some(d), _) => match block.end(d) {
+                    Some(end) => self.to_ms(end),
+                    None => continue,
+                },
+                (None, Some(ns)) => start_ms.saturating_add(ns / 1_000_000),
+                (None, None) => sorted
+                    .get(i + 1)
+                    .map(|next| self.to_ms(next.timestamp))
+                    .unwrap_or(start_ms.saturating_add(2000)),
+            };
+            let mut cue = TextCue::new(
+                start_ms,
+                end_ms,
+                String::from_utf8_lossy(&block.data).into_owned(),
+            );
+            if let Some(additional) = &block.additional {
+                // WebVTT in Matroska: settings line, then identifier line.
+                let additional = String::from_utf8_lossy(additional);
+                let mut lines = additional.lines();
+                cue.settings = lines.next().map(str::to_string);
+                cue.id = lines.next().map(str::to_string);
+            }
+            cues.push(cue);
+        }
+        cues
+    }
+
+    /// Rebuilds an ASS/SSA script from CodecPrivate and the stored events,
+    /// which Matroska keeps as `ReadOrder,Layer,Style,Name,...,Text`.
+    fn render_ssa(
+        &self,
+        track: &TrackEntry,
+        blocks: &[Block],
+        format: TextFormat,
+    ) -> (String, usize) {
+        let mut script = String::from_utf8_lossy(&track.codec_private)
+            .trim_end()
+            .replace("\r\n", "\n");
+        if !script.contains("[Events]") {
+            let first = if format == TextFormat::Ssa {
+                "Marked"
+            } else {
+                "Layer"
+            };
+            let _ = write!(
+                script,
+                "\n\n[Events]\nFormat: {first}, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text"
+            );
+        }
+        script.push('\n');
+
+        let mut events: Vec<(u64, String)> = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let data = String::from_utf8_lossy(&block.data);
+            let fields: Vec<&str> = data.splitn(9, ',').collect();
+            if fields.len() < 9 {
+                continue;
+            }
+            let read_order = fields[0].trim().parse().unwrap_or(u64::MAX);
+            let start = self.to_ms(block.timestamp);
+            let end = match block.duration.map(|d| block.end(d)) {
+                Some(Some(end)) => self.to_ms(end),
+                Some(None) => continue,
+                None => start.saturating_add(2000),
+            };
+            let line = format!(
+                "Dialogue: {},{},{},{}\n",
+                fields[1],
+                ssa_timestamp(start),
+                ssa_timestamp(end),
+                fields[2..].join(",")
+            );
+            events.push((read_order, line));
+        }
+        events.sort_by_key(|(order, _)| *order);
+        let count = events.len();
+        for (_, line) in events {
+            script.push_str(&line);
+        }
+        (script, count)
+    }
+}
+
+/// Undoes track-level content compression.
+fn decode(track: &TrackEntry, data: Vec<u8>) -> io::Result<Vec<u8>> {
+    match &track.compression {
+        None => Ok(data),
+        Some(Compression::Zlib) => {
+            let mut out = Vec::with_capacity(data.len() * 2);
+            flate2::read::ZlibDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+            Ok(out)
+        }
+        Some(Compression::HeaderStripping(prefix)) => {
+            let mut out = prefix.clone();
+            out.extend_from_slice(&data);
+            Ok(out)
+        }
+        Some(Compression::Other(algo)) => Err(ebml::invalid(&format!(
+            "unsupported content compression {algo} on track {}",
+            track.number
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn list_mkv_subtitle_tracks(path: String) -> Result<Vec<SubtitleTrack>, MediaError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        MatroskaFile::open(Path::new(&path)).map(|file| file.subtitle_tracks())
+    })
+    .await
+    .map_err(|e| MediaError::Task(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn extract_mkv_subtitles(
+    path: String,
+    tracks: Option<Vec<u64>>,
+    output_dir: Option<String>,
+) -> Result<Vec<ExtractedSubtitle>, MediaError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut file = MatroskaFile::open(Path::new(&path))?;
+        file.extract(
+            &tracks.unwrap_or_default(),
+            output_dir.as_deref().map(Path::new),
+        )
+    })
+    .await
+    .map_err(|e| MediaError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(id: u32, size: u64, payload: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&b| b == 0)
+            .collect();
+        out.push(0x01);
+        out.extend_from_slice(&size.to_be_bytes()[1..]);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn master(id: u32, children: &[Vec<u8>]) -> Vec<u8> {
+        let payload = children.concat();
+        element(id, payload.len() as u64, &payload)
+    }
+
+    /// A file with one SRT track and a cluster at `timestamp` holding `block`.
+    fn file_with_block(name: &str, timestamp: &[u8], block: Vec<u8>) -> PathBuf {
+        let track = master(
+            ids::TRACK_ENTRY,
+            &[
+                element(ids::TRACK_NUMBER, 1, &[1]),
+                element(ids::TRACK_TYPE, 1, &[TRACK_TYPE_SUBTITLE as u8]),
+                element(ids::CODEC_ID, 11, b"S_TEXT/UTF8"),
+            ],
+        );
+        let cluster = master(
+            ids::CLUSTER,
+            &[
+                element(ids::TIMESTAMP, timestamp.len() as u64, timestamp),
+                block,
+            ],
+        );
+        let bytes = [
+            master(ids::EBML, &[element(ids::DOC_TYPE, 8, b"matroska")]),
+            master(ids::SEGMENT, &[master(ids::TRACKS, &[track]), cluster]),
+        ]
+        .concat();
+        let path = std::env::temp_dir().join(format!("{}-{name}.mkv", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn extracts_a_block() {
+        let path = file_with_block(
+            "block",
+            &[0],
+            element(
+                ids::SIMPLE_BLOCK,
+                9,
+                &[0x81, 0, 0, 0x80, b'H', b'e', b'l', b'l', b'o'],
+            ),
+        );
+        let mut file = MatroskaFile::open(&path).unwrap();
+        let blocks = file.read_blocks(&HashSet::from([1])).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].data, b"Hello");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_block_larger_than_its_cluster() {
+        for (name, size) in [("huge", 1 << 55), ("overflow", (1 << 56) - 2)] {
+            let path = file_with_block(
+                name,
+                &[0],
+                element(ids::SIMPLE_BLOCK, size, &[0x81, 0, 0, 0x80, b'H', b'i']),
+            );
+            let mut file = MatroskaFile::open(&path).unwrap();
+            let error = file.extract(&[1], Some(&std::env::temp_dir())).unwrap_err();
+            assert!(matches!(error, MediaError::Malformed(_)), "{error:?}");
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn skips_blocks_timed_out_of_range() {
+        let path = file_with_block(
+            "late",
+            &i64::MAX.to_be_bytes(),
+            element(ids::SIMPLE_BLOCK, 6, &[0x81, 0, 1, 0x80, b'H', b'i']),
+        );
+        let mut file = MatroskaFile::open(&path).unwrap();
+        assert!(file.read_blocks(&HashSet::from([1])).unwrap().is_empty());
+
+        let block = |timestamp, duration| Block {
+            track: 1,
+            timestamp,
+            duration: Some(duration),
+            data: b"Hi".to_vec(),
+            additional: None,
+        };
+        let blocks = [
+            block(1, u64::MAX),
+            block(1, i64::MAX as u64),
+            block(0, 1000),
+        ];
+        let cues = file.to_cues(&file.tracks[0], &blocks);
+        assert_eq!(cues.len(), 1);
+        assert_eq!((cues[0].start_ms, cues[0].end_ms), (0, 1000));
+        std::fs::remove_file(path).unwrap();
+    }
+}
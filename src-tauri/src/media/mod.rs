@@ -0,0 +1,275 @@
+//! Native readers for video containers.
+//!
+//! These replace the ffmpeg.wasm round trip in the webview: containers are
+//! parsed header-first with seeks, so only the bytes that are actually needed
+//! are pulled from disk.
+
+pub mod avi;
+pub mod ebml;
+pub mod matroska;
+pub mod mp4;
+pub mod mpegts;
+pub mod probe;
+
+use crate::error::impl_serialize_error;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MediaError {
+    #[error("could not read {}: {source}", .path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("could not write {}: {source}", .path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("{} is not a {expected} file", .path.display())]
+    UnsupportedContainer {
+        path: PathBuf,
+        expected: &'static str,
+    },
+    #[error("malformed container: {0}")]
+    Malformed(String),
+    #[error("track {0} not found")]
+    TrackNotFound(u64),
+    #[error("track {track} uses unsupported codec {codec}")]
+    UnsupportedCodec { track: u64, codec: String },
+    #[error("extraction task failed: {0}")]
+    Task(String),
+}
+
+impl MediaError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Write { .. } => "write",
+            Self::UnsupportedContainer { .. } => "unsupportedContainer",
+            Self::Malformed(_) => "malformed",
+            Self::TrackNotFound(_) => "trackNotFound",
+            Self::UnsupportedCodec { .. } => "unsupportedCodec",
+            Self::Task(_) => "task",
+        }
+    }
+
+    /// Attributes an I/O error to `path`, treating bad data as malformed input.
+    pub(crate) fn io(path: &Path, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::InvalidData => Self::Malformed(err.to_string()),
+            io::ErrorKind::UnexpectedEof => Self::Malformed("unexpected end of file".into()),
+            _ => Self::Io {
+                path: path.to_path_buf(),
+                source: err,
+            },
+        }
+    }
+}
+
+impl_serialize_error!(MediaError);
+
+pub(crate) fn open(path: &Path) -> Result<File, MediaError> {
+    File::open(path).map_err(|e| MediaError::io(path, e))
+}
+
+/// Text format of a subtitle file written by an extractor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    Srt,
+    Ass,
+    Ssa,
+    Vtt,
+}
+
+impl TextFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Ass => "ass",
+            Self::Ssa => "ssa",
+            Self::Vtt => "vtt",
+        }
+    }
+}
+
+/// A timed text sample pulled out of a container.
+#[derive(Debug, Clone)]
+pub struct TextCue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    /// WebVTT cue identifier, when the container carries one.
+    pub id: Option<String>,
+    /// WebVTT cue settings (`position:10% align:start`), when present.
+    pub settings: Option<String>,
+}
+
+impl TextCue {
+    pub fn new(start_ms: u64, end_ms: u64, text: String) -> Self {
+        Self {
+            start_ms,
+            end_ms,
+            text,
+            id: None,
+            settings: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtitleTrack {
+    pub track_number: u64,
+    pub codec_id: String,
+    pub language: String,
+    pub language_bcp47: Option<String>,
+    pub name: Option<String>,
+    pub default: bool,
+    pub forced: bool,
+    pub hearing_impaired: bool,
+    /// Whether the codec is a text format we can export.
+    pub extractable: bool,
+}
+
+/// Describes one subtitle file produced by an extract command.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedSubtitle {
+    pub track_number: u64,
+    pub path: PathBuf,
+    pub format: TextFormat,
+    pub language: Option<String>,
+    pub forced: bool,
+    pub cue_count: usize,
+}
+
+pub fn render_srt(cues: &[TextCue]) -> String {
+    let mut out = String::new();
+    for (index, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{}\r\n{} --> {}\r\n{}\r\n\r\n",
+            index + 1,
+            timestamp(cue.start_ms, ','),
+            timestamp(cue.end_ms, ','),
+            cue.text
+                .trim_end()
+                .replace("\r\n", "\n")
+                .replace('\n', "\r\n"),
+        );
+    }
+    out
+}
+
+/// Renders cues as WebVTT. `header` is the text after the `WEBVTT` signature
+/// line (style and region blocks), if the source carried one.
+pub fn render_vtt(cues: &[TextCue], header: Option<&str>) -> String {
+    let mut out = match header.map(str::trim).filter(|h| !h.is_empty()) {
+        Some(h) if h.starts_with("WEBVTT") => format!("{h}\n\n"),
+        Some(h) => format!("WEBVTT\n\n{h}\n\n"),
+        None => "WEBVTT\n\n".to_string(),
+    };
+    for cue in cues {
+        if let Some(id) = cue.id.as_deref().filter(|id| !id.is_empty()) {
+            let _ = writeln!(out, "{id}");
+        }
+        let _ = write!(
+            out,
+            "{} --> {}",
+            timestamp(cue.start_ms, '.'),
+            timestamp(cue.end_ms, '.')
+        );
+        if let Some(settings) = cue.settings.as_deref().filter(|s| !s.is_empty()) {
+            let _ = write!(out, " {settings}");
+        }
+        let _ = write!(out, "\n{}\n\n", cue.text.trim_end().replace("\r\n", "\n"));
+    }
+    out
+}
+
+/// `HH:MM:SS<sep>mmm`, as used by SRT (`,`) and WebVTT (`.`).
+pub(crate) fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// `H:MM:SS.cc`, the ASS/SSA timestamp format.
+pub(crate) fn ssa_timestamp(ms: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000 / 10
+    )
+}
+
+/// Builds `<dir>/<stem>.<track>[.<lang>][.forced].<ext>` for an extracted track.
+/// The language comes from the container, so one that is not a plain tag of
+/// letters, digits and hyphens (`eng`, `pt-BR`) is left out of the name
+/// rather than let it reach outside `dir`.
+pub(crate) fn output_path(
+    source: &Path,
+    output_dir: Option<&Path>,
+    track_number: u64,
+    language: Option<&str>,
+    forced: bool,
+    format: TextFormat,
+) -> PathBuf {
+    let dir = output_dir
+        .map(Path::to_path_buf)
+        .or_else(|| source.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "subtitle".into());
+    let mut name = format!("{stem}.{track_number}");
+    let tag = |l: &&str| l.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+    if let Some(lang) = language.filter(|l| !l.is_empty() && *l != "und" && tag(l)) {
+        let _ = write!(name, ".{lang}");
+    }
+    if forced {
+        name.push_str(".forced");
+    }
+    let _ = write!(name, ".{}", format.extension());
+    dir.join(name)
+}
+
+pub(crate) fn write_file(path: &Path, contents: &str) -> Result<(), MediaError> {
+    std::fs::write(path, contents).map_err(|source| MediaError::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_path_keeps_hostile_languages_out() {
+        let dir = Path::new("/tmp/out");
+        let path = |lang| {
+            output_path(
+                Path::new("/m/Movie.mkv"),
+                Some(dir),
+                3,
+                Some(lang),
+                false,
+                TextFormat::Srt,
+            )
+        };
+        assert_eq!(path("pt-BR"), dir.join("Movie.3.pt-BR.srt"));
+        for lang in ["../../x", "/etc/foo", "..", "a/b", "c:\\x", "en\0"] {
+            let out = path(lang);
+            assert_eq!(out, dir.join("Movie.3.srt"), "{lang:?}");
+            assert_eq!(out.parent(), Some(dir));
+        }
+    }
+}
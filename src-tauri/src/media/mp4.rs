@@ -0,0 +1,1063 @@
+//! ISO-BMFF (MP4 / MOV) subtitle tracks.
+//!
+//! Only box headers are read while walking the file; `moov` and each `moof`
+//! are loaded into memory (they are small compared to the media data) and the
+//! subtitle samples themselves are fetched with positioned reads. Both classic
+//! `stbl` sample tables and fragmented files (`moof`/`trun`, common in
+//! WEB-DLs) are supported.
+
+use super::{ExtractedSubtitle, MediaError, SubtitleTrack, TextCue, TextFormat};
+use crate::movie_hash::read_exact_at;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Box types that may legitimately open an MP4 or QuickTime file.
+const LEADING_BOXES: [&[u8; 4]; 7] = [
+    b"ftyp", b"moov", b"mdat", b"free", b"skip", b"wide", b"pnot",
+];
+
+/// Upper bound for boxes we load into memory (`moov`, `moof`).
+const MAX_META_BOX: u64 = 256 * 1024 * 1024;
+
+/// Upper bound for samples in one `trun` or one subtitle track's sample
+/// table, where the box itself does not bound the count.
+const MAX_SAMPLES: u32 = 1 << 20;
+
+/// Upper bound for a subtitle sample; real ones are a few hundred bytes.
+const MAX_SAMPLE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Handler types used by subtitle and caption tracks.
+const SUBTITLE_HANDLERS: [&str; 3] = ["text", "sbtl", "subt"];
+
+#[derive(Debug, Clone, Copy)]
+struct BoxHeader {
+    kind: [u8; 4],
+    start: u64,
+    data_start: u64,
+    end: u64,
+}
+
+fn read_box_header(file: &File, pos: u64, file_len: u64) -> io::Result<BoxHeader> {
+    let mut buf = [0u8; 16];
+    read_exact_at(file, &mut buf[..8], pos)?;
+    let size = u32::from_be_bytes(buf[..4].try_into().unwrap()) as u64;
+    let kind: [u8; 4] = buf[4..8].try_into().unwrap();
+    let (size, header_len) = match size {
+        0 => (file_len - pos, 8),
+        1 => {
+            read_exact_at(file, &mut buf[8..16], pos + 8)?;
+            (u64::from_be_bytes(buf[8..16].try_into().unwrap()), 16)
+        }
+        n => (n, 8),
+    };
+    if size < header_len {
+        return Err(invalid("box smaller than its header"));
+    }
+    Ok(BoxHeader {
+        kind,
+        start: pos,
+        data_start: pos + header_len,
+        end: pos.saturating_add(size).min(file_len),
+    })
+}
+
+/// Big-endian cursor over an in-memory box payload.
+pub(crate) struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if n > self.remaining() {
+            return Err(invalid("truncated box"));
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    pub(crate) fn skip(&mut self, n: usize) -> io::Result<()> {
+        self.take(n).map(|_| ())
+    }
+
+    pub(crate) fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.pos..];
+        self.pos = self.data.len();
+        rest
+    }
+}
+
+/// Iterates `(type, payload)` over the child boxes in `data`.
+pub(crate) fn boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || {
+        if data.len() - pos < 8 {
+            return None;
+        }
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+        let (size, header_len) = match size {
+            0 => (data.len() - pos, 8),
+            1 if data.len() - pos >= 16 => {
+                let large = u64::from_be_bytes(data[pos + 8..pos + 16].try_into().unwrap());
+                (usize::try_from(large).unwrap_or(usize::MAX), 16)
+            }
+            n => (n, 8),
+        };
+        if size < header_len || size > data.len() - pos {
+            return None;
+        }
+        let payload = &data[pos + header_len..pos + size];
+        pos += size;
+        Some((kind, payload))
+    })
+}
+
+fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes(data)
+        .find(|(k, _)| k == kind)
+        .map(|(_, payload)| payload)
+}
+
+fn fourcc(kind: &[u8]) -> String {
+    String::from_utf8_lossy(kind)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    offset: u64,
+    size: u32,
+    /// Decode time in media timescale units.
+    time: u64,
+    duration: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mp4Track {
+    pub id: u32,
+    pub handler: String,
+    /// Four-character code of the first sample description (`avc1`, `tx3g`, ...).
+    pub codec: String,
+    /// Sample description payload following the box header.
+    pub sample_entry: Vec<u8>,
+    /// ISO 639-2 code from `mdhd`.
+    pub language: String,
+    pub name: Option<String>,
+    pub enabled: bool,
+    pub timescale: u32,
+    /// In `timescale` units.
+    pub duration: u64,
+    pub width: u32,
+    pub height: u32,
+    pub sample_count: u64,
+    samples: Vec<Sample>,
+}
+
+impl Mp4Track {
+    pub fn is_subtitle(&self) -> bool {
+        SUBTITLE_HANDLERS.contains(&self.handler.as_str())
+            || matches!(self.codec.as_str(), "tx3g" | "wvtt" | "stpp")
+    }
+
+    /// Output format for text codecs we can export.
+    pub fn text_format(&self) -> Option<TextFormat> {
+        match self.codec.as_str() {
+            "tx3g" | "text" => Some(TextFormat::Srt),
+            "wvtt" => Some(TextFormat::Vtt),
+            _ => None,
+        }
+    }
+
+    /// 3GPP timed text marks all-forced tracks in the sample entry display flags.
+    fn forced(&self) -> bool {
+        self.codec == "tx3g" && self.sample_entry.len() >= 12 && self.sample_entry[8] & 0x80 != 0
+    }
+
+    fn to_ms(&self, time: u64) -> u64 {
+        (u128::from(time) * 1000 / u128::from(self.timescale.max(1))) as u64
+    }
+}
+
+impl From<&Mp4Track> for SubtitleTrack {
+    fn from(track: &Mp4Track) -> Self {
+        Self {
+            track_number: u64::from(track.id),
+            codec_id: track.codec.clone(),
+            language: track.language.clone(),
+            language_bcp47: None,
+            name: track.name.clone(),
+            default: track.enabled,
+            forced: track.forced(),
+            hearing_impaired: false,
+            extractable: track.text_format().is_some(),
+        }
+    }
+}
+
+pub struct Mp4File {
+    path: PathBuf,
+    file: File,
+    /// File length; box, table and sample sizes are checked against it.
+    len: u64,
+    /// Movie timescale from `mvhd`.
+    pub timescale: u32,
+    /// In movie timescale units.
+    pub duration: u64,
+    pub tracks: Vec<Mp4Track>,
+    /// Start offsets of top-level `moof` boxes, for fragmented files.
+    fragments: Vec<u64>,
+    /// `trex` default sample duration and size per track.
+    trex: HashMap<u32, (u32, u32)>,
+}
+
+impl Mp4File {
+    pub fn open(path: &Path) -> Result<Self, MediaError> {
+        let file = super::open(path)?;
+        let len = file.metadata().map_err(|e| MediaError::io(path, e))?.len();
+        let mut mp4 = Self {
+            path: path.to_path_buf(),
+            file,
+            len,
+            timescale: 1000,
+            duration: 0,
+            tracks: Vec::new(),
+            fragments: Vec::new(),
+            trex: HashMap::new(),
+        };
+        let found = mp4.parse_top_level().map_err(|e| MediaError::io(path, e))?;
+        if !found {
+            return Err(MediaError::UnsupportedContainer {
+                path: path.to_path_buf(),
+                expected: "MP4/QuickTime",
+            });
+        }
+        Ok(mp4)
+    }
+
+    pub fn subtitle_tracks(&self) -> Vec<SubtitleTrack> {
+        self.tracks
+            .iter()
+            .filter(|t| t.is_subtitle())
+            .map(SubtitleTrack::from)
+            .collect()
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        (u128::from(self.duration) * 1000 / u128::from(self.timescale.max(1))) as u64
+    }
+
+    /// Walks top-level boxes. Returns false if the file is not ISO-BMFF.
+    fn parse_top_level(&mut self) -> io::Result<bool> {
+        let file_len = self.len;
+        let mut pos = 0;
+        let mut moov = None;
+        while pos + 8 <= file_len {
+            let header = read_box_header(&self.file, pos, file_len)?;
+            if pos == 0 && !LEADING_BOXES.contains(&&header.kind) {
+                return Ok(false);
+            }
+            match &header.kind {
+                b"moov" => moov = Some(self.read_payload(&header)?),
+                b"moof" => self.fragments.push(header.start),
+                _ => {}
+            }
+            pos = header.end;
+        }
+        let Some(moov) = moov else {
+            return Ok(false);
+        };
+        self.parse_moov(&moov)?;
+        if !self.fragments.is_empty() {
+            self.load_fragments()?;
+        }
+        Ok(true)
+    }
+
+    fn read_payload(&self, header: &BoxHeader) -> io::Result<Vec<u8>> {
+        let len = header.end - header.data_start;
+        if len > MAX_META_BOX {
+            return Err(invalid("metadata box is implausibly large"));
+        }
+        let mut buf = vec![0u8; len as usize];
+        read_exact_at(&self.file, &mut buf, header.data_start)?;
+        Ok(buf)
+    }
+
+    fn parse_moov(&mut self, moov: &[u8]) -> io::Result<()> {
+        for (kind, payload) in boxes(moov) {
+            match &kind {
+                b"mvhd" => {
+                    let mut r = ByteReader::new(payload);
+                    let version = r.u8()?;
+                    r.skip(3)?;
+                    if version == 1 {
+                        r.skip(16)?;
+                        self.timescale = r.u32()?;
+                        self.duration = r.u64()?;
+                    } else {
+                        r.skip(8)?;
+                        self.timescale = r.u32()?;
+                        self.duration = u64::from(r.u32()?);
+                    }
+                }
+                b"trak" => self.tracks.push(parse_trak(payload, self.len)?),
+                b"mvex" => {
+                    for (kind, trex) in boxes(payload) {
+                        if &kind == b"trex" {
+                            let mut r = ByteReader::new(trex);
+                            r.skip(4)?;
+                            let track_id = r.u32()?;
+                            r.skip(4)?;
+                            let duration = r.u32()?;
+                            let size = r.u32()?;
+                            self.trex.insert(track_id, (duration, size));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends samples described by `moof`/`traf`/`trun` to their tracks.
+    fn load_fragments(&mut self) -> io::Result<()> {
+        let file_len = self.len;
+        let mut next_time: HashMap<u32, u64> = HashMap::new();
+        let fragments = self.fragments.clone();
+        for moof_start in fragments {
+            let header = read_box_header(&self.file, moof_start, file_len)?;
+            let moof = self.read_payload(&header)?;
+            for (kind, traf) in boxes(&moof) {
+                if &kind != b"traf" {
+                    continue;
+                }
+                let Some(tfhd) = find_box(traf, b"tfhd") else {
+                    continue;
+                };
+                let mut r = ByteReader::new(tfhd);
+                let flags = r.u32()? & 0x00FF_FFFF;
+                let track_id = r.u32()?;
+                let Some(index) = self.tracks.iter().position(|t| t.id == track_id) else {
+                    continue;
+                };
+                let base_offset = if flags & 0x01 != 0 {
+                    r.u64()?
+                } else {
+                    moof_start
+                };
+                if flags & 0x02 != 0 {
+                    r.skip(4)?;
+                }
+                let (trex_duration, trex_size) =
+                    self.trex.get(&track_id).copied().unwrap_or((0, 0));
+                let default_duration = if flags & 0x08 != 0 {
+                    r.u32()?
+                } else {
+                    trex_duration
+                };
+                let default_size = if flags & 0x10 != 0 {
+                    r.u32()?
+                } else {
+                    trex_size
+                };
+
+                let mut time = match find_box(traf, b"tfdt") {
+                    Some(tfdt) => {
+                        let mut r = ByteReader::new(tfdt);
+                        if r.u8()? == 1 {
+                            r.skip(3)?;
+                            r.u64()?
+                        } else {
+                            r.skip(3)?;
+                            u64::from(r.u32()?)
+                        }
+                    }
+                    None => next_time.get(&track_id).copied().unwrap_or(0),
+                };
+
+                for (kind, trun) in boxes(traf) {
+                    if &kind != b"trun" {
+                        continue;
+                    }
+                    let mut r = ByteReader::new(trun);
+                    let flags = r.u32()? & 0x00FF_FFFF;
+                    let count = r.u32()?;
+                    let mut offset = base_offset;
+                    if flags & 0x01 != 0 {
+                        offset = offset.wrapping_add_signed(i64::from(r.u32()? as i32));
+                    }
+                    if flags & 0x04 != 0 {
+                        r.skip(4)?;
+                    }
+                    // Each sample takes its per-sample fields from the box,
+                    // or else its default size from the file.
+                    let field_len = 4 * (flags & 0xF00).count_ones() as usize;
+                    let fits = match r.remaining().checked_div(field_len) {
+                        Some(room) => count as usize <= room,
+                        None => {
+                            count <= MAX_SAMPLES
+                                && u64::from(count) * u64::from(default_size) <= file_len
+                        }
+                    };
+                    if !fits {
+                        return Err(invalid("trun describes more samples than it holds"));
+                    }
+                    for _ in 0..count {
+                        let duration = if flags & 0x100 != 0 {
+                            r.u32()?
+                        } else {
+                            default_duration
+                        };
+                        let size = if flags & 0x200 != 0 {
+                            r.u32()?
+                        } else {
+                            default_size
+                        };
+                        if flags & 0x400 != 0 {
+                            r.skip(4)?;
+                        }
+                        if flags & 0x800 != 0 {
+                            r.skip(4)?;
+                        }
+                        let track = &mut self.tracks[index];
+                        // Empty samples carry no cue and are never read.
+                        if track.is_subtitle() && size != 0 {
+                            track.samples.push(Sample {
+                                offset,
+                                size,
+                                time,
+                                duration,
+                            });
+                        }
+                        track.sample_count += 1;
+                        offset = offset.saturating_add(u64::from(size));
+                        time = time.saturating_add(u64::from(duration));
+                    }
+                }
+                next_time.insert(track_id, time);
+            }
+        }
+        for track in &mut self.tracks {
+            if let Some(&end) = next_time.get(&track.id) {
+                track.duration = track.duration.max(end);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_sample(&self, sample: &Sample) -> io::Result<Vec<u8>> {
+        let end = sample.offset.checked_add(u64::from(sample.size));
+        if end.is_none_or(|end| end > self.len) {
+            return Err(invalid("sample extends past end of file"));
+        }
+        if sample.size > MAX_SAMPLE_SIZE {
+            return Err(invalid("subtitle sample is implausibly large"));
+        }
+        let mut buf = vec![0u8; sample.size as usize];
+        read_exact_at(&self.file, &mut buf, sample.offset)?;
+        Ok(buf)
+    }
+
+    fn track_cues(&self, track: &Mp4Track) -> io::Result<Vec<TextCue>> {
+        let mut cues = Vec::new();
+        for sample in &track.samples {
+            if sample.size == 0 {
+                continue;
+            }
+            let data = self.read_sample(sample)?;
+            let start_ms = track.to_ms(sample.time);
+            let end_ms = track.to_ms(sample.time + u64::from(sample.duration));
+            match track.codec.as_str() {
+                "wvtt" => cues.extend(decode_wvtt(&data, start_ms, end_ms)?),
+                _ => {
+                    let text = decode_tx3g(&data)?;
+                    if !text.trim().is_empty() {
+                        cues.push(TextCue::new(start_ms, end_ms, text));
+                    }
+                }
+            }
+        }
+        Ok(cues)
+    }
+
+    /// Exports the given text tracks (all extractable subtitle tracks when
+    /// `ids` is empty) into `output_dir`, or next to the source file.
+    pub fn extract(
+        &self,
+        ids: &[u64],
+        output_dir: Option<&Path>,
+    ) -> Result<Vec<ExtractedSubtitle>, MediaError> {
+        let selected: Vec<&Mp4Track> = if ids.is_empty() {
+            self.tracks
+                .iter()
+                .filter(|t| t.is_subtitle() && t.text_format().is_some())
+                .collect()
+        } else {
+            ids.iter()
+                .map(|&id| {
+                    let track = self
+                        .tracks
+                        .iter()
+                        .find(|t| u64::from(t.id) == id && t.is_subtitle())
+                        .ok_or(MediaError::TrackNotFound(id))?;
+                    match track.text_format() {
+                        Some(_) => Ok(track),
+                        None => Err(MediaError::UnsupportedCodec {
+                            track: id,
+                            codec: track.codec.clone(),
+                        }),
+                    }
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut extracted = Vec::new();
+        for track in selected {
+            let cues = self
+                .track_cues(track)
+                .map_err(|e| MediaError::io(&self.path, e))?;
+            let format = track.text_format().expect("selected tracks are text");
+            let contents = match format {
+                TextFormat::Vtt => {
+                    let header = wvtt_config(&track.sample_entry);
+                    super::render_vtt(&cues, header.as_deref())
+                }
+                _ => super::render_srt(&cues),
+            };
+            let out = super::output_path(
+                &self.path,
+                output_dir,
+                u64::from(track.id),
+                Some(&track.language),
+                track.forced(),
+                format,
+            );
+            super::write_file(&out, &contents)?;
+            extracted.push(ExtractedSubtitle {
+                track_number: u64::from(track.id),
+                path: out,
+                format,
+                language: Some(track.language.clone()),
+                forced: track.forced(),
+                cue_count: cues.len(),
+            });
+        }
+        Ok(extracted)
+    }
+}
+
+fn parse_trak(trak: &[u8], file_len: u64) -> io::Result<Mp4Track> {
+    let mut track = Mp4Track {
+        language: "und".into(),
+        ..Default::default()
+    };
+    if let Some(tkhd) = find_box(trak, b"tkhd") {
+        let mut r = ByteReader::new(tkhd);
+        let version = r.u8()?;
+        let flags = u32::from(r.u16()?) << 8 | u32::from(r.u8()?);
+        track.enabled = flags & 0x01 != 0;
+        r.skip(if version == 1 { 16 } else { 8 })?;
+        track.id = r.u32()?;
+        // reserved, duration, reserved[2], layer, alternate_group, volume,
+        // reserved, matrix[9]
+        r.skip(if version == 1 { 12 } else { 8 } + 8 + 8 + 36)?;
+        track.width = r.u32()? >> 16;
+        track.height = r.u32()? >> 16;
+    }
+    if let Some(name) = find_box(trak, b"udta").and_then(|udta| find_box(udta, b"name")) {
+        let name = fourcc(name);
+        track.name = (!name.is_empty()).then_some(name);
+    }
+    let Some(mdia) = find_box(trak, b"mdia") else {
+        return Ok(track);
+    };
+    if let Some(mdhd) = find_box(mdia, b"mdhd") {
+        let mut r = ByteReader::new(mdhd);
+        let version = r.u8()?;
+        r.skip(3)?;
+        if version == 1 {
+            r.skip(16)?;
+            track.timescale = r.u32()?;
+            track.duration = r.u64()?;
+        } else {
+            r.skip(8)?;
+            track.timescale = r.u32()?;
+            track.duration = u64::from(r.u32()?);
+        }
+        track.language = decode_language(r.u16()?);
+    }
+    if let Some(hdlr) = find_box(mdia, b"hdlr") {
+        let mut r = ByteReader::new(hdlr);
+        r.skip(8)?;
+        track.handler = fourcc(r.take(4)?);
+    }
+    let Some(stbl) = find_box(mdia, b"minf").and_then(|minf| find_box(minf, b"stbl")) else {
+        return Ok(track);
+    };
+    parse_stbl(stbl, &mut track, file_len)?;
+    Ok(track)
+}
+
+fn parse_stbl(stbl: &[u8], track: &mut Mp4Track, file_len: u64) -> io::Result<()> {
+    if let Some(stsd) = find_box(stbl, b"stsd") {
+        if let Some((kind, entry)) = boxes(stsd.get(8..).unwrap_or_default()).next() {
+            track.codec = fourcc(&kind);
+            track.sample_entry = entry.to_vec();
+        }
+    }
+
+    let mut durations = Vec::new();
+    if let Some(stts) = find_box(stbl, b"stts") {
+        let mut r = ByteReader::new(stts);
+        r.skip(4)?;
+        for _ in 0..r.u32()? {
+            durations.push((r.u32()?, r.u32()?));
+        }
+    }
+
+    // Sample tables are only materialised for subtitle tracks; video and
+    // audio tracks can have millions of samples we never touch.
+    if !track.is_subtitle() {
+        track.sample_count = match sample_count(stbl)? {
+            Some(count) => count,
+            None => durations.iter().map(|(n, _)| u64::from(*n)).sum(),
+        };
+        return Ok(());
+    }
+
+    let sizes: Vec<u32> = if let Some(stsz) = find_box(stbl, b"stsz") {
+        let mut r = ByteReader::new(stsz);
+        r.skip(4)?;
+        let constant = r.u32()?;
+        let count = r.u32()?;
+        if constant != 0 {
+            if count > MAX_SAMPLES || u64::from(constant) * u64::from(count) > file_len {
+                return Err(invalid("stsz sample sizes exceed the file"));
+            }
+            vec![constant; count as usize]
+        } else {
+            (0..count).map(|_| r.u32()).collect::<io::Result<_>>()?
+        }
+    } else if let Some(stz2) = find_box(stbl, b"stz2") {
+        let mut r = ByteReader::new(stz2);
+        r.skip(7)?;
+        let field_size = r.u8()?;
+        let count = r.u32()? as usize;
+        let fits = match field_size {
+            4 => r.remaining() * 2,
+            8 => r.remaining(),
+            _ => r.remaining() / 2,
+        };
+        if count > fits {
+            return Err(invalid("stz2 sample count exceeds the box"));
+        }
+        let mut sizes = Vec::with_capacity(count);
+        while sizes.len() < count {
+            match field_size {
+                4 => {
+                    let byte = r.u8()?;
+                    sizes.push(u32::from(byte >> 4));
+                    sizes.push(u32::from(byte & 0x0F));
+                }
+                8 => sizes.push(u32::from(r.u8()?)),
+                _ => sizes.push(u32::from(r.u16()?)),
+            }
+        }
+        sizes.truncate(count);
+        sizes
+    } else {
+        Vec::new()
+    };
+    track.sample_count = sizes.len() as u64;
+
+    let mut chunk_offsets = Vec::new();
+    if let Some(stco) = find_box(stbl, b"stco") {
+        let mut r = ByteReader::new(stco);
+        r.skip(4)?;
+        for _ in 0..r.u32()? {
+            chunk_offsets.push(u64::from(r.u32()?));
+        }
+    } else if let Some(co64) = find_box(stbl, b"co64") {
+        let mut r = ByteReader::new(co64);
+        r.skip(4)?;
+        for _ in 0..r.u32()? {
+            chunk_offsets.push(r.u64()?);
+        }
+    }
+
+    let mut stsc = Vec::new();
+    if let Some(data) = find_box(stbl, b"stsc") {
+        let mut r = ByteReader::new(data);
+        r.skip(4)?;
+        for _ in 0..r.u32()? {
+            let first_chunk = r.u32()?;
+            let per_chunk = r.u32()?;
+            r.skip(4)?;
+            stsc.push((first_chunk, per_chunk));
+        }
+    }
+
+    let mut durations = durations
+        .into_iter()
+        .flat_map(|(count, delta)| std::iter::repeat_n(delta, count as usize));
+    let mut sample_index = 0usize;
+    let mut time = 0u64;
+    for (chunk_index, &chunk_offset) in chunk_offsets.iter().enumerate() {
+        let chunk_number = chunk_index as u32 + 1;
+        let per_chunk = stsc
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= chunk_number)
+            .map_or(0, |(_, n)| *n);
+        let mut offset = chunk_offset;
+        for _ in 0..per_chunk {
+            let Some(&size) = sizes.get(sample_index) else {
+                break;
+            };
+            let duration = durations.next().unwrap_or(0);
+            track.samples.push(Sample {
+                offset,
+                size,
+                time,
+                duration,
+            });
+            offset = offset.saturating_add(u64::from(size));
+            time += u64::from(duration);
+            sample_index += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Sample count from `stsz`/`stz2` without reading the size table.
+fn sample_count(stbl: &[u8]) -> io::Result<Option<u64>> {
+    if let Some(stsz) = find_box(stbl, b"stsz") {
+        let mut r = ByteReader::new(stsz);
+        r.skip(8)?;
+        return Ok(Some(u64::from(r.u32()?)));
+    }
+    if let Some(stz2) = find_box(stbl, b"stz2") {
+        let mut r = ByteReader::new(stz2);
+        r.skip(8)?;
+        return Ok(Some(u64::from(r.u32()?)));
+    }
+    Ok(None)
+}
+
+/// Decodes the packed ISO 639-2 code from `mdhd`. Values below 0x400 are
+/// classic QuickTime language codes.
+fn decode_language(packed: u16) -> String {
+    if packed < 0x400 {
+        return QUICKTIME_LANGUAGES
+            .get(packed as usize)
+            .copied()
+            .unwrap_or("und")
+            .to_string();
+    }
+    if packed == 0x7FFF {
+        return "und".into();
+    }
+    [(packed >> 10) & 0x1F, (packed >> 5) & 0x1F, packed & 0x1F]
+        .iter()
+        .map(|&c| char::from(c as u8 + 0x60))
+        .collect()
+}
+
+const QUICKTIME_LANGUAGES: [&str; 34] = [
+    "eng", "fre", "ger", "ita", "dut", "swe", "spa", "dan", "por", "nor", "heb", "jpn", "ara",
+    "fin", "gre", "ice", "mlt", "tur", "hrv", "chi", "urd", "hin", "tha", "kor", "lit", "pol",
+    "hun", "est", "lav", "smi", "fao", "per", "rus", "chi",
+];
+
+/// Decodes a 3GPP timed-text (`tx3g` / QuickTime `text`) sample, turning
+/// `styl` runs into `<b>`/`<i>`/`<u>` tags.
+fn decode_tx3g(data: &[u8]) -> io::Result<String> {
+    let mut r = ByteReader::new(data);
+    if r.remaining() < 2 {
+        return Ok(String::new());
+    }
+    let len = r.u16()? as usize;
+    let raw = r.take(len.min(r.remaining()))?;
+    let text: Vec<char> = if raw.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = raw[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else {
+        String::from_utf8_lossy(raw)
+            .trim_start_matches('\u{feff}')
+            .chars()
+            .collect()
+    };
+
+    let mut opening: HashMap<usize, String> = HashMap::new();
+    let mut closing: HashMap<usize, String> = HashMap::new();
+    if let Some(styl) = find_box(r.rest(), b"styl") {
+        let mut r = ByteReader::new(styl);
+        for _ in 0..r.u16()? {
+            let start = r.u16()? as usize;
+            let end = (r.u16()? as usize).min(text.len());
+            r.skip(2)?;
+            let face = r.u8()?;
+            r.skip(5)?;
+            if start >= end {
+                continue;
+            }
+            for (bit, tag) in [(1, "b"), (2, "i"), (4, "u")] {
+                if face & bit != 0 {
+                    opening
+                        .entry(start)
+                        .or_default()
+                        .push_str(&format!("<{tag}>"));
+                    closing
+                        .entry(end)
+                        .or_default()
+                        .insert_str(0, &format!("</{tag}>"));
+                }
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.iter().enumerate() {
+        if let Some(tags) = closing.get(&i) {
+            out.push_str(tags);
+        }
+        if let Some(tags) = opening.get(&i) {
+            out.push_str(tags);
+        }
+        out.push(*c);
+    }
+    if let Some(tags) = closing.get(&text.len()) {
+        out.push_str(tags);
+    }
+    Ok(out.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+/// Decodes an ISO 14496-30 WebVTT sample: one `vttc` box per cue, or a
+/// single `vtte` box for an empty interval.
+fn decode_wvtt(data: &[u8], start_ms: u64, end_ms: u64) -> io::Result<Vec<TextCue>> {
+    let mut cues = Vec::new();
+    for (kind, vttc) in boxes(data) {
+        if &kind != b"vttc" {
+            continue;
+        }
+        let mut cue = TextCue::new(start_ms, end_ms, String::new());
+        for (kind, payload) in boxes(vttc) {
+            let value = String::from_utf8_lossy(payload).into_owned();
+            match &kind {
+                b"payl" => cue.text = value,
+                b"iden" => cue.id = Some(value),
+                b"sttg" => cue.settings = Some(value),
+                _ => {}
+            }
+        }
+        cues.push(cue);
+    }
+    Ok(cues)
+}
+
+/// Header text from the `vttC` box inside a `wvtt` sample entry.
+fn wvtt_config(entry: &[u8]) -> Option<String> {
+    find_box(entry.get(8..)?, b"vttC").map(|c| String::from_utf8_lossy(c).into_owned())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[tauri::command]
+pub async fn list_mp4_subtitle_tracks(path: String) -> Result<Vec<SubtitleTrack>, MediaError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        Mp4File::open(Path::new(&path)).map(|file| file.subtitle_tracks())
+    })
+    .await
+    .map_err(|e| MediaError::Task(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn extract_mp4_subtitles(
+    path: String,
+    tracks: Option<Vec<u64>>,
+    output_dir: Option<String>,
+) -> Result<Vec<ExtractedSubtitle>, MediaError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = Mp4File::open(Path::new(&path))?;
+        file.extract(
+            &tracks.unwrap_or_default(),
+            output_dir.as_deref().map(Path::new),
+        )
+    })
+    .await
+    .map_err(|e| MediaError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = (payload.len() as u32 + 8).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// Full box payload: version and flags, then `fields`.
+    fn full_box(kind: &[u8; 4], fields: &[u32]) -> Vec<u8> {
+        let payload: Vec<u8> = std::iter::once(0)
+            .chain(fields.iter().copied())
+            .flat_map(u32::to_be_bytes)
+            .collect();
+        mp4_box(kind, &payload)
+    }
+
+    fn text_track() -> Mp4Track {
+        Mp4Track {
+            handler: "text".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rejects_sample_tables_larger_than_the_file() {
+        let stsz = full_box(b"stsz", &[1000, u32::MAX]);
+        let error = parse_stbl(&stsz, &mut text_track(), 1 << 20).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut stz2 = full_box(b"stz2", &[8, u32::MAX]);
+        stz2.extend_from_slice(&[1, 2, 3]);
+        let error = parse_stbl(&stz2, &mut text_track(), 1 << 20).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_samples_past_the_end_of_the_file() {
+        let sample = [&[0, 5][..], b"Hello"].concat();
+        let stbl = mp4_box(
+            b"stbl",
+            &[
+                mp4_box(
+                    b"stsd",
+                    &[&[0, 0, 0, 0, 0, 0, 0, 1][..], &mp4_box(b"tx3g", &[0; 8])].concat(),
+                ),
+                full_box(b"stts", &[1, 1, 1000]),
+                full_box(b"stsz", &[0, 1, sample.len() as u32]),
+                full_box(b"stsc", &[1, 1, 1, 1]),
+                full_box(b"stco", &[1, 0]),
+            ]
+            .concat(),
+        );
+        let file = |chunk_offset: u32| {
+            let mut stbl = stbl.clone();
+            let at = stbl.len() - 4;
+            stbl[at..].copy_from_slice(&chunk_offset.to_be_bytes());
+            let mdia = [
+                full_box(b"mdhd", &[0, 0, 1000, 1000, 0x55C4 << 16]),
+                full_box(b"hdlr", &[0, u32::from_be_bytes(*b"text")]),
+                mp4_box(b"minf", &stbl),
+            ]
+            .concat();
+            let moov = mp4_box(b"moov", &mp4_box(b"trak", &mp4_box(b"mdia", &mdia)));
+            [mp4_box(b"ftyp", b"isom"), mp4_box(b"mdat", &sample), moov].concat()
+        };
+        let dir = std::env::temp_dir();
+        for (name, chunk_offset, ok) in [("inside", 20, true), ("outside", u32::MAX - 3, false)] {
+            let path = dir.join(format!("{}-{name}.mp4", std::process::id()));
+            std::fs::write(&path, file(chunk_offset)).unwrap();
+            let mp4 = Mp4File::open(&path).unwrap();
+            match mp4.extract(&[], Some(&dir)) {
+                Ok(extracted) => {
+                    assert!(ok);
+                    assert_eq!(extracted[0].cue_count, 1);
+                    let srt = std::fs::read_to_string(&extracted[0].path).unwrap();
+                    assert!(srt.contains("Hello"), "{srt}");
+                    std::fs::remove_file(&extracted[0].path).unwrap();
+                }
+                Err(error) => assert!(
+                    !ok && matches!(error, MediaError::Malformed(_)),
+                    "{error:?}"
+                ),
+            }
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    /// A fragmented file with one track and a `moof` holding `traf`.
+    fn fragmented(name: &str, traf: &[Vec<u8>]) -> PathBuf {
+        let mut tkhd = vec![0; 20];
+        tkhd[2] = 1;
+        let moov = mp4_box(b"moov", &mp4_box(b"trak", &full_box(b"tkhd", &tkhd)));
+        let traf = [full_box(b"tfhd", &[1])]
+            .into_iter()
+            .chain(traf.iter().cloned());
+        let moof = mp4_box(
+            b"moof",
+            &mp4_box(b"traf", &traf.collect::<Vec<_>>().concat()),
+        );
+        let path = std::env::temp_dir().join(format!("{}-{name}.mp4", std::process::id()));
+        std::fs::write(&path, [mp4_box(b"ftyp", b"isom"), moov, moof].concat()).unwrap();
+        path
+    }
+
+    #[test]
+    fn rejects_a_trun_of_endless_empty_samples() {
+        let path = fragmented("endless", &[full_box(b"trun", &[u32::MAX])]);
+        let error = Mp4File::open(&path).err().unwrap();
+        assert!(matches!(error, MediaError::Malformed(_)), "{error:?}");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn fragment_times_saturate() {
+        let mut tfdt = mp4_box(b"tfdt", &[1, 0, 0, 0]);
+        tfdt.extend_from_slice(&(u64::MAX - 5).to_be_bytes());
+        tfdt[3] += 8;
+        let mut trun = full_box(b"trun", &[2, u32::MAX, u32::MAX]);
+        trun[10] = 0x01;
+        let path = fragmented("late", &[tfdt, trun]);
+        let mp4 = Mp4File::open(&path).unwrap();
+        assert_eq!(mp4.tracks[0].sample_count, 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_implausible_sample_tables() {
+        let stsz = full_box(b"stsz", &[1, u32::MAX]);
+        let error = parse_stbl(&stsz, &mut text_track(), u64::MAX).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,573 @@
+//! MPEG transport stream probing (`.ts`, `.m2ts`).
+//!
+//! A window at the start of the file gives the PAT/PMT, the stream types and
+//! languages, the video frame size and the frame rate (from PTS spacing); a
+//! window at the end gives the last PCR, so the duration is exact without
+//! reading the middle of the file.
+
+use super::probe::{AudioTrack, Container, VideoProbe};
+use super::MediaError;
+use crate::movie_hash::read_exact_at;
+use std::collections::HashMap;
+use std::path::Path;
+
+const SYNC_BYTE: u8 = 0x47;
+const TS_PACKET: usize = 188;
+/// Bytes read from each end of the file.
+const WINDOW: u64 = 4 * 1024 * 1024;
+/// Video elementary stream bytes collected when looking for a sequence header.
+const MAX_ES_BYTES: usize = 512 * 1024;
+/// PCR and PTS wrap at 2^33 ticks of the 90 kHz clock.
+const PTS_WRAP: u64 = 1 << 33;
+
+/// Returns the packet size (188 for plain TS, 192 for M2TS) if `head` looks
+/// like a transport stream.
+pub fn packet_size(head: &[u8]) -> Option<usize> {
+    [TS_PACKET, TS_PACKET + 4].into_iter().find(|&size| {
+        let offset = size - TS_PACKET;
+        (0..3).all(|i| head.get(offset + i * size) == Some(&SYNC_BYTE))
+    })
+}
+
+struct Packet<'a> {
+    pid: u16,
+    unit_start: bool,
+    pcr: Option<u64>,
+    payload: &'a [u8],
+}
+
+/// Iterates well-formed packets in `data`, resynchronising on the sync byte.
+fn packets(data: &[u8], size: usize) -> impl Iterator<Item = Packet<'_>> {
+    let offset = size - TS_PACKET;
+    let mut pos = 0usize;
+    std::iter::from_fn(move || loop {
+        let packet = data.get(pos + offset..pos + size)?;
+        if packet[0] != SYNC_BYTE {
+            pos += 1;
+            continue;
+        }
+        pos += size;
+        let pid = u16::from(packet[1] & 0x1F) << 8 | u16::from(packet[2]);
+        let unit_start = packet[1] & 0x40 != 0;
+        let adaptation = (packet[3] >> 4) & 0x03;
+        let mut payload_start = 4;
+        let mut pcr = None;
+        if adaptation & 0x02 != 0 {
+            let len = usize::from(packet[4]);
+            if len >= 7 && packet[5] & 0x10 != 0 {
+                let b = &packet[6..12];
+                let base = u64::from(b[0]) << 25
+                    | u64::from(b[1]) << 17
+                    | u64::from(b[2]) << 9
+                    | u64::from(b[3]) << 1
+                    | u64::from(b[4]) >> 7;
+                pcr = Some(base);
+            }
+            payload_start = 5 + len;
+        }
+        let payload = if adaptation & 0x01 != 0 && payload_start < TS_PACKET {
+            &packet[payload_start..]
+        } else {
+            &[]
+        };
+        return Some(Packet {
+            pid,
+            unit_start,
+            pcr,
+            payload,
+        });
+    })
+}
+
+/// Returns the section body (after the pointer field) of a PSI packet.
+fn section(payload: &[u8]) -> Option<&[u8]> {
+    let pointer = usize::from(*payload.first()?);
+    let section = payload.get(1 + pointer..)?;
+    let len = (usize::from(section.get(1)? & 0x0F) << 8) | usize::from(*section.get(2)?);
+    section.get(..(3 + len).min(section.len()))
+}
+
+struct Stream {
+    stream_type: u8,
+    codec: Option<&'static str>,
+    language: Option<String>,
+    is_video: bool,
+}
+
+pub fn probe(path: &Path, byte_size: u64) -> Result<VideoProbe, MediaError> {
+    let file = super::open(path)?;
+    let read_window = |offset: u64| -> Result<Vec<u8>, MediaError> {
+        let len = WINDOW.min(byte_size - offset) as usize;
+        let mut buf = vec![0u8; len];
+        read_exact_at(&file, &mut buf, offset).map_err(|e| MediaError::io(path, e))?;
+        Ok(buf)
+    };
+    let head = read_window(0)?;
+    let size = packet_size(&head).ok_or_else(|| MediaError::Malformed("lost TS sync".into()))?;
+
+    let mut pmt_pid = None;
+    let mut pcr_pid = None;
+    let mut streams: HashMap<u16, Stream> = HashMap::new();
+    let mut order = Vec::new();
+    let mut first_pcr = None;
+    let mut video_pts = Vec::new();
+    let mut video_es = Vec::new();
+    let mut video_pid = None;
+
+    for packet in packets(&head, size) {
+        match packet.pid {
+            0 if packet.unit_start && pmt_pid.is_none() => {
+                pmt_pid = section(packet.payload).and_then(parse_pat);
+            }
+            pid if Some(pid) == pmt_pid && packet.unit_start && pcr_pid.is_none() => {
+                if let Some((pcr, parsed)) = section(packet.payload).and_then(parse_pmt) {
+                    pcr_pid = Some(pcr);
+                    for (pid, stream) in parsed {
+                        if stream.is_video && video_pid.is_none() {
+                            video_pid = Some(pid);
+                        }
+                        order.push(pid);
+                        streams.insert(pid, stream);
+                    }
+                }
+            }
+            _ => {}
+        }
+        if first_pcr.is_none() && Some(packet.pid) == pcr_pid {
+            first_pcr = packet.pcr;
+        }
+        if Some(packet.pid) == video_pid {
+            let payload = if packet.unit_start {
+                if let Some(pts) = pes_pts(packet.payload) {
+                    video_pts.push(pts);
+                }
+                pes_payload(packet.payload)
+            } else {
+                packet.payload
+            };
+            if video_es.len() < MAX_ES_BYTES {
+                video_es.extend_from_slice(payload);
+            }
+        }
+    }
+
+    let mut probe = VideoProbe::new(Container::MpegTs, byte_size);
+    for pid in &order {
+        let stream = &streams[pid];
+        if stream.is_video && probe.video_codec.is_none() {
+            probe.video_codec = stream.codec.map(str::to_string);
+        } else if !stream.is_video {
+            if let Some(codec) = stream.codec {
+                probe.audio_tracks.push(AudioTrack {
+                    codec: codec.to_string(),
+                    language: stream.language.clone(),
+                    channels: None,
+                    sample_rate: None,
+                });
+            }
+        }
+    }
+
+    if let Some(stream_type) = video_pid.map(|pid| streams[&pid].stream_type) {
+        let dims = match stream_type {
+            0x01 | 0x02 => mpeg2_sequence_header(&video_es),
+            0x1B => h264_dimensions(&video_es).map(|(w, h)| (w, h, None)),
+            _ => None,
+        };
+        if let Some((width, height, fps)) = dims {
+            probe.width = Some(width);
+            probe.height = Some(height);
+            probe.fps = fps;
+        }
+        if probe.fps.is_none() {
+            probe.fps = fps_from_pts(&mut video_pts);
+        }
+    }
+
+    if let (Some(first), Some(pcr_pid)) = (first_pcr, pcr_pid) {
+        let tail_start = byte_size.saturating_sub(WINDOW);
+        let tail = if tail_start == 0 {
+            head
+        } else {
+            read_window(tail_start)?
+        };
+        let last = packets(&tail, size)
+            .filter(|p| p.pid == pcr_pid)
+            .filter_map(|p| p.pcr)
+            .last();
+        if let Some(last) = last {
+            let ticks = (last + PTS_WRAP - first) % PTS_WRAP;
+            probe.duration_ms = Some(ticks / 90);
+        }
+    }
+    Ok(probe)
+}
+
+/// PMT PID of the first real program in the PAT.
+fn parse_pat(section: &[u8]) -> Option<u16> {
+    let body = section.get(8..section.len().saturating_sub(4))?;
+    body.chunks_exact(4).find_map(|entry| {
+        let program = u16::from_be_bytes([entry[0], entry[1]]);
+        (program != 0).then(|| u16::from(entry[2] & 0x1F) << 8 | u16::from(entry[3]))
+    })
+}
+
+/// Returns the PCR PID and the elementary streams of a PMT section.
+fn parse_pmt(section: &[u8]) -> Option<(u16, Vec<(u16, Stream)>)> {
+    let pcr_pid = u16::from(section.get(8)? & 0x1F) << 8 | u16::from(*section.get(9)?);
+    let info_len = (usize::from(section.get(10)? & 0x0F) << 8) | usize::from(*section.get(11)?);
+    let end = section.len().saturating_sub(4);
+    let mut pos = 12 + info_len;
+    let mut streams = Vec::new();
+    while pos + 5 <= end {
+        let stream_type = section[pos];
+        let pid = u16::from(section[pos + 1] & 0x1F) << 8 | u16::from(section[pos + 2]);
+        let es_len = (usize::from(section[pos + 3] & 0x0F) << 8) | usize::from(section[pos + 4]);
+        let descriptors = section
+            .get(pos + 5..(pos + 5 + es_len).min(end))
+            .unwrap_or_default();
+        streams.push((pid, describe_stream(stream_type, descriptors)));
+        pos += 5 + es_len;
+    }
+    Some((pcr_pid, streams))
+}
+
+fn describe_stream(stream_type: u8, descriptors: &[u8]) -> Stream {
+    let mut language = None;
+    let mut private_codec = None;
+    let mut pos = 0;
+    while pos + 2 <= descriptors.len() {
+        let tag = descriptors[pos];
+        let len = usize::from(descriptors[pos + 1]);
+        let body = descriptors.get(pos + 2..pos + 2 + len).unwrap_or_default();
+        match tag {
+            0x0A if body.len() >= 3 => {
+                language = Some(String::from_utf8_lossy(&body[..3]).to_lowercase())
+            }
+            0x05 if body.len() >= 4 => {
+                private_codec = match &body[..4] {
+                    b"AC-3" => Some("ac3"),
+                    b"EAC3" => Some("eac3"),
+                    b"DTS1" | b"DTS2" | b"DTS3" => Some("dts"),
+                    b"HEVC" => Some("hevc"),
+                    _ => private_codec,
+                }
+            }
+            0x6A => private_codec = Some("ac3"),
+            0x7A => private_codec = Some("eac3"),
+            0x7B => private_codec = Some("dts"),
+            _ => {}
+        }
+        pos += 2 + len;
+    }
+    let (codec, is_video) = match stream_type {
+        0x01 => (Some("mpeg1video"), true),
+        0x02 => (Some("mpeg2video"), true),
+        0x10 => (Some("mpeg4"), true),
+        0x1B => (Some("h264"), true),
+        0x24 => (Some("hevc"), true),
+        0xEA => (Some("vc1"), true),
+        0x03 | 0x04 => (Some("mp2"), false),
+        0x0F | 0x11 => (Some("aac"), false),
+        0x80 => (Some("pcm"), false),
+        0x81 => (Some("ac3"), false),
+        0x82 | 0x85 | 0x86 | 0x8A => (Some("dts"), false),
+        0x83 => (Some("truehd"), false),
+        0x84 | 0x87 => (Some("eac3"), false),
+        0x06 => (
+            private_codec.filter(|c| *c != "hevc"),
+            private_codec == Some("hevc"),
+        ),
+        _ => (None, false),
+    };
+    Stream {
+        stream_type,
+        codec,
+        language,
+        is_video,
+    }
+}
+
+/// PTS of a PES packet header, if present.
+fn pes_pts(pes: &[u8]) -> Option<u64> {
+    if pes.get(..3)? != [0, 0, 1] || pes.get(7)? & 0x80 == 0 {
+        return None;
+    }
+    let p = pes.get(9..14)?;
+    Some(
+        u64::from(p[0] >> 1 & 0x07) << 30
+            | u64::from(p[1]) << 22
+            | u64::from(p[2] >> 1) << 15
+            | u64::from(p[3]) << 7
+            | u64::from(p[4] >> 1),
+    )
+}
+
+/// Elementary stream bytes following a PES header.
+fn pes_payload(pes: &[u8]) -> &[u8] {
+    match pes.get(8) {
+        Some(&len) if pes.starts_with(&[0, 0, 1]) => {
+            pes.get(9 + usize::from(len)..).unwrap_or_default()
+        }
+        _ => pes,
+    }
+}
+
+/// Frame rate from the smallest spacing between presentation timestamps,
+/// snapped to the common broadcast/film rates.
+fn fps_from_pts(pts: &mut [u64]) -> Option<f64> {
+    pts.sort_unstable();
+    let delta = pts
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|d| *d > 0)
+        .min()?;
+    let fps = 90_000.0 / delta as f64;
+    let snapped = [23.976, 24.0, 25.0, 29.97, 30.0, 50.0, 59.94, 60.0]
+        .into_iter()
+        .find(|rate| (fps - rate).abs() / rate < 0.005);
+    Some(snapped.unwrap_or(fps))
+}
+
+fn find_start_code(data: &[u8], code: impl Fn(u8) -> bool) -> Option<&[u8]> {
+    data.windows(4)
+        .position(|w| w[..3] == [0, 0, 1] && code(w[3]))
+        .map(|i| &data[i + 4..])
+}
+
+/// Width, height and frame rate from an MPEG-1/2 sequence header.
+fn mpeg2_sequence_header(es: &[u8]) -> Option<(u32, u32, Option<f64>)> {
+    let header = find_start_code(es, |c| c == 0xB3)?;
+    let h = header.get(..4)?;
+    let width = u32::from(h[0]) << 4 | u32::from(h[1] >> 4);
+    let height = u32::from(h[1] & 0x0F) << 8 | u32::from(h[2]);
+    let fps = match h[3] & 0x0F {
+        1 => Some(23.976),
+        2 => Some(24.0),
+        3 => Some(25.0),
+        4 => Some(29.97),
+        5 => Some(30.0),
+        6 => Some(50.0),
+        7 => Some(59.94),
+        8 => Some(60.0),
+        _ => None,
+    };
+    Some((width, height, fps))
+}
+
+/// MSB-first bit reader with Exp-Golomb support, over an RBSP.
+struct BitReader {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl BitReader {
+    fn bit(&mut self) -> Option<u32> {
+        let byte = self.data.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+        Some(u32::from(bit))
+    }
+
+    fn bits(&mut self, n: u32) -> Option<u32> {
+        (0..n).try_fold(0, |acc, _| Some(acc << 1 | self.bit()?))
+    }
+
+    fn ue(&mut self) -> Option<u32> {
+        let mut zeros = 0;
+        while self.bit()? == 0 {
+            zeros += 1;
+            if zeros > 31 {
+                return None;
+            }
+        }
+        Some((1u32 << zeros) - 1 + self.bits(zeros)?)
+    }
+
+    fn se(&mut self) -> Option<i32> {
+        let v = i64::from(self.ue()?);
+        i32::try_from(if v & 1 == 1 { (v + 1) / 2 } else { -(v / 2) }).ok()
+    }
+}
+
+/// Display width and height from the first H.264 sequence parameter set.
+fn h264_dimensions(es: &[u8]) -> Option<(u32, u32)> {
+    let nal = find_start_code(es, |c| c & 0x1F == 7)?;
+    let end = nal
+        .windows(3)
+        .position(|w| w == [0, 0, 1])
+        .unwrap_or(nal.len().min(256));
+    // Strip emulation-prevention bytes (00 00 03 -> 00 00).
+    let mut rbsp = Vec::with_capacity(end);
+    for &byte in &nal[..end] {
+        if byte == 3 && rbsp.ends_with(&[0, 0]) {
+            continue;
+        }
+        rbsp.push(byte);
+    }
+    let mut r = BitReader { data: rbsp, pos: 0 };
+    let profile = r.bits(8)?;
+    r.bits(16)?;
+    r.ue()?;
+    let mut chroma_format = 1;
+    let mut separate_planes = 0;
+    if matches!(
+        profile,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format = r.ue()?;
+        if chroma_format == 3 {
+            separate_planes = r.bit()?;
+        }
+        r.ue()?;
+        r.ue()?;
+        r.bit()?;
+        if r.bit()? == 1 {
+            let lists = if chroma_format == 3 { 12 } else { 8 };
+            for i in 0..lists {
+                if r.bit()? == 1 {
+                    let size = if i < 6 { 16 } else { 64 };
+                    let (mut last, mut next) = (8i32, 8i32);
+                    for _ in 0..size {
+                        if next != 0 {
+                            next = last.wrapping_add(r.se()?).rem_euclid(256);
+                        }
+                        last = if next == 0 { last } else { next };
+                    }
+                }
+            }
+        }
+    }
+    r.ue()?;
+    match r.ue()? {
+        0 => {
+            r.ue()?;
+        }
+        1 => {
+            r.bit()?;
+            r.se()?;
+            r.se()?;
+            for _ in 0..r.ue()? {
+                r.se()?;
+            }
+        }
+        _ => {}
+    }
+    r.ue()?;
+    r.bit()?;
+    let width_mbs = r.ue()?.checked_add(1)?;
+    let height_units = r.ue()?.checked_add(1)?;
+    let frame_mbs_only = r.bit()?;
+    if frame_mbs_only == 0 {
+        r.bit()?;
+    }
+    r.bit()?;
+    let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+    if r.bit()? == 1 {
+        left = r.ue()?;
+        right = r.ue()?;
+        top = r.ue()?;
+        bottom = r.ue()?;
+    }
+    let (crop_x, crop_y) = match (chroma_format, separate_planes) {
+        (1, 0) => (2, 2 * (2 - frame_mbs_only)),
+        (2, 0) => (2, 2 - frame_mbs_only),
+        _ => (1, 2 - frame_mbs_only),
+    };
+    // The fields are unbounded exp-Golomb codes; sizes that overflow are
+    // left unknown.
+    let crop = |unit: u32, a: u32, b: u32| unit.checked_mul(a.checked_add(b)?);
+    let width = width_mbs
+        .checked_mul(16)?
+        .checked_sub(crop(crop_x, left, right)?)?;
+    let height = (2 - frame_mbs_only)
+        .checked_mul(height_units)?
+        .checked_mul(16)?
+        .checked_sub(crop(crop_y, top, bottom)?)?;
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes an H.264 SPS NAL unit with start code and emulation prevention.
+    #[derive(Default)]
+    struct Sps(Vec<bool>);
+
+    impl Sps {
+        fn bits(mut self, n: u32, value: u64) -> Self {
+            self.0.extend((0..n).rev().map(|i| value >> i & 1 == 1));
+            self
+        }
+
+        fn ue(self, value: u64) -> Self {
+            let code = value + 1;
+            let len = 64 - code.leading_zeros();
+            self.bits(len - 1, 0).bits(len, code)
+        }
+
+        fn nal(self) -> Vec<u8> {
+            let bits = self.bits(1, 1);
+            let rbsp: Vec<u8> = bits
+                .0
+                .chunks(8)
+                .map(|c| (0..8).fold(0, |acc, i| acc << 1 | u8::from(c.get(i) == Some(&true))))
+                .collect();
+            let mut out = vec![0, 0, 1, 0x67];
+            for byte in rbsp {
+                if byte <= 3 && out.ends_with(&[0, 0]) {
+                    out.push(3);
+                }
+                out.push(byte);
+            }
+            out
+        }
+    }
+
+    /// Baseline profile SPS down to the frame size and cropping.
+    fn sps(width_mbs: u64, height_mbs: u64, crop: Option<[u64; 4]>) -> Vec<u8> {
+        let sps = Sps::default()
+            .bits(8, 66)
+            .bits(16, 0x001E)
+            .ue(0)
+            .ue(0)
+            .ue(2)
+            .ue(1)
+            .bits(1, 0)
+            .ue(width_mbs - 1)
+            .ue(height_mbs - 1)
+            .bits(1, 1)
+            .bits(1, 1);
+        match crop {
+            Some(crop) => crop.into_iter().fold(sps.bits(1, 1), Sps::ue),
+            None => sps.bits(1, 0),
+        }
+        .nal()
+    }
+
+    #[test]
+    fn h264_frame_size() {
+        assert_eq!(
+            h264_dimensions(&sps(120, 68, Some([0, 0, 0, 4]))),
+            Some((1920, 1080))
+        );
+        assert_eq!(h264_dimensions(&sps(45, 36, None)), Some((720, 576)));
+    }
+
+    #[test]
+    fn h264_frame_size_out_of_range() {
+        let huge = u64::from(u32::MAX) - 1;
+        assert_eq!(h264_dimensions(&sps(huge, 68, None)), None);
+        assert_eq!(h264_dimensions(&sps(u64::from(u32::MAX), 68, None)), None);
+        assert_eq!(h264_dimensions(&sps(120, 68, Some([0, huge, 0, 0]))), None);
+        assert_eq!(
+            h264_dimensions(&sps(120, 68, Some([huge, huge, 0, 0]))),
+            None
+        );
+        assert_eq!(
+            h264_dimensions(&sps(120, 68, Some([0, 0, 1 << 31, 0]))),
+            None
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Container probing for the `moviefps` / `movietimems` / `movieframes` /
+//! `moviebytesize` upload fields.
+//!
+//! Everything comes from container headers; nothing is decoded, so probing a
+//! 100 GB remux costs a handful of small reads.
+
+use super::matroska::{MatroskaFile, TRACK_TYPE_AUDIO, TRACK_TYPE_VIDEO};
+use super::mp4::Mp4File;
+use super::{avi, mpegts, MediaError};
+use crate::movie_hash::read_exact_at;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    Matroska,
+    Webm,
+    Mp4,
+    Mov,
+    Avi,
+    MpegTs,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrack {
+    pub codec: String,
+    pub language: Option<String>,
+    pub channels: Option<u32>,
+    pub sample_rate: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoProbe {
+    pub container: Container,
+    pub byte_size: u64,
+    pub duration_ms: Option<u64>,
+    pub fps: Option<f64>,
+    pub frame_count: Option<u64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub video_codec: Option<String>,
+    pub audio_tracks: Vec<AudioTrack>,
+}
+
+impl VideoProbe {
+    pub(crate) fn new(container: Container, byte_size: u64) -> Self {
+        Self {
+            container,
+            byte_size,
+            duration_ms: None,
+            fps: None,
+            frame_count: None,
+            width: None,
+            height: None,
+            video_codec: None,
+            audio_tracks: Vec::new(),
+        }
+    }
+
+    /// Derives whichever of duration / frame count is missing from the other.
+    fn complete(mut self) -> Self {
+        if let Some(fps) = self.fps.filter(|f| *f > 0.0) {
+            self.fps = Some((fps * 1000.0).round() / 1000.0);
+            match (self.duration_ms, self.frame_count) {
+                (Some(ms), None) => {
+                    self.frame_count = Some((ms as f64 * fps / 1000.0).round() as u64)
+                }
+                (None, Some(frames)) => {
+                    self.duration_ms = Some((frames as f64 * 1000.0 / fps).round() as u64)
+                }
+                _ => {}
+            }
+        }
+        self
+    }
+}
+
+/// Identifies the container from its first bytes.
+pub fn sniff(path: &Path) -> Result<Option<Container>, MediaError> {
+    let file = super::open(path)?;
+    let mut head = [0u8; 400];
+    let len = file.metadata().map_err(|e| MediaError::io(path, e))?.len();
+    let n = head.len().min(len as usize);
+    read_exact_at(&file, &mut head[..n], 0).map_err(|e| MediaError::io(path, e))?;
+    let head = &head[..n];
+
+    if head.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Ok(Some(Container::Matroska));
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"AVI " {
+        return Ok(Some(Container::Avi));
+    }
+    if head.len() >= 12 {
+        match &head[4..8] {
+            b"ftyp" if &head[8..12] == b"qt  " => return Ok(Some(Container::Mov)),
+            b"ftyp" => return Ok(Some(Container::Mp4)),
+            b"moov" | b"mdat" | b"free" | b"skip" | b"wide" | b"pnot" => {
+                return Ok(Some(Container::Mov))
+            }
+            _ => {}
+        }
+    }
+    if mpegts::packet_size(head).is_some() {
+        return Ok(Some(Container::MpegTs));
+    }
+    Ok(None)
+}
+
+pub fn probe(path: &Path) -> Result<VideoProbe, MediaError> {
+    let byte_size = std::fs::metadata(path)
+        .map_err(|e| MediaError::io(path, e))?
+        .len();
+    let probe = match sniff(path)? {
+        Some(Container::Matroska | Container::Webm) => probe_matroska(path, byte_size)?,
+        Some(container @ (Container::Mp4 | Container::Mov)) => {
+            probe_mp4(path, container, byte_size)?
+        }
+        Some(Container::Avi) => avi::probe(path, byte_size)?,
+        Some(Container::MpegTs) => mpegts::probe(path, byte_size)?,
+        None => {
+            return Err(MediaError::UnsupportedContainer {
+                path: path.to_path_buf(),
+                expected: "Matroska, MP4, AVI or MPEG-TS",
+            })
+        }
+    };
+    Ok(probe.complete())
+}
+
+fn probe_matroska(path: &Path, byte_size: u64) -> Result<VideoProbe, MediaError> {
+    let file = MatroskaFile::open(path)?;
+    let container = if file.doc_type == "webm" {
+        Container::Webm
+    } else {
+        Container::Matroska
+    };
+    let mut probe = VideoProbe::new(container, byte_size);
+    probe.duration_ms = file.duration_ms().map(|ms| ms.round() as u64);
+    if let Some(video) = file
+        .tracks
+        .iter()
+        .find(|t| t.track_type == TRACK_TYPE_VIDEO)
+    {
+        probe.video_codec = Some(codec_name(&video.codec_id));
+        probe.width = video.pixel_width.map(|w| w as u32);
+        probe.height = video.pixel_height.map(|h| h as u32);
+        probe.fps = video
+            .default_duration
+            .filter(|ns| *ns > 0)
+            .map(|ns| 1_000_000_000.0 / ns as f64);
+    }
+    probe.audio_tracks = file
+        .tracks
+        .iter()
+        .filter(|t| t.track_type == TRACK_TYPE_AUDIO)
+        .map(|t| AudioTrack {
+            codec: codec_name(&t.codec_id),
+            language: Some(
+                t.language_bcp47
+                    .clone()
+                    .unwrap_or_else(|| t.language.clone()),
+            ),
+            channels: t.channels.map(|c| c as u32),
+            sample_rate: t.sampling_frequency.map(|f| f.round() as u32),
+        })
+        .collect();
+    Ok(probe)
+}
+
+fn probe_mp4(path: &Path, container: Container, byte_size: u64) -> Result<VideoProbe, MediaError> {
+    let file = Mp4File::open(path)?;
+    let mut probe = VideoProbe::new(container, byte_size);
+    probe.duration_ms = Some(file.duration_ms()).filter(|ms| *ms > 0);
+    if let Some(video) = file.tracks.iter().find(|t| t.handler == "vide") {
+        probe.video_codec = Some(codec_name(&video.codec));
+        // The visual sample entry carries the coded size; tkhd has the display size.
+        let entry = &video.sample_entry;
+        let (width, height) = if entry.len() >= 28 {
+            (
+                u32::from(u16::from_be_bytes([entry[24], entry[25]])),
+                u32::from(u16::from_be_bytes([entry[26], entry[27]])),
+            )
+        } else {
+            (video.width, video.height)
+        };
+        probe.width = Some(if video.width > 0 { video.width } else { width });
+        probe.height = Some(if video.height > 0 {
+            video.height
+        } else {
+            height
+        });
+        if video.duration > 0 && video.timescale > 0 {
+            let seconds = video.duration as f64 / f64::from(video.timescale);
+            probe.fps = Some(video.sample_count as f64 / seconds);
+            probe.frame_count = Some(video.sample_count);
+            probe
+                .duration_ms
+                .get_or_insert((seconds * 1000.0).round() as u64);
+        }
+    }
+    probe.audio_tracks = file
+        .tracks
+        .iter()
+        .filter(|t| t.handler == "soun")
+        .map(|t| {
+            let entry = &t.sample_entry;
+            let (channels, sample_rate) = if entry.len() >= 28 {
+                (
+                    Some(u32::from(u16::from_be_bytes([entry[16], entry[17]]))),
+                    Some(u32::from_be_bytes(entry[24..28].try_into().unwrap()) >> 16),
+                )
+            } else {
+                (None, None)
+            };
+            AudioTrack {
+                codec: codec_name(&t.codec),
+                language: Some(t.language.clone()),
+                channels,
+                sample_rate,
+            }
+        })
+        .collect();
+    Ok(probe)
+}
+
+/// Normalizes Matroska codec IDs and MP4/AVI four-character codes to the
+/// short names the upload form shows.
+pub(crate) fn codec_name(raw: &str) -> String {
+    let name = match raw.trim() {
+        "V_MPEG4/ISO/AVC" | "avc1" | "avc3" | "H264" | "h264" | "X264" | "x264" | "AVC1" => "h264",
+        "V_MPEGH/ISO/HEVC" | "hvc1" | "hev1" | "HEVC" | "H265" | "hevc" => "hevc",
+        "V_AV1" | "av01" => "av1",
+        "V_VP8" => "vp8",
+        "V_VP9" | "vp09" => "vp9",
+        "V_MPEG4/ISO/ASP" | "V_MPEG4/ISO/SP" | "mp4v" | "XVID" | "xvid" | "DIVX" | "divx"
+        | "DX50" | "FMP4" => "mpeg4",
+        "V_MPEG2" | "V_MPEG1" | "mp2v" => "mpeg2video",
+        "A_AAC" | "A_AAC/MPEG4/LC" | "A_AAC/MPEG2/LC" | "mp4a" => "aac",
+        "A_AC3" | "ac-3" => "ac3",
+        "A_EAC3" | "ec-3" => "eac3",
+        "A_DTS" => "dts",
+        "A_TRUEHD" | "mlpa" => "truehd",
+        "A_FLAC" | "fLaC" => "flac",
+        "A_OPUS" | "Opus" => "opus",
+        "A_VORBIS" => "vorbis",
+        "A_MPEG/L3" | ".mp3" => "mp3",
+        "A_MPEG/L2" => "mp2",
+        other if other.starts_with("A_PCM") || other == "lpcm" || other == "sowt" => "pcm",
+        other if other.starts_with("A_AAC") => "aac",
+        other => return other.to_string(),
+    };
+    name.to_string()
+}
+
+#[tauri::command]
+pub async fn probe_video(path: String) -> Result<VideoProbe, MediaError> {
+    tauri::async_runtime::spawn_blocking(move || probe(Path::new(&path)))
+        .await
+        .map_err(|e| MediaError::Task(e.to_string()))?
+}
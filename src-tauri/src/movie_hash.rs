@@ -0,0 +1,181 @@
+//! OpenSubtitles movie hash.
+//!
+//! The hash is the file size plus the wrapping sum of every little-endian
+//! `u64` in the first and last 64 KiB of the file. Only those two chunks are
+//! read, with positioned reads, so hashing a 60 GB remux on an SMB share costs
+//! two round trips instead of streaming the file through the webview.
+
+use crate::error::impl_serialize_error;
+use serde::Serialize;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Size of each of the two chunks that contribute to the hash.
+pub const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Smallest file the algorithm accepts: the head and tail chunks must not overlap.
+pub const MIN_FILE_SIZE: u64 = CHUNK_SIZE * 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovieHash {
+    /// 16 lowercase hex digits, as expected by `moviehash` in the XML-RPC API.
+    pub hash: String,
+    pub byte_size: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MovieHashError {
+    #[error("file not found: {}", .0.display())]
+    NotFound(PathBuf),
+    #[error("permission denied: {}", .0.display())]
+    PermissionDenied(PathBuf),
+    #[error("{} is {size} bytes, movie hash needs at least {MIN_FILE_SIZE}", .path.display())]
+    TooSmall { path: PathBuf, size: u64 },
+    #[error("file shrank while hashing {}", .0.display())]
+    Truncated(PathBuf),
+    #[error("could not read {}: {source}", .path.display())]
+    Unreadable { path: PathBuf, source: io::Error },
+    #[error("hashing task failed: {0}")]
+    Task(String),
+}
+
+impl MovieHashError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "notFound",
+            Self::PermissionDenied(_) => "permissionDenied",
+            Self::TooSmall { .. } => "tooSmall",
+            Self::Truncated(_) => "truncated",
+            Self::Unreadable { .. } => "unreadable",
+            Self::Task(_) => "task",
+        }
+    }
+
+    fn from_io(path: &Path, err: io::Error) -> Self {
+        let path = path.to_path_buf();
+        match err.kind() {
+            io::ErrorKind::NotFound => Self::NotFound(path),
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied(path),
+            io::ErrorKind::UnexpectedEof => Self::Truncated(path),
+            _ => Self::Unreadable { path, source: err },
+        }
+    }
+}
+
+impl_serialize_error!(MovieHashError);
+
+/// Computes the movie hash of the file at `path`.
+pub fn compute(path: &Path) -> Result<MovieHash, MovieHashError> {
+    let file = File::open(path).map_err(|e| MovieHashError::from_io(path, e))?;
+    let byte_size = file
+        .metadata()
+        .map_err(|e| MovieHashError::from_io(path, e))?
+        .len();
+    if byte_size < MIN_FILE_SIZE {
+        return Err(MovieHashError::TooSmall {
+            path: path.to_path_buf(),
+            size: byte_size,
+        });
+    }
+
+    let mut chunk = vec![0u8; CHUNK_SIZE as usize];
+    let mut hash = byte_size;
+    for offset in [0, byte_size - CHUNK_SIZE] {
+        read_exact_at(&file, &mut chunk, offset).map_err(|e| MovieHashError::from_io(path, e))?;
+        hash = chunk
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .fold(hash, u64::wrapping_add);
+    }
+
+    Ok(MovieHash {
+        hash: format!("{hash:016x}"),
+        byte_size,
+    })
+}
+
+/// Fills `buf` from `offset` without touching the shared file cursor.
+pub(crate) fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileExt;
+        let mut filled = 0;
+        while filled < buf.len() {
+            match file.seek_read(&mut buf[filled..], offset + filled as u64) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn compute_movie_hash(path: String) -> Result<MovieHash, MovieHashError> {
+    tauri::async_runtime::spawn_blocking(move || compute(Path::new(&path)))
+        .await
+        .map_err(|e| MovieHashError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_of(name: &str, data: &[u8]) -> Result<MovieHash, MovieHashError> {
+        let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let result = compute(&path);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn hashes_head_tail_and_size() {
+        // Expected values computed independently of this module.
+        let patterned: Vec<u8> = (0..200_003u32)
+            .map(|i| ((i * 31) ^ (i >> 9)) as u8)
+            .collect();
+        let cases = [
+            ("zeros", vec![0; MIN_FILE_SIZE as usize], "0000000000020000"),
+            // Every word is `u64::MAX`, so the sum wraps.
+            (
+                "ones",
+                vec![0xff; MIN_FILE_SIZE as usize],
+                "000000000001c000",
+            ),
+            // An odd size, so the tail chunk is not word-aligned.
+            ("patterned", patterned, "7f7c7bfc0006f143"),
+        ];
+        for (name, data, expected) in cases {
+            let hash = hash_of(&format!("hash-{name}"), &data).unwrap();
+            assert_eq!(hash.hash, expected, "{name}");
+            assert_eq!(hash.byte_size, data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn refuses_files_under_two_chunks() {
+        for size in [0, MIN_FILE_SIZE - 1] {
+            let error = hash_of("hash-small", &vec![0; size as usize]).unwrap_err();
+            assert!(
+                matches!(error, MovieHashError::TooSmall { size: s, .. } if s == size),
+                "{error:?}"
+            );
+            assert_eq!(error.kind(), "tooSmall");
+        }
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let path = std::env::temp_dir().join(format!("{}-hash-missing", std::process::id()));
+        assert_eq!(compute(&path).unwrap_err().kind(), "notFound");
+    }
+}
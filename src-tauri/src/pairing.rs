@@ -0,0 +1,592 @@
+//! Pairs subtitles with the videos they belong to.
+//!
+//! Every subtitle is scored against every video nearby (same folder, or a
+//! folder up to two levels above, as in `Movie/Subs/2_English.srt`):
+//!
+//! - names are reduced to normalized release tokens and compared by edit
+//!   distance, after stripping sidecar language and `forced`/`sdh` tags;
+//! - a subtitle whose own name says nothing (`2_English.srt`) borrows the
+//!   name of its nearest meaningful folder (`Subs/Movie.2019.1080p/`);
+//! - season/episode numbers (`S01E02`, `1x02`, `E02` inside `Season 1/`)
+//!   must agree, which settles season packs;
+//! - closer folders score higher.
+//!
+//! A video may take any number of subtitles. Each pair carries a confidence
+//! in `0.0..=1.0` and a reason string for the UI.
+
+use crate::languages::{self, Language};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+pub const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "sub", "ssa", "ass", "vtt", "smi", "mpl"];
+
+pub const VIDEO_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "m4v", "avi", "mov", "wmv", "mpg", "mpeg", "ts", "m2ts", "webm", "ogm", "divx",
+    "flv",
+];
+
+/// Pairs scoring below this are reported without a video.
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Floor for a video that is the only one at its distance from a subtitle.
+const LONE_VIDEO: f64 = 0.6;
+
+/// How far above a subtitle's folder videos are still considered.
+pub const MAX_LEVELS_UP: usize = 2;
+
+/// Folder names that say where subtitles are, not what they are for.
+const SUBTITLE_FOLDERS: &[&str] = &["subs", "sub", "subtitles", "subtitle", "srt"];
+
+/// Tags trailing a subtitle name that are not part of the release name.
+const SUBTITLE_TAGS: &[&str] = &["forced", "sdh", "hi", "cc", "full", "default"];
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+pub fn is_subtitle(path: &Path) -> bool {
+    has_extension(path, SUBTITLE_EXTENSIONS)
+}
+
+pub fn is_video(path: &Path) -> bool {
+    has_extension(path, VIDEO_EXTENSIONS)
+}
+
+/// `Subs/`, `Subtitles/` and the like.
+pub fn is_subtitle_folder(dir: &Path) -> bool {
+    dir.file_name().is_some_and(|name| {
+        SUBTITLE_FOLDERS.contains(&name.to_string_lossy().to_lowercase().as_str())
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Episode {
+    pub season: Option<u32>,
+    pub episode: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pair {
+    pub subtitle: PathBuf,
+    pub video: Option<PathBuf>,
+    pub confidence: f64,
+    pub reason: String,
+    /// Language named by the subtitle file name, if any.
+    pub language: Option<&'static Language>,
+    pub episode: Option<Episode>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pairing {
+    pub pairs: Vec<Pair>,
+    /// Videos no subtitle was paired with.
+    pub unpaired_videos: Vec<PathBuf>,
+}
+
+/// Maps release-name spellings of the same thing onto one token.
+fn canonical(token: &str) -> &str {
+    match token {
+        "h264" | "avc" | "x264" => "x264",
+        "h265" | "hevc" | "x265" => "x265",
+        "bluray" | "bdrip" | "brrip" | "bdremux" => "bluray",
+        "webrip" | "webdl" | "web" => "web",
+        "dvdrip" | "dvd" => "dvd",
+        "hdtv" | "pdtv" => "hdtv",
+        "ddp" | "eac3" | "dd+" => "eac3",
+        "dd" | "ac3" => "ac3",
+        other => other,
+    }
+}
+
+fn tokens(name: &str) -> Vec<String> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| canonical(t).to_string())
+        .collect()
+}
+
+/// `s01e02`, `s01e02e03`, `1x02`, or `e02` with the season from a folder.
+fn parse_episode(token: &str) -> Option<Episode> {
+    let digits = |s: &str| -> Option<u32> {
+        (!s.is_empty() && s.len() <= 3 && s.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| s.parse().ok())
+            .flatten()
+    };
+    if let Some(rest) = token.strip_prefix('s') {
+        let (season, rest) = rest.split_once('e')?;
+        let episode = rest.split('e').next()?;
+        return Some(Episode {
+            season: Some(digits(season)?),
+            episode: digits(episode)?,
+        });
+    }
+    if let Some(rest) = token.strip_prefix('e') {
+        return Some(Episode {
+            season: None,
+            episode: digits(rest)?,
+        });
+    }
+    let (season, episode) = token.split_once('x')?;
+    (season.len() <= 2).then_some(())?;
+    Some(Episode {
+        season: Some(digits(season)?),
+        episode: digits(episode)?,
+    })
+}
+
+fn find_episode(tokens: &[String]) -> Option<Episode> {
+    tokens.iter().find_map(|t| parse_episode(t))
+}
+
+/// Season number of a `Season 1` / `S01` / `Staffel 2` folder.
+fn folder_season(dir: &Path) -> Option<u32> {
+    let tokens = tokens(&dir.file_name()?.to_string_lossy());
+    match tokens.as_slice() {
+        [word, number]
+            if matches!(word.as_str(), "season" | "staffel" | "saison" | "temporada") =>
+        {
+            number.parse().ok()
+        }
+        [single] => single.strip_prefix('s')?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// What a file's name says once tags and numbering are stripped.
+#[derive(Debug)]
+struct Name {
+    tokens: Vec<String>,
+    /// Where `tokens` came from, for the reason string.
+    from_folder: Option<String>,
+    episode: Option<Episode>,
+}
+
+impl Name {
+    fn joined(&self) -> String {
+        self.tokens.join(".")
+    }
+}
+
+fn video_name(video: &Path) -> Name {
+    let tokens = tokens(&video.file_stem().unwrap_or_default().to_string_lossy());
+    let mut episode = find_episode(&tokens);
+    if let Some(ep) = episode.as_mut().filter(|ep| ep.season.is_none()) {
+        ep.season = video.parent().and_then(folder_season);
+    }
+    Name {
+        tokens,
+        from_folder: None,
+        episode,
+    }
+}
+
+/// Strips trailing language and tag tokens and a leading track number, and
+/// returns the sidecar language found on the way.
+fn subtitle_name(subtitle: &Path) -> (Name, Option<&'static Language>) {
+    let mut tokens = tokens(&subtitle.file_stem().unwrap_or_default().to_string_lossy());
+    let mut language = None;
+    while let Some(last) = tokens.last() {
+        // `pt-BR`, `es-419`: a language and region split into two tokens.
+        let regional = match tokens.as_slice() {
+            [.., _, lang, region] if language.is_none() => {
+                languages::lookup(&format!("{lang}-{region}"), true)
+            }
+            _ => None,
+        };
+        if SUBTITLE_TAGS.contains(&last.as_str()) {
+            tokens.pop();
+        } else if let Some(found) = regional {
+            language = Some(found);
+            tokens.truncate(tokens.len() - 2);
+        } else if let Some(found) =
+            languages::lookup(last, tokens.len() > 1).filter(|_| language.is_none())
+        {
+            language = Some(found);
+            tokens.pop();
+        } else {
+            break;
+        }
+    }
+    // `2_English.srt`: what is left is a track index.
+    if let [index] = tokens.as_slice() {
+        if index.len() <= 2 && index.bytes().all(|b| b.is_ascii_digit()) {
+            tokens.clear();
+        }
+    }
+    let mut episode = find_episode(&tokens);
+    let mut from_folder = None;
+    if tokens.is_empty() {
+        // Borrow the nearest folder name that means something.
+        for dir in subtitle.ancestors().skip(1).take(MAX_LEVELS_UP + 1) {
+            let Some(folder) = dir.file_name() else { break };
+            if is_subtitle_folder(dir) {
+                continue;
+            }
+            let folder = folder.to_string_lossy();
+            tokens = self::tokens(&folder);
+            episode = find_episode(&tokens);
+            from_folder = Some(folder.into_owned());
+            break;
+        }
+    }
+    if let Some(ep) = episode.as_mut().filter(|ep| ep.season.is_none()) {
+        ep.season = subtitle
+            .ancestors()
+            .skip(1)
+            .take(MAX_LEVELS_UP + 1)
+            .find_map(folder_season);
+    }
+    (
+        Name {
+            tokens,
+            from_folder,
+            episode,
+        },
+        language,
+    )
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// `1.0` for identical names, `0.0` for nothing in common.
+fn similarity(a: &str, b: &str) -> f64 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 0.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+/// Levels from the video's folder down to the subtitle's, if the video
+/// folder is the subtitle folder or one of its close ancestors.
+fn levels_up(subtitle: &Path, video: &Path) -> Option<usize> {
+    let video_dir = video.parent()?;
+    subtitle
+        .ancestors()
+        .skip(1)
+        .take(MAX_LEVELS_UP + 1)
+        .position(|dir| dir == video_dir)
+}
+
+struct Score {
+    confidence: f64,
+    reasons: Vec<String>,
+}
+
+fn score(subtitle: &Name, video: &Name, levels: usize) -> Option<Score> {
+    let mut reasons = Vec::new();
+    let episode_match = match (subtitle.episode, video.episode) {
+        (Some(s), Some(v)) => {
+            let seasons_agree = s.season.zip(v.season).is_none_or(|(a, b)| a == b);
+            if s.episode != v.episode || !seasons_agree {
+                return None;
+            }
+            reasons.push(match v.season.or(s.season) {
+                Some(season) => format!("episode S{season:02}E{:02} matches", v.episode),
+                None => format!("episode {} matches", v.episode),
+            });
+            true
+        }
+        _ => false,
+    };
+
+    let (sub, vid) = (subtitle.joined(), video.joined());
+    let name = if sub.is_empty() {
+        0.0
+    } else if sub == vid {
+        reasons.push(match &subtitle.from_folder {
+            Some(folder) => format!("folder {folder:?} matches the video name"),
+            None => "same name as the video".into(),
+        });
+        1.0
+    } else {
+        // Below half, names share little more than their length; counting
+        // that would let any video in the folder clear `MIN_CONFIDENCE`.
+        let similarity = similarity(&sub, &vid);
+        if similarity < 0.5 {
+            0.0
+        } else {
+            reasons.push(format!("name similarity {similarity:.2}"));
+            similarity
+        }
+    };
+
+    let proximity = match levels {
+        0 => 1.0,
+        1 => 0.8,
+        _ => 0.6,
+    };
+    reasons.push(match levels {
+        0 => "same folder".into(),
+        n => format!("subtitle {n} folder(s) below the video"),
+    });
+
+    let mut confidence = 0.65 * name + 0.35 * proximity;
+    if episode_match {
+        confidence = confidence.max(0.6) + 0.25;
+    }
+    Some(Score {
+        confidence: confidence.min(1.0),
+        reasons,
+    })
+}
+
+/// Pairs the subtitles in `paths` with the videos in `paths`; other files
+/// are ignored.
+pub fn pair(paths: &[PathBuf]) -> Pairing {
+    let videos: Vec<_> = paths
+        .iter()
+        .filter(|p| is_video(p))
+        .map(|p| (p, video_name(p)))
+        .collect();
+    let mut used = vec![false; videos.len()];
+    let mut pairs: Vec<Pair> = paths
+        .iter()
+        .filter(|p| is_subtitle(p))
+        .map(|subtitle| {
+            let (name, language) = subtitle_name(subtitle);
+            let nearby: Vec<_> = videos
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (video, video_name))| {
+                    Some((i, *video, video_name, levels_up(subtitle, video)?))
+                })
+                .collect();
+            let scored: Vec<_> = nearby
+                .iter()
+                .filter_map(|(i, video, video_name, levels)| {
+                    Some((*i, *video, *levels, score(&name, video_name, *levels)?))
+                })
+                .collect();
+            // The closest video is a decent bet even when the names say
+            // nothing, as long as no other video is as close.
+            let closest = nearby.iter().map(|n| n.3).min();
+            let lone = match nearby.iter().filter(|n| Some(n.3) == closest).count() {
+                1 => closest,
+                _ => None,
+            };
+            let best = scored
+                .into_iter()
+                .map(|(i, video, levels, mut score)| {
+                    if Some(levels) == lone && score.confidence < LONE_VIDEO {
+                        score.confidence = LONE_VIDEO;
+                        score.reasons.push("only video this close".into());
+                    }
+                    (i, video, score)
+                })
+                .max_by(|a, b| a.2.confidence.total_cmp(&b.2.confidence));
+            let mut pair = Pair {
+                subtitle: subtitle.clone(),
+                video: None,
+                confidence: 0.0,
+                reason: "no video nearby".into(),
+                language,
+                episode: name.episode,
+            };
+            if let Some((i, video, score)) = best {
+                if score.confidence >= MIN_CONFIDENCE {
+                    used[i] = true;
+                    pair.video = Some(video.clone());
+                    pair.confidence = (score.confidence * 100.0).round() / 100.0;
+                    pair.reason = score.reasons.join("; ");
+                } else {
+                    pair.reason = "no video name close enough".into();
+                }
+            } else if !nearby.is_empty() {
+                pair.reason = "episode numbers differ from every video nearby".into();
+            }
+            if let Some(language) = language {
+                pair.reason
+                    .push_str(&format!("; language {} from file name", language.name));
+            }
+            pair
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.subtitle.cmp(&b.subtitle));
+    Pairing {
+        pairs,
+        unpaired_videos: videos
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|((video, _), _)| (*video).clone())
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub fn pair_files(paths: Vec<PathBuf>) -> Pairing {
+    pair(&paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates `files` under a fresh temporary folder and pairs them.
+    fn pair_tree(name: &str, files: &[&str]) -> (PathBuf, Pairing) {
+        let root = std::env::temp_dir().join(format!("{}-pairing-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let paths: Vec<_> = files.iter().map(|file| root.join(file)).collect();
+        for path in &paths {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        (root, pair(&paths))
+    }
+
+    /// A subtitle, the file name of its video and its language.
+    type Expected = (&'static str, Option<&'static str>, Option<&'static str>);
+
+    #[test]
+    fn pairs_known_layouts() {
+        let cases: &[(&str, &[&str], &[Expected])] = &[
+            (
+                "subs-folder",
+                &[
+                    "Movie.2019.1080p/Movie.2019.1080p.mkv",
+                    "Movie.2019.1080p/Subs/2_English.srt",
+                ],
+                &[(
+                    "Movie.2019.1080p/Subs/2_English.srt",
+                    Some("Movie.2019.1080p.mkv"),
+                    Some("eng"),
+                )],
+            ),
+            (
+                "nested-release-folder",
+                &[
+                    "Movie.2019.1080p/Movie.2019.1080p.mkv",
+                    "Movie.2019.1080p/Subs/Movie.2019.1080p/3_Spa.srt",
+                ],
+                &[(
+                    "Movie.2019.1080p/Subs/Movie.2019.1080p/3_Spa.srt",
+                    Some("Movie.2019.1080p.mkv"),
+                    Some("spa"),
+                )],
+            ),
+            (
+                "many-subtitles",
+                &[
+                    "Film.mkv",
+                    "Film.en.srt",
+                    "Film.fr.forced.srt",
+                    "Film.pt-BR.srt",
+                ],
+                &[
+                    ("Film.en.srt", Some("Film.mkv"), Some("eng")),
+                    ("Film.fr.forced.srt", Some("Film.mkv"), Some("fre")),
+                    ("Film.pt-BR.srt", Some("Film.mkv"), Some("pob")),
+                ],
+            ),
+            (
+                "season-pack",
+                &[
+                    "Season 1/Show.S01E01.720p.mkv",
+                    "Season 1/Show.S01E02.720p.mkv",
+                    "Season 1/Show.1x02.srt",
+                    "Season 1/Subs/E01.English.srt",
+                ],
+                &[
+                    ("Season 1/Show.1x02.srt", Some("Show.S01E02.720p.mkv"), None),
+                    (
+                        "Season 1/Subs/E01.English.srt",
+                        Some("Show.S01E01.720p.mkv"),
+                        Some("eng"),
+                    ),
+                ],
+            ),
+            (
+                "ambiguous",
+                &[
+                    "Collection/A.Movie.mkv",
+                    "Collection/B.Other.Film.mkv",
+                    "Collection/2_English.srt",
+                ],
+                &[("Collection/2_English.srt", None, Some("eng"))],
+            ),
+            (
+                "wrong-episode",
+                &["Show.S01E01.mkv", "Show.S01E03.srt"],
+                &[("Show.S01E03.srt", None, None)],
+            ),
+            (
+                "too-far",
+                &["a/Video.mkv", "a/b/c/d/Video.srt", "e/Video.srt"],
+                &[
+                    ("a/b/c/d/Video.srt", None, None),
+                    ("e/Video.srt", None, None),
+                ],
+            ),
+        ];
+        for (name, files, expected) in cases {
+            let (root, pairing) = pair_tree(name, files);
+            let found: Vec<_> = pairing
+                .pairs
+                .iter()
+                .map(|pair| {
+                    (
+                        pair.subtitle.clone(),
+                        pair.video
+                            .as_ref()
+                            .and_then(|v| v.file_name())
+                            .map(PathBuf::from),
+                        pair.language.map(|l| l.code),
+                    )
+                })
+                .collect();
+            let expected: Vec<_> = expected
+                .iter()
+                .map(|(subtitle, video, language)| {
+                    (root.join(subtitle), video.map(PathBuf::from), *language)
+                })
+                .collect();
+            assert_eq!(found, expected, "{name}");
+            for pair in &pairing.pairs {
+                assert!(
+                    pair.video.is_none() || pair.confidence >= MIN_CONFIDENCE,
+                    "{name}"
+                );
+                assert!(!pair.reason.is_empty(), "{name}");
+            }
+            std::fs::remove_dir_all(root).unwrap();
+        }
+    }
+
+    #[test]
+    fn reports_why_nothing_paired() {
+        let (root, pairing) = pair_tree(
+            "reasons",
+            &["Show.S01E01.mkv", "Show.S01E03.srt", "x/y/z/Other.srt"],
+        );
+        let reasons: Vec<_> = pairing.pairs.iter().map(|p| p.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            [
+                "episode numbers differ from every video nearby",
+                "no video nearby"
+            ]
+        );
+        assert_eq!(pairing.unpaired_videos, [root.join("Show.S01E01.mkv")]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,726 @@
+//! Release-name parsing, done locally instead of through guessit:
+//! `The.Show.S01E01E02.Pilot.1080p.WEB-DL.DDP5.1.H.264-GROUP.mkv` → title,
+//! season, episodes, episode title, screen size, source, codecs, group, ...
+//!
+//! The result has the shape of `/utilities/guessit` ([`GuessitResult`]), so
+//! the frontend can use either without caring which produced it.
+//!
+//! Names are split into tokens at dots, spaces, dashes and brackets. The
+//! title runs up to the first token that is clearly not part of it: a year,
+//! an episode number or a technical tag such as `1080p` or `BluRay`. Tags that
+//! could just as well be words (`NF`, `ITA`, `5.1`) are only recognized after
+//! that point.
+
+use crate::api::rest::GuessitResult;
+use crate::languages::{self, Language};
+use crate::pairing::{SUBTITLE_EXTENSIONS, VIDEO_EXTENSIONS};
+use serde_json::Value;
+
+struct Token {
+    /// As written, for the title and group.
+    text: String,
+    /// Lowercase, alphanumerics only, for matching.
+    lower: String,
+    /// Preceded by a dash: `x264-GROUP`, `E01-E03`.
+    after_dash: bool,
+    /// Joined to the previous token by a bare hyphen: `Spider-Man`.
+    hyphenated: bool,
+    /// Preceded by an opening bracket: `[Group]`, `(2019)`.
+    bracketed: bool,
+}
+
+fn tokenize(name: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let (mut after_dash, mut bracketed) = (false, false);
+    let mut separator = String::new();
+    let mut text = String::new();
+    for c in name.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || c == '\'' {
+            text.push(c);
+            continue;
+        }
+        if !text.is_empty() {
+            let lower: String = text
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect();
+            if !lower.is_empty() {
+                tokens.push(Token {
+                    text: std::mem::take(&mut text),
+                    lower,
+                    after_dash,
+                    hyphenated: separator == "-",
+                    bracketed,
+                });
+            }
+            text.clear();
+            separator.clear();
+            (after_dash, bracketed) = (false, false);
+        }
+        separator.push(c);
+        after_dash |= c == '-';
+        bracketed |= matches!(c, '[' | '(' | '{');
+    }
+    tokens
+}
+
+/// A guessit field and its value.
+type Tag = (&'static str, &'static str);
+
+/// Token sequences that end a title wherever they appear.
+const STRONG: &[(&[&str], &[Tag])] = &[
+    (&["2160p"], &[("screen_size", "2160p")]),
+    (&["4k"], &[("screen_size", "2160p")]),
+    (&["1080p"], &[("screen_size", "1080p")]),
+    (&["1080i"], &[("screen_size", "1080i")]),
+    (&["720p"], &[("screen_size", "720p")]),
+    (&["576p"], &[("screen_size", "576p")]),
+    (&["480p"], &[("screen_size", "480p")]),
+    (&["360p"], &[("screen_size", "360p")]),
+    (&["uhd", "bluray"], &[("source", "Ultra HD Blu-ray")]),
+    (&["bluray"], &[("source", "Blu-ray")]),
+    (&["blu", "ray"], &[("source", "Blu-ray")]),
+    (&["bdrip"], &[("source", "Blu-ray"), ("other", "Rip")]),
+    (&["brrip"], &[("source", "Blu-ray"), ("other", "Rip")]),
+    (&["bdremux"], &[("source", "Blu-ray"), ("other", "Remux")]),
+    (&["web", "dl"], &[("source", "Web")]),
+    (&["webdl"], &[("source", "Web")]),
+    (&["webrip"], &[("source", "Web"), ("other", "Rip")]),
+    (&["web"], &[("source", "Web")]),
+    (&["hdtv"], &[("source", "HDTV")]),
+    (&["pdtv"], &[("source", "TV")]),
+    (&["dvdrip"], &[("source", "DVD"), ("other", "Rip")]),
+    (&["dvd"], &[("source", "DVD")]),
+    (&["dvd5"], &[("source", "DVD")]),
+    (&["dvd9"], &[("source", "DVD")]),
+    (&["hddvd"], &[("source", "HD-DVD")]),
+    (&["hdrip"], &[("other", "HD"), ("other", "Rip")]),
+    (&["camrip"], &[("source", "Camera"), ("other", "Rip")]),
+    (&["hdcam"], &[("source", "HD Camera")]),
+    (&["telesync"], &[("source", "Telesync")]),
+    (&["hdts"], &[("source", "HD Telesync")]),
+    (&["telecine"], &[("source", "Telecine")]),
+    (&["satrip"], &[("source", "Satellite"), ("other", "Rip")]),
+    (&["remux"], &[("other", "Remux")]),
+    (&["x264"], &[("video_codec", "H.264")]),
+    (&["h264"], &[("video_codec", "H.264")]),
+    (&["h", "264"], &[("video_codec", "H.264")]),
+    (&["avc"], &[("video_codec", "H.264")]),
+    (&["x265"], &[("video_codec", "H.265")]),
+    (&["h265"], &[("video_codec", "H.265")]),
+    (&["h", "265"], &[("video_codec", "H.265")]),
+    (&["hevc"], &[("video_codec", "H.265")]),
+    (&["xvid"], &[("video_codec", "Xvid")]),
+    (&["divx"], &[("video_codec", "DivX")]),
+    (&["av1"], &[("video_codec", "AV1")]),
+    (&["vp9"], &[("video_codec", "VP9")]),
+    (&["vc1"], &[("video_codec", "VC-1")]),
+    (&["mpeg2"], &[("video_codec", "MPEG-2")]),
+    (&["10bit"], &[("color_depth", "10-bit")]),
+    (&["hdr10"], &[("other", "HDR10")]),
+    (&["hdr"], &[("other", "HDR10")]),
+    (
+        &["dts", "hd", "ma"],
+        &[("audio_codec", "DTS-HD"), ("audio_profile", "Master Audio")],
+    ),
+    (&["dts", "hd"], &[("audio_codec", "DTS-HD")]),
+    (&["dtshd"], &[("audio_codec", "DTS-HD")]),
+    (&["dts", "x"], &[("audio_codec", "DTS:X")]),
+    (&["truehd"], &[("audio_codec", "Dolby TrueHD")]),
+    (&["atmos"], &[("audio_codec", "Dolby Atmos")]),
+    (&["directors", "cut"], &[("edition", "Director's Cut")]),
+    (&["extended", "cut"], &[("edition", "Extended")]),
+    (&["extended", "edition"], &[("edition", "Extended")]),
+    (&["extended"], &[("edition", "Extended")]),
+    (&["unrated"], &[("edition", "Unrated")]),
+    (&["uncut"], &[("edition", "Uncut")]),
+    (&["theatrical", "cut"], &[("edition", "Theatrical")]),
+    (&["remastered"], &[("edition", "Remastered")]),
+    (&["criterion"], &[("edition", "Criterion")]),
+    (&["imax"], &[("edition", "IMAX")]),
+    (&["special", "edition"], &[("edition", "Special")]),
+    (&["collectors", "edition"], &[("edition", "Collector")]),
+    (&["alternative", "cut"], &[("edition", "Alternative Cut")]),
+];
+
+/// Token sequences that are only tags once the title has ended.
+const WEAK: &[(&[&str], &[Tag])] = &[
+    (&["cam"], &[("source", "Camera")]),
+    (&["ts"], &[("source", "Telesync")]),
+    (&["tc"], &[("source", "Telecine")]),
+    (&["vhs"], &[("source", "VHS")]),
+    (&["proper"], &[("other", "Proper")]),
+    (&["repack"], &[("other", "Proper")]),
+    (&["dv"], &[("other", "Dolby Vision")]),
+    (&["dovi"], &[("other", "Dolby Vision")]),
+    (&["limited"], &[("other", "Limited")]),
+    (&["complete"], &[("other", "Complete")]),
+    (&["nf"], &[("streaming_service", "Netflix")]),
+    (&["amzn"], &[("streaming_service", "Amazon Prime")]),
+    (&["dsnp"], &[("streaming_service", "Disney")]),
+    (&["hmax"], &[("streaming_service", "HBO Max")]),
+    (&["atvp"], &[("streaming_service", "Apple TV+")]),
+    (&["hulu"], &[("streaming_service", "Hulu")]),
+    (&["pcok"], &[("streaming_service", "Peacock")]),
+    (&["dc"], &[("edition", "Director's Cut")]),
+    (&["theatrical"], &[("edition", "Theatrical")]),
+    (&["aac"], &[("audio_codec", "AAC")]),
+    (&["ac3"], &[("audio_codec", "Dolby Digital")]),
+    (&["dd"], &[("audio_codec", "Dolby Digital")]),
+    (&["ddp"], &[("audio_codec", "Dolby Digital Plus")]),
+    (&["eac3"], &[("audio_codec", "Dolby Digital Plus")]),
+    (&["dts"], &[("audio_codec", "DTS")]),
+    (&["flac"], &[("audio_codec", "FLAC")]),
+    (&["mp3"], &[("audio_codec", "MP3")]),
+    (&["opus"], &[("audio_codec", "Opus")]),
+    (&["lpcm"], &[("audio_codec", "PCM")]),
+    (&["7", "1"], &[("audio_channels", "7.1")]),
+    (&["5", "1"], &[("audio_channels", "5.1")]),
+    (&["2", "0"], &[("audio_channels", "2.0")]),
+    (&["6ch"], &[("audio_channels", "5.1")]),
+    (&["2ch"], &[("audio_channels", "2.0")]),
+];
+
+/// Longest sequence in `table` starting at `tokens[i]`.
+fn match_tag(
+    table: &'static [(&[&str], &[Tag])],
+    tokens: &[Token],
+    i: usize,
+) -> Option<(usize, &'static [Tag])> {
+    table
+        .iter()
+        .filter(|(seq, _)| {
+            tokens.len() >= i + seq.len()
+                && seq.iter().zip(&tokens[i..]).all(|(s, t)| *s == t.lower)
+        })
+        .max_by_key(|(seq, _)| seq.len())
+        .map(|(seq, tags)| (seq.len(), *tags))
+}
+
+/// `DDP5.1`, `AAC2.0`: a codec and channel count run together.
+fn match_codec_channels(tokens: &[Token], i: usize) -> Option<(usize, [Tag; 2])> {
+    let lower = &tokens[i].lower;
+    let (codec, digit) = lower.split_at(lower.char_indices().next_back()?.0);
+    let next = &tokens.get(i + 1)?.lower;
+    if !digit.bytes().all(|b| b.is_ascii_digit())
+        || next.len() != 1
+        || !next.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let channels = match (digit, next.as_str()) {
+        ("7", "1") => "7.1",
+        ("5", "1") => "5.1",
+        ("2", "0") => "2.0",
+        ("1", "0") => "1.0",
+        _ => return None,
+    };
+    let codec = WEAK.iter().find_map(|(seq, tags)| match (seq, tags) {
+        ([s], [("audio_codec", name)]) if *s == codec => Some(*name),
+        _ => None,
+    })?;
+    Some((2, [("audio_codec", codec), ("audio_channels", channels)]))
+}
+
+fn digits(s: &str, max_len: usize) -> Option<u32> {
+    (!s.is_empty() && s.len() <= max_len && s.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| s.parse().ok())
+        .flatten()
+}
+
+/// `s01`, `s01e02`, `s01e02e03`, `1x02`; `e02` only with a season known.
+fn parse_episode_token(token: &str, season_known: bool) -> Option<(Option<u32>, Vec<u32>)> {
+    if let Some(rest) = token.strip_prefix('s') {
+        let mut parts = rest.split('e');
+        let season = digits(parts.next()?, 2)?;
+        let episodes = parts.map(|e| digits(e, 3)).collect::<Option<Vec<_>>>()?;
+        return Some((Some(season), episodes));
+    }
+    if let Some(rest) = token.strip_prefix('e').filter(|_| season_known) {
+        return Some((None, vec![digits(rest, 3)?]));
+    }
+    let (season, episode) = token.split_once('x')?;
+    Some((Some(digits(season, 2)?), vec![digits(episode, 3)?]))
+}
+
+#[derive(Debug, Default)]
+struct EpisodeMatch {
+    season: Option<u32>,
+    episodes: Vec<u32>,
+    /// Tokens covered, from the first.
+    len: usize,
+}
+
+/// Season/episode markers at `tokens[i]`, including continuations such as
+/// `S01E01-E03`, `S01E01-03` and `S01 E02`, and spelled-out `Season 2`.
+fn match_episode(tokens: &[Token], i: usize) -> Option<EpisodeMatch> {
+    let lower = |j: usize| tokens.get(j).map(|t| t.lower.as_str());
+    let mut found = EpisodeMatch::default();
+    match lower(i)? {
+        "season" | "saison" | "staffel" => {
+            found.season = Some(digits(lower(i + 1)?, 2)?);
+            found.len = 2;
+        }
+        "episode" | "ep" => {
+            found.episodes.push(digits(lower(i + 1)?, 3)?);
+            found.len = 2;
+            return Some(found);
+        }
+        token => {
+            let (season, episodes) = parse_episode_token(token, false)?;
+            found.season = season;
+            found.episodes = episodes;
+            found.len = 1;
+        }
+    }
+    // Continuations.
+    while let Some(token) = tokens.get(i + found.len) {
+        let next = if let Some(rest) = token.lower.strip_prefix('e') {
+            digits(rest, 3)
+        } else if token.after_dash && !found.episodes.is_empty() {
+            digits(&token.lower, 3)
+        } else if matches!(token.lower.as_str(), "episode" | "ep") {
+            let n = lower(i + found.len + 1).and_then(|t| digits(t, 3));
+            if n.is_some() {
+                found.len += 1;
+            }
+            n
+        } else {
+            None
+        };
+        let Some(next) = next else { break };
+        match found.episodes.last() {
+            // `E01-E03` is a range; `E01E03` would have been one token.
+            Some(&last) if token.after_dash && next > last => {
+                found.episodes.extend(last + 1..=next)
+            }
+            _ => found.episodes.push(next),
+        }
+        found.len += 1;
+    }
+    Some(found)
+}
+
+/// A bare episode number set off by a dash, as anime releases number them.
+fn absolute_episode(token: &Token) -> Option<u32> {
+    token.after_dash.then(|| digits(&token.lower, 3)).flatten()
+}
+
+fn year(token: &Token) -> Option<u32> {
+    digits(&token.lower, 4).filter(|y| (1900..=2099).contains(y) && token.lower.len() == 4)
+}
+
+/// `pt-BR`, not OpenSubtitles' `pb`: guessit reports language tags.
+fn language_tag(language: &Language) -> &'static str {
+    match language.code {
+        "pob" => "pt-BR",
+        "spl" => "es-MX",
+        "zht" => "zh-TW",
+        _ => language.iso639_1,
+    }
+}
+
+/// Release-language markers that are not plain language names.
+fn release_language(lower: &str) -> Option<(bool, &'static Language)> {
+    let (subtitles, name) = match lower {
+        "vostfr" | "subfrench" => (true, "fre"),
+        "truefrench" | "vff" | "vfq" | "vf" => (false, "fre"),
+        "engsub" | "engsubs" => (true, "eng"),
+        "nlsub" | "nlsubs" => (true, "dut"),
+        _ => return None,
+    };
+    Some((subtitles, languages::lookup(name, false)?))
+}
+
+/// Serializes one value as a scalar and several as a list, like guessit.
+fn one_or_many<T: Into<Value> + PartialEq>(mut values: Vec<T>) -> Option<Value> {
+    let mut unique = Vec::with_capacity(values.len());
+    for value in values.drain(..) {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    match unique.len() {
+        0 => None,
+        1 => unique.pop().map(Into::into),
+        _ => Some(Value::Array(unique.into_iter().map(Into::into).collect())),
+    }
+}
+
+/// Parses a file name (a path is fine; only the last component is read).
+pub fn parse(filename: &str) -> GuessitResult {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let mut result = GuessitResult::default();
+
+    let (mut stem, mut is_subtitle) = (name, false);
+    if let Some((base, ext)) = name.rsplit_once('.') {
+        let ext = ext.to_ascii_lowercase();
+        let subtitle = SUBTITLE_EXTENSIONS.contains(&ext.as_str());
+        if subtitle || VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+            stem = base;
+            is_subtitle = subtitle;
+            result.extra.insert("container".into(), ext.into());
+        }
+    }
+    let mut tokens = tokenize(stem);
+
+    // `Movie.2019.en.srt`, `Movie.pt-BR.forced.srt`: the subtitle's own
+    // language and flags come last.
+    let mut subtitle_languages = Vec::new();
+    let mut other = Vec::new();
+    if is_subtitle {
+        while tokens.len() > 1 {
+            let n = tokens.len();
+            let regional = (n > 2)
+                .then(|| {
+                    let (lang, region) = (&tokens[n - 2].lower, &tokens[n - 1].lower);
+                    languages::lookup(&format!("{lang}-{region}"), true)
+                })
+                .flatten();
+            if let Some(language) = regional {
+                subtitle_languages.push(language_tag(language));
+                tokens.truncate(n - 2);
+            } else if let Some(language) = languages::lookup(&tokens[n - 1].lower, true) {
+                subtitle_languages.push(language_tag(language));
+                tokens.pop();
+            } else if matches!(tokens[n - 1].lower.as_str(), "forced" | "sdh" | "hi" | "cc") {
+                other.push(match tokens[n - 1].lower.as_str() {
+                    "forced" => "Forced",
+                    _ => "Hearing Impaired",
+                });
+                tokens.pop();
+            } else {
+                break;
+            }
+        }
+        subtitle_languages.reverse();
+    }
+
+    // `[Group] Show - 01 [1080p].mkv`
+    let mut start = 0;
+    let mut release_group = None;
+    if tokens.len() > 1 && tokens[0].bracketed && year(&tokens[0]).is_none() {
+        release_group = Some(tokens[0].text.clone());
+        start = 1;
+    }
+
+    let mut used = vec![false; tokens.len()];
+    let mut tags: Vec<Tag> = Vec::new();
+    let (mut season, mut episodes) = (None, Vec::new());
+    let mut episode_end = None;
+    let mut years = Vec::new();
+    let mut title_end = tokens.len();
+
+    let mut i = start;
+    while i < tokens.len() {
+        if let Some(found) = match_episode(&tokens, i) {
+            season = season.or(found.season);
+            episodes.extend(found.episodes);
+            used[i..i + found.len].fill(true);
+            title_end = title_end.min(i);
+            episode_end = Some(i + found.len);
+            i += found.len;
+        } else if let Some(episode) = absolute_episode(&tokens[i]).filter(|_| i > start) {
+            // `Frieren - 05`
+            episodes.push(episode);
+            used[i] = true;
+            title_end = title_end.min(i);
+            episode_end = Some(i + 1);
+            i += 1;
+        } else if let Some(y) = year(&tokens[i]).filter(|_| i > start) {
+            years.push((i, y));
+            used[i] = true;
+            title_end = title_end.min(i);
+            i += 1;
+        } else if let Some((len, found)) = match_tag(STRONG, &tokens, i).filter(|_| i > start) {
+            tags.extend_from_slice(found);
+            used[i..i + len].fill(true);
+            title_end = title_end.min(i);
+            i += len;
+        } else {
+            i += 1;
+        }
+    }
+
+    // `Blade.Runner.2049.2017`: the first of two years belongs to the title.
+    if let [(first, _), (second, _), ..] = years[..] {
+        if first == title_end && second == first + 1 {
+            used[first] = false;
+            title_end = second;
+            years.remove(0);
+        }
+    }
+    result.year = years.first().map(|&(_, y)| y);
+
+    // `Amelie.FRENCH.1080p`: shouted language names end the title too.
+    while title_end > start + 1 {
+        let token = &tokens[title_end - 1];
+        let shouted = token.text.chars().all(|c| !c.is_lowercase()) && token.lower.len() > 2;
+        if shouted && languages::lookup(&token.lower, false).is_some() {
+            title_end -= 1;
+        } else {
+            break;
+        }
+    }
+
+    let mut spoken = Vec::new();
+    let mut i = title_end;
+    while i < tokens.len() {
+        if used[i] {
+            i += 1;
+            continue;
+        }
+        let token = &tokens[i];
+        if let Some((len, found)) = match_codec_channels(&tokens, i) {
+            tags.extend_from_slice(&found);
+            used[i..i + len].fill(true);
+            i += len;
+            continue;
+        }
+        if let Some((len, found)) = match_tag(WEAK, &tokens, i) {
+            tags.extend_from_slice(found);
+            used[i..i + len].fill(true);
+            i += len;
+            continue;
+        }
+        if token.lower == "multi" {
+            spoken.push("mul");
+            used[i] = true;
+        } else if let Some((subtitles, language)) = release_language(&token.lower) {
+            if subtitles {
+                subtitle_languages.push(language_tag(language));
+            } else {
+                spoken.push(language_tag(language));
+            }
+            used[i] = true;
+        } else if let Some(language) = languages::lookup(&token.lower, false).filter(|_| {
+            // Short codes only when written as tags (`ITA`), not words.
+            token.lower.len() > 3 || token.text.chars().all(|c| !c.is_lowercase())
+        }) {
+            spoken.push(language_tag(language));
+            used[i] = true;
+        }
+        i += 1;
+    }
+
+    // `...x264-GROUP`
+    if release_group.is_none() && title_end < tokens.len() {
+        if let Some(last) = tokens
+            .len()
+            .checked_sub(1)
+            .filter(|&i| !used[i] && i >= title_end)
+        {
+            if tokens[last].after_dash {
+                release_group = Some(tokens[last].text.clone());
+                used[last] = true;
+            }
+        }
+    }
+
+    let mut title = String::new();
+    for token in &tokens[start..title_end] {
+        if !title.is_empty() {
+            title.push(if token.hyphenated { '-' } else { ' ' });
+        }
+        title.push_str(&token.text);
+    }
+    if !title.is_empty() {
+        result.title = Some(title);
+    }
+
+    if let Some(end) = episode_end {
+        let episode_title: Vec<&str> = tokens[end..]
+            .iter()
+            .zip(&used[end..])
+            .take_while(|(_, used)| !**used)
+            .map(|(t, _)| t.text.as_str())
+            .collect();
+        if !episode_title.is_empty() {
+            result.episode_title = Some(episode_title.join(" "));
+        }
+    }
+    let is_episode = season.is_some() || !episodes.is_empty();
+    result.kind = Some(if is_episode { "episode" } else { "movie" }.into());
+    result.season = season.map(Value::from);
+    result.episode = one_or_many(episodes);
+
+    let values = |field: &str| -> Vec<&'static str> {
+        tags.iter()
+            .filter(|(f, _)| *f == field)
+            .map(|&(_, v)| v)
+            .collect()
+    };
+    let first = |field: &str| values(field).first().map(|v| v.to_string());
+    result.screen_size = first("screen_size");
+    result.source = one_or_many(values("source"));
+    result.streaming_service = first("streaming_service");
+    result.video_codec = one_or_many(values("video_codec"));
+    result.audio_codec = one_or_many(values("audio_codec"));
+    result.audio_profile = one_or_many(values("audio_profile"));
+    result.audio_channels = first("audio_channels");
+    result.edition = one_or_many(values("edition"));
+    other.extend(values("other"));
+    result.other = one_or_many(other);
+    if let Some(depth) = first("color_depth") {
+        result.extra.insert("color_depth".into(), depth.into());
+    }
+    result.language = one_or_many(spoken);
+    result.subtitle_language = one_or_many(subtitle_languages);
+    result.release_group = release_group;
+    result
+}
+
+#[tauri::command]
+pub fn parse_release_name(filename: String) -> GuessitResult {
+    parse(&filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_ascii_release_group() {
+        let result = parse("Movie.2019.1080p.BluRay.x264-Grüpé.mkv");
+        assert_eq!(result.title.as_deref(), Some("Movie"));
+        assert_eq!(result.year, Some(2019));
+        assert_eq!(result.release_group.as_deref(), Some("Grüpé"));
+    }
+
+    #[test]
+    fn non_ascii_episode_title() {
+        let result = parse("Lupin.S01E01.Chapitre.Un.Café.1080p.NF.WEB-DL.DDP5.1.x264.mkv");
+        assert_eq!(result.title.as_deref(), Some("Lupin"));
+        assert_eq!(result.season, Some(1.into()));
+        assert_eq!(result.episode, Some(1.into()));
+        assert_eq!(result.audio_channels.as_deref(), Some("5.1"));
+    }
+
+    #[test]
+    fn cyrillic_title() {
+        let result = parse("Кухня.S01E01.Серия.1.WEB-DLRip.avi");
+        assert_eq!(result.title.as_deref(), Some("Кухня"));
+        assert_eq!(result.season, Some(1.into()));
+        assert_eq!(result.episode, Some(1.into()));
+    }
+
+    #[test]
+    fn codec_and_channels_run_together() {
+        let result = parse("Movie.2020.1080p.WEB-DL.DDP5.1.H.264-GRP.mkv");
+        assert_eq!(result.audio_codec, Some("Dolby Digital Plus".into()));
+        assert_eq!(result.audio_channels.as_deref(), Some("5.1"));
+    }
+
+    #[test]
+    fn multi_episode_releases() {
+        let result = parse("The.Show.S01E01E02.Pilot.1080p.WEB-DL.DDP5.1.H.264-GROUP.mkv");
+        assert_eq!(result.title.as_deref(), Some("The Show"));
+        assert_eq!(result.kind.as_deref(), Some("episode"));
+        assert_eq!(result.season, Some(1.into()));
+        assert_eq!(result.episode, Some(serde_json::json!([1, 2])));
+        assert_eq!(result.episode_title.as_deref(), Some("Pilot"));
+        assert_eq!(result.screen_size.as_deref(), Some("1080p"));
+        assert_eq!(result.source, Some("Web".into()));
+        assert_eq!(result.video_codec, Some("H.264".into()));
+        assert_eq!(result.release_group.as_deref(), Some("GROUP"));
+
+        let cases = [
+            (
+                "Show.S02E03-E05.720p.HDTV.x264-GRP.mkv",
+                serde_json::json!([3, 4, 5]),
+            ),
+            (
+                "Show.S02E03-05.720p.HDTV.x264-GRP.mkv",
+                serde_json::json!([3, 4, 5]),
+            ),
+            (
+                "Show.S02E03.E04.720p.HDTV.x264-GRP.mkv",
+                serde_json::json!([3, 4]),
+            ),
+            ("Show S02 E03 720p HDTV x264-GRP.mkv", serde_json::json!(3)),
+        ];
+        for (name, episode) in cases {
+            let result = parse(name);
+            assert_eq!(result.title.as_deref(), Some("Show"), "{name}");
+            assert_eq!(result.season, Some(2.into()), "{name}");
+            assert_eq!(result.episode, Some(episode), "{name}");
+        }
+    }
+
+    #[test]
+    fn season_by_episode_numbering() {
+        let result = parse("show.name.1x02.the.title.hdtv.xvid-lol.avi");
+        assert_eq!(result.title.as_deref(), Some("show name"));
+        assert_eq!(result.season, Some(1.into()));
+        assert_eq!(result.episode, Some(2.into()));
+        assert_eq!(result.episode_title.as_deref(), Some("the title"));
+        assert_eq!(result.source, Some("HDTV".into()));
+        assert_eq!(result.release_group.as_deref(), Some("lol"));
+
+        let result = parse("Show Name 12x103.en.forced.srt");
+        assert_eq!(result.title.as_deref(), Some("Show Name"));
+        assert_eq!(result.season, Some(12.into()));
+        assert_eq!(result.episode, Some(103.into()));
+        assert_eq!(result.subtitle_language, Some("en".into()));
+        assert_eq!(result.other, Some("Forced".into()));
+    }
+
+    #[test]
+    fn editions() {
+        let cases = [
+            (
+                "Movie.2010.Extended.1080p.BluRay.x264-GRP.mkv",
+                serde_json::json!("Extended"),
+            ),
+            (
+                "Movie.2010.EXTENDED.EDITION.720p.mkv",
+                serde_json::json!("Extended"),
+            ),
+            (
+                "Movie.2010.Directors.Cut.1080p.mkv",
+                serde_json::json!("Director's Cut"),
+            ),
+            (
+                "Movie 2010 Director's Cut 1080p.mkv",
+                serde_json::json!("Director's Cut"),
+            ),
+            (
+                "Movie.2010.UNRATED.DVDRip.XviD-GRP.avi",
+                serde_json::json!("Unrated"),
+            ),
+            (
+                "Movie.Extended.Unrated.1080p.mkv",
+                serde_json::json!(["Extended", "Unrated"]),
+            ),
+        ];
+        for (name, edition) in cases {
+            let result = parse(name);
+            assert_eq!(result.title.as_deref(), Some("Movie"), "{name}");
+            assert_eq!(result.kind.as_deref(), Some("movie"), "{name}");
+            assert_eq!(result.edition, Some(edition), "{name}");
+        }
+    }
+
+    #[test]
+    fn release_groups() {
+        let cases = [
+            ("Movie.2019.1080p.BluRay.x264-SPARKS.mkv", Some("SPARKS")),
+            ("Movie.2019.1080p.WEB.h264-GRP.en.srt", Some("GRP")),
+            (
+                "[SubsPlease] Frieren - 05 (1080p) [ABCD1234].mkv",
+                Some("SubsPlease"),
+            ),
+            ("Spider-Man.2002.1080p.BluRay.x264.mkv", None),
+            ("Spider-Man.mkv", None),
+        ];
+        for (name, group) in cases {
+            assert_eq!(parse(name).release_group.as_deref(), group, "{name}");
+        }
+        let result = parse("[SubsPlease] Frieren - 05 (1080p) [ABCD1234].mkv");
+        assert_eq!(result.title.as_deref(), Some("Frieren"));
+        assert_eq!(result.episode, Some(5.into()));
+        let result = parse("Spider-Man.2002.1080p.BluRay.x264.mkv");
+        assert_eq!(result.title.as_deref(), Some("Spider-Man"));
+    }
+}
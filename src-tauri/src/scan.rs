@@ -0,0 +1,624 @@
+//! Library folder scanning: walks a tree on a few threads and reports the
+//! videos, subtitles and subtitle archives in it as it goes, so dropping a
+//! large folder does not wait for the whole walk.
+//!
+//! Samples, trailers, extras and OS junk are skipped by glob rules matched
+//! against entry names (or, for rules containing `/`, against the path below
+//! the root). Directory symlinks are followed at most once per target, and
+//! the walk stops at `maxDepth`.
+
+use crate::archive::is_archive;
+use crate::error::impl_serialize_error;
+use crate::pairing::{is_subtitle, is_video};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::ipc::Channel;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScanError {
+    #[error("cannot scan {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid ignore rule {pattern:?}: {message}")]
+    InvalidRule { pattern: String, message: String },
+    #[error("background task failed: {0}")]
+    Task(String),
+}
+
+impl ScanError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::InvalidRule { .. } => "invalidRule",
+            Self::Task(_) => "task",
+        }
+    }
+}
+
+impl_serialize_error!(ScanError);
+
+pub const DEFAULT_IGNORE: &[&str] = &[
+    // Samples and trailers shipped alongside releases.
+    "sample",
+    "sample.*",
+    "*-sample.*",
+    "*.sample.*",
+    "*_sample.*",
+    "trailer",
+    "trailers",
+    "trailer.*",
+    "*-trailer.*",
+    "*.trailer.*",
+    // Bonus material folders as media servers name them.
+    "extras",
+    "featurettes",
+    "behind the scenes",
+    "deleted scenes",
+    "interviews",
+    "shorts",
+    // OS and NAS junk.
+    "$RECYCLE.BIN",
+    "System Volume Information",
+    "lost+found",
+    "@eaDir",
+    "#recycle",
+    ".DS_Store",
+    "._*",
+    "Thumbs.db",
+    "desktop.ini",
+    // Downloads still in progress.
+    "*.part",
+    "*.crdownload",
+    "*.!qB",
+];
+
+const BATCH_SIZE: usize = 256;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Most worker threads a scan starts, whatever the options ask for.
+const MAX_THREADS: usize = 32;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanOptions {
+    /// Replaces [`DEFAULT_IGNORE`] when given.
+    pub ignore: Vec<String>,
+    /// Directories nested deeper than this below the root are not entered.
+    pub max_depth: usize,
+    pub follow_symlinks: bool,
+    pub include_hidden: bool,
+    /// Worker threads; 0 picks one per core, up to 8. Capped at
+    /// [`MAX_THREADS`].
+    pub threads: usize,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            ignore: DEFAULT_IGNORE.iter().map(|s| s.to_string()).collect(),
+            max_depth: 32,
+            follow_symlinks: true,
+            include_hidden: false,
+            threads: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKind {
+    Video,
+    Subtitle,
+    /// A subtitle pack; see [`crate::archive`].
+    Archive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedFile {
+    pub path: PathBuf,
+    pub kind: FileKind,
+    pub size: u64,
+    /// Unix milliseconds, where the filesystem records it.
+    pub modified: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanCounts {
+    pub directories: usize,
+    pub videos: usize,
+    pub subtitles: usize,
+    pub archives: usize,
+    /// Files that are neither videos, subtitles nor archives.
+    pub other: usize,
+    /// Entries skipped by ignore rules, hidden names, depth or loop guard.
+    pub ignored: usize,
+    /// Entries that could not be read; the walk carries on without them.
+    pub errors: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum ScanEvent {
+    Files(Vec<ScannedFile>),
+    Progress(ScanCounts),
+    Error { path: PathBuf, message: String },
+    Done(ScanCounts),
+}
+
+struct IgnoreRules(Vec<(Pattern, bool)>);
+
+impl IgnoreRules {
+    const OPTIONS: MatchOptions = MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    fn compile(rules: &[String]) -> Result<Self, ScanError> {
+        rules
+            .iter()
+            .map(|rule| {
+                let pattern = Pattern::new(rule).map_err(|e| ScanError::InvalidRule {
+                    pattern: rule.clone(),
+                    message: e.msg.to_string(),
+                })?;
+                Ok((pattern, rule.contains('/')))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    fn matches(&self, name: &str, relative: &Path) -> bool {
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.0.iter().any(|(pattern, by_path)| {
+            let subject = if *by_path { relative.as_str() } else { name };
+            pattern.matches_with(subject, Self::OPTIONS)
+        })
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    directories: AtomicUsize,
+    videos: AtomicUsize,
+    subtitles: AtomicUsize,
+    archives: AtomicUsize,
+    other: AtomicUsize,
+    ignored: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl Counters {
+    fn snapshot(&self) -> ScanCounts {
+        ScanCounts {
+            directories: self.directories.load(Ordering::Relaxed),
+            videos: self.videos.load(Ordering::Relaxed),
+            subtitles: self.subtitles.load(Ordering::Relaxed),
+            archives: self.archives.load(Ordering::Relaxed),
+            other: self.other.load(Ordering::Relaxed),
+            ignored: self.ignored.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn bump(counter: &AtomicUsize) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Directories waiting to be read, and how many workers are reading one.
+struct Work {
+    pending: Vec<(PathBuf, usize)>,
+    busy: usize,
+}
+
+struct Walk<'a> {
+    root: &'a Path,
+    options: &'a ScanOptions,
+    rules: IgnoreRules,
+    work: Mutex<Work>,
+    ready: Condvar,
+    /// Canonical paths of directories already queued, against symlink loops.
+    visited: Mutex<HashSet<PathBuf>>,
+    counters: Counters,
+    last_progress: Mutex<Instant>,
+    emit: &'a (dyn Fn(ScanEvent) + Sync),
+}
+
+impl Walk<'_> {
+    fn worker(&self) {
+        let mut batch = Vec::new();
+        loop {
+            let next = {
+                let mut work = self.work.lock().expect("scan queue poisoned");
+                loop {
+                    if let Some(next) = work.pending.pop() {
+                        work.busy += 1;
+                        break Some(next);
+                    }
+                    if work.busy == 0 {
+                        break None;
+                    }
+                    work = self.ready.wait(work).expect("scan queue poisoned");
+                }
+            };
+            let Some((dir, depth)) = next else { break };
+            self.read_dir(&dir, depth, &mut batch);
+            let mut work = self.work.lock().expect("scan queue poisoned");
+            work.busy -= 1;
+            if work.busy == 0 && work.pending.is_empty() {
+                self.ready.notify_all();
+            }
+        }
+        self.flush(&mut batch);
+    }
+
+    fn read_dir(&self, dir: &Path, depth: usize, batch: &mut Vec<ScannedFile>) {
+        bump(&self.counters.directories);
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => return self.error(dir, &e),
+        };
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.error(dir, &e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative = path.strip_prefix(self.root).unwrap_or(&path);
+            if (name.starts_with('.') && !self.options.include_hidden)
+                || self.rules.matches(&name, relative)
+            {
+                bump(&self.counters.ignored);
+                continue;
+            }
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(e) => {
+                    self.error(&path, &e);
+                    continue;
+                }
+            };
+            let metadata = if file_type.is_symlink() {
+                if !self.options.follow_symlinks {
+                    bump(&self.counters.ignored);
+                    continue;
+                }
+                fs::metadata(&path)
+            } else {
+                entry.metadata()
+            };
+            match metadata {
+                Ok(meta) if meta.is_dir() => {
+                    if depth >= self.options.max_depth || !self.first_visit(&path) {
+                        bump(&self.counters.ignored);
+                    } else {
+                        subdirs.push((path, depth + 1));
+                    }
+                }
+                Ok(meta) => self.file(path, &meta, batch),
+                Err(e) => self.error(&path, &e),
+            }
+        }
+        if !subdirs.is_empty() {
+            let mut work = self.work.lock().expect("scan queue poisoned");
+            work.pending.extend(subdirs);
+            self.ready.notify_all();
+        }
+        if batch.len() >= BATCH_SIZE {
+            self.flush(batch);
+        }
+        self.progress(false);
+    }
+
+    fn file(&self, path: PathBuf, meta: &fs::Metadata, batch: &mut Vec<ScannedFile>) {
+        let kind = if is_video(&path) {
+            bump(&self.counters.videos);
+            FileKind::Video
+        } else if is_subtitle(&path) {
+            bump(&self.counters.subtitles);
+            FileKind::Subtitle
+        } else if is_archive(&path) {
+            bump(&self.counters.archives);
+            FileKind::Archive
+        } else {
+            bump(&self.counters.other);
+            return;
+        };
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64);
+        batch.push(ScannedFile {
+            path,
+            kind,
+            size: meta.len(),
+            modified,
+        });
+    }
+
+    fn first_visit(&self, dir: &Path) -> bool {
+        let key = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        self.visited
+            .lock()
+            .expect("visited set poisoned")
+            .insert(key)
+    }
+
+    fn error(&self, path: &Path, error: &std::io::Error) {
+        bump(&self.counters.errors);
+        (self.emit)(ScanEvent::Error {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        });
+    }
+
+    fn flush(&self, batch: &mut Vec<ScannedFile>) {
+        if !batch.is_empty() {
+            (self.emit)(ScanEvent::Files(std::mem::take(batch)));
+            self.progress(true);
+        }
+    }
+
+    /// Sends counts at most every [`PROGRESS_INTERVAL`] unless `force`d.
+    fn progress(&self, force: bool) {
+        let Ok(mut last) = self.last_progress.try_lock() else {
+            return;
+        };
+        if force || last.elapsed() >= PROGRESS_INTERVAL {
+            *last = Instant::now();
+            (self.emit)(ScanEvent::Progress(self.counters.snapshot()));
+        }
+    }
+}
+
+/// Walks `root` (a directory, or a single file) and passes what it finds to
+/// `emit` in batches. Returns the final counts; `emit` does not get `Done`.
+pub fn scan(
+    root: &Path,
+    options: &ScanOptions,
+    emit: &(dyn Fn(ScanEvent) + Sync),
+) -> Result<ScanCounts, ScanError> {
+    let rules = IgnoreRules::compile(&options.ignore)?;
+    let meta = fs::metadata(root).map_err(|source| ScanError::Io {
+        path: root.to_path_buf(),
+        source,
+    })?;
+    let walk = Walk {
+        root,
+        options,
+        rules,
+        work: Mutex::new(Work {
+            pending: Vec::new(),
+            busy: 0,
+        }),
+        ready: Condvar::new(),
+        visited: Mutex::new(HashSet::new()),
+        counters: Counters::default(),
+        last_progress: Mutex::new(Instant::now()),
+        emit,
+    };
+    if !meta.is_dir() {
+        let mut batch = Vec::new();
+        walk.file(root.to_path_buf(), &meta, &mut batch);
+        walk.flush(&mut batch);
+        return Ok(walk.counters.snapshot());
+    }
+
+    walk.first_visit(root);
+    walk.work
+        .lock()
+        .expect("scan queue poisoned")
+        .pending
+        .push((root.to_path_buf(), 0));
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map_or(4, |n| n.get().min(8)),
+        n => n.min(MAX_THREADS),
+    };
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| walk.worker());
+        }
+    });
+    Ok(walk.counters.snapshot())
+}
+
+#[tauri::command]
+pub async fn scan_directory(
+    path: PathBuf,
+    options: Option<ScanOptions>,
+    on_event: Channel<ScanEvent>,
+) -> Result<ScanCounts, ScanError> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        // A closed channel only means the window stopped listening.
+        let counts = scan(&path, &options, &|event| {
+            let _ = on_event.send(event);
+        })?;
+        let _ = on_event.send(ScanEvent::Done(counts));
+        Ok(counts)
+    })
+    .await
+    .map_err(|e| ScanError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates `files` below a fresh directory named after the test.
+    fn tree(name: &str, files: &[&str]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("{}-scan-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+        root
+    }
+
+    /// Scans `root` and returns the counts and the files found, relative to
+    /// `root` and sorted.
+    fn run(root: &Path, options: &ScanOptions) -> (ScanCounts, Vec<String>) {
+        let found = Mutex::new(Vec::new());
+        let counts = scan(root, options, &|event| {
+            if let ScanEvent::Files(files) = event {
+                found
+                    .lock()
+                    .unwrap()
+                    .extend(files.into_iter().map(|f| f.path));
+            }
+        })
+        .unwrap();
+        let mut found: Vec<_> = found
+            .into_inner()
+            .unwrap()
+            .iter()
+            .map(|path| {
+                let relative = path.strip_prefix(root).unwrap();
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        found.sort();
+        (counts, found)
+    }
+
+    #[test]
+    fn finds_media_and_skips_default_ignores() {
+        let root = tree(
+            "defaults",
+            &[
+                "Movie/Movie.mkv",
+                "Movie/Movie.en.srt",
+                "Movie/Subs.zip",
+                "Movie/Movie.nfo",
+                "Movie/Movie-sample.mkv",
+                "Movie/Sample/sample.mkv",
+                "Movie/Extras/Making of.mkv",
+                "Movie/.hidden.srt",
+                "@eaDir/Movie.srt",
+                "Show/Show.S01E01.mkv.part",
+            ],
+        );
+        let (counts, found) = run(&root, &ScanOptions::default());
+        assert_eq!(
+            found,
+            ["Movie/Movie.en.srt", "Movie/Movie.mkv", "Movie/Subs.zip"]
+        );
+        assert_eq!(
+            (
+                counts.videos,
+                counts.subtitles,
+                counts.archives,
+                counts.other
+            ),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(counts.directories, 3);
+        assert_eq!(counts.ignored, 6);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn applies_name_and_path_rules() {
+        let root = tree(
+            "rules",
+            &[
+                "Season 1/Show.S01E01.mkv",
+                "Season 1/Show.S01E01.srt",
+                "Season 2/Show.S02E01.srt",
+                "Show.SRT",
+            ],
+        );
+        let options = ScanOptions {
+            ignore: vec!["season 1/*.srt".into(), "*.mkv".into()],
+            ..ScanOptions::default()
+        };
+        let (counts, found) = run(&root, &options);
+        // Matching ignores case; path rules only match below the root.
+        assert_eq!(found, ["Season 2/Show.S02E01.srt", "Show.SRT"]);
+        assert_eq!(counts.ignored, 2);
+
+        let options = ScanOptions {
+            ignore: vec!["[".into()],
+            ..ScanOptions::default()
+        };
+        let error = scan(&root, &options, &|_| {}).unwrap_err();
+        assert_eq!(error.kind(), "invalidRule");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn stops_at_the_depth_cap() {
+        let root = tree("depth", &["0.srt", "a/1.srt", "a/b/2.srt", "a/b/c/3.srt"]);
+        let options = ScanOptions {
+            max_depth: 2,
+            ..ScanOptions::default()
+        };
+        let (counts, found) = run(&root, &options);
+        assert_eq!(found, ["0.srt", "a/1.srt", "a/b/2.srt"]);
+        assert_eq!((counts.directories, counts.ignored), (3, 1));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn caps_worker_threads() {
+        let root = tree("threads", &["a/1.srt", "b/2.srt"]);
+        let options = ScanOptions {
+            threads: usize::MAX,
+            ..ScanOptions::default()
+        };
+        let (counts, found) = run(&root, &options);
+        assert_eq!(found, ["a/1.srt", "b/2.srt"]);
+        assert_eq!(counts.directories, 3);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_a_symlink_cycle_once() {
+        use std::os::unix::fs::symlink;
+
+        let root = tree("cycle", &["a/1.srt", "b/2.srt"]);
+        symlink(&root, root.join("a/up")).unwrap();
+        symlink(root.join("b"), root.join("a/b")).unwrap();
+        let (counts, found) = run(&root, &ScanOptions::default());
+        // `a/up` leads back to the root and `a/b` to a folder already
+        // queued; whichever of `b` and `a/b` is reached first is walked.
+        assert_eq!(found.len(), 2, "{found:?}");
+        assert!(found.contains(&"a/1.srt".to_string()), "{found:?}");
+        assert_eq!((counts.directories, counts.ignored), (3, 2));
+
+        let options = ScanOptions {
+            follow_symlinks: false,
+            ..ScanOptions::default()
+        };
+        let (counts, found) = run(&root, &options);
+        assert_eq!(found, ["a/1.srt", "b/2.srt"]);
+        assert_eq!(counts.ignored, 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,569 @@
+//! Runtime settings: `settings.toml` in the app config directory, so the
+//! API key and endpoints can change without a rebuild.
+//!
+//! The file carries a `version`. Older files are brought up to date by the
+//! steps in [`MIGRATIONS`] (the original is kept as `settings.v<N>.toml`);
+//! files from a newer release are refused rather than rewritten. Missing
+//! keys take their defaults and unknown keys are errors, so a typo does not
+//! silently fall back to a default.
+//!
+//! Environment variables override single values without touching the file:
+//!
+//! | Variable                          | Setting                   |
+//! |-----------------------------------|---------------------------|
+//! | `OPENSUBTITLES_API_KEY`           | `api.key`                 |
+//! | `OPENSUBTITLES_REST_URL`          | `api.restBaseUrl`         |
+//! | `OPENSUBTITLES_XMLRPC_URL`        | `api.xmlrpcEndpoint`      |
+//! | `OPENSUBTITLES_CACHE_MB`          | `cache.maxMegabytes`      |
+//! | `OPENSUBTITLES_CREDENTIAL_STORE`  | `credentials.store`       |
+//!
+//! The API key applies immediately; the other settings on the next start.
+//! An invalid override leaves the stored settings in effect and is reported
+//! in [`SettingsView::override_error`]; the file can still be edited.
+
+use crate::api::{rest, xmlrpc};
+use crate::credentials::StoreKind;
+use crate::error::impl_serialize_error;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Name of the event carrying the new [`SettingsView`] to the webview.
+pub const SETTINGS_EVENT: &str = "settings-changed";
+
+pub const FILE_NAME: &str = "settings.toml";
+
+/// Layout version written by this build.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Upgrades a parsed settings file by one version; `MIGRATIONS[n]` takes a
+/// version `n + 1` file to version `n + 2`. Append a step whenever a key is
+/// renamed, moved or changes meaning, and bump [`CURRENT_VERSION`].
+type Migration = fn(&mut toml::Table) -> Result<(), String>;
+
+const MIGRATIONS: &[Migration] = &[drop_memory_store];
+
+const _: () = assert!(MIGRATIONS.len() as u32 == CURRENT_VERSION - 1);
+
+/// Version 1 to 2: the in-memory credential store is gone, and files that
+/// chose it get `auto`.
+fn drop_memory_store(table: &mut toml::Table) -> Result<(), String> {
+    if let Some(toml::Value::Table(credentials)) = table.get_mut("credentials") {
+        if credentials.get("store").and_then(toml::Value::as_str) == Some("memory") {
+            credentials.insert("store".into(), "auto".into());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("could not access {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {message}", .path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("{}: written by a newer version (settings version {found}, this build reads up to {CURRENT_VERSION})", .path.display())]
+    TooNew { path: PathBuf, found: u32 },
+    #[error("{}: migrating from version {from} failed: {message}", .path.display())]
+    Migration {
+        path: PathBuf,
+        from: u32,
+        message: String,
+    },
+    #[error("invalid setting {field}: {message}")]
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
+}
+
+impl SettingsError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Parse { .. } => "parse",
+            Self::TooNew { .. } => "tooNew",
+            Self::Migration { .. } => "migration",
+            Self::Invalid { .. } => "invalid",
+        }
+    }
+
+    fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    fn invalid(field: &'static str, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl_serialize_error!(SettingsError);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Settings {
+    pub version: u32,
+    pub api: ApiSettings,
+    pub cache: CacheSettings,
+    pub credentials: CredentialSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            api: ApiSettings::default(),
+            cache: CacheSettings::default(),
+            credentials: CredentialSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct ApiSettings {
+    /// Consumer key for api.opensubtitles.com; without one the build-time
+    /// key, if any, is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub rest_base_url: String,
+    pub xmlrpc_endpoint: String,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            key: None,
+            rest_base_url: rest::DEFAULT_BASE_URL.into(),
+            xmlrpc_endpoint: xmlrpc::DEFAULT_ENDPOINT.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct CacheSettings {
+    /// Size cap of the API response cache.
+    pub max_megabytes: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            max_megabytes: crate::cache::DEFAULT_MAX_BYTES / (1024 * 1024),
+        }
+    }
+}
+
+/// Largest cache accepted, 10 GiB.
+const MAX_CACHE_MEGABYTES: u64 = 10 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct CredentialSettings {
+    pub store: StoreKind,
+}
+
+fn check_url(field: &'static str, value: &str) -> Result<(), SettingsError> {
+    let url =
+        reqwest::Url::parse(value).map_err(|e| SettingsError::invalid(field, e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(SettingsError::invalid(field, "must be an http(s) URL"));
+    }
+    Ok(())
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.version != CURRENT_VERSION {
+            return Err(SettingsError::invalid(
+                "version",
+                format!("must be {CURRENT_VERSION}"),
+            ));
+        }
+        if let Some(key) = &self.api.key {
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_graphic()) {
+                return Err(SettingsError::invalid(
+                    "api.key",
+                    "must be non-empty printable ASCII without spaces",
+                ));
+            }
+        }
+        check_url("api.restBaseUrl", &self.api.rest_base_url)?;
+        check_url("api.xmlrpcEndpoint", &self.api.xmlrpc_endpoint)?;
+        if !(1..=MAX_CACHE_MEGABYTES).contains(&self.cache.max_megabytes) {
+            return Err(SettingsError::invalid(
+                "cache.maxMegabytes",
+                format!("must be between 1 and {MAX_CACHE_MEGABYTES}"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The API key requests are made with: this one, or the build-time key.
+    pub fn api_key(&self) -> Option<String> {
+        self.api.key.clone().or_else(rest::api_key_from_env)
+    }
+
+    pub fn cache_max_bytes(&self) -> u64 {
+        self.cache.max_megabytes * 1024 * 1024
+    }
+
+    /// Applies the environment overrides, returning the settings they set.
+    fn apply_env(
+        &mut self,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Vec<&'static str>, SettingsError> {
+        let mut overridden = Vec::new();
+        for (name, field, value) in env_values(var) {
+            match field {
+                "api.key" => self.api.key = Some(value),
+                "api.restBaseUrl" => self.api.rest_base_url = value,
+                "api.xmlrpcEndpoint" => self.api.xmlrpc_endpoint = value,
+                "cache.maxMegabytes" => {
+                    self.cache.max_megabytes = value.parse().map_err(|_| {
+                        SettingsError::invalid(field, format!("{name} is not a whole number"))
+                    })?
+                }
+                "credentials.store" => {
+                    self.credentials.store = value
+                        .parse()
+                        .map_err(|message| SettingsError::invalid(field, message))?
+                }
+                _ => unreachable!("override for unknown setting {field}"),
+            }
+            overridden.push(field);
+        }
+        Ok(overridden)
+    }
+
+    /// Copies `fields` over from `other`.
+    fn copy_fields(&mut self, other: &Settings, fields: &[&'static str]) {
+        for &field in fields {
+            match field {
+                "api.key" => self.api.key = other.api.key.clone(),
+                "api.restBaseUrl" => self.api.rest_base_url = other.api.rest_base_url.clone(),
+                "api.xmlrpcEndpoint" => {
+                    self.api.xmlrpc_endpoint = other.api.xmlrpc_endpoint.clone()
+                }
+                "cache.maxMegabytes" => self.cache.max_megabytes = other.cache.max_megabytes,
+                "credentials.store" => self.credentials.store = other.credentials.store,
+                _ => unreachable!("unknown setting {field}"),
+            }
+        }
+    }
+
+    /// Whether a change from `self` to `other` only applies after a restart.
+    fn needs_restart(&self, other: &Settings) -> bool {
+        self.api.rest_base_url != other.api.rest_base_url
+            || self.api.xmlrpc_endpoint != other.api.xmlrpc_endpoint
+            || self.cache != other.cache
+            || self.credentials != other.credentials
+    }
+}
+
+const OVERRIDES: &[(&str, &str)] = &[
+    (rest::API_KEY_VAR, "api.key"),
+    ("OPENSUBTITLES_REST_URL", "api.restBaseUrl"),
+    ("OPENSUBTITLES_XMLRPC_URL", "api.xmlrpcEndpoint"),
+    ("OPENSUBTITLES_CACHE_MB", "cache.maxMegabytes"),
+    ("OPENSUBTITLES_CREDENTIAL_STORE", "credentials.store"),
+];
+
+/// The overrides whose variable is set, as `(variable, setting, value)`.
+fn env_values(var: impl Fn(&str) -> Option<String>) -> Vec<(&'static str, &'static str, String)> {
+    OVERRIDES
+        .iter()
+        .filter_map(|&(name, field)| {
+            let value = var(name)?.trim().to_string();
+            (!value.is_empty()).then_some((name, field, value))
+        })
+        .collect()
+}
+
+/// Settings an environment variable is set for, whether or not its value
+/// is valid.
+fn overridden(var: impl Fn(&str) -> Option<String>) -> Vec<&'static str> {
+    env_values(var)
+        .into_iter()
+        .map(|(_, field, _)| field)
+        .collect()
+}
+
+/// Reads the settings file at `path`, migrating it if it is older than this
+/// build. A missing file gives the defaults.
+pub fn read(path: &Path) -> Result<Settings, SettingsError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(SettingsError::io(path, e)),
+    };
+    let parse_error = |message: String| SettingsError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+    let mut table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| parse_error(e.to_string()))?;
+    let version = match table.get("version") {
+        None => CURRENT_VERSION,
+        Some(toml::Value::Integer(v)) if *v >= 1 => u32::try_from(*v).unwrap_or(u32::MAX),
+        Some(other) => return Err(parse_error(format!("invalid version {other}"))),
+    };
+    if version > CURRENT_VERSION {
+        return Err(SettingsError::TooNew {
+            path: path.to_path_buf(),
+            found: version,
+        });
+    }
+    for (from, migrate) in (version..).zip(&MIGRATIONS[version as usize - 1..]) {
+        migrate(&mut table).map_err(|message| SettingsError::Migration {
+            path: path.to_path_buf(),
+            from,
+            message,
+        })?;
+    }
+    table.insert(
+        "version".into(),
+        toml::Value::Integer(CURRENT_VERSION.into()),
+    );
+    let settings: Settings = table
+        .try_into()
+        .map_err(|e: toml::de::Error| parse_error(e.to_string()))?;
+    settings.validate()?;
+    if version < CURRENT_VERSION {
+        let backup = path.with_file_name(format!("settings.v{version}.toml"));
+        fs::copy(path, &backup).map_err(|e| SettingsError::io(&backup, e))?;
+        write(path, &settings)?;
+    }
+    Ok(settings)
+}
+
+fn write(path: &Path, settings: &Settings) -> Result<(), SettingsError> {
+    let text = toml::to_string_pretty(settings).expect("settings serialize");
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| SettingsError::io(dir, e))?;
+    }
+    let tmp = path.with_extension("toml.tmp");
+    let write = || {
+        let mut file = File::create(&tmp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().map_err(|e| SettingsError::io(path, e))
+}
+
+/// Settings in effect, as shown to the UI.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsView {
+    #[serde(flatten)]
+    pub settings: Settings,
+    /// Settings set by environment variables; edits to these are ignored.
+    pub overridden: Vec<&'static str>,
+    /// Whether settings differ from those the app started with in ways
+    /// that only apply after a restart.
+    pub restart_required: bool,
+    pub path: PathBuf,
+    /// Why the file was ignored at startup, if it was.
+    pub load_error: Option<String>,
+    /// Why the environment overrides were ignored, if they were.
+    pub override_error: Option<String>,
+}
+
+type Listener = Arc<dyn Fn(&SettingsView) + Send + Sync>;
+
+struct Shared {
+    path: PathBuf,
+    /// As stored in the file, without environment overrides.
+    stored: Mutex<Settings>,
+    /// What the app started with.
+    initial: Settings,
+    load_error: Option<String>,
+}
+
+/// Shared settings handle; cheap to clone.
+#[derive(Clone)]
+pub struct SettingsStore {
+    shared: Arc<Shared>,
+    on_change: Option<Listener>,
+}
+
+/// Stored settings plus the environment overrides on top.
+fn effective(stored: &Settings) -> Result<(Settings, Vec<&'static str>), SettingsError> {
+    let mut settings = stored.clone();
+    let overridden = settings.apply_env(|name| std::env::var(name).ok())?;
+    settings.validate()?;
+    Ok((settings, overridden))
+}
+
+impl SettingsStore {
+    fn with(
+        path: PathBuf,
+        stored: Settings,
+        initial: Settings,
+        load_error: Option<String>,
+    ) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                path,
+                stored: Mutex::new(stored),
+                initial,
+                load_error,
+            }),
+            on_change: None,
+        }
+    }
+
+    /// Loads `settings.toml` from `dir`.
+    pub fn open(dir: &Path) -> Result<Self, SettingsError> {
+        let path = dir.join(FILE_NAME);
+        let stored = read(&path)?;
+        let (initial, _) = effective(&stored)?;
+        Ok(Self::with(path, stored, initial, None))
+    }
+
+    /// Like [`open`](Self::open), but starts from the defaults when the file
+    /// or an override is invalid. The file is left alone until the next
+    /// update and the problem is reported in [`SettingsView::load_error`].
+    pub fn open_or_default(dir: &Path) -> Self {
+        Self::open(dir).unwrap_or_else(|e| {
+            let stored = Settings::default();
+            let initial = effective(&stored).map_or_else(|_| stored.clone(), |(s, _)| s);
+            Self::with(dir.join(FILE_NAME), stored, initial, Some(e.to_string()))
+        })
+    }
+
+    /// Calls `listener` after every successful update.
+    pub fn on_change(mut self, listener: impl Fn(&SettingsView) + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(listener));
+        self
+    }
+
+    /// The settings in effect, environment overrides included.
+    pub fn current(&self) -> Settings {
+        self.view().settings
+    }
+
+    pub fn view(&self) -> SettingsView {
+        let stored = self.shared.stored.lock().unwrap().clone();
+        let (settings, overridden, override_error) = match effective(&stored) {
+            Ok((settings, overridden)) => (settings, overridden, None),
+            Err(e) => (stored, Vec::new(), Some(e.to_string())),
+        };
+        SettingsView {
+            restart_required: self.shared.initial.needs_restart(&settings),
+            settings,
+            overridden,
+            path: self.shared.path.clone(),
+            load_error: self.shared.load_error.clone(),
+            override_error,
+        }
+    }
+
+    /// Validates and saves `settings`, keeping the stored value of anything
+    /// an environment variable overrides. Only the stored settings are
+    /// validated; a bad override shows up in the returned view instead.
+    pub fn update(&self, mut settings: Settings) -> Result<SettingsView, SettingsError> {
+        {
+            let mut stored = self.shared.stored.lock().unwrap();
+            settings.copy_fields(&stored, &overridden(|name| std::env::var(name).ok()));
+            settings.validate()?;
+            write(&self.shared.path, &settings)?;
+            *stored = settings;
+        }
+        let view = self.view();
+        if let Some(listener) = &self.on_change {
+            listener(&view);
+        }
+        Ok(view)
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(store: tauri::State<'_, SettingsStore>) -> SettingsView {
+    store.view()
+}
+
+#[tauri::command]
+pub fn update_settings(
+    store: tauri::State<'_, SettingsStore>,
+    settings: Settings,
+) -> Result<SettingsView, SettingsError> {
+    store.update(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrates_the_memory_store() {
+        let dir = temp_dir("settings-v1");
+        let path = dir.join(FILE_NAME);
+        fs::write(&path, "version = 1\n\n[credentials]\nstore = \"memory\"\n").unwrap();
+
+        let settings = read(&path).unwrap();
+        assert_eq!(settings.version, CURRENT_VERSION);
+        assert_eq!(settings.credentials.store, StoreKind::Auto);
+        let backup = fs::read_to_string(dir.join("settings.v1.toml")).unwrap();
+        assert!(backup.contains("memory"));
+        assert_eq!(read(&path).unwrap(), settings);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bad_overrides_still_name_their_setting() {
+        let var = |name: &str| (name == "OPENSUBTITLES_CACHE_MB").then(|| "lots".to_string());
+        let mut settings = Settings::default();
+        assert!(matches!(
+            settings.apply_env(var),
+            Err(SettingsError::Invalid {
+                field: "cache.maxMegabytes",
+                ..
+            })
+        ));
+        assert_eq!(overridden(var), ["cache.maxMegabytes"]);
+
+        let stored = Settings::default();
+        let mut edited = Settings::default();
+        edited.cache.max_megabytes = 128;
+        edited.api.key = Some("key".into());
+        edited.copy_fields(&stored, &overridden(var));
+        assert_eq!(edited.cache, stored.cache);
+        assert_eq!(edited.api.key.as_deref(), Some("key"));
+    }
+
+    #[test]
+    fn update_saves_the_file() {
+        let dir = temp_dir("settings-update");
+        let store = SettingsStore::open(&dir).unwrap();
+        let mut settings = Settings::default();
+        settings.cache.max_megabytes = 128;
+        let view = store.update(settings.clone()).unwrap();
+        assert!(view.override_error.is_none());
+        assert_eq!(read(&dir.join(FILE_NAME)).unwrap(), settings);
+
+        settings.cache.max_megabytes = 0;
+        assert!(store.update(settings).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
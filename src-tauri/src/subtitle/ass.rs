@@ -0,0 +1,231 @@
+//! Advanced SubStation Alpha and SubStation Alpha: `[Section]`s of
+//! `Key: value` lines, with styles and events laid out by `Format` lines.
+
+use super::markup;
+use super::parse::{clock, Diagnostics};
+use super::{Cue, Format, Style, Subtitle};
+use crate::media::ssa_timestamp;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Event fields when the `Format` line is missing.
+const DEFAULT_EVENT_FORMAT: &str =
+    "Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+/// Script info and a `Default` style for scripts written from other formats.
+const ASS_HEADER: &str = "[Script Info]
+ScriptType: v4.00+
+PlayResX: 384
+PlayResY: 288
+WrapStyle: 0
+ScaledBorderAndShadow: yes
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, \
+Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,2,2,\
+2,10,10,10,1";
+
+const SSA_HEADER: &str = "[Script Info]
+ScriptType: v4.00
+
+[V4 Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, TertiaryColour, BackColour, \
+Bold, Italic, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, AlphaLevel, \
+Encoding
+Style: Default,Arial,20,16777215,255,0,0,0,0,1,2,2,2,10,10,10,0,1";
+
+/// Tells ASS from SSA: only ASS has `[V4+ Styles]` and script type v4.00+.
+pub(crate) fn flavour(text: &str) -> Format {
+    let lower = text.to_ascii_lowercase();
+    if lower.contains("[v4+ styles]") || lower.contains("v4.00+") {
+        Format::Ass
+    } else {
+        Format::Ssa
+    }
+}
+
+fn fields(value: &str) -> Vec<String> {
+    value.split(',').map(|f| f.trim().to_string()).collect()
+}
+
+/// The value of field `name` in a line split per `format`.
+fn field<'a>(format: &[String], values: &[&'a str], name: &str) -> Option<&'a str> {
+    let i = format.iter().position(|f| f.eq_ignore_ascii_case(name))?;
+    values.get(i).map(|v| v.trim())
+}
+
+/// The name and formatting of a `Style` line laid out per `format`.
+fn style(format: &[String], value: &str) -> Option<(String, Style)> {
+    let values: Vec<&str> = value.split(',').collect();
+    // -1 is true in SSA; some writers use 1.
+    let flag = |name| field(format, &values, name).is_some_and(|v| v != "0");
+    let name = field(format, &values, "Name")?;
+    let style = Style {
+        italic: flag("Italic"),
+        bold: flag("Bold"),
+        underline: flag("Underline"),
+    };
+    Some((name.to_string(), style))
+}
+
+/// The styles defined in a script header.
+fn styles(header: &str) -> HashMap<String, Style> {
+    let mut format = Vec::new();
+    let mut styles = HashMap::new();
+    for line in header.lines() {
+        match line.trim().split_once(':') {
+            Some(("Format", value)) => format = fields(value),
+            Some(("Style", value)) => styles.extend(style(&format, value.trim_start())),
+            _ => {}
+        }
+    }
+    styles
+}
+
+pub(crate) fn parse(lines: &[&str], format: Format, diagnostics: &mut Diagnostics) -> Subtitle {
+    let mut section = String::new();
+    let mut header = Vec::new();
+    let (mut style_format, mut event_format) = (Vec::new(), Vec::new());
+    let mut styles = HashMap::new();
+    let mut cues = Vec::new();
+    for (i, raw) in lines.iter().enumerate() {
+        let line = raw.trim();
+        let is_section = line.starts_with('[') && line.ends_with(']');
+        if is_section {
+            section = line[1..line.len() - 1].to_ascii_lowercase();
+        }
+        // Everything but the events is kept to write the file back.
+        if section != "events" {
+            header.push(*raw);
+        }
+        if is_section || line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            if section == "events" {
+                diagnostics.warning(
+                    i + 1,
+                    "strayText",
+                    format!("text outside an event: {line:?}"),
+                );
+            }
+            continue;
+        };
+        let value = value.trim_start();
+        match (section.as_str(), key.trim()) {
+            ("v4+ styles" | "v4 styles", "Format") => style_format = fields(value),
+            ("v4+ styles" | "v4 styles", "Style") => styles.extend(style(&style_format, value)),
+            ("events", "Format") => event_format = fields(value),
+            ("events", "Dialogue") => {
+                if event_format.is_empty() {
+                    diagnostics.warning(i + 1, "missingFormat", "events have no Format line");
+                    event_format = fields(DEFAULT_EVENT_FORMAT);
+                }
+                // The text is last and may contain commas.
+                let values: Vec<&str> = value.splitn(event_format.len(), ',').collect();
+                if values.len() < event_format.len() {
+                    diagnostics.error(
+                        i + 1,
+                        "badEvent",
+                        format!(
+                            "dialogue has {} fields, expected {}",
+                            values.len(),
+                            event_format.len()
+                        ),
+                    );
+                    continue;
+                }
+                let get = |name| field(&event_format, &values, name);
+                let (Some(start), Some(end)) =
+                    (get("Start").and_then(clock), get("End").and_then(clock))
+                else {
+                    diagnostics.error(
+                        i + 1,
+                        "badTimestamp",
+                        format!(
+                            "cannot read the times {:?} and {:?}",
+                            get("Start").unwrap_or_default(),
+                            get("End").unwrap_or_default()
+                        ),
+                    );
+                    continue;
+                };
+                // `*Default` is the same style as `Default`.
+                let style_name = get("Style").map(|name| name.trim_start_matches('*'));
+                let base = match style_name.and_then(|name| styles.get(name)) {
+                    Some(style) => *style,
+                    None => {
+                        if let Some(name) = style_name.filter(|_| !styles.is_empty()) {
+                            diagnostics.warning(
+                                i + 1,
+                                "unknownStyle",
+                                format!("style {name:?} is not defined"),
+                            );
+                        }
+                        Style::default()
+                    }
+                };
+                let text = values.last().copied().unwrap_or_default();
+                let mut cue = Cue::new(i + 1, start, end, markup::ass(text, base, &styles));
+                cue.style = style_name.map(str::to_string);
+                cues.push(cue);
+            }
+            ("events", "Comment" | "Picture" | "Sound" | "Movie" | "Command") => {}
+            ("events", _) => {
+                diagnostics.warning(i + 1, "strayText", format!("unknown event type {key:?}"));
+            }
+            _ => {}
+        }
+    }
+    // Players sort events by start time; their order in the file is free.
+    cues.sort_by_key(|cue| cue.start_ms);
+    let mut subtitle = Subtitle::new(format, cues);
+    subtitle.header = Some(header.join("\n").trim_end().to_string()).filter(|h| !h.is_empty());
+    subtitle
+}
+
+/// Writes cues as an ASS or SSA script. `header` is the script info and
+/// styles [`parse`] kept from a script of the same flavour; without one, a
+/// `Default` style is written and every cue uses it.
+pub(crate) fn render(cues: &[Cue], format: Format, header: Option<&str>) -> String {
+    let (mut out, styles) = match header {
+        Some(header) => (header.trim_end().to_string(), styles(header)),
+        None => {
+            let header = if format == Format::Ssa {
+                SSA_HEADER
+            } else {
+                ASS_HEADER
+            };
+            (header.to_string(), HashMap::new())
+        }
+    };
+    let (first, marked) = match format {
+        Format::Ssa => ("Marked", "Marked=0"),
+        _ => ("Layer", "0"),
+    };
+    let _ = write!(
+        out,
+        "\n\n[Events]\nFormat: {first}, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n"
+    );
+    for cue in cues {
+        let name = cue
+            .style
+            .as_deref()
+            .filter(|name| styles.contains_key(*name));
+        let base = name
+            .and_then(|name| styles.get(name))
+            .copied()
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "Dialogue: {marked},{},{},{},,0,0,0,,{}",
+            ssa_timestamp(cue.start_ms),
+            ssa_timestamp(cue.end_ms),
+            name.unwrap_or("Default"),
+            markup::to_ass(&cue.spans, base)
+        );
+    }
+    out
+}
@@ -0,0 +1,393 @@
+//! Converting subtitles between formats through the cue model.
+//!
+//! Timing and text always survive; italics, bold and underline do where the
+//! target has a way to write them. Whatever a conversion drops is reported
+//! as a [`Loss`], so a file is not uploaded in a worse state than the user
+//! thinks.
+
+use super::parse::{parse_file, Parsed};
+use super::{ass, microdvd, srt, subviewer, vtt, write};
+use super::{check_fps, Cue, Diagnostic, Format, Subtitle, SubtitleError};
+use crate::media;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Something a conversion could not carry over.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Loss {
+    /// Stable identifier for the UI, e.g. `formatting`.
+    pub kind: &'static str,
+    pub message: String,
+    /// Cues affected; 0 when the loss is not about particular cues.
+    pub cues: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Converted {
+    pub output: PathBuf,
+    pub from: Format,
+    pub to: Format,
+    /// Character encoding the source was read as; the output is UTF-8.
+    pub encoding: &'static str,
+    pub cue_count: usize,
+    /// Frame rate MicroDVD frames were read or written with.
+    pub fps: Option<f64>,
+    pub losses: Vec<Loss>,
+    /// What was wrong with the source, as for `parse_subtitle`.
+    pub diagnostics: Vec<Diagnostic>,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// Records a loss affecting `cues` cues, if any are.
+fn lost(losses: &mut Vec<Loss>, kind: &'static str, cues: usize, message: String) {
+    if cues > 0 {
+        losses.push(Loss {
+            kind,
+            message,
+            cues,
+        });
+    }
+}
+
+fn count(cues: &[Cue], affected: impl Fn(&Cue) -> bool) -> usize {
+    cues.iter().filter(|cue| affected(cue)).count()
+}
+
+/// Whether any visible text of the cue is formatted.
+fn formatted(cue: &Cue) -> bool {
+    cue.spans.iter().any(|span| {
+        let style = span.style;
+        (style.italic || style.bold || style.underline) && !span.text.trim().is_empty()
+    })
+}
+
+/// Whether the cue uses a script style other than `Default`.
+fn styled(cue: &Cue) -> bool {
+    cue.style
+        .as_deref()
+        .is_some_and(|name| !name.eq_ignore_ascii_case("default"))
+}
+
+/// Renders `subtitle` as `to`, listing what does not carry over. `fps` is
+/// the frame rate for MicroDVD output; the result says which was used.
+pub fn convert(
+    subtitle: &Subtitle,
+    to: Format,
+    fps: Option<f64>,
+) -> Result<(String, Option<f64>, Vec<Loss>), SubtitleError> {
+    check_fps(fps)?;
+    let (from, cues) = (subtitle.format, subtitle.cues.as_slice());
+    let mut losses = Vec::new();
+
+    // Only a script of the same flavour keeps the styles of the source;
+    // anything else shows every cue in the target's default look.
+    if matches!(from, Format::Ass | Format::Ssa) && from != to {
+        let styled = count(cues, styled);
+        lost(
+            &mut losses,
+            "styles",
+            styled,
+            "styles other than Default, with their fonts, colours and placement, are dropped"
+                .into(),
+        );
+    }
+    if from == Format::Vtt && to != Format::Vtt {
+        let settings = count(cues, |cue| cue.settings.is_some());
+        lost(
+            &mut losses,
+            "cueSettings",
+            settings,
+            "cue positions and alignment are dropped".into(),
+        );
+        let ids = count(cues, |cue| cue.id.is_some());
+        lost(
+            &mut losses,
+            "cueIds",
+            ids,
+            "cue identifiers are dropped".into(),
+        );
+        // The signature line is all a header needs; more is style or regions.
+        if subtitle
+            .header
+            .as_deref()
+            .is_some_and(|h| h.lines().count() > 1)
+        {
+            losses.push(Loss {
+                kind: "header",
+                message: "WebVTT STYLE and REGION blocks are dropped".into(),
+                cues: 0,
+            });
+        }
+    }
+
+    let header = subtitle.header.as_deref().filter(|_| from == to);
+    let (text, fps) = match to {
+        Format::Srt => (srt::render(cues), None),
+        Format::Vtt => (vtt::render(cues, header), None),
+        Format::Ass | Format::Ssa => (ass::render(cues, to, header), None),
+        Format::SubViewer2 => {
+            let styled = count(cues, formatted);
+            lost(
+                &mut losses,
+                "formatting",
+                styled,
+                format!("{} has no italics, bold or underline", to.name()),
+            );
+            (subviewer::render_v2(cues), None)
+        }
+        Format::MicroDvd => {
+            let fps = fps.or(subtitle.fps).unwrap_or_else(|| {
+                losses.push(Loss {
+                    kind: "frameRate",
+                    message: format!(
+                        "no frame rate given or found in the video; frames assume {} fps",
+                        microdvd::DEFAULT_FPS
+                    ),
+                    cues: 0,
+                });
+                microdvd::DEFAULT_FPS
+            });
+            let (text, partial) = microdvd::render(cues, fps);
+            lost(
+                &mut losses,
+                "formatting",
+                partial,
+                "formatting of part of a line is dropped; MicroDVD styles whole lines".into(),
+            );
+            let rounded = count(cues, |cue| {
+                [cue.start_ms, cue.end_ms].iter().any(|&ms| {
+                    let back = (microdvd::frame(ms, fps) as f64 * 1000.0 / fps).round() as u64;
+                    back != ms
+                })
+            });
+            lost(
+                &mut losses,
+                "timing",
+                rounded,
+                format!("times are rounded to frames at {fps} fps"),
+            );
+            (text, Some(fps))
+        }
+        Format::SubViewer1 | Format::Mpl2 | Format::TmPlayer => {
+            return Err(SubtitleError::Unwritable(to));
+        }
+    };
+    if matches!(to, Format::Ass | Format::Ssa | Format::SubViewer2) {
+        let rounded = count(cues, |cue| cue.start_ms % 10 != 0 || cue.end_ms % 10 != 0);
+        lost(
+            &mut losses,
+            "timing",
+            rounded,
+            "times are cut to hundredths of a second".into(),
+        );
+    }
+    Ok((text, fps, losses))
+}
+
+/// Where a converted file goes by default: next to the source with the
+/// target's extension, or `.converted` added when that is the source.
+fn default_output(path: &Path, to: Format) -> PathBuf {
+    let output = path.with_extension(to.extension());
+    if output == path {
+        path.with_extension(format!("converted.{}", to.extension()))
+    } else {
+        output
+    }
+}
+
+/// Converts a subtitle file to `to`, written as UTF-8. MicroDVD frames are
+/// written at `fps`, else the frame rate probed from `video`, else the one
+/// the source was read at; a MicroDVD source is read at the rate it
+/// declares, if it does.
+pub fn convert_file(
+    path: &Path,
+    to: Format,
+    output: Option<&Path>,
+    from: Option<Format>,
+    fps: Option<f64>,
+    video: Option<&Path>,
+    language: Option<&str>,
+) -> Result<Converted, SubtitleError> {
+    let fps = match (fps, video) {
+        (Some(fps), _) => Some(fps),
+        (None, Some(video)) => media::probe::probe(video)?.fps,
+        (None, None) => None,
+    };
+    let parsed = parse_file(path, from, fps, language)?;
+    let output = output.map_or_else(|| default_output(path, to), Path::to_path_buf);
+    write_parsed(parsed, to, fps, output)
+}
+
+/// Writes a parsed subtitle to `output` as `to`; see [`convert`].
+pub(crate) fn write_parsed(
+    parsed: Parsed,
+    to: Format,
+    fps: Option<f64>,
+    output: PathBuf,
+) -> Result<Converted, SubtitleError> {
+    let Parsed {
+        subtitle,
+        encoding,
+        diagnostics,
+        errors,
+        warnings,
+    } = parsed;
+    let (text, written_fps, losses) = convert(&subtitle, to, fps)?;
+    write(&output, text.as_bytes())?;
+    Ok(Converted {
+        output,
+        from: subtitle.format,
+        to,
+        encoding,
+        cue_count: subtitle.cues.len(),
+        fps: written_fps.or(subtitle.fps),
+        losses,
+        diagnostics,
+        errors,
+        warnings,
+    })
+}
+
+/// Converts a subtitle file to another format, reporting what the target
+/// cannot hold.
+#[tauri::command]
+pub async fn convert_subtitle(
+    path: String,
+    to: Format,
+    output: Option<String>,
+    from: Option<Format>,
+    fps: Option<f64>,
+    video: Option<String>,
+    language: Option<String>,
+) -> Result<Converted, SubtitleError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        convert_file(
+            Path::new(&path),
+            to,
+            output.as_deref().map(Path::new),
+            from,
+            fps,
+            video.as_deref().map(Path::new),
+            language.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| SubtitleError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse::{parse_text, Diagnostics};
+
+    const SCRIPT: &str = "[Script Info]\nScriptType: v4.00+\n\n[V4+ Styles]\n\
+Format: Name, Fontname, Fontsize, Bold, Italic, Underline\n\
+Style: Default,Arial,20,0,0,0\nStyle: Sign,Arial,28,-1,0,0\n\n[Events]\n\
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hello\n\
+Dialogue: 0,0:00:03.00,0:00:04.00,*Default,,0,0,0,,there\n";
+
+    fn styles_lost(text: &str, to: Format) -> Option<usize> {
+        let subtitle = parse_text(text, None, None, &mut Diagnostics::default()).unwrap();
+        let (_, _, losses) = convert(&subtitle, to, None).unwrap();
+        losses.iter().find(|l| l.kind == "styles").map(|l| l.cues)
+    }
+
+    #[test]
+    fn styles_are_lost_only_where_used_and_dropped() {
+        assert_eq!(styles_lost(SCRIPT, Format::Srt), None);
+        let signed = format!("{SCRIPT}Dialogue: 0,0:00:05.00,0:00:06.00,Sign,,0,0,0,,EXIT\n");
+        assert_eq!(styles_lost(&signed, Format::Srt), Some(1));
+        assert_eq!(styles_lost(&signed, Format::Ssa), Some(1));
+        assert_eq!(styles_lost(&signed, Format::Ass), None);
+    }
+
+    /// Times and text of each cue, with formatting as SubRip tags.
+    fn cues(subtitle: &Subtitle) -> Vec<(u64, u64, String)> {
+        let cues = subtitle.cues.iter();
+        cues.map(|cue| {
+            let text = crate::subtitle::markup::to_tagged(&cue.spans, false);
+            (cue.start_ms, cue.end_ms, text)
+        })
+        .collect()
+    }
+
+    fn reparse(text: &str) -> Subtitle {
+        parse_text(text, None, None, &mut Diagnostics::default()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_through_every_writable_format() {
+        // Times on 25 fps frames, so MicroDVD keeps them too.
+        let source = reparse(
+            "1\n00:00:01,000 --> 00:00:02,520\n<i>Hello</i>\nfish & <b>chips</b>\n\n\
+             2\n01:00:03,040 --> 01:00:04,000\n<u>Bye</u>\n",
+        );
+        for to in [
+            Format::Srt,
+            Format::Vtt,
+            Format::Ass,
+            Format::Ssa,
+            Format::MicroDvd,
+            Format::SubViewer2,
+        ] {
+            let (text, fps, losses) = convert(&source, to, Some(25.0)).unwrap();
+            let written = reparse(&text);
+            assert_eq!(written.format, to);
+            let kinds: Vec<_> = losses.iter().map(|l| (l.kind, l.cues)).collect();
+            match to {
+                Format::SubViewer2 => {
+                    assert_eq!(kinds, [("formatting", 2)]);
+                    let plain: Vec<_> = source.cues.iter().map(|cue| &cue.text).collect();
+                    let written: Vec<_> = written.cues.iter().map(|cue| &cue.text).collect();
+                    assert_eq!(written, plain);
+                }
+                Format::MicroDvd => {
+                    assert_eq!(fps, Some(25.0));
+                    assert_eq!(written.fps, Some(25.0));
+                    // Bold is only on part of its line.
+                    assert_eq!(kinds, [("formatting", 1)]);
+                    let (text, _, _) = convert(&written, Format::Srt, None).unwrap();
+                    assert!(text.contains("<i>Hello</i>\r\nfish & chips"), "{text}");
+                }
+                _ => {
+                    assert_eq!(kinds, [], "{to:?}");
+                    assert_eq!(cues(&written), cues(&source), "{to:?}");
+                }
+            }
+            // Writing what was read back gives the same file.
+            let (again, _, _) = convert(&written, to, fps).unwrap();
+            assert_eq!(again, text, "{to:?}");
+        }
+        for to in [Format::SubViewer1, Format::Mpl2, Format::TmPlayer] {
+            let error = convert(&source, to, None).unwrap_err();
+            assert_eq!(error.kind(), "unwritable");
+        }
+    }
+
+    #[test]
+    fn keeps_the_header_of_the_same_format() {
+        let vtt = "WEBVTT - made by hand\n\nSTYLE\n::cue { color: yellow }\n\n\
+                   intro\n00:00:01.000 --> 00:00:02.000 align:start\nHello\n";
+        let signed = format!("{SCRIPT}Dialogue: 0,0:00:05.00,0:00:06.00,Sign,,0,0,0,,EXIT\n");
+        for text in [vtt, signed.as_str()] {
+            let source = reparse(text);
+            let (written, _, losses) = convert(&source, source.format, None).unwrap();
+            assert!(losses.is_empty());
+            let written = reparse(&written);
+            assert_eq!(written.header, source.header);
+            assert_eq!(cues(&written), cues(&source));
+            // Identifiers, settings and style names, which only the same
+            // format keeps.
+            let extras = |s: &Subtitle| {
+                let cues = s.cues.iter();
+                cues.map(|c| (c.id.clone(), c.settings.clone(), c.style.clone()))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(extras(&written), extras(&source));
+        }
+    }
+}
@@ -0,0 +1,824 @@
+//! Character-set detection and conversion to UTF-8.
+//!
+//! A byte-order mark settles the question, and a file that decodes as UTF-8
+//! is UTF-8. Anything else is decoded with each legacy charset in turn and
+//! the results are rated for plausibility: letters of the charset's script,
+//! the frequent letters of its languages, no control characters, no words
+//! that mix scripts or pile up accented letters. The subtitle's language,
+//! when known, favours the charsets used for it. Confidence reflects both
+//! how plausible the winner is and how far ahead of the runner-up it is.
+
+use super::{read, write, SubtitleError};
+use crate::languages;
+use encoding_rs::{DecoderResult, Encoding};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Bytes rated per candidate; subtitles rarely get near this.
+const SAMPLE: usize = 64 * 1024;
+
+/// Undecodable sequences listed individually; the rest are only counted.
+const MAX_LISTED: usize = 100;
+
+/// Confidence below which callers should ask before trusting the result.
+pub const LOW_CONFIDENCE: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Bengali,
+    Tamil,
+    Telugu,
+    Kannada,
+    Malayalam,
+    Thai,
+    Myanmar,
+    Georgian,
+    Khmer,
+    Han,
+    Kana,
+    Hangul,
+    Other,
+}
+
+/// Script of a non-ASCII character.
+pub(crate) fn script(c: char) -> Script {
+    match c as u32 {
+        0x00C0..=0x024F | 0x1E00..=0x1EFF => Script::Latin,
+        0x0400..=0x052F => Script::Cyrillic,
+        0x0370..=0x03FF | 0x1F00..=0x1FFF => Script::Greek,
+        0x0530..=0x058F => Script::Armenian,
+        0x0590..=0x05FF => Script::Hebrew,
+        0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0980..=0x09FF => Script::Bengali,
+        0x0B80..=0x0BFF => Script::Tamil,
+        0x0C00..=0x0C7F => Script::Telugu,
+        0x0C80..=0x0CFF => Script::Kannada,
+        0x0D00..=0x0D7F => Script::Malayalam,
+        0x0E00..=0x0E7F => Script::Thai,
+        0x1000..=0x109F => Script::Myanmar,
+        0x10A0..=0x10FF => Script::Georgian,
+        0x1780..=0x17FF => Script::Khmer,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF => Script::Han,
+        0x3040..=0x30FF => Script::Kana,
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+        _ => Script::Other,
+    }
+}
+
+struct Charset {
+    encoding: &'static Encoding,
+    script: Script,
+    /// Non-ASCII letters of the charset's languages, lowercase and most
+    /// frequent first.
+    letters: &'static str,
+    /// Letters that point to a sibling charset, e.g. simplified Chinese
+    /// characters in a Big5 candidate.
+    unlikely: &'static str,
+    /// `SubLanguageID`s the charset is used for.
+    languages: &'static [&'static str],
+    /// How common the charset is among subtitles; breaks near-ties.
+    prior: f64,
+}
+
+const WESTERN: &str = "éèàçêáíóúñãüöäâôîûùëïßõåæœ";
+const CENTRAL: &str = "áéíóúýčěřšžůąęłńśźżćőöüűăâîșțşţďťňľĺŕäôđëç";
+const BALTIC: &str = "āēīūšžčļņķģąęėįųõäöüå";
+const TURKISH: &str = "ıüşçğöâîİ";
+const VIETNAMESE: &str = "ưđôơêăâàáảãạèéẻẽẹìíỉĩịòóỏõọùúủũụỳýỷỹỵ";
+const CYRILLIC: &str = "оеаинтсрвлкмдпуяыьгзбчйхжшюцщэфъёіїєґўјљњћђџѓќѕ";
+const GREEK: &str = "αοετινσςρκπυλμηάέίόήύώδγχθφβωξζψϊϋΐΰ";
+const HEBREW: &str = "יוהאלמרבנתשעכדקסחפגצזטךםןףץ";
+const ARABIC: &str = "اليمنوهرتبعكدفقسحجشصطزخضذثظغةىأإآئءؤپچژگک";
+const THAI: &str = "านรอกเงมยลวดทสตะีิไบปคจพหขใัึุูโชแ็ผฟญถฉซฮธฐฒณภศษฆฌฝฤฦำ";
+/// Characters frequent in both simplified and traditional Chinese, and in
+/// Japanese.
+const HAN: &str = "的一是不了人我在有他中大上到和你地出道也年得就那要下以生自去之家可她小心多天而能好都然日起成事只作想看文手十用主行方又如前所本面公同三已老知什吧呢啊";
+pub(crate) const SIMPLIFIED: &str = "这们个来说时为会过对后么国学发经见现还没问长动开关门间听话东车书进实应写样头当给点让边体电吗钱爱谢里无从两着于";
+pub(crate) const TRADITIONAL: &str = "這們個來說時為會過對後麼國學發經見現還沒問長動開關門間聽話東車書進實應寫樣頭當給點讓邊體電嗎錢愛謝裡無從兩著於";
+const HANGUL: &str =
+    "이다는에의을가하고를지한서기로사니도나요리어아게까그해주내보시수면만거습있없것우네죠야";
+
+const WESTERN_CODES: &[&str] = &[
+    "eng", "fre", "ger", "spa", "spl", "ita", "por", "pob", "dut", "swe", "nor", "dan", "fin",
+    "ice", "cat", "baq", "glg", "ind", "may", "alb",
+];
+const CENTRAL_CODES: &[&str] = &[
+    "pol", "cze", "slo", "slv", "hun", "rum", "hrv", "scc", "bos", "alb",
+];
+const CYRILLIC_CODES: &[&str] = &["rus", "ukr", "bul", "mac", "scc"];
+
+macro_rules! charset {
+    ($encoding:ident, $script:ident, $letters:expr, $unlikely:expr, $languages:expr, $prior:expr) => {
+        Charset {
+            encoding: encoding_rs::$encoding,
+            script: Script::$script,
+            letters: $letters,
+            unlikely: $unlikely,
+            languages: $languages,
+            prior: $prior,
+        }
+    };
+}
+
+/// Legacy charsets considered when there is no BOM and the file is not
+/// UTF-8. ISO-8859-1 and -9 decode as windows-1252 and -1254, their
+/// supersets.
+const CHARSETS: &[Charset] = &[
+    charset!(WINDOWS_1252, Latin, WESTERN, "", WESTERN_CODES, 1.0),
+    charset!(ISO_8859_15, Latin, WESTERN, "", WESTERN_CODES, 0.9),
+    charset!(WINDOWS_1250, Latin, CENTRAL, "", CENTRAL_CODES, 0.95),
+    charset!(ISO_8859_2, Latin, CENTRAL, "", CENTRAL_CODES, 0.9),
+    charset!(ISO_8859_16, Latin, CENTRAL, "", &["rum"], 0.8),
+    charset!(WINDOWS_1254, Latin, TURKISH, "", &["tur"], 0.95),
+    charset!(WINDOWS_1257, Latin, BALTIC, "", &["est", "lav", "lit"], 0.9),
+    charset!(ISO_8859_13, Latin, BALTIC, "", &["est", "lav", "lit"], 0.85),
+    charset!(WINDOWS_1258, Latin, VIETNAMESE, "", &["vie"], 0.85),
+    charset!(WINDOWS_1251, Cyrillic, CYRILLIC, "", CYRILLIC_CODES, 1.0),
+    charset!(KOI8_R, Cyrillic, CYRILLIC, "", &["rus", "bul"], 0.9),
+    charset!(KOI8_U, Cyrillic, CYRILLIC, "", &["ukr"], 0.85),
+    charset!(ISO_8859_5, Cyrillic, CYRILLIC, "", CYRILLIC_CODES, 0.8),
+    charset!(IBM866, Cyrillic, CYRILLIC, "", &["rus"], 0.8),
+    charset!(WINDOWS_1253, Greek, GREEK, "", &["ell"], 1.0),
+    charset!(ISO_8859_7, Greek, GREEK, "", &["ell"], 0.9),
+    charset!(WINDOWS_1255, Hebrew, HEBREW, "", &["heb"], 1.0),
+    charset!(ISO_8859_8, Hebrew, HEBREW, "", &["heb"], 0.9),
+    charset!(WINDOWS_1256, Arabic, ARABIC, "", &["ara", "per"], 1.0),
+    charset!(ISO_8859_6, Arabic, ARABIC, "", &["ara"], 0.85),
+    charset!(WINDOWS_874, Thai, THAI, "", &["tha"], 1.0),
+    charset!(GB18030, Han, SIMPLIFIED, TRADITIONAL, &["chi"], 1.0),
+    charset!(BIG5, Han, TRADITIONAL, SIMPLIFIED, &["zht"], 1.0),
+    charset!(SHIFT_JIS, Kana, "", "", &["jpn"], 1.0),
+    charset!(EUC_JP, Kana, "", "", &["jpn"], 0.9),
+    charset!(EUC_KR, Hangul, HANGUL, "", &["kor"], 1.0),
+];
+
+/// A byte sequence with no meaning in the encoding it was decoded with.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Undecodable {
+    /// Byte offset in the file.
+    pub offset: usize,
+    pub length: usize,
+    /// 1-based line number.
+    pub line: usize,
+}
+
+/// Text decoded to UTF-8; undecodable sequences became U+FFFD.
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub text: String,
+    pub undecodable: Vec<Undecodable>,
+    pub undecodable_count: usize,
+}
+
+impl Decoded {
+    fn note(&mut self, offset: usize, length: usize, line: usize) {
+        self.undecodable_count += 1;
+        if self.undecodable.len() < MAX_LISTED {
+            self.undecodable.push(Undecodable {
+                offset,
+                length,
+                line,
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alternative {
+    pub encoding: &'static str,
+    /// How plausible the file reads this way, language hint included.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Detection {
+    /// WHATWG name: `UTF-8`, `windows-1251`, `Shift_JIS`, ...
+    pub encoding: &'static str,
+    /// 0 to 1.
+    pub confidence: f64,
+    pub bom: bool,
+    /// Only ASCII bytes, which every candidate reads the same way.
+    pub ascii: bool,
+    /// Next best legacy charsets that read the file differently, best first.
+    pub alternatives: Vec<Alternative>,
+    /// Sequences the detected encoding cannot decode.
+    pub undecodable: Vec<Undecodable>,
+    pub undecodable_count: usize,
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Resolves an encoding label such as `cp1251`, `latin2` or `sjis`.
+pub fn lookup(label: &str) -> Result<&'static Encoding, SubtitleError> {
+    Encoding::for_label(label.trim().as_bytes())
+        .filter(|e| *e != encoding_rs::REPLACEMENT)
+        .ok_or_else(|| SubtitleError::UnknownEncoding(label.into()))
+}
+
+/// Rejects the UTF-32 BOMs, which encoding_rs would take for UTF-16LE.
+fn check_utf32(bytes: &[u8]) -> Result<(), SubtitleError> {
+    if bytes.starts_with(&[0xFF, 0xFE, 0, 0]) || bytes.starts_with(&[0, 0, 0xFE, 0xFF]) {
+        return Err(SubtitleError::UnsupportedEncoding("UTF-32"));
+    }
+    Ok(())
+}
+
+/// Decodes `bytes[skip..]`; reported offsets are into `bytes`. C1 control
+/// characters from single-byte charsets mean the byte is unassigned and are
+/// reported and replaced like malformed sequences.
+fn decode_from(bytes: &[u8], skip: usize, encoding: &'static Encoding) -> Decoded {
+    let input = &bytes[skip..];
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = |len| decoder_capacity(encoding, len);
+    let mut decoded = Decoded {
+        text: String::with_capacity(capacity(input.len())),
+        undecodable: Vec::new(),
+        undecodable_count: 0,
+    };
+    let (mut read, mut line, mut counted) = (0, 1, 0);
+    loop {
+        let (result, n) =
+            decoder.decode_to_string_without_replacement(&input[read..], &mut decoded.text, true);
+        read += n;
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::OutputFull => decoded.text.reserve(capacity(input.len() - read)),
+            DecoderResult::Malformed(bad, extra) => {
+                line += decoded.text[counted..].matches('\n').count();
+                let end = read - extra as usize;
+                let start = end.saturating_sub(bad as usize);
+                decoded.note(skip + start, bad as usize, line);
+                decoded.text.push(char::REPLACEMENT_CHARACTER);
+                counted = decoded.text.len();
+            }
+        }
+    }
+    if encoding.is_single_byte() && decoded.text.chars().any(is_c1) {
+        // One character per byte, so character i came from byte i.
+        let text = std::mem::take(&mut decoded.text);
+        let mut line = 1;
+        for (i, c) in text.chars().enumerate() {
+            if c == '\n' {
+                line += 1;
+            }
+            if is_c1(c) {
+                decoded.note(skip + i, 1, line);
+                decoded.text.push(char::REPLACEMENT_CHARACTER);
+            } else {
+                decoded.text.push(c);
+            }
+        }
+    }
+    decoded
+}
+
+fn decoder_capacity(encoding: &'static Encoding, len: usize) -> usize {
+    encoding
+        .new_decoder_without_bom_handling()
+        .max_utf8_buffer_length_without_replacement(len)
+        .unwrap_or(len.saturating_mul(3))
+}
+
+fn is_c1(c: char) -> bool {
+    ('\u{80}'..='\u{9F}').contains(&c)
+}
+
+/// Decodes `bytes` as `encoding`, skipping its BOM if there is one.
+pub fn decode_as(bytes: &[u8], encoding: &'static Encoding) -> Decoded {
+    let skip = match Encoding::for_bom(bytes) {
+        Some((bom, len)) if bom == encoding => len,
+        _ => 0,
+    };
+    decode_from(bytes, skip, encoding)
+}
+
+/// UTF-16 without a BOM: most code units of subtitle text are ASCII, so one
+/// byte of each pair is nearly always zero.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    if sample.len() < 16 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let zeros = |parity| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    if odd * 10 >= pairs * 4 && even * 20 < pairs {
+        Some(encoding_rs::UTF_16LE)
+    } else if even * 10 >= pairs * 4 && odd * 20 < pairs {
+        Some(encoding_rs::UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Greek and Hebrew letters written only at the end of a word.
+const FINAL: &str = "ςךםןףץ";
+
+/// Letters of the word being scored.
+#[derive(Default)]
+struct Word {
+    script: Option<Script>,
+    mixed: bool,
+    /// Accented Latin letters in a row.
+    run: usize,
+    last: Option<char>,
+}
+
+impl Word {
+    /// Adds a letter, returning the penalty it earns.
+    fn push(&mut self, c: char, letter_script: Script) -> f64 {
+        let mut penalty = 0.0;
+        match self.script {
+            None => self.script = Some(letter_script),
+            Some(script) if script != letter_script => self.mixed = true,
+            Some(_) => {}
+        }
+        if letter_script == Script::Latin && !c.is_ascii() {
+            self.run += 1;
+            if self.run > 2 {
+                penalty += 1.2;
+            }
+        } else {
+            self.run = 0;
+        }
+        match self.last {
+            // Capitals inside a word, outside of ASCII names like McCoy.
+            Some(last)
+                if c.is_uppercase()
+                    && last.is_lowercase()
+                    && !(c.is_ascii() && last.is_ascii()) =>
+            {
+                penalty += 1.0
+            }
+            // Final forms only end words.
+            Some(last) if FINAL.contains(last) => penalty += 1.5,
+            // No Cyrillic word starts with a hard or soft sign, or with ы.
+            None if "ъьыЪЬЫ".contains(c) => penalty += 1.5,
+            _ => {}
+        }
+        self.last = Some(c);
+        penalty
+    }
+
+    /// Ends the word, returning the penalty for mixing scripts, or a bonus
+    /// for a final form in its place.
+    fn end(&mut self) -> f64 {
+        let (mixed, last) = (self.mixed, self.last);
+        *self = Self::default();
+        if mixed {
+            2.0
+        } else if last.is_some_and(|c| FINAL.contains(c)) {
+            -0.5
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Rates how much `text` reads like the languages of `charset`: about 1 per
+/// non-ASCII character when each is a frequent letter, negative for garbage.
+fn plausibility(text: &str, charset: &Charset) -> f64 {
+    // Words are only told apart in alphabetic scripts.
+    let alphabetic = !matches!(charset.script, Script::Han | Script::Kana | Script::Hangul);
+    let (mut points, mut counted) = (0.0, 0usize);
+    let mut word = Word::default();
+    let mut previous = ' ';
+    for c in text.chars() {
+        let letter_script = match c {
+            'a'..='z' | 'A'..='Z' => Script::Latin,
+            _ => script(c),
+        };
+        // A lead byte that swallowed an ASCII letter as its trail byte
+        // leaves ideographs stuck to Latin words.
+        if !alphabetic && (c.is_ascii_alphabetic() != previous.is_ascii_alphabetic()) {
+            let other = if c.is_ascii() { previous } else { c };
+            if matches!(script(other), Script::Han | Script::Kana | Script::Hangul) {
+                points -= 0.75;
+            }
+        }
+        previous = c;
+        if c.is_alphabetic() {
+            if !c.is_ascii() {
+                counted += 1;
+                points += letter_weight(c, letter_script, charset);
+            }
+            if alphabetic {
+                points -= word.push(c, letter_script);
+            }
+            continue;
+        }
+        // Combining accents, and vowel or tone marks of the charset's script.
+        if ('\u{300}'..='\u{36F}').contains(&c) || letter_script == charset.script {
+            counted += 1;
+            points += 0.3;
+            continue;
+        }
+        if alphabetic {
+            points -= word.end();
+        }
+        if c.is_ascii() {
+            continue;
+        }
+        counted += 1;
+        points += match c {
+            char::REPLACEMENT_CHARACTER => -3.0,
+            c if c.is_control() => -2.0,
+            '«' | '»' | '„' | '“' | '”' | '‘' | '’' | '‚' | '–' | '—' | '…' | '•' | '·' | '°'
+            | '♪' | '♫' | '¡' | '¿' | '\u{A0}' => 0.5,
+            '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF65}' if !alphabetic => 0.8,
+            _ => -0.5,
+        };
+    }
+    if alphabetic {
+        points -= word.end();
+    }
+    if counted == 0 {
+        0.0
+    } else {
+        points / counted as f64
+    }
+}
+
+fn letter_weight(c: char, letter_script: Script, charset: &Charset) -> f64 {
+    let common = |c: char| charset.letters.contains(c) || HAN.contains(c);
+    match (charset.script, letter_script) {
+        (Script::Han, Script::Han) if charset.unlikely.contains(c) => 0.2,
+        (Script::Han | Script::Kana, Script::Han) if common(c) => 1.0,
+        (Script::Han | Script::Kana, Script::Han) => 0.6,
+        // Hiragana, then katakana.
+        (Script::Kana, Script::Kana) if c < '\u{30A0}' => 1.0,
+        (Script::Kana, Script::Kana) => 0.8,
+        (Script::Hangul, Script::Hangul) if charset.letters.contains(c) => 1.0,
+        (Script::Hangul, Script::Hangul) => 0.6,
+        (Script::Han | Script::Hangul, Script::Han | Script::Kana) => 0.3,
+        (Script::Han | Script::Kana | Script::Hangul, _) => 0.1,
+        (expected, actual) if expected == actual => {
+            // From 1 for the most frequent letter down to 0.4 for the least.
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            let rank = charset.letters.chars().position(|l| l == c || l == lower);
+            let count = charset.letters.chars().count() as f64;
+            let weight = rank.map_or(0.3, |rank| 1.0 - 0.6 * rank as f64 / count);
+            if c.is_uppercase() {
+                weight * 0.8
+            } else {
+                weight
+            }
+        }
+        // French letters in windows-1256 and the like.
+        (_, Script::Latin) => 0.3,
+        _ => 0.1,
+    }
+}
+
+/// Detects the encoding of `bytes` and decodes them. `language` is a hint
+/// in any form [`languages::lookup`] understands.
+pub fn detect_and_decode(
+    bytes: &[u8],
+    language: Option<&str>,
+) -> Result<(Detection, Decoded), SubtitleError> {
+    check_utf32(bytes)?;
+    let known = |encoding: &'static Encoding, skip, bom, confidence| {
+        let decoded = decode_from(bytes, skip, encoding);
+        let detection = Detection {
+            encoding: encoding.name(),
+            confidence,
+            bom,
+            ascii: false,
+            alternatives: Vec::new(),
+            undecodable: decoded.undecodable.clone(),
+            undecodable_count: decoded.undecodable_count,
+        };
+        (detection, decoded)
+    };
+    if let Some((encoding, skip)) = Encoding::for_bom(bytes) {
+        return Ok(known(encoding, skip, true, 1.0));
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return Ok(known(encoding, 0, false, 0.9));
+    }
+    if bytes.is_ascii() {
+        let (mut detection, decoded) = known(encoding_rs::UTF_8, 0, false, 1.0);
+        detection.ascii = true;
+        return Ok((detection, decoded));
+    }
+    let utf8 = decode_from(bytes, 0, encoding_rs::UTF_8);
+    if utf8.undecodable_count == 0 {
+        let (detection, _) = known(encoding_rs::UTF_8, 0, false, 0.99);
+        return Ok((detection, utf8));
+    }
+    // Mostly UTF-8 with a few stray bytes, e.g. two files pasted together:
+    // still UTF-8, with the bad bytes reported.
+    let valid = utf8.text.chars().filter(|c| !c.is_ascii()).count() - utf8.undecodable_count;
+    if valid >= 4 * utf8.undecodable_count {
+        let share = valid as f64 / (valid + utf8.undecodable_count) as f64;
+        let (mut detection, _) = known(encoding_rs::UTF_8, 0, false, round(share * 0.9));
+        detection.undecodable = utf8.undecodable.clone();
+        detection.undecodable_count = utf8.undecodable_count;
+        return Ok((detection, utf8));
+    }
+
+    let hint = language.and_then(languages::sub_language_id);
+    let sample = &bytes[..bytes.len().min(SAMPLE)];
+    let mut scored: Vec<(f64, &Charset, String)> = CHARSETS
+        .iter()
+        .map(|charset| {
+            let text = decode_from(sample, 0, charset.encoding).text;
+            let mut score = plausibility(&text, charset) * charset.prior;
+            match hint {
+                Some(hint) if charset.languages.contains(&hint) => score *= 1.3,
+                Some(_) => score *= 0.85,
+                None => {}
+            }
+            (score, charset, text)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let (best, charset, best_text) = &scored[0];
+    // Charsets that read the file exactly like the winner are no competition.
+    let rivals: Vec<_> = scored[1..]
+        .iter()
+        .filter(|(_, _, text)| text != best_text)
+        .collect();
+    let runner_up = rivals.first().map_or(0.0, |(score, _, _)| *score);
+    let margin = if *best > 0.0 && runner_up > 0.0 {
+        ((best - runner_up) / best).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let confidence = round(best.clamp(0.0, 1.0) * (0.5 + 0.5 * margin));
+    let (mut detection, decoded) = known(charset.encoding, 0, false, confidence);
+    detection.alternatives = rivals
+        .iter()
+        .take(3)
+        .map(|(score, charset, _)| Alternative {
+            encoding: charset.encoding.name(),
+            score: round(score.max(0.0)),
+        })
+        .collect();
+    Ok((detection, decoded))
+}
+
+pub fn detect(bytes: &[u8], language: Option<&str>) -> Result<Detection, SubtitleError> {
+    detect_and_decode(bytes, language).map(|(detection, _)| detection)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcoded {
+    pub output: PathBuf,
+    /// Encoding the file was read as.
+    pub encoding: &'static str,
+    /// Set when the encoding was detected rather than given.
+    pub detection: Option<Detection>,
+    pub undecodable: Vec<Undecodable>,
+    pub undecodable_count: usize,
+}
+
+/// Writes `path` as UTF-8 without a BOM to `output`, which may be `path`
+/// itself. The source encoding is detected unless `encoding` names it.
+pub fn transcode(
+    path: &Path,
+    output: &Path,
+    encoding: Option<&str>,
+    language: Option<&str>,
+) -> Result<Transcoded, SubtitleError> {
+    let bytes = read(path)?;
+    let (name, detection, decoded) = match encoding {
+        Some(label) => {
+            check_utf32(&bytes)?;
+            let encoding = lookup(label)?;
+            (encoding.name(), None, decode_as(&bytes, encoding))
+        }
+        None => {
+            let (detection, decoded) = detect_and_decode(&bytes, language)?;
+            (detection.encoding, Some(detection), decoded)
+        }
+    };
+    write(output, decoded.text.as_bytes())?;
+    Ok(Transcoded {
+        output: output.to_path_buf(),
+        encoding: name,
+        detection,
+        undecodable: decoded.undecodable,
+        undecodable_count: decoded.undecodable_count,
+    })
+}
+
+#[tauri::command]
+pub async fn detect_subtitle_encoding(
+    path: String,
+    language: Option<String>,
+) -> Result<Detection, SubtitleError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        detect(&read(Path::new(&path))?, language.as_deref())
+    })
+    .await
+    .map_err(|e| SubtitleError::Task(e.to_string()))?
+}
+
+/// Converts a subtitle to UTF-8, detecting its encoding unless `encoding`
+/// is given.
+#[tauri::command]
+pub async fn transcode_subtitle(
+    path: String,
+    output: String,
+    encoding: Option<String>,
+    language: Option<String>,
+) -> Result<Transcoded, SubtitleError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        transcode(
+            Path::new(&path),
+            Path::new(&output),
+            encoding.as_deref(),
+            language.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| SubtitleError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUE: &str = "1\n00:00:01,000 --> 00:00:02,000\n";
+
+    /// `text` as one cue, encoded with `encoding`.
+    fn encoded(text: &str, encoding: &'static Encoding) -> Vec<u8> {
+        let text = format!("{CUE}{text}\n");
+        let (bytes, _, unmappable) = encoding.encode(&text);
+        assert!(!unmappable, "{text:?} in {}", encoding.name());
+        bytes.into_owned()
+    }
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match big_endian {
+                true => unit.to_be_bytes(),
+                false => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn strips_byte_order_marks() {
+        let text = format!("{CUE}Grüße, Ελλάδα\n");
+        let cases = [
+            ("UTF-8", [&[0xEF, 0xBB, 0xBF][..], text.as_bytes()].concat()),
+            (
+                "UTF-16LE",
+                [&[0xFF, 0xFE][..], &utf16(&text, false)].concat(),
+            ),
+            (
+                "UTF-16BE",
+                [&[0xFE, 0xFF][..], &utf16(&text, true)].concat(),
+            ),
+        ];
+        for (name, bytes) in cases {
+            let (detection, decoded) = detect_and_decode(&bytes, None).unwrap();
+            assert_eq!(detection.encoding, name);
+            assert!(detection.bom, "{name}");
+            assert_eq!(detection.confidence, 1.0);
+            assert_eq!(decoded.text, text, "{name}");
+            // Decoding as the named encoding drops the BOM too.
+            assert_eq!(decode_as(&bytes, lookup(name).unwrap()).text, text);
+        }
+        let error = detect(&[0xFF, 0xFE, 0, 0, b'1', 0, 0, 0], None).unwrap_err();
+        assert_eq!(error.kind(), "unsupportedEncoding");
+    }
+
+    #[test]
+    fn sniffs_utf16_without_a_byte_order_mark() {
+        let text = format!("{CUE}Привет, как дела?\n");
+        for (name, big_endian) in [("UTF-16LE", false), ("UTF-16BE", true)] {
+            let (detection, decoded) = detect_and_decode(&utf16(&text, big_endian), None).unwrap();
+            assert_eq!((detection.encoding, detection.bom), (name, false));
+            assert_eq!(decoded.text, text);
+        }
+    }
+
+    #[test]
+    fn tells_legacy_charsets_apart() {
+        let polish = "Zażółć gęślą jaźń. Gdzie jesteś? Źle się czuję, muszę iść.";
+        let russian = "Привет! Где ты был всё это время? Я тебя искала весь день.";
+        let arabic = "مرحبا، أين كنت طوال هذا الوقت؟ لقد بحثت عنك في كل مكان.";
+        let cases = [
+            (polish, encoding_rs::WINDOWS_1250, "pol"),
+            (polish, encoding_rs::ISO_8859_2, "pol"),
+            (russian, encoding_rs::WINDOWS_1251, "rus"),
+            (russian, encoding_rs::KOI8_R, "rus"),
+            (russian, encoding_rs::ISO_8859_5, "rus"),
+            (arabic, encoding_rs::WINDOWS_1256, "ara"),
+            (arabic, encoding_rs::ISO_8859_6, "ara"),
+            (
+                "我们现在就走吧，这里没有时间了。你说得对。",
+                encoding_rs::GB18030,
+                "chi",
+            ),
+            (
+                "我們現在就走吧，這裡沒有時間了。你說得對。",
+                encoding_rs::BIG5,
+                "zht",
+            ),
+            (
+                "私たちは今すぐ行かなければならない。ここには時間がない。",
+                encoding_rs::SHIFT_JIS,
+                "jpn",
+            ),
+        ];
+        for (text, encoding, language) in cases {
+            let bytes = encoded(text, encoding);
+            let (detection, decoded) = detect_and_decode(&bytes, None).unwrap();
+            assert_eq!(detection.encoding, encoding.name(), "no hint");
+            assert_eq!(decoded.text, format!("{CUE}{text}\n"));
+            // The language hint favours the right charset further.
+            let hinted = detect(&bytes, Some(language)).unwrap();
+            assert_eq!(hinted.encoding, encoding.name(), "{language}");
+            assert!(
+                hinted.confidence > detection.confidence,
+                "{language}: {} after {}",
+                hinted.confidence,
+                detection.confidence
+            );
+        }
+    }
+
+    #[test]
+    fn reports_where_bytes_do_not_decode() {
+        // Mostly UTF-8, with a stray Latin-1 byte on line 3.
+        let mut bytes = format!("{CUE}Ça va très bien, merci. Déjà vu.\n").into_bytes();
+        let offset = bytes.len() - 3;
+        bytes.insert(offset, 0xE9);
+        let (detection, decoded) = detect_and_decode(&bytes, None).unwrap();
+        assert_eq!(detection.encoding, "UTF-8");
+        assert!(detection.confidence < 0.99);
+        assert_eq!(detection.undecodable_count, 1);
+        let bad = &detection.undecodable[0];
+        assert_eq!((bad.offset, bad.length, bad.line), (offset, 1, 3));
+        assert!(decoded.text.contains("v\u{FFFD}u."), "{:?}", decoded.text);
+
+        // Bytes a single-byte charset leaves unassigned count as well.
+        let decoded = decode_as(b"ok\nnot \x81 ok\n\x8d", encoding_rs::WINDOWS_1252);
+        assert_eq!(decoded.text, "ok\nnot \u{FFFD} ok\n\u{FFFD}");
+        let found: Vec<_> = decoded
+            .undecodable
+            .iter()
+            .map(|u| (u.offset, u.length, u.line))
+            .collect();
+        assert_eq!(found, [(7, 1, 2), (12, 1, 3)]);
+    }
+
+    #[test]
+    fn transcodes_to_utf8() {
+        let dir = std::env::temp_dir().join(format!("{}-transcode", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("in.srt"), dir.join("out.srt"));
+        let text = format!("{CUE}Привет! Где ты был всё это время?\n");
+        std::fs::write(
+            &input,
+            encoded(
+                "Привет! Где ты был всё это время?",
+                encoding_rs::WINDOWS_1251,
+            ),
+        )
+        .unwrap();
+
+        let transcoded = transcode(&input, &output, None, Some("ru")).unwrap();
+        assert_eq!(transcoded.encoding, "windows-1251");
+        assert!(transcoded.detection.is_some());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), text);
+
+        // In place, with the encoding given.
+        let transcoded = transcode(&input, &input, Some("cp1251"), None).unwrap();
+        assert!(transcoded.detection.is_none());
+        assert_eq!(std::fs::read(&input).unwrap(), text.as_bytes());
+        // Now it is UTF-8, and converting again changes nothing.
+        transcode(&input, &input, None, None).unwrap();
+        assert_eq!(std::fs::read(&input).unwrap(), text.as_bytes());
+
+        let error = transcode(&input, &output, Some("klingon"), None).unwrap_err();
+        assert_eq!(error.kind(), "unknownEncoding");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
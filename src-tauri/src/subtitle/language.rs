@@ -0,0 +1,1454 @@
+//! Language identification without the API, so subtitles still get a
+//! language when `/utilities/fasttext/language/detect/file` is slow,
+//! rate-limited or out of reach.
+//!
+//! A script only one of the languages is written in settles it: Hangul is
+//! Korean, kana Japanese, Greek letters Greek. Devanagari, Bengali and Hebrew
+//! letters point at Hindi, Bengali and Hebrew too, but Marathi, Nepali,
+//! Assamese, Manipuri and Yiddish share them, so those guesses are left for
+//! the API to confirm. Latin, Cyrillic and Arabic text is cut into letter trigrams,
+//! ranked by frequency and compared with the ranked trigrams of each language
+//! written that way; the language whose ranking is least out of place wins.
+//! Confidence grows with the length of the text and the lead over the
+//! runner-up.
+//!
+//! Regional variants read the same: Brazilian and Mozambican Portuguese are
+//! `por`, European and Latin American Spanish `spa`, and Cantonese and
+//! bilingual Chinese `chi` or `zht`, as the API reports them, so `pob`,
+//! `pom`, `spn`, `spl`, `zhc` and `zhe` are never identified here.
+//!
+//! Nor are the languages without a profile, such as Kurdish, Dari, Mongolian,
+//! Breton or Toki Pona. Text in a script not read here (Odia, Sinhalese,
+//! Syriac, Ge'ez, Ol Chiki) comes out unidentified and is left to the API;
+//! text in one shared with profiled languages may come out as the nearest
+//! of them.
+
+use super::encoding::{self, script, Script, SIMPLIFIED, TRADITIONAL};
+use super::parse::{parse_text, Diagnostics};
+use super::{read, SubtitleError};
+use crate::api::rest::{LanguageDetection, RestClient};
+use crate::languages::{self, Language};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Confidence at which the offline guess is used without asking the API.
+pub const HIGH_CONFIDENCE: f64 = 0.8;
+
+/// Used when the API does not report a score.
+const SERVER_DEFAULT: f64 = 0.8;
+
+/// Fewer letters than this say too little to go on.
+const MIN_LETTERS: usize = 20;
+
+/// Trigrams of the text compared; each language profile ranks as many.
+const TRIGRAMS: usize = 300;
+
+/// Runners-up listed.
+const MAX_ALTERNATIVES: usize = 3;
+
+struct Profile {
+    code: &'static str,
+    script: Script,
+    /// Most frequent first, space-separated; `_` marks the edge of a word.
+    trigrams: &'static str,
+}
+
+macro_rules! profile {
+    ($code:literal, $script:ident, $trigrams:literal) => {
+        Profile {
+            code: $code,
+            script: Script::$script,
+            trigrams: $trigrams,
+        }
+    };
+}
+
+/// Scripts that belong to one language here.
+const SCRIPT_LANGUAGES: &[(Script, &str)] = &[
+    (Script::Greek, "ell"),
+    (Script::Armenian, "arm"),
+    (Script::Hebrew, "heb"),
+    (Script::Devanagari, "hin"),
+    (Script::Bengali, "ben"),
+    (Script::Tamil, "tam"),
+    (Script::Telugu, "tel"),
+    (Script::Kannada, "kan"),
+    (Script::Malayalam, "mal"),
+    (Script::Thai, "tha"),
+    (Script::Myanmar, "bur"),
+    (Script::Georgian, "geo"),
+    (Script::Khmer, "khm"),
+    (Script::Hangul, "kor"),
+];
+
+/// Scripts in [`SCRIPT_LANGUAGES`] that other languages, with no profile
+/// here or not offered at all, are written in as well.
+const SHARED_SCRIPTS: &[Script] = &[Script::Devanagari, Script::Bengali, Script::Hebrew];
+
+/// Highest confidence of a guess from a shared script, short of
+/// [`HIGH_CONFIDENCE`] so the API is asked.
+const SHARED_SCRIPT_CONFIDENCE: f64 = 0.6;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alternative {
+    pub code: &'static str,
+    /// How well the text matches the language, 0 to 1.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identification {
+    pub language: &'static Language,
+    /// 0 to 1.
+    pub confidence: f64,
+    /// Next best languages of the same script, best first.
+    pub alternatives: Vec<Alternative>,
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Script of a letter, or of a vowel sign or tone mark within a word.
+fn letter_script(c: char) -> Option<Script> {
+    match c {
+        'a'..='z' | 'A'..='Z' => Some(Script::Latin),
+        c if c.is_ascii() => None,
+        c => match script(c) {
+            Script::Other => None,
+            Script::Latin if !c.is_alphabetic() => None,
+            script => Some(script),
+        },
+    }
+}
+
+/// Letters counted per script, most used first.
+fn scripts(text: &str) -> Vec<(Script, usize)> {
+    let mut counts: HashMap<Script, usize> = HashMap::new();
+    for script in text.chars().filter_map(letter_script) {
+        *counts.entry(script).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    counts
+}
+
+/// Trigrams of the words of `text` written in `script`, most frequent
+/// first; ties in alphabetical order so profiles come out the same.
+pub(crate) fn trigrams(text: &str, script: Script) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut word = vec!['_'];
+    for c in text.chars().chain([' ']) {
+        if letter_script(c) == Some(script) {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+        if word.len() > 1 {
+            word.push('_');
+            for trigram in word.windows(3) {
+                *counts.entry(trigram.iter().collect()).or_default() += 1;
+            }
+            word.truncate(1);
+        }
+    }
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.into_iter().map(|(trigram, _)| trigram).collect()
+}
+
+/// How far the text's ranking is from the profile's, 0 for the same order
+/// and 1 for nothing in common.
+fn distance(text: &[String], profile: &Profile) -> f64 {
+    let ranks: HashMap<&str, usize> = profile
+        .trigrams
+        .split(' ')
+        .enumerate()
+        .map(|(rank, trigram)| (trigram, rank))
+        .collect();
+    let total: usize = text
+        .iter()
+        .enumerate()
+        .map(|(i, trigram)| {
+            ranks
+                .get(trigram.as_str())
+                .map_or(TRIGRAMS, |&j| i.abs_diff(j))
+        })
+        .sum();
+    total as f64 / (text.len() * TRIGRAMS) as f64
+}
+
+fn language(code: &str) -> &'static Language {
+    languages::lookup(code, false).expect("identified languages are in LANGUAGES")
+}
+
+/// Identifies the language of `text`; `None` when there is too little of it
+/// or its script is not one a known language is written in.
+pub fn identify(text: &str) -> Option<Identification> {
+    let scripts = scripts(text);
+    let letters: usize = scripts.iter().map(|(_, count)| count).sum();
+    let &(main, _) = scripts.first()?;
+    if letters < MIN_LETTERS {
+        return None;
+    }
+    let count = |script| {
+        scripts
+            .iter()
+            .find(|(s, _)| *s == script)
+            .map_or(0, |(_, count)| *count)
+    };
+    let share = |count: usize| count as f64 / letters as f64;
+    let settled = |code, confidence| {
+        Some(Identification {
+            language: language(code),
+            confidence: round(confidence),
+            alternatives: Vec::new(),
+        })
+    };
+
+    let kana = count(Script::Kana);
+    match main {
+        // Japanese mixes kanji with kana; Chinese has none.
+        Script::Han | Script::Kana if kana * 10 >= letters => {
+            settled("jpn", share(kana + count(Script::Han)))
+        }
+        Script::Han => {
+            let (simplified, traditional) = text.chars().fold((0, 0), |(s, t), c| {
+                (
+                    s + usize::from(SIMPLIFIED.contains(c)),
+                    t + usize::from(TRADITIONAL.contains(c)),
+                )
+            });
+            let code = if traditional > simplified {
+                "zht"
+            } else {
+                "chi"
+            };
+            settled(code, share(count(Script::Han)))
+        }
+        _ => {
+            if let Some((_, code)) = SCRIPT_LANGUAGES.iter().find(|(s, _)| *s == main) {
+                let mut confidence = share(count(main));
+                if SHARED_SCRIPTS.contains(&main) {
+                    confidence = confidence.min(SHARED_SCRIPT_CONFIDENCE);
+                }
+                return settled(code, confidence);
+            }
+            let text = trigrams(text, main);
+            let text = &text[..text.len().min(TRIGRAMS)];
+            let mut scored: Vec<(f64, &Profile)> = PROFILES
+                .iter()
+                .filter(|profile| profile.script == main)
+                .map(|profile| (1.0 - distance(text, profile), profile))
+                .collect();
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            let &(best, profile) = scored.first()?;
+            let runner_up = scored.get(1).map_or(0.0, |(score, _)| *score);
+            // A line or two of dialogue is not much to go on, and languages
+            // as close as Croatian and Serbian end up only slightly apart.
+            let length = (text.len() as f64 / TRIGRAMS as f64).sqrt();
+            let lead = ((best - runner_up) / best * 8.0).min(1.0);
+            Some(Identification {
+                language: language(profile.code),
+                confidence: round(share(count(main)) * length * lead),
+                alternatives: scored[1..]
+                    .iter()
+                    .take(MAX_ALTERNATIVES)
+                    .map(|(score, profile)| Alternative {
+                        code: profile.code,
+                        score: round(*score),
+                    })
+                    .collect(),
+            })
+        }
+    }
+}
+
+/// Identifies the language of a subtitle file's text: the cues when the
+/// format is recognised, else all of it.
+pub fn identify_subtitle(bytes: &[u8]) -> Option<Identification> {
+    let (_, decoded) = encoding::detect_and_decode(bytes, None).ok()?;
+    let mut diagnostics = Diagnostics::default();
+    match parse_text(&decoded.text, None, None, &mut diagnostics) {
+        Some(subtitle) => {
+            let text: Vec<&str> = subtitle.cues.iter().map(|cue| cue.text.as_str()).collect();
+            identify(&text.join("\n"))
+        }
+        None => identify(&decoded.text),
+    }
+}
+
+/// Who decided on the language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Decision {
+    /// Identified offline with high confidence; the API was not asked.
+    Local,
+    /// Language detection on the server.
+    Server,
+    /// Identified offline after the API failed or could not tell.
+    LocalFallback,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageDecision {
+    /// `SubLanguageID`; `None` when neither could tell.
+    pub language: Option<String>,
+    pub confidence: f64,
+    pub source: Option<Decision>,
+    pub local: Option<Identification>,
+    pub server: Option<LanguageDetection>,
+    /// Why the API could not be used, e.g. a timeout or rate limit.
+    pub server_error: Option<String>,
+}
+
+/// Decides on the language of the subtitle at `path`: `local` when it is
+/// sure, else the API, else `local` whatever its confidence.
+pub async fn decide(
+    rest: &RestClient,
+    path: &Path,
+    local: Option<Identification>,
+) -> LanguageDecision {
+    let mut decision = LanguageDecision {
+        language: None,
+        confidence: 0.0,
+        source: None,
+        local,
+        server: None,
+        server_error: None,
+    };
+    if let Some(local) = decision
+        .local
+        .as_ref()
+        .filter(|local| local.confidence >= HIGH_CONFIDENCE)
+    {
+        decision.language = Some(local.language.code.to_string());
+        decision.confidence = local.confidence;
+        decision.source = Some(Decision::Local);
+        return decision;
+    }
+    match rest.detect_language(path).await {
+        Ok(detection) => {
+            let code = detection
+                .iso639_2
+                .clone()
+                .or(detection.language_code.clone());
+            if let Some(code) = code {
+                decision.language =
+                    Some(languages::sub_language_id(&code).map_or(code, |id| id.to_string()));
+                // Some responses give a percentage rather than a fraction.
+                decision.confidence = detection
+                    .confidence
+                    .map(|c| if c > 1.0 { c / 100.0 } else { c })
+                    .unwrap_or(SERVER_DEFAULT)
+                    .clamp(0.0, 1.0);
+                decision.source = Some(Decision::Server);
+            }
+            decision.server = Some(detection);
+        }
+        Err(e) => decision.server_error = Some(e.to_string()),
+    }
+    if decision.source.is_none() {
+        if let Some(local) = &decision.local {
+            decision.language = Some(local.language.code.to_string());
+            decision.confidence = local.confidence;
+            decision.source = Some(Decision::LocalFallback);
+        }
+    }
+    decision
+}
+
+/// Detects the language of a subtitle, offline when the text leaves no
+/// doubt and through the API otherwise, falling back to the offline guess
+/// when the API fails. The result says which decided.
+#[tauri::command]
+pub async fn detect_subtitle_language(
+    client: tauri::State<'_, RestClient>,
+    path: String,
+) -> Result<LanguageDecision, SubtitleError> {
+    let file = Path::new(&path).to_path_buf();
+    let local = tauri::async_runtime::spawn_blocking(move || {
+        read(&file).map(|bytes| identify_subtitle(&bytes))
+    })
+    .await
+    .map_err(|e| SubtitleError::Task(e.to_string()))??;
+    Ok(decide(&client, Path::new(&path), local).await)
+}
+
+/// Trigram rankings of a few thousand characters per language, mostly
+/// everyday dialogue with some narration.
+const PROFILES: &[Profile] = &[
+    profile!(
+        "eng",
+        Latin,
+        "_th the he_ _to to_ re_ _an _yo you and er_ nd_ ing is_ _he ng_ _be ou_ _i_ _we \
+         ed_ her me_ it_ on_ _wa _ha _is in_ ome _it _of en_ for _do _go _in of_ tha ve_ \
+         _fo _wh ld_ ll_ st_ ver _ca _fi _ne _so as_ ave ay_ nt_ _co _ho _me _re _wi all \
+         an_ ent ere han our us_ ut_ _ar _mo are at_ et_ eve hat hav his ill ion ly_ or_ \
+         rea ter ts_ we_ _al _ba _fa _lo _no _us _ye ar_ be_ day dy_ ear ee_ ery es_ est \
+         hou oin one ore ow_ rs_ se_ thi _br _bu _di _fr _hi _la _ma _mi _on _ou _sh _su \
+         _t_ _wo ate ati ce_ com don ey_ ght go_ goi hom ht_ igh ist mor not ot_ oth ous \
+         out rk_ ry_ sit tio ur_ wil _a_ _ch _ci _ev _mu _pr _ri _s_ _si ake ank ant ark \
+         ast bee can een fin get has hey hin id_ ild ini le_ ne_ nin old oul par ree rom \
+         rot she som ste th_ uld was wor _af _by _de _ex _ga _ge _kn _pa _pl _sa _vi ack \
+         ad_ aft ain ang ann ath bac bef bod bro but by_ ch_ cit ck_ did do_ ds_ ead eal \
+         efo end ers fro fte hea hel hen hes ien ind ish ive ke_ kno lly loo men min mos \
+         nce nds new nge nis nk_ now nti oda ody off ok_ om_ ook ork orn ost oun per ple \
+         pro res rie riv rni str sts tho tod ty_ unt ure use wan war wee wer whe yea _ac \
+         _ag _cl _dr _gr _le _li _my _pe _po _sc _se _st _te _tw _un act ady age al_ alm"
+    ),
+    profile!(
+        "spa",
+        Latin,
+        "os_ _de _es es_ la_ de_ _la _qu el_ que _el ue_ est _lo do_ en_ los _en as_ no_ \
+         _a_ _y_ ent _ca _ha _no ien te_ _se des _pa _pr _te sta _pe _ve lo_ mos nte ra_ \
+         _mi _po ado ar_ ro_ stá _an _co _su aci ero na_ nos or_ to_ ver ía_ _al _to ana \
+         da_ dos res ta_ tod tá_ _ma _sa _si amo ant cas dad er_ ist ndo nto par per por \
+         _ci _di _fu _me _mu _pu _un _va al_ ame ano con erm ier ió_ me_ mo_ on_ re_ ría \
+         sal ste tar uda ás_ _he _ti _tr _vi _vo aba ad_ alg an_ bie cad ce_ ció emp ene \
+         go_ he_ ido ir_ ión les lla man odo olv pue rma sa_ se_ su_ tie tra ued uie ón_ \
+         _ay _bi _do _ho _má _so _tu ada ara arl asa ces che cio co_ dar deb del edi ell \
+         end ere err esd fue gra hac ita las llo men mer más nce nta onc orm oy_ pre pro \
+         qué ran rda rra sde sit so_ ten tes uen un_ ué_ ven vis vol ños _ab _ag _añ _du \
+         _dó _gr _gu _le _nu _ta ace ade adr ale ali and ard ast ayu año ber bue car cin \
+         ciu com cul dre dón ebe eci ede ema emo era erd erí esi eso esp for gos gue gui \
+         ha_ han her hoy ias ica ico ida iem igo igu ill ima imo inc io_ ira is_ iud lam \
+         lgu lic lve ma_ med mig mil min mir mis mpo mpr nci nde nes och oda ona pel pri \
+         qui rad rme rmi ron rqu rá_ sie sto sé_ tam tan ter ton tu_ uch uel uer és_ ónd"
+    ),
+    profile!(
+        "fre",
+        Latin,
+        "_de es_ _le le_ nt_ is_ re_ _la de_ ent la_ _qu on_ er_ que _pa lle us_ et_ _et \
+         our ue_ ux_ _ma _es _je ais est eux it_ les ns_ _tu il_ je_ ne_ _a_ _ce _il _on \
+         _so st_ tu_ _al _pe _pr _re as_ ous ur_ _au _d_ _mo _no _to ait ant ce_ du_ ill \
+         men pas res rs_ tre _du _se ion mai qu_ te_ ut_ ère _ai _an _av _ch _en _l_ _po \
+         _un _vi _vo ati ava eur ien nou ont son tou _co _me _pl _sa _su _te _ét ai_ all \
+         ans au_ dan in_ jou out par rai rd_ ts_ té_ ui_ uis vai _da _dé _fa _mi _n_ _tr \
+         _à_ ain cha dev ell ez_ ie_ ir_ peu plu pou rès tio un_ ure ès_ _ap _va _ve ann \
+         arc ard com cor dep eme end epu ier ler leu lus nne ntr oi_ oir ois ons ort pro \
+         prè pui tem ter uel uve ven voi vra êtr _bi _bo _do _el _fi _hi _ne _êt alo ang \
+         bie che cou der des en_ ens era erc eve fai ger iqu lor mat moi mon nge nte ord \
+         ore ors ouv qui ren se_ soi ten ujo urs vil vou ée_ és_ été _ac _as _ca _di _fe \
+         _fo _fr _hu _j_ _où _vr _y_ _ça _éc ami app apr auj ave bre ché ci_ cie deu dit \
+         eau ec_ elq emp enc enn enu ern err frè gar hie hui ide ime ini ire iso ist ite \
+         lez lli lqu lé_ mar me_ mer mes mil mis mme nco nné nts nu_ omm ond où_ pen rch \
+         rci rde roi rre rt_ rèr sit sse ste sui sur tai ton tro ubl uer uit une urd va_"
+    ),
+    profile!(
+        "ger",
+        Latin,
+        "en_ er_ ich st_ ch_ ie_ _ge nd_ _da _di _de der ist _wi _ha die _un cht ein sch \
+         und das te_ _is _si _zu che ir_ _du as_ den ste _ic abe du_ es_ hen ht_ in_ ten \
+         _be _mi aus ben wir _sc _se ach ges hr_ nde _ih it_ _bi _ni _we ass eit rde _an \
+         _au _na _wa ine nen nic on_ sei sse ter um_ _ve _vo ann ern est gen hab men mme \
+         rn_ sen ss_ ver zu_ _es _ka _so _wo auf de_ fen hau ihr ind kom nac ng_ ns_ omm \
+         ren rt_ sie us_ ute war wei _al _er _he _in _me ahr and bis dan end ens eut hre \
+         lle mit se_ uch ung use _ab _br _ei _gu _hi _im _ko _mu _wu be_ ber bes chl chs \
+         ei_ em_ ent ers fer gut hat heu hst iel ier ion lei lic ne_ nst sin sst tig uns \
+         wie ück _fr _ma _st _um _vi _ze als am_ ang ast at_ cho cke dei dem ede ee_ ehe \
+         ell ema erd ere ert ge_ geh he_ hne hon hte hör ig_ ihn im_ le_ mir mus nge nt_ \
+         och or_ rau rüc sta ude uf_ ufe uss ut_ vie von vor was üss _am _ba _fa _fe _fi \
+         _ga _ja _je _ki _la _le _mo _mü _ne _re adt all ank ar_ art ate ati au_ bei bev \
+         ble bru cha ck_ des dir dt_ ebe ege ehr el_ eru esc ese esu et_ eue ffe gan geg \
+         ger gew gt_ ieg ige itt jah kan ke_ ker kli lan lch los ls_ lt_ ma_ mac mei mer \
+         mor müs ner neu nn_ nns oll one org rbe rei rge rte rud rüh sag seh sic so_ suc"
+    ),
+    profile!(
+        "ita",
+        Latin,
+        "to_ no_ re_ la_ _di _la te_ _an _pr di_ ti_ _e_ _ch _de _il il_ _qu _se ne_ on_ \
+         _a_ _co _no _è_ che ent he_ non ra_ ro_ _so are le_ _ca _da _pa _pe ci_ ell est \
+         li_ mo_ per _ci _fi and ma_ ono _do _ha _ma _tu all ann ato del na_ sta _al _st \
+         azi ima ion lo_ mi_ ni_ nte qua sa_ son ta_ _i_ _lo _mi _si _su _te iam lla one \
+         sto zio _fa _un amo dal do_ fin gio ici llo lor ora par pre pro que si_ tor tti \
+         utt _es _in _pi _ve ate ati att cas chi com cos dov el_ era erc ett ggi gli ia_ \
+         ini io_ ist men nno orn oro pri res ri_ so_ tat tto tut _be _fo _ho _mo _po _ri \
+         _to ai_ arl asa cit col con da_ dia ei_ ene er_ eri ero ess ha_ ho_ ico in_ ior \
+         iù_ nda olt ort più po_ rat rna rte se_ ssi str tam ues uo_ ve_ _du _gi _gu _le \
+         _li _me _ne _nu _og _sa _tr _vi al_ alc ami ant ata ben bia co_ dat emp ere gra \
+         iat imo ino iti ito lle me_ mer ndi ndo nta nti oi_ ola ome ore ori oss ove ric \
+         rim rmi sci sen ser sim tan tel ter tim tta ual uoi va_ ven ver _av _fr _fu _gr \
+         _ie _l_ _pu _ra _re _va adr aff ale ali ama amb ame ani ano arc ard art asi avo \
+         bbe be_ cat cch ché cia cin cun der det dop dre due ebb edi end err esi evi fon \
+         for fra gi_ han hé_ iar ica ier ine ire ita itt ive lcu lio lti lto mat mbi mol"
+    ),
+    profile!(
+        "por",
+        Latin,
+        "os_ _de que _a_ as_ de_ ão_ _es _qu da_ ar_ est _o_ _se do_ ue_ ra_ _co _pa _te \
+         te_ _e_ em_ es_ nte ent _no _po com par _ma _vo _do _pr ara is_ stá tá_ _an _ca \
+         _da _os or_ _nã mos não tem ele eu_ ia_ ro_ se_ sso sta tar _me _on des ei_ igo \
+         iss nos ou_ por sa_ _na _pe _tr _vi ada ais am_ ant cê_ er_ ma_ mai no_ ocê ont \
+         res so_ to_ ver voc _al _ci _di _fa _fi _is _mu _to _um ado amo car del ida ir_ \
+         iu_ na_ ois om_ tra uer _en _fo _ir _mi _ou _sa _ve _é_ ade asa cas dad dar ela \
+         era ess go_ gos ist la_ le_ lha me_ mig mo_ per pre pro ram ria rio ta_ tod _ac \
+         _ao _as _be _el _em _eu _lo _su aba alg ame ana ao_ açã bem cid con dei dos ema \
+         emp enh ens gen ha_ ica iga io_ ita ite ive lgu lo_ man mas men nas nde nho ns_ \
+         nto olt ond sem sen sit tam uda um_ us_ vol ção _ag _ce _ch _gu _ho _li _ob _ol \
+         _re _so _ta _tu _va _vã ai_ al_ alh ano ard arr ata bal bri cad cio co_ dep dia \
+         dis doi eci edi eir err esd eus eça fic foi gad gra har hoj hor ico ilh im_ ima \
+         imo ind inh irm isa ito je_ les lho lig lta mer mil mpo mui mão nda ndo nti ntã \
+         obr ode odo oi_ oje omi omo ora ore orm pai pod rab rec rig rmã rqu rra sas sde \
+         sei ser seu ssa sse são tas ten ter tes tiv tud tão ua_ uas udo uen uit uma ura"
+    ),
+    profile!(
+        "dut",
+        Latin,
+        "en_ de_ et_ _he _de er_ aar an_ _we _da at_ ar_ het _be _ge je_ dat is_ _je _me \
+         _va den _en _is van _ik aan ijn ik_ men _ve _zi ie_ ver _wa cht jn_ nie oor ste \
+         ten _ko _ni _zo ij_ in_ we_ zij _in _te _vo and iet ken nd_ nde ren ter wee _aa \
+         _ma _mi _na ben der ere heb kom te_ _al _hi _mo _ze ag_ dan eer end ht_ ist moe \
+         nt_ ome ord sch _di _ga _on _pr een est gen hij lle maa ns_ voo wer _br _ee _ha \
+         _om ach al_ bel ede ele ent erd ete gaa ijk it_ lan na_ oed oet om_ ond rde uis \
+         waa _do _go _hu _la _op _st _wi aat bbe bro daa die eb_ eet ege eke ens erk ger \
+         goe hui ien ier ig_ mee met mij naa oer ons or_ pen rie roe st_ ze_ _ho _ie _ki \
+         _no _th _to aag ad_ ade all am_ ang app ark bes ebb ed_ eld elk eme era eri es_ \
+         gel haa hee ind ker lem len me_ og_ op_ par raa rd_ rk_ sta uit un_ uwe vol wil \
+         wor zou _bi _dr _er _gi _gr _ja _kl _pa _sc _si _ui _vr _wo ank ant ate ati bij \
+         bli chi doe eda eek eel ei_ ek_ eko el_ ell ema erm ers eru erw ezo ga_ geg gek \
+         ges gew gis gra hel hie hoo ich ies ijd il_ jaa jk_ ke_ kij kt_ ld_ le_ lge lij \
+         man nda ng_ oe_ oek oen olg on_ ot_ ou_ oud ouw per pre pro ran reg rge rt_ rug \
+         sen tad thu tie tij ull ur_ uur ven zo_ zoe zon _af _an _ba _bl _bo _du _ec _el"
+    ),
+    profile!(
+        "swe",
+        Latin,
+        "en_ _de er_ är_ ar_ tt_ _ha an_ att _at te_ _fö de_ et_ _vi ag_ _i_ _va _är för \
+         _oc ch_ na_ och var _ti ka_ _du _ja _sk du_ jag ör_ _he den _in ade har ill ll_ \
+         ta_ _mi rna sta _ko _me der em_ ig_ kom nte _hä _st gen int it_ re_ ste ter til \
+         vi_ _ka _på han ing om_ or_ på_ ra_ ska _so dag det här med nna rde år_ _lä _se \
+         _ta _än dem es_ in_ mig nde on_ sku ss_ vil _al _be _en _fr _gå _mo _os _si _sä \
+         all and ari arn av_ ck_ da_ dan des ed_ fte gt_ går kan kul la_ lle mer mor omm \
+         oss pa_ rit sen tad tan ull äst _an _av _br _di _ef _ga _gö _ho _hu _kl _ma _må \
+         _nä _nå _pr _ri _så _tr _tv _ut _ve _vä ack ann ans ara ark art eda eft enn ern \
+         frå ga_ gon gör hel hem hen hon ige isk itt le_ lig men min mme nge nne ns_ nta \
+         näs någ one ord ror rt_ rån sko som tac ten tid und ur_ äls än_ äng änn ån_ _ba \
+         _bo _by _da _då _fl _gr _ni _pa _re _sa _tu _un _år aff age aka app are as_ at_ \
+         bak bar bli bor bro dda din dra då_ ej_ ela ens era eri far ger gra id_ igt ion \
+         ist ken ker kla kli kor lar len lt_ ma_ mma mås nan nas nat ndr nen ner nga ni_ \
+         nin nis nu_ ran ren rig rin rst sa_ sar sed sna stä så_ tig tio tre tta tän use \
+         äll ätt ågo åst örd örs _ar _bi _bl _bö _dä _el _er _fi _fo _gi _go _hi _hj _hö"
+    ),
+    profile!(
+        "nor",
+        Latin,
+        "en_ er_ _de et_ eg_ _ha te_ _me ke_ ne_ _vi re_ ste _er _i_ de_ _fo _og _ti ene \
+         for og_ _du ar_ du_ or_ _je jeg le_ gen ikk il_ kke til _he den det enn _av _sk \
+         an_ har _be _en _hv _ka _ko _si _ve at_ av_ em_ ig_ kom lle nge nne ren ten tte \
+         _at _ik _på _å_ der ere est ett ing på_ vi_ _bl _se _væ ed_ lig meg men mme om_ \
+         rt_ vær _gj al_ bes ent han kal ker med nes vil ør_ _al _br _da _gå _hj _os akk \
+         ble dag dem ge_ hje hvo ide kan ken kk_ omm ord oss ra_ ska ss_ ter vor år_ _di \
+         _el _fr _fø _le _må _no _so _st ag_ ake all ann eng før gje jor mer ner noe ort \
+         rke se_ sen sid ske som tak tet tt_ ven ære _an _by _dr _et _ga _gr _mo _ne _sa \
+         _så _ta _ut _va ans bak bli bye da_ dre far fra gjo gt_ går hel hen in_ ist jem \
+         jen jør len mor må_ ngt nin nn_ oen one ore rde tem use ært _ba _bu _fa _fe _fi \
+         _hu _hø _ki _kj _kl _la _ma _mi _na _ny _te _tr _tu _år and ark att bro dan dde \
+         dig din eld ele es_ ffe gjø gre gte her hør id_ ige ill itt lan ld_ lit lke na_ \
+         nde ndr nen ns_ nt_ nte opp ove par pe_ rda rdi rer rin ror rst rte seg sku så_ \
+         søk tid tre ute ved vel ver vet vis yen øke ørs _ar _go _ig _jo _kr _kv _mu _nø \
+         _op _pa _pr _ra _re _ri _sn _to add ade aff age agt ald am_ amm ang ant arb are"
+    ),
+    profile!(
+        "dan",
+        Latin,
+        "er_ en_ _de et_ de_ _ha _i_ _er _vi den or_ og_ _he _je _og eg_ kke _fo _me ar_ \
+         at_ ed_ for jeg ste te_ _at _du du_ ge_ ke_ _af der det gen ig_ re_ _sk _ti il_ \
+         _mi af_ em_ ger ikk le_ lle ne_ nge vil _hv _ik an_ end til _be _en _ko _si _ve \
+         _bl _la _væ ble ere fte har kom men nde vi_ vær age ave dem ede han her ige ing \
+         ken ker lev med mig mme mor nd_ nne ord ska ske ter ve_ ære ør_ _br _by _hj _ka \
+         _mo _os _på _så _ta al_ dag ene gt_ hav hen hvo igt kal lig ner om_ os_ på_ ret \
+         rke rne sid så_ ten ved ven vor _al _ef _gå _må _no _so _st _va ag_ and bye eft \
+         enn ern es_ get hje ide ill ind ns_ omm rig tte år_ _an _di _fr _fø _hu _kl _næ \
+         _pr _ri _ud ade ak_ all ark bag bes din dst dt_ ege ele ens ent ev_ far fra før \
+         in_ ion jem lan mer nog nu_ næs ove ra_ rer rt_ se_ som tak tig var ver æst _da \
+         _el _fa _fi _fl _ga _go _hø _ki _kø _le _na _nu _se _su _to _tu _år ann bro dan \
+         dda eds esk eve ffe gge god gti gå_ hel hør is_ ist jor kan ket kør lav len lke \
+         min nes nin oge one præ rde rdi red ren rin ror sta søg tet tid tre ukk und usi \
+         vet yen ænd øge øre _ar _ba _bi _bu _ge _gr _gø _jo _kr _læ _ma _mu _ny _ok _op \
+         _pa _re _sa _sl _sø _te _tr _tø _æn ad_ aff aft agd agt am_ ang ans arb art ati"
+    ),
+    profile!(
+        "fin",
+        Latin,
+        "en_ ta_ in_ _ka tä_ an_ ist si_ sta _mi on_ _ol hän än_ aan at_ ja_ ti_ _hä _me \
+         _on le_ _ja ett lle min _ko _ku _si _tu _tä et_ nen _mu een itä ksi lla ole sa_ \
+         tta vat _ei _en _jo all isi ko_ mis _et _se aa_ ast ell inu mei na_ sin ten ttä \
+         äne _jä den enn iin itt la_ lä_ nä_ oit ova ssa un_ ut_ _as _ki _pi _ra _so _ta \
+         _te _vi aik ais eid ia_ ise iss maa men oll pää sä_ tte tti ust utt _ai _ha _he \
+         _ov _pä _ti _to _va ann ei_ ide iit ikk kai kau kii kä_ lee llu lut me_ sti taa \
+         tul ull _il _le _lä _pa _pu alu ana aup eet eil emm ens idä ill inä it_ jok kan \
+         kki kui kun len li_ lis lli lta mme mmi ni_ nna nne nää oku oli pun se_ sit ssä \
+         ste tii tos tyy tän ua_ uri vii yy_ änä ät_ ään _ih _ke _ma _ne _ni _sa _uu _ve \
+         ain ats dän eis ent hal iel ika ink ito ka_ kaa kak kat kes ki_ kot kuu lei lje \
+         llä läh ne_ nii nnä ois ott pit sel set sto stä tie tko uka upu uut ää_ ääs _aa \
+         _an _au _el _hy _is _os _pe _su _vo _vu aam ake aks ano ans ass dot dä_ eit ele \
+         eli elj elo elä eni eri es_ esi esk est han hei hyv ied ien iik iis iks ile imm \
+         iti itk jel jäl kee kol kus kuv let lke lok lua mik mit mut muu naa net nko noi \
+         nsa nsi nte nua nun ona os_ oti rak san sen sii sku soi suu tai tam tap tar tei"
+    ),
+    profile!(
+        "ice",
+        Latin,
+        "að_ um_ _að ið_ nn_ _vi _í_ inn ur_ ar_ _he _er _ha ir_ _og na_ og_ ann ver _ve \
+         _ég er_ ég_ han við _en st_ ki_ _al _hv _þa _þe in_ ma_ num ra_ tu_ _ek ekk kki \
+         nar nna stu _br _ko _me _ti _á_ all an_ eim enn gin kom ta_ til _fy _mi _va _æt \
+         _þá en_ hei hva il_ inu tur var ði_ ðin ðu_ ður þá_ _ge _st _sí _þú ett gar hve \
+         im_ ing kku kur lan llt ndi org ri_ rin rið rni rum rði rðu tta ynd áðu _af _ei \
+         _ka _la _le _ma _ok _sk _sv _ár _þi agi arn dag efu ein fyr hef ist ja_ la_ leg \
+         lt_ með nda nnu nu_ okk rir rna vað vin yri ætl ér_ ða_ ðum það þet _da _el _ga \
+         _hú _kl _mé _næ _sa _se _sé _ta akk ana and ast bor di_ eit eng eri eru erð eð_ \
+         fa_ fti fur gi_ gt_ haf hen ig_ ina ka_ kan man min mma myn mér nd_ nga ni_ nni \
+         næs oma rgi ru_ str síð tak ti_ tir un_ ust vil ær_ æst þin þú_ _bo _bí _bú _ef \
+         _fr _fó _gæ _hr _lo _my _ná _ný _sj _tv _um _úr _út _þr af_ afa ag_ amm arð aða \
+         aði aðu bró eft ega els era ern ert etu eyt fi_ ga_ ger get gja gun gær gði hún \
+         ill iss jál kað kk_ kka kvæ lag len nin nir ns_ nýj omi rft rgu rju ráð róð sjá \
+         sta sum tin tla tra tt_ tum und urð vei væm ákv ætt ðan ðið öld öru ún_ úr_ út_ \
+         þar þei _ba _be _by _bæ _bö _fa _fe _fi _fö _gl _gö _hi _hj _hl _hæ _hé _hö _mj"
+    ),
+    profile!(
+        "pol",
+        Latin,
+        "ie_ dzi nie _po _je _ni _do zie wie _pr em_ _na rze _w_ _za _dz _i_ go_ _od ego \
+         na_ sta ze_ _mi aj_ ch_ esz mi_ _wi _z_ _że edz jes my_ prz to_ ej_ iem ła_ łem \
+         _cz _mo _ta _te _to ani ałe cie cy_ cze czo do_ est ić_ ię_ mie obi ost rob rzy \
+         sz_ że_ _by _ch _mu _pa _si _ty aki as_ ci_ ia_ iec ied im_ je_ ją_ nas nia owi \
+         pra się st_ ta_ _ba _ci _ma _st _wr _wy ale ast ała brz cza dni dob iał ien ięk \
+         ki_ mam moż mu_ now obr od_ oże pie po_ sia sto szc sze szy tał wsz ysz zcz zia \
+         zor zos zy_ zys _a_ _br _ja _la _mn _ob _pi _ra _są _wc _ws _zo ad_ ają ana at_ \
+         awi ać_ ał_ był ce_ cha chc cja dom dro eci emy eni eś_ ias ich iej ies ił_ jak \
+         kim ku_ lic mus nad naj nim ny_ oni ore osz owa pom pos pow raj rat raw róc trz \
+         usi wa_ wcz wró yst zed zek óci ów_ ło_ ły_ ść_ żes _al _bę _co _gd _go _ju _ko \
+         _kt _lu _ro _rz _sa _sp _sz _sł _we _wo _zn _zw ach acj am_ amo apo ard ark ata \
+         ało bar bra by_ byś będ cho czy eby ecz edł eka gdz haj hce iad iaj iek ier ili \
+         isz iu_ ią_ iła ja_ jac jed jeg jej już ję_ ka_ kuj lat le_ lek li_ lud mia mni \
+         ne_ ni_ och odr odw omu ony ora oś_ par pro rac raz rdz rem rod ros sam sza szk \
+         są_ tak tat teg tem tu_ uch uję uż_ we_ woj wys ych ym_ zas zeg zię zro zyj ędz"
+    ),
+    profile!(
+        "cze",
+        Latin,
+        "_je _ne _do _po _se _a_ na_ _js _pr je_ se_ em_ ou_ _by _mě _na ch_ _ta _za la_ \
+         mi_ sem _př _v_ ce_ do_ děl ho_ jse jí_ pro _ná _od _s_ ak_ dne le_ li_ me_ ní_ \
+         sta ím_ že_ _mi _mu _ně _sl _že at_ byl dy_ hle led lo_ nás ta_ tak _br _dě _ja \
+         _ni _te _to al_ bud by_ de_ dob edn ej_ el_ ení eš_ ež_ il_ jak ka_ ma_ ne_ než \
+         nov ná_ ně_ ra_ sou to_ ás_ čer _ab _bu _ch _dn _kd _mo _mů _pa _si _ti _ve _z_ \
+         _ze aby ají ale ane bře cho ci_ dal dom eme era es_ ich ili ist it_ jso moc mus \
+         mám měs můž nem nes ni_ nic no_ od_ ost pol pře si_ sle te_ ích ít_ ěla ěst ští \
+         ší_ _al _an _ce _hl _ho _jí _ka _ko _le _no _ob _op _rá _st _té _vr _vy _vá _ví \
+         _vč _vě _vš _zd _zv _ře ala alo ama ani atr avn bra chc chn co_ da_ dem den dos \
+         děk edo eho emá ent esk esn et_ hra ina jed jeh ju_ jít kde kdo ku_ kuj ky_ káv \
+         lal lat lav ly_ mar mil mu_ mě_ neb ned nej nič něk obř oc_ ole ovi ová ože pod \
+         pom poč prá pří rat rav rán rát ses ska ste tan tel ter til ty_ tí_ tě_ ude uju \
+         usí vrá vá_ vím vče ych yla ze_ ám_ áti ých ěku ře_ řek šel ště ůže žeš _co _da \
+         _dv _dů _fi _ji _ká _li _ma _mn _o_ _pe _pů _ro _su _sv _tr _tv _ty _tá _tř _už \
+         _vo _zp _zů _ča _ří ady aký ali aně ark ará avd avš ba_ bez brz byc bys cel chl"
+    ),
+    profile!(
+        "slo",
+        Latin,
+        "_po _do _pr _je _sa _a_ sa_ ch_ ie_ _ne mi_ om_ _ni _si _na je_ ko_ si_ _v_ me_ \
+         nie to_ _ak _so by_ em_ la_ na_ pre _ná _ta _za bud li_ ne_ som tak te_ _s_ al_ \
+         de_ ej_ ia_ nás ov_ sta že_ _bo _bu _by _ma _od _ro _ve _že ako bol dom era ho_ \
+         ich iek il_ iť_ ost poč voj _ab _me _mi _mu _sú _te _ti _to aby ale ať_ ce_ ci_ \
+         dal do_ el_ eme jú_ kto lo_ ma_ nem nov oko ola re_ ri_ rok rát sto sú_ vie ás_ \
+         ých čer šie _al _br _ch _dn _ho _mô _sl _tr _vo _z_ _zo aj_ ak_ ali ane ani bre \
+         dne dob dy_ eni er_ es_ est eš_ ide iem ili ist mes mus môž naj nes nič ná_ obr \
+         oro ou_ pri prá ra_ rat rob sle sť_ ti_ tor tu_ tvo uje ved vše ôže ým_ _an _ce \
+         _de _ic _ka _kd _ko _no _ob _pa _rá _sm _sp _st _su _tu _tý _vi _vr _vč _vš _zd \
+         _zv _ís _ďa ajú aku aký alo ama ami at_ bra chc chl del dem dni dos eda ede edo \
+         eho ekt emá ent ená ete hce hla hra ina jeh jej jem kam kde kov ku_ kuj káv ké_ \
+         kým le_ led mal mam mar men mer mu_ mám ni_ nia no_ ny_ né_ och od_ oho ojn olo \
+         omo ori ova ovi oča ože po_ pol poz pro red rie rán sme ta_ til tov tre tým ude \
+         udú usí vaj vrá vče ách ám_ áti áva ísť ými čas čo_ ďak šet žeš _dv _eš _fi _hl \
+         _id _kt _ká _ký _le _mn _mo _mň _o_ _op _ot _pe _pô _ri _sk _sv _tv _už _vy _zb"
+    ),
+    profile!(
+        "slv",
+        Latin,
+        "je_ _po _je _na _pr em_ _da _se aj_ la_ _za da_ in_ li_ _in _v_ ti_ _bi jo_ ko_ \
+         na_ pre _bo _ne _si red _do ali naj no_ pri se_ si_ sta to_ _me _ni _nj _so _ve \
+         ih_ lo_ nje va_ _mo _ob _pa _re ga_ mi_ ne_ ni_ ora sem so_ val _ka _ti al_ bi_ \
+         ji_ mor nji rav _ga _iz _od _te _to ala ate dal edi el_ en_ es_ eva il_ ila iti \
+         jen kaj nov ov_ tan te_ ve_ voj _br _de _ko _ma _mi _sp _st _vs ako ano bil bo_ \
+         del dom ede eni ijo ilo lju nar ost ovi oče pa_ pom pra raj rat rek sti ta_ tem \
+         vo_ če_ _la _tr _vr _z_ _še _že ahk aju ani bra dan den dnj do_ dov ed_ ej_ eka \
+         ena est gov hko ija imi ist ja_ ju_ kot lah let lje mam me_ men mes nil nim od_ \
+         okl omo ot_ ova pod pol pot rem sko sto tis _dv _gl _gr _ki _kj _le _lj _no _os \
+         _s_ _sk _ta _tu _vi _vo _zn _ča _ži ane are ata avo aš_ ce_ di_ dne edn ejo ek_ \
+         eke ela elj er_ era eri eti eč_ gla gle gre hva ica ije im_ ima ino iz_ jal jat \
+         jem jih ka_ kat ki_ kje kor lad led lic ma_ med mov nek nes nik nit obr odn ogl \
+         ogo oj_ om_ oma ome ote ove oči par po_ pog ral rij rni ro_ sed stv tel ter tih \
+         tre tu_ tvo ubi uje ust ved več vlj za_ že_ živ _am _an _dr _fi _go _hi _hv _it \
+         _jo _ke _km _ok _on _oč _pe _sl _tv _us _vč _zd _ze _zg abi adn aja ajt ak_ aka"
+    ),
+    profile!(
+        "hun",
+        Latin,
+        "_a_ _me gy_ meg _az _ho _mi an_ em_ en_ és_ _és tt_ ek_ _el _ne az_ hog ogy _va \
+         et_ _ke _te min nem ket _ha _le _sz am_ lt_ nk_ sz_ án_ ben eg_ ele ki_ _ez _vá \
+         ak_ elm ett ok_ ros ség tam vár áro _eg _fo _há _ki _kö _ma _re _tu agy al_ bb_ \
+         egy el_ ell elő enn ere kel kor leg lye ma_ nt_ van zt_ ól_ _ak _ké _má _ve ala \
+         alá azt ban egn ent ete ind int it_ ja_ lak lme lőt men ne_ nek nke olt ond par \
+         sok szt ta_ tek tud unk val vol zer ák_ ég_ él_ ét_ őtt _am _an _be _fi _gy _it \
+         _jö _mo _na _né _pa _so _vi _vo _új abb ap_ aza ból den dta end ene esz gna gye \
+         ily ink is_ itt lam let ll_ mil már nap ni_ nál néz or_ ost ott ra_ re_ ren ssz \
+         sza sze tal te_ tok tos tta vel yan ár_ át_ ész ünk _al _bá _cs _de _em _fe _hé \
+         _is _ko _ta _tö _ut _ér _év ait aki akk ama ami ark ato bba bát dbe de_ egé egí \
+         eke eze fel fon gaz gya gés hal haz het hol ig_ igy iss jel kba ked kko kös lat \
+         len li_ lán mar mel mon nag nak ndb nde nto nya ol_ oly om_ ont os_ osa oss ot_ \
+         rom rsé rt_ san st_ szá szé szö tbe teg tem tet ti_ tot ták tán udo utá vis vő_ \
+         yen yer zab zön ád_ ált ány áty érs ért ök_ önö ösz ött _ap _ba _dé _en _es _go \
+         _hí _id _ig _je _jó _ká _la _lé _mu _mé _ni _po _pé _rá _se _sü _to _tá _té _ví"
+    ),
+    profile!(
+        "rum",
+        Latin,
+        "_de _în de_ să_ _să _a_ te_ ul_ și_ _ma ai_ ne_ ți_ _și că_ ine _am _nu ea_ ii_ \
+         le_ _ce _cu ei_ nu_ _as _mi ele it_ în_ _la am_ ar_ at_ ta_ tre tă_ _că _me _o_ \
+         _po _pr ast ate ci_ mai min or_ rea ui_ _di _e_ _fi _fo _mu _pe _se _tr are ce_ \
+         eni la_ nă_ se_ _ai _an _ca _ne _su _te ată au_ cu_ cul ent eri fos ie_ lui nii \
+         ntr ost ra_ re_ st_ tru ști _au _aș _bi _lu _un _ve ați bun cum din ebu ia_ mul \
+         ni_ tel tor um_ uni vre înc înt _ac _bu _du _ră _vi _vr ame bin bui chi cin eme \
+         esc iar ici in_ lor men mer nci oar par pro pă_ rat reb ru_ ră_ sta str sun tat \
+         toa ult uri va_ ând ște _ap _at _ci _da _do _ei _ia _mă _or _pâ _pă _re _si _to \
+         _șt ale ani atu cas dup em_ erg erm eva eșt imi int ita ma_ mân mă_ nev nt_ nte \
+         oat oi_ ora pe_ pen per pre ri_ ril rmi sc_ tea tim tur ulu ulț upă ut_ zit ămâ \
+         _ad _aj _ar _fa _fe _fr _i_ _ie _oa _pa _sc _tă _ui _ur aca aju arc art asc asă \
+         așa ața car dar dec dev dim eaț eci edi ere eți fac fra gem icu ier ilo im_ imp \
+         ini is_ ist ite iți luc lțu me_ mi_ mii muz măr nat nde nea nic nit nto nul oam \
+         ort ouă oți poț pri pân raș rei rel rge ric rit rma rul scu stă tem ter tul tun \
+         tău tăz uie uit ume unc und unt ună urm uă_ ven zi_ ână ât_ îna ău_ ăzi șa_ ța_"
+    ),
+    profile!(
+        "hrv",
+        Latin,
+        "je_ _je ije ti_ da_ _po li_ _na _pr _ne _u_ ma_ na_ _da am_ ati rat to_ _bi _i_ \
+         _mo _sa ko_ _se _ti io_ la_ ne_ _za rij sam se_ _do _nj _su ali ao_ as_ ima iti \
+         mo_ nov ro_ ći_ _go _iz _to _vr ada ako jed ju_ nas no_ sta vo_ će_ _ka _li _ob \
+         _ra _s_ _st _tr ala im_ ja_ lo_ ora pos pri rad što _gl _ko _ku _mi _od _on _ot \
+         _će aj_ dje eka eš_ ga_ gra jek lij nje od_ pro sje su_ ta_ te_ val ve_ _a_ _br \
+         _ga _ni _os _re _si _sv _št an_ ana bit edn emo ih_ ija ina jem jim kak lim lju \
+         mam mož nem nji om_ ova ove ovi ra_ red si_ tu_ zna ću_ _al _gr _id _ju _ma _no \
+         _ov _vi _zn ate bra bro cij dan di_ din eda ego ema ena ili išt jes jih ka_ ke_ \
+         koj kuć lje lji me_ mi_ mij mor obr odi oj_ ost oti ovo ras sno sti stv tit tko \
+         tvo vat vij vra _du _gd _is _jo _lj _me _op _pa _ta _ve ama amo ano ata ava avi \
+         bi_ bil ce_ dni dob eba edi eko eli em_ eme era esi et_ etk eće gdj gla gle god \
+         got gov hva ici ide iju ila ine ini ist iz_ jat jel jen jet jez ji_ jut juč led \
+         mlj naj nak nap nda nic nij niš nja nom oje oji on_ ona ond osj oto ože pod pol \
+         pom pre ran rav reb rek tan tel tio tka tov tra tre tro udi utr uće ući uče uči \
+         van vi_ vu_ za_ zaš čer žeš _an _ba _ci _dj _dv _fi _hv _kr _ml _mn _mu _pe _pu"
+    ),
+    profile!(
+        "scc",
+        Latin,
+        "da_ je_ _je _da _po _sa _ne li_ ma_ _na na_ ti_ _pr _u_ am_ _i_ _se rat to_ _bi \
+         _mo ko_ ne_ _do eš_ ima la_ mo_ rad ro_ sam se_ te_ _ko _nj _za as_ io_ ju_ lo_ \
+         nov pos _iz _su _ti ao_ ati de_ emo nas sa_ sta _ob _od _ra _st _to _vr ada ali \
+         ora pre ta_ ve_ vo_ će_ _ga _go _ka _ku _li _on _re _si _tr _će ala eka ga_ gra \
+         im_ ja_ lju nem nje od_ ove ra_ su_ val što _a_ _br _me _mi _ni _ot _sv _ta _vi \
+         _št adi aj_ ako ana ena ide ih_ ija ina kak led lim mam mož nji no_ oj_ om_ ova \
+         pro re_ red si_ sko sle ura _al _gl _gr _id _is _jo _ju _ma _no _os _ov _ve alo \
+         an_ bi_ bra bro dan dem di_ din eda ede ego eko ema era ete eće ije ili ist jih \
+         ka_ ke_ koj kuć le_ lja me_ men mi_ mor nda obr odi ost ovi po_ rav rek rem sti \
+         tvo vra vre zna ći_ ću_ šta _ba _dr _gd _le _mn _mu _op _pa _sk _sl _te _ur _zn \
+         aju ama amo ano ari ata ate ava avi avn ašt bal bio ce_ dob dru eba edi edn em_ \
+         eme esi et_ gde go_ god gov hva ici ila ine ion iz_ iš_ išt jen jes jim još jut \
+         juč kom kor kup lek mlj mno mog naj nek neš ni_ nic nij niš nog nom og_ oji oli \
+         ona ond oro ose osl oti oš_ ože pod pol pom ran ras reb rim rug set stv taj tan \
+         tin tio tka tor tra tre tu_ ude utr uče van vi_ viš vni za_ zaš šti žeš _an _bu"
+    ),
+    profile!(
+        "bos",
+        Latin,
+        "je_ _je ije ti_ _po da_ _na li_ _da _ne na_ ma_ _pr am_ _se _u_ rat _i_ ati io_ \
+         to_ _bi _mo _sa ko_ la_ ro_ _do _za ne_ pos rad sam se_ _nj ao_ as_ ima iti ju_ \
+         mo_ nov rij _iz _ko _su _ti ali nas sta ći_ _ka _li _ob _ra _s_ _st _to _vr ada \
+         ako no_ ora te_ će_ _go _ku _mi _od _on _si _tr _će aj_ ala ana eka emo eš_ gra \
+         im_ ja_ kak lij lje nem nje od_ ra_ su_ ta_ val ve_ što _a_ _br _ga _gl _ni _ot \
+         _sv _št adi bro dje ena ga_ ih_ ija ina jed jen lo_ mam mož nji obr oj_ om_ ova \
+         ove pri pro red si_ sje tu_ ura vu_ ću_ _al _ba _dr _gr _jo _ju _ma _no _os _ov \
+         _pa _ta _ur _vi an_ bra ce_ cij dan de_ din dob eda edn ego ema era ila ili ist \
+         jek jem jih ke_ koj kuć lim lja lju me_ mi_ mij mor odi osl ost oti ovi ras sli \
+         sti tvo vat vo_ vra zna šta _gd _hi _id _is _lj _me _mu _op _re _te _zn abo ahv \
+         aju ama amo ano ari ava avi avn bi_ bit di_ dio dni do_ dru eba eko eme esi et_ \
+         ete eće gdj gle god gov hva ici ide iju ine iz_ išt jes jet ji_ jim još jut juč \
+         ka_ kom kor ku_ led men naj nda nek ni_ nic nij niš nom og_ oji ona ond oro osj \
+         oš_ ože par pod pol pom pre ran rav reb rek rim rug sko sno stv taj tan tio tit \
+         tka tor tra tre tro udi utr uče va_ van vi_ vij vni za_ zaš čer žeš _an _ci _du"
+    ),
+    profile!(
+        "alb",
+        Latin,
+        "të_ _të në_ _sh më_ _e_ sht _në _dh _më et_ he_ dhe htë rë_ _do _pa do_ it_ ësh \
+         _gj _i_ jet se_ ën_ _di _mi _që _se _ës anë ish me_ par që_ ta_ _me _mu _nu _po \
+         _pë het për ra_ shk shu te_ ër_ _ka _nd erë etë hum jë_ nuk re_ sh_ ti_ uk_ umë \
+         und _du _ja _nj _pr _si atë end gje nde ni_ shë tet ur_ ës_ ët_ _je _kë _mb _ng \
+         _qe _vi _vë ar_ gji hko hë_ in_ ith jes jit mi_ mir na_ ndi qen rit shi _at _ku \
+         _lu _ma _na _rr _ta _th _ve ai_ ara arë ata der duh eri ga_ ia_ im_ irë jan je_ \
+         jmë jnë jo_ men mun mën nd_ nga një po_ rat toj tyr tëp ua_ uar uhe ve_ ëpi _a_ \
+         _bë _de _dy _fa _fi _fu _ga _ki _ko _kt _sa _so _te _ti _u_ as_ at_ det emi esi \
+         her ht_ iku ja_ koj kth kur kët mos ndo ngj nte ojm ojn oni osh ot_ ova pas por \
+         qyt ri_ ris rën sa_ sho si_ tar the thë tur tës va_ ysh yte ënd ëng ërt _an _ar \
+         _bu _dë _he _kj _kr _mo _pu _qy _tr _ty _yt aj_ am_ ark ash ati de_ di_ dih dit \
+         dëg efo ej_ ele ent enë esh ete eti fal fil fon fun gat gjo hat hme ij_ ijë ind \
+         ist ite itu ka_ kaf kis kjo ku_ kë_ lai lef lem lla ma_ mij min mua ndr ndë nën \
+         ojë okë onë or_ os_ pi_ pre rdh rku rte rua rëm sha shm sis sot së_ tel tha tij \
+         tre tu_ tëh tër uan vël yre yt_ zit ëgj ëhe ëll ëri ësi ëta _ai _ap _ba _ci _dj"
+    ),
+    profile!(
+        "tur",
+        Latin,
+        "_bi lar an_ ve_ in_ yor _ka _ve en_ _bu bir _ba _be iyo _sa _ya ak_ arı im_ ir_ \
+         ler ni_ _ge _on bil or_ _ha ama de_ ece ede eni eri ini nla ın_ _de _ol _so _ta \
+         ada ar_ ara dan den ki_ le_ nda onu un_ ün_ _ar aba da_ di_ ek_ ere ili lma na_ \
+         nu_ oru sin _da _gi _he _mi _ne _şe ard bu_ ce_ cek dı_ er_ eği içi man ne_ nı_ \
+         rde rın zi_ ınd _et _iç _pa _te _ön aha am_ ağı ban ben dah erk gel gün ha_ iz_ \
+         izi la_ liy ma_ ok_ ola rke rum son um_ çin ık_ ım_ _ev _ik _in _ko _ço aca aki \
+         akl ana anl ark ası değ ele end eye her ine ist kad kar kla nce ra_ rak red ri_ \
+         rı_ tme yle yıl öyl ıyo _dö _dü _ed _gö _gü _ku _sö _ye _yı _za alı anı aya ayı \
+         azı baş biz cak cağ dön dün edi ehi eli emi erd esi etm ey_ ger hir iki ile imi \
+         kan ken kle kur lec li_ lıy mi_ min nde ner nin on_ par rin rle san si_ sın tam \
+         tim tı_ unu uru yar zam çok çık önc ıl_ ını şek _an _bü _is _me _mü _na _o_ _pe \
+         _sü _sı _uz _yo _ça _çı abi adı ahv ann apa arl ava az_ aşl aşı bab bah bak bin \
+         bug dak dar der deş diy eki ekk ekl ekt et_ eve eşe eşi gid gör ide il_ ilm ins \
+         ird isi iti iya işt kah kkü kom kür lda ldı lim lir lis lli lık mak mam mek mey \
+         miz mla nas ndü niz nle nne nra nsa nun onl onr rad rdi rdı rme rsi run söy sıl"
+    ),
+    profile!(
+        "est",
+        Latin,
+        "ma_ st_ _te le_ _ol id_ _sa on_ _ma _on _se _ku as_ sa_ _mi _ta ast ed_ ja_ _ja \
+         _ko _me me_ ema ist sel ta_ ud_ _ka _va na_ et_ ole _ei _et da_ est ga_ ks_ nna \
+         nud pea _pe _tä ad_ ei_ in_ lle min sta _jä aga eid el_ es_ gi_ is_ te_ _ne _pa \
+         _tu aks ata ida inn ise kas kui lis ti_ us_ ära _mu ade aja al_ ell les mei sed \
+         tän äna _en _ke _lä _ni _si _su _ve aat ab_ aha ee_ mis ra_ saa se_ see sin tul \
+         _ho _ki _kõ _na _pä _vä asi eal eed ega eil enn iku ime ju_ jär ksi kus lem lin \
+         lli mas mik muu nde ne_ ng_ oli sid sii sti tea tem ter ull uut vaa _ai _aj _an \
+         _ar _he _hä _kü _pi _sõ _võ _är aad aas ale alj an_ and at_ atu ava de_ deg eda \
+         eis end eri eva gas gu_ iim ik_ ile ilm ima ind ing ini inu kes kor kuu kõi la_ \
+         lit lja lla lnu lt_ mid nd_ nei ni_ nin oln oma pal pse pär ras si_ ste tas tee \
+         tes tle uid ul_ ust uul vad val või _aa _is _jõ _li _mõ _nä _om _po _ra _tõ _vi \
+         _üt ada aeg agi aid ait ala all ama ari ase ate dal das des dis ead egi eks elt \
+         ena esi ha_ hom htu igi iis ike isa ita its jal ke_ kin koj kül las lev lju lmi \
+         loo läh met mil mmi nag nal nas nee nne nu_ oju omm ool ord par rgi ses tag tah \
+         tal tat tei täh töö ui_ ule unu use van vee ven vii väl älj ärg ööd ütl _ag _al"
+    ),
+    profile!(
+        "lav",
+        Latin,
+        "es_ _vi _va as_ _pa iem tu_ em_ viņ _es _ir ir_ _un ies un_ _ka _ma ās_ _ne ai_ \
+         ar_ ka_ _la _ti _no _pi _ta _tu _tā ju_ _ga die iņa ja_ _sa ien kā_ ms_ tie vai \
+         _at _da _kā inā lai ts_ _na _te aiz dzī gri is_ kar man māj no_ pēc tik ūs_ _ar \
+         _br _iz _ku _mā aka am_ aud ba_ du_ et_ jau jās kur lab par sie tas var vis ēc_ \
+         ība _bi _di _ie _ja _ko _mu _mē _mū _nā _pē _to _ve ad_ bi_ bij dar dz_ ika iņi \
+         ku_ mēs nāt pal pie pir ra_ ri_ rie si_ ska tā_ ums vak vie vs_ ēs_ ņa_ _ai _ap \
+         _be _bū _dr _dz _gr _kl _pr _sk _uz _vē _zi _šo aik an_ arb ari aun aus av_ dau \
+         drī dzi dzē dēj eid en_ esi ezi gad gal iek iet iez ija iji iju ils im_ irm izg \
+         iņš jis jā_ kat ko_ kād lsē līd mai mu_ mum mūs nav ned nie nu_ nāk os_ pil rau \
+         rms rā_ rāk sav sti sēt ta_ tav to_ tās udz vei zi_ zie zin zīv āju ājā āk_ āka \
+         ētu īdz īs_ ņš_ ši_ _ci _jā _jū _sl _tē _tū abi adu adz aja ajā ald anī ara atg \
+         aug avs bet brā būt cie cij cin div ds_ eic ekl ent esm ev_ gs_ ie_ iel iks it_ \
+         jad jie kam kas ki_ kla ksi kst kt_ lau lie lu_ ma_ nek ni_ nā_ nās nīc odi oja \
+         rib rāl rīt rīz sim smi stu sāk tad tei tev tgr ti_ trā tēv ur_ us_ usi uz_ vaj \
+         vas vec vu_ znī zēt āds āja ākā āli āpē ēji ēta īt_ īvo īz_ ņie šie šod ūt_ _an"
+    ),
+    profile!(
+        "lit",
+        Latin,
+        "ai_ _pa _ka os_ ti_ is_ as_ au_ _ne _ta us_ ar_ _tu _da _ir _iš _ma ir_ me_ iau \
+         tur vo_ _bu _ji _nu _pr _sa _va dar kad kai _la _na _su _vi ad_ aug gal iu_ sta \
+         tas uos uri čia _ga _mi ada avo jo_ ės_ _ar _gr _jo _ko _mu ali aus buv iai ien \
+         ini kar man pas su_ tai tų_ _be _me _ši aba ais dau ime kur nuo nės oli ryt ta_ \
+         uvo vis _ge _ja _ju _ku _o_ _po _ti _to _ži aka and arb bus da_ eik era es_ ger \
+         grį ia_ ie_ ių_ ji_ jis juo ko_ kra lab mie mus nam nau pad ra_ rai ro_ sav si_ \
+         ska tik tu_ uo_ vak vie _at _br _bū _ei _ki _no _pi _se _į_ ank asi ask aži die \
+         ei_ ent etu ian ies ika ist iuo iš_ jai jam jau jos ją_ jų_ kas kin kit kok kst \
+         lan li_ ma_ met mo_ ndi no_ nor oja ona ori po_ pri ria rie rįž sau se_ sti te_ \
+         tą_ važ vei yra čiu ėti žiu _an _ap _gy _mo _re _ry _sv _te _tr _yr aci aik aip \
+         ait ame ami ant anę aro ary ast ata auj ava ači bai bet bro bė_ cij dav eit en_ \
+         esn est et_ etų gai gyv ias iek iem iki ikr ima ino inė isi ite iti ius iči iūr \
+         je_ kam ki_ kia ksl ką_ lai lau lbė lia lik lis liu mes mon ms_ nei net ntr nęs \
+         oks ono ose oti pag par pre pro ras rau rei ri_ rim ris riu rol sak sia siu sli \
+         snė sto tad tin tis to_ tos tum ur_ urė usi vai yti ėjo ėl_ ėsi ęs_ šal šia šit"
+    ),
+    profile!(
+        "cat",
+        Latin,
+        "_de _el es_ la_ ls_ _se de_ _la _qu el_ que ar_ _al at_ _ha els ue_ _a_ _es _an \
+         _i_ _no _pe ns_ est per ra_ _ca re_ va_ és_ na_ nt_ _to _va er_ no_ ts_ _l_ _mi \
+         al_ ia_ _co _pa _po _te _ve da_ ent eu_ ir_ sta _d_ _en _ma _pr em_ res _am _di \
+         _fa _ho _un _és car des sa_ tat _aq _do _he _so an_ ant aqu cs_ del ens it_ os_ \
+         ta_ tot us_ _ci _hi _tr aci ada amb ana en_ ho_ is_ les mb_ nar om_ orn ots par \
+         rna ser tar tor una via _ai _av _me aba are as_ ció com ha_ he_ hi_ ire ió_ on_ \
+         ort pot sen seu ues ure _ac _ga _ge _ja _le _mé _on _pu _re _t_ air aix alg als \
+         ane ara ari asa avi ban bé_ cas cos ell emp era erm esp eur fa_ gai han has ica \
+         ics inc ist ita lic mat mil més nat nca nem obl ons osa ot_ pre ria tin tit tre \
+         tru ui_ _ab _ah _bo _du _em _fi _gr _gu _ll _m_ _mo _s_ _sa _si _ta _vi _vo ahi \
+         aig ame ani ans any ard ats avu bar ca_ cad can cat cie ciu cre dia don dos eix \
+         enc eri err ers erò ess eus fin ger hir iar ies ig_ im_ ima ina ine ins ion ira \
+         iut ja_ lar lla lls man mar men mer mic mir mà_ nci nir nti ola olt or_ orm pel \
+         pri pro qui què ran reb reu rin rmà rs_ rti rà_ rò_ sco seg sev sit sor ssi str \
+         tan tem teu tra uca uen un_ uta uè_ ver ves veu vui _aj _bu _bé _cr _ed _et _ex"
+    ),
+    profile!(
+        "baq",
+        Latin,
+        "en_ an_ _be ko_ _da ra_ _et eta ik_ ta_ _ba _du _eg ak_ ber era tu_ in_ rri bai \
+         _ze ait ez_ go_ ren te_ _di ain da_ egi err ia_ ri_ _ez itu la_ ste _bi ago are \
+         eko tze _ga arr kin na_ re_ rik rra _es _ha _na dut eki har ira tik _hi _la ald \
+         atz eha gin ntz on_ zen zu_ _ar _as _go _it _ni _on _zu ar_ atu dag ea_ ego ela \
+         ent gun ita itz tan uen _de _ho _le _no _or _za ana ari ate aur bat beh ean ek_ \
+         ere gau ino ite nai rai rat ria rte ter tzu un_ ur_ ut_ uzu zue _al _an _at _ge \
+         _zi agu ako ama ara ast duz ene etx hir hor iri ke_ lak leh ndo no_ ond ora orr \
+         rre sko tea tek txe _am _er _he _ja _jo _os _ur ada aha aia ala ask at_ ati dak \
+         dat den dir egu ehe ena eri esk gia hen ila lag lde mai nek net ngo oan oiz ori \
+         rek ten tur tza ua_ uan urr usi ute zea zek zer zul _ai _az _ka _mi _pa aiz ark \
+         azk de_ du_ dua ero ert est eza gar git goa goe gon hau iak iar ien iko ind ire \
+         it_ iz_ izu kar ker kit lan nah nir oak ola ord per rag ro_ rua tor tsu tue ura \
+         urt uru zar zo_ _gi _lu _te abe agi ahi aik ake aki art au_ beg bes biz bur dek \
+         dik dio do_ eit ema eti ger gir goi hai hi_ iek iet iki iku ina ing int iru ist \
+         iti iza ize izi joa ka_ kat kus mar men mil nak nar ndi non ntu ona ort oso par"
+    ),
+    profile!(
+        "glg",
+        Latin,
+        "os_ que _qu es_ _a_ _de ue_ as_ de_ _es _no est _o_ _se da_ on_ _ca do_ _e_ _po \
+         ra_ _te en_ nte _co _os _pa ar_ te_ ent _an _pe _pr des mos non ver _da eu_ nos \
+         sta ta_ _do _me _ve ro_ _mi _to is_ or_ par stá ón_ _ao _mo _na _on _sa _vo ada \
+         ai_ er_ ero ida nde per se_ tar to_ ía_ _al _ci _en _fa _ma _un aci ade ant cas \
+         dad gos ist ión la_ mo_ na_ no_ odo por res ría sa_ tes tod tá_ _be _fo _ir _tr \
+         _vi _é_ ame amo ana ao_ ara cad ció com ei_ ema end igo ive les lo_ me_ moi oit \
+         olv ou_ ous pod rda ron tem uer us_ vol _ag _as _du _ho _is _má _re _so _va _xa \
+         aba ado alg asa ata ben ber ca_ car che cid cio con dar das deb den dos eci edi \
+         egu emp for go_ gra ha_ ima imo ira ita ma_ mer mái nha ns_ ode oi_ ont pre re_ \
+         saí seg sem seu so_ tiv tra uen un_ unh vai xa_ zas áis án_ _at _ax _aí _di _el \
+         _gr _im _la _ou _sú al_ all alo ano aos ard arí ase cha cos cre dou ebe ele emo \
+         erc ere eri err exa eño gue hox iam ico ide ill inc io_ ir_ irm iso ite ito iu_ \
+         lic llo lve mat med men mig mil mir mpo mán nas nci nda ndo ntó oa_ och omo ond \
+         orm ort oxe pai pol pri pro rca rec rme rmá rqu rra rta sei sit sti sto str súa \
+         tam teu tón und ven xe_ ño_ úa_ _am _av _bo _ce _ch _cr _dí _ed _ex _fi _fu _gu"
+    ),
+    profile!(
+        "vie",
+        Latin,
+        "ng_ _ch nh_ _th _nh _kh _tr _ng ông anh _an _ph ôi_ _và _là hôn khô on_ và_ _tô \
+         _đã chú tôi úng đã_ _co con hún _cá _qu _gi _ta _đư ta_ ấy_ ồi_ _có _rồ ay_ các \
+         có_ hà_ nhà ong rồi ời_ _cả _nà _sẽ _đi _đâ là_ sẽ_ ơn_ ại_ _mẹ _ở_ ai_ hàn mẹ_ \
+         ào_ ác_ ên_ đượ ười ược ất_ ến_ ới_ ợc_ _bà _bố _nó _về _ấy bố_ ch_ làm qua ron \
+         tro về_ àm_ ày_ ần_ _bá _củ _từ _vậ bà_ cho của gườ ho_ ngh ngư này thà uốn ành \
+         ây_ đi_ ước ườn ớc_ ủa_ _hơ _lạ _mộ _na _sa _sá _ti _đế cả_ hơn hải hữn lại một \
+         nay như nhữ phả tra trư từ_ ua_ vậy ăm_ đây đến ải_ ậy_ ết_ ện_ ống ổi_ ột_ ững \
+         _ba _bi _bạ _ha _hô _họ _mu _mớ _nă _tì _vớ _xe ang bán bạn chí hi_ him hìn hín \
+         hôm hể_ hố_ im_ iến iết khi nói năm phi phố rướ sán thể với àng ách áng áo_ âu_ \
+         ìn_ ính ói_ ôm_ đâu ạn_ ảo_ ối_ ốn_ ừng ữa_ _bu _bê _bả _cô _cũ _gì _hà _nê _nư \
+         _rấ _số _vư _xo _đa _đó _để _độ _ơn an_ ao_ au_ ba_ biế bên bảo chi chắ cảm em_ \
+         ghe giờ gì_ gần hai hay he_ hiề hưn hất hấy hế_ họ_ iều iện iờ_ muố mới ngu nhi \
+         nhấ nào nên nó_ nướ rai rất sau tha thô thấ thế tìm vào vườ xem xon ài_ ánh ìm_ \
+         đan để_ ơi_ ưng ảm_ ập_ ắc_ ều_ ọi_ ội_ ờn_ ờng _ai _bọ _bộ _cà _cử _dâ _dự _gầ \
+         _gọ _hộ _ki _lậ _lị _mư _mấ _nữ _sô _tu _tă _tạ _tố _vi _vì _vẫ _à_ _đô _đẹ _đị"
+    ),
+    profile!(
+        "ind",
+        Latin,
+        "an_ ang ng_ _me ah_ _se _di ya_ _da nya kan _ka men _ak _pe _te di_ ta_ _ke ku_ \
+         _be _su ak_ aku per dan gi_ mu_ aka ari ni_ _ba _in _ya eng ini kit nga ran ri_ \
+         yan _ha _ki _ma _pa _ta ita lah _ti ber ela kam ter ada amu ana eka man ntu _de \
+         _ko ala ar_ ara dak lan tu_ ung _bi _sa agi at_ da_ dah dar har ing na_ tid tuk \
+         uda uk_ _ja _la _un aik ali aru ata au_ aya emb era eri gan ibu ida ih_ itu kal \
+         mem ora seb sud tah tan unt _du _pu ann apa asi bu_ den ebe elu in_ kak li_ ma_ \
+         mer ngg nny pa_ pi_ sel _an _ib ahu am_ ama any ban bel dia ema emu ere erg esa \
+         ia_ ian ima ir_ ka_ kem kot lam lu_ ngi ngk ngu ota pan rgi rus sa_ sek ti_ ua_ \
+         uan uh_ us_ _ad _le _li _mu _or ai_ al_ bah bai bih ebi eja ele epa ers eta ik_ \
+         ika ila ilm isa jan leb pag pul rek tap tel uka ula um_ un_ uta _ap _bu _fi _it \
+         _si adi amp ant ap_ asa bag beg bil bis bun dua egi ena end ene ent eny erb eru \
+         fil ga_ gai gga gin gun hu_ ja_ ker lum mar mba mpa nja nta pad ra_ rin sej si_ \
+         tam tar tem tet ulu yak _ay _ju _ra _ru _tu aha ahm aja alu anj api awa bar ben \
+         car dal dul eh_ eke eme eni epi erj ese gal ggu git gka gu_ hat hmu hun ikl jad \
+         jak kah kar kas ke_ khi kin kla kop la_ lal les lia mah mas mat mau mbe mua mus"
+    ),
+    profile!(
+        "may",
+        Latin,
+        "an_ ang _se ng_ _da ah_ _di _me ak_ ya_ kan _sa _ka _ke aya _ma _te _be say ta_ \
+         ada _ba _pe awa da_ _ta ar_ ari di_ ni_ dan dar ini ita nga _pa ber _su at_ gi_ \
+         kit men ter _ki aka ala dia ia_ ing per ran ri_ tu_ _in ahu am_ ban eng kam yan \
+         asa itu lah na_ sem uk_ _aw _ha _la _ya ana ela ik_ lam man nya ung _ti amu dah \
+         eka era eri mu_ ngg ngk ntu pad pan seb tan tuk _ak _un agi ama ata den ema epa \
+         ere gan ih_ mak nda ua_ unt wak _de _du _it _pu _si ara elu emu gka har hu_ ka_ \
+         lan ma_ mah mer ora sa_ tah ti_ _bu _ja _le _mu _or ai_ al_ ali amp and apa as_ \
+         bah bel dak ebi eh_ ga_ gar jan kal ker mem pa_ pas pi_ rek uda _tu aha ap_ bih \
+         ebe eja ele ena erg eta gga ima ipa ke_ leb leh li_ lum mal mas mpa mua ole rgi \
+         rip sek sel tak tel uh_ ul_ um_ uma wan _bo _he _ju _ne _ru aba aca aga aik ant \
+         any asi au_ bai bol bu_ bua dua eba end eni eru ggu gu_ iap ibu ika ir_ ja_ kat \
+         kaw lag lu_ mat nin on_ ra_ rja rum san sej sih sin sud tam tia uat ula ulu un_ \
+         uta _ab _ad _ca _ce _ko _ra anj ann api ati atu bal beg bet bin cam car dal dat \
+         efo ega egi eko emb enc ent erb erj etu fon gai gal gat git hab hul hun ikl ina \
+         is_ it_ kas kel ken kin kla la_ lef lep lik lim mac mel nah neg ngu nja nti ong"
+    ),
+    profile!(
+        "afr",
+        Latin,
+        "ie_ _di die et_ er_ is_ _he an_ ek_ _ge _is het nie en_ om_ aar _ni _wa at_ it_ \
+         _ek _en _on _va ar_ ns_ van al_ ons _ko aan ou_ te_ _be _hu _jy _me _te _ve in_ \
+         jy_ ste wee _we de_ dit ter _in _ma and es_ kom ver _da ier ig_ le_ nde _al _ga \
+         _ho _mo _my gaa ges maa moe my_ nd_ oor _om _sa _so ag_ dat eer end lle uis _jo \
+         _no _vi ens hul ist tig ull wat _by _ha _hi _pr _to _vo as_ dan der era est hui \
+         ir_ jou ker na_ oer oet rie rk_ sal sie vir _ba _br _do _hy _ka _n_ _pa _re _st \
+         _sy _wo aak aat ak_ bel bes bro dag ees el_ gen ger haa hie hy_ ies kan met oe_ \
+         ond op_ ord re_ reg roe sy_ vol waa wer _af _as _kl _mi _na _oo _wi ad_ ark by_ \
+         eek eet eko ele erd erk ers esi gti iek ien ing laa lie mee men nda ng_ nou nt_ \
+         oek oen olg on_ pre raa rs_ ry_ se_ sta tad toe ur_ wil _aa _dr _ee _fl _gi _go \
+         _gr _hê _ie _ja _ky _ou _se _sk _vr aam af_ aie am_ ang ank bai ede ee_ ema ere \
+         eri eru fli gek gel gem gge gis goe hel hoe hom hoo hê_ idd iem iet ike il_ ise \
+         jaa ke_ kie kyk lan lge ma_ mal man mid od_ og_ ood oon or_ pa_ par ran rd_ rdi \
+         res rge rug saa son sê_ tyd uit uur voo was we_ wor yk_ _an _bl _el _ju _kr _la \
+         _mu _nu _ná _og _ri _si _su _tw _ty _ui aai ai_ all alm ann ant arl arm asi att"
+    ),
+    profile!(
+        "epo",
+        Latin,
+        "_la la_ as_ is_ _mi aj_ est _vi on_ _es oj_ _de _ka mi_ aŭ_ kaj vi_ tas sta in_ \
+         li_ ne_ _ki _ni _po de_ en_ os_ to_ ni_ _ma _ti ro_ _ne _ve _ĝi ia_ jn_ _en _re \
+         _ĉi _al _an _ke _li _pa _pl _pr al_ aro do_ no_ ta_ vas ven _da _he _se eno ili \
+         ke_ ova por sti un_ _do _el _ku _te _ĉu an_ dis el_ ero fon ion iri iuj kon kun \
+         ojn or_ roj sto tis ĉu_ _bo _tr _ŝi ajn ant ate bon era iu_ jo_ las mal nis non \
+         nta pli pre re_ ris ten uj_ ĉi_ ĝi_ ĝis _du _fr _ho _il _ir _ri _si am_ ank ano \
+         ato dan dev du_ ejo eve hej ist kie mat mil min nin nov par per rat raŭ rev ti_ \
+         tiu tos tro ver _am _aŭ _ba _di _fa _fi _fo _hi _ja _ko _lo _mu _no _ol _sc anĝ \
+         ard da_ ejm ele eni ere ian idi ie_ ier io_ ita iĝi kiu men mon nko ol_ ome ona \
+         oni ono pro rbo res ri_ ron sci str taŭ tio ult urb via _be _mo _pe _ra _so _su \
+         _tu _ur _vo _ĉe ado atr ava ble bo_ bor cia dia dir ed_ efo ej_ eli ene eva ezi \
+         far fin for fra gra hie hod iaj iam iaŭ iel iko ini ino ito jar jme kia koj lef \
+         lin lir loĝ maj me_ moj mul muz na_ njo noj nto odi ola omo one ons ont ora ort \
+         ost pat pos pov ran ras rig rmi sed ser spe ste te_ tel ter toj tra tri tru tu_ \
+         tur uta vol zit ĉiu ĝin ŝi_ _at _av _ce _eb _fe _gr _ha _in _io _iu _je _kr _lu"
+    ),
+    profile!(
+        "tgl",
+        Latin,
+        "ng_ ang _na _an _ka _ma _sa sa_ an_ na_ ong _ng _pa at_ _ta ala ay_ _si in_ _at \
+         _ba ayo ga_ aka on_ ya_ gan iya ko_ _mo aha ila la_ nan yon mo_ _mg ata mga to_ \
+         _ko ama ara tay yo_ _ni aga ing lan _ak _ay ali as_ ati awa hin nga ana asa han \
+         ka_ pan tin ung _ha aan aba ina ini ito nak _it _mu abi ag_ ako ano kay mag mam \
+         mas siy ta_ ula uma _hi _tu ago aki bag nag ndi niy yan _al _gu ail ani aya ba_ \
+         di_ go_ ind it_ ita kai kal lam mul noo od_ os_ po_ tat _da _di _ga _ku _la _li \
+         _lu _po _su apa apo asy bah bi_ gus hal ili kin lin maa may nta par pat ra_ ro_ \
+         umu ust _bi _in _pe _pu ahi ami dal gay hay isi kak kap law lun mah man mun nat \
+         nil per sal sto tag tao unt wan _ar _iy _um ado aho am_ and aon aw_ gin hat id_ \
+         ig_ iha ika is_ iyo kan kas kat kon kun lag lik lis ma_ mak mal mar mat mus nap \
+         nas ngg nin nit no_ non og_ oon pa_ pag pam pas pin pon raw saa sig sin tan tap \
+         ulo upa wa_ wag wal _bu _il _pi _ti _up _wa agi agp alo ap_ ark ayi ays bak ban \
+         big bil bin bis dig do_ ent era gaw gma gon gpa gso hap ibi iga ipa iwa kab kag \
+         kau kit lah li_ lib lim lo_ log lon mil nda ngs ngu nig niw nod paa pos pun rke \
+         sab sak si_ sil sit sod sum sya syo tah tal tid tul una uno usa usu uwi wi_ yin"
+    ),
+    profile!(
+        "swa",
+        Latin,
+        "wa_ na_ _ya ni_ _na _wa ili ka_ _ka ya_ _ma ali _ku _ni ana _ha _kw _hi aka li_ \
+         ma_ _ki _ba _si ani ari ata da_ kwa ika mba _sa _ta ang ini ita ko_ ri_ yo_ za_ \
+         azi hi_ si_ te_ wan _za di_ end ia_ iki la_ nda sik tu_ ua_ yak _la _vi aki ama \
+         ati atu awa cha iku kat kuw ngu ta_ tan una uwa wen zim _an _il _mi _ny aba ake \
+         amb ara ba_ ila ima ish iyo ke_ ki_ kwe lik mu_ uli usi _hu _mt _us aji asa gu_ \
+         ha_ ifa isi ji_ kil ku_ nil nyu sa_ san umb vyo zi_ _al _ch _mp _tu _un adh aku \
+         ame anz asi bad bu_ do_ eo_ eza fu_ hal hiv hiy ibu idi iko imu ivy iwa kar kul \
+         kun mak naw nga nis nzi ote rib sin tak tat ti_ tik to_ ung uni wak wam wat wez \
+         ye_ yum zo_ _am _as _ga _ja _le _mb _mj _mu _um aad adi aha aid ako and any ao_ \
+         apo awe aya bab bar dhi ema eng fad fik han hat haw ime ina isa itu je_ kin kit \
+         kut laz leo lia lim lis liz maj mar mji mpa nat nge oka oto ra_ rud sha tar taz \
+         tok udi uu_ _at _he _li _mw _nd _nz _wi _wo abl ada ado afa afi ahi amu ann apa \
+         api aru ate ayo aza baa ban bas bil bla bun che de_ dil eke eli eni eny ewa fan \
+         ga_ gaz gi_ go_ hif hwa ian ich ii_ ija iji iri ivi iye jan jua kab kah kak kan \
+         kaz kia lak lit liv liy mbi mtu mwa nde ndi ngi nin nya nye pa_ pig pya saa saw"
+    ),
+    profile!(
+        "wel",
+        Latin,
+        "yn_ _i_ _yn dd_ _ma _r_ _y_ edd od_ ae_ mae _n_ _dd ydd di_ _ei _ar _di _ni _o_ \
+         ddi th_ _bo _dy oed wed _we ch_ ddo edi ni_ _fe _ff au_ eth yr_ _gw _a_ _ti bod \
+         wn_ _cy _dw _he _ne _rh _yr an_ ei_ ti_ _gy _hi _pa ad_ aet ar_ en_ lla na_ nd_ \
+         wyd _ad _br _by _ca _ym dda dw_ el_ ell hi_ id_ odd on_ _ac _fy _ga _ôl ai_ all \
+         am_ awn da_ dim dod dy_ fe_ fel fod gwy im_ ych yd_ ôl_ _am _co _da _hy _ll ac_ \
+         ach af_ ais arc byd din doe io_ ir_ lly ma_ nas nes os_ wel yma ynd ynn _an _be \
+         _ha _nh _un _wy aid ara cyn dai ddw diw er_ es_ hon hw_ iau ina lad lan li_ new \
+         nhw nio nna ol_ ref rha st_ wyt yw_ _ba _bl _ch _do _fo _ia _id _mi _my _ro _si \
+         _wa _yw _â_ add adr ael afo as_ awd ble bob ddy dia dio ef_ ein eis eu_ eud ffe \
+         ffi fon fyn hai hed hel hyd iaw idd ig_ iol is_ ist ith lai lli lwy ly_ lyn myn \
+         neu obl rch rdd re_ rhy roe sia ud_ un_ wy_ wyb ybo _al _de _e_ _et _go _me _na \
+         _oe _on _pe _se _ty _wi _ys ada ana ann aro ast bor bro car cho dad dae dan do_ \
+         dre dwy dyc dyd dyn eil eld ene enw eto ewy far fen ffo fi_ fil fy_ fyd gor gwe \
+         gyd gyn han hod hyf hyn ila ili in_ ion isi iw_ lch ld_ le_ ll_ lu_ lyw mam ned \
+         nne no_ nyd olc ond oni ore orf pen rad rae rau raw ria rth to_ uni wai wd_ wir"
+    ),
+    profile!(
+        "aze",
+        Latin,
+        "_n_ ir_ _m_ _bi _on _r_ lar an_ ün_ _ki _t_ _v_ _d_ il_ _bu _h_ _ol ki_ _qa _sa \
+         arı onu _ha _k_ _l_ _s_ _yi aq_ _ya bir bu_ da_ dan dir edi in_ ın_ _ed _g_ _ni \
+         _ri _üç ada ar_ axş ayı di_ nd_ rın ını ır_ _b_ _ba _gö _q_ _so _ş_ aca bil im_ \
+         izi na_ nda ni_ nla nu_ ox_ ri_ un_ zi_ çün _da _de _et _ev _ge _gü _he _is _li \
+         _mi _zi _ço am_ is_ la_ nı_ ola rad rl_ son vv_ yir çox üçü _c_ _pa _vv _ye alı \
+         amı ara ard caq dır etm eyi gün har ib_ ili ind ini ist lma min nun qay rd_ rda \
+         rs_ tm_ xşı yax şam şı_ _ax _do _dü _fi _ik _il _ld _mü _nd _rd _rs _te _ti _y_ \
+         _yo _z_ aha ana arl ata axt aşı biz dah dey dim dün ed_ ev_ ha_ idi ik_ iki imi \
+         irl iya iyy kim ma_ on_ onl par rla st_ sın tdi tir ur_ yen yla yox yy_ zl_ ör_ \
+         ıb_ ınd _an _ar _kö _la _lk _na _ne _qo _rl _ta _va _ça _öl aba adı anı ari ark \
+         art ası at_ ağı bax bur daş ec_ eni etd ey_ eyl ged gör göz ilm irm iti iş_ kin \
+         lan ld_ ldi lin lir lis lk_ lm_ maq mi_ miş ml_ müh mış nec nim nin nra olu ona \
+         ond onr ort qal qar qla ra_ rtı sab san sı_ tan tur uma ura xşa ya_ yar yil yıt \
+         ziy çay ür_ üç_ ıq_ ız_ _al _am _at _bü _el _ft _hi _hv _in _iz _iş _kk _le _mu \
+         _nb _ng _o_ _pr _qu _rk _su _sö _sü _tl _to _uz _ve _xm _ç_ _çö _çı _şe aat ab_"
+    ),
+    profile!(
+        "bul",
+        Cyrillic,
+        "на_ та_ _на да_ _да _от _по то_ ата ва_ _пр те_ _до _е_ _и_ _не _то от_ _се не_ \
+         ни_ ще_ _за _си ите ли_ се_ _в_ но_ _го _ка ова си_ ти_ _ще де_ лед _из _ли _тр \
+         ава ат_ ди_ еди ина ото пре ред сле че_ ъде _об _па _с_ _са _сл ван го_ гра ето \
+         ме_ мен ра_ са_ ят_ _гр _ме _мо _ни _ст _ти _че бва бра год дин доб ен_ за_ зи_ \
+         обр рав рад ряб тря ябв _бр _вс _въ _ко _ми _му _но _ня _ос аме два ед_ ия_ ият \
+         ка_ как ма_ мож нат нов оже оти пар по_ рат ри_ ска тов ше_ _бе _бъ _ви _вр _ис \
+         _къ _ма _мн _ра _ре _су _съ _та _те _ѝ_ ади ай_ ам_ ана ари бре ват веч ви_ ги_ \
+         едн ем_ еме ент ера ече еш_ еше ил_ ист кат къд ла_ ми_ мно нал нап ник ног обе \
+         ове ови ога ого ода оди ой_ паз про сно ста сти тел тид три ът_ яко ята _а_ _ба \
+         _би _бл _ве _во _дв _дн _зн _ощ _см _тя _у_ _ча ави аго аза азв ази акъ амо ане \
+         ани ано арк аха аци аща беш бла бъд вам вит вот вре вси вър га_ гав дар дем дне \
+         док дом езе ези ека ени ес_ ете же_ зва зем зна иде ик_ или ини ион иск ица ичк \
+         иш_ йде каз ки_ ко_ кои кой къв лаг лиц му_ нам нес нит нта няк ома оме она опа \
+         оре очн още пов пом пос поч пра при ран ре_ рек рем ро_ ря_ сет сич сто тво тни \
+         тог тор точ тях утр ха_ цат чер ши_ що_ ъв_ ях_ _ан _вч _ги _жи _зд _зе _оп _пе"
+    ),
+    profile!(
+        "scc",
+        Cyrillic,
+        "да_ је_ _да _је _по _са ма_ _не ли_ _на _у_ _пр ла_ на_ ам_ ти_ _и_ _се не_ _мо \
+         рат то_ _би еш_ има мо_ рад ро_ сам се_ те_ _до _за _ко ас_ ко_ нов _из _од _су \
+         ао_ ве_ де_ емо ло_ нас пос са_ ста _об _ра _ст ала ати во_ од_ ора пре ју_ ће_ \
+         _ви _вр _го _ка _ку _ли _ме _ни _он _ре _си _ти _то _тр _ће ада али гра ека им_ \
+         ио_ как ове ра_ су_ та_ што _а_ _бр _ве _га _ми _ов _от _св _та _шт _ње ади ако \
+         ан_ вал га_ ди_ ена иде ина их_ ија ије лим мож нем но_ ова ови ом_ ој_ про ре_ \
+         ред си_ ско сле ура _гр _ид _ис _ма _мн _но _ос _јо _ју _њи ало ана ај_ би_ бра \
+         бро ви_ вра го_ дан дем дин его еде ера ете еће зна ила или ист ка_ ке_ кој кућ \
+         ле_ лед мам мен ми_ мно мор нда ниј ног обр оди ост рав рек сти тво ја_ ћи_ ћу_ \
+         _ал _ба _гд _гл _др _зн _му _па _ск _сл _те _ур ава ави авн акв ама амо ано ари \
+         ата ате ашт ају бал вид виш вни вре где гов год доб дру еба еда еди еко ем_ ема \
+         ене еси жеш за_ заш из_ ине ион ици иш_ ком кор ме_ мог нај нек неш ни_ ник ниц \
+         ном оже оли она онд оро осе осл оти ош_ оји по_ под пом ран рас реб рем рим руг \
+         сет ств тан тај тка тор тра тре ту_ уде утр уче хва це_ шта шти јес још јут њих \
+         _ан _бу _дв _де _ду _жи _зд _зе _ле _оп _пе _ус _фи _хв _хи _хо _це _чу _љу _ћу"
+    ),
+    profile!(
+        "mac",
+        Cyrillic,
+        "_на та_ _да _по _не ата на_ те_ да_ от_ _се _во се_ во_ то_ _од не_ _до _то ат_ \
+         ите ме_ _и_ _пр _за ти_ _мо од_ ја_ ќе_ _го _де _е_ ам_ ка_ _ка _со _ќе аш_ ека \
+         нат ни_ рат _ре _си _ти _ја ва_ де_ ро_ што _би _ве _вр _ме _ми _па ави ај_ ви_ \
+         гра еко ето ина иот мен ми_ обр ова ови оди пре ред ува _гр _шт ари го_ год дек \
+         дин ед_ еме ене еш_ ко_ ли_ ма_ мож но_ ов_ оже ој_ рав рад ри_ со_ ушт ше_ ште \
+         _бр _ви _из _ко _му _ос _ра _сл _ст _тр аа_ аат адо ако ара бра бро ваа гаш ди_ \
+         доб едн ела еше за_ ив_ ист ија лед нов ога ода ора ра_ си_ ста _а_ _дв _ма _мн \
+         _н_ _ни _об _ов _уш ава аде ади али аме ана ати ба_ вра ги_ гу_ дал дом его езе \
+         ени еќе зем зна иде име ини как кој ку_ мно мор му_ нај нес нио ног оа_ огу ои_ \
+         ома осе ото пар пос про рам рек сет сле тан тат тоа јав јат јде _ба _бе _бл _ги \
+         _гл _др _зе _зн _ил _ис _но _от _са _су _та _те _це або аго ака арк аци ајд би_ \
+         бид бил бла веќ вид вит вот вој вре гов дар дат дел ден дим дни дов дру еба еда \
+         еде еди емј ен_ ена ера ес_ есе ет_ жеш зле изл ил_ или ино ион ици иш_ кад ков \
+         кои кот ла_ лаг лад лат лез лку нег нек неш нив ние ник нот ола оре оче оја по_ \
+         пол пом поч пра реб рем реч руг сак сед сит сти тво тко тог тор тој тре тро уга"
+    ),
+    profile!(
+        "rus",
+        Cyrillic,
+        "_по _на то_ _не не_ _и_ _чт ла_ но_ что _в_ _пр _я_ _до ть_ _бы _с_ _ты да_ ет_ \
+         ой_ _за их_ мен ми_ на_ сь_ ся_ ты_ _он _со _те _то ала му_ ня_ ом_ оро пос ра_ \
+         сле ти_ _во _мо год его ера ить ка_ ло_ _вс _вы _го _из _ка _мн _но _об _хо _эт \
+         был ени как лед он_ осл ста сто тво тор это _де _зн _ис _ко _ма _ме _мы _ни _ос \
+         _па _ра _се _сл _ст ани ас_ бра буд бы_ гда го_ дел дня ей_ ем_ ему ешь зна из_ \
+         ист ия_ ли_ ма_ мож мы_ нам нас ны_ одн она оче при ран сег чер шь_ ые_ _бу _ве \
+         _их _ле _от _ре _са _см _сп аду ак_ ал_ ам_ ать ают ая_ бол во_ вой все дал дет \
+         дом еди ент ень еня за_ ие_ им_ мно ник нит нов обы ов_ огд ого ода оже ока омо \
+         ост ото пок рав рат ри_ ств тоб том тьс уде ься ют_ _а_ _бо _бр _вч _гд _др _ег \
+         _её _зд _ми _оп _св _ск _у_ аза аки али ало ами арк асн аци аю_ бе_ ван вда вче \
+         где гор де_ дем ди_ дит до_ доб дру ебе еде едн еду ее_ ела еле есь ети её_ жно \
+         ико ил_ ион ит_ ите йти ки_ ким ле_ лет лиц лос льш мал мам мер мот над ни_ ние \
+         ния ной обе ова оли оль ому они ось отр очн ошо пар пас поч пра пре рек ро_ род \
+         рош ска смо спа та_ те_ теб тог точ три тся ту_ хор хоч ция чен ше_ шо_ шёл ыла \
+         ых_ ёл_ ём_ _ан _бе _ва _вр _дв _дл _ей _ещ _жи _к_ _кл _кт _му _ну _од _оч _сд"
+    ),
+    profile!(
+        "ukr",
+        Cyrillic,
+        "ти_ _по _ві _на _до _за не_ ого _що ся_ _не ми_ _я_ від _бу _з_ _ти _і_ ла_ му_ \
+         на_ ні_ _то го_ ть_ _ма _пр ому що_ _у_ ися их_ ку_ сь_ _зн _пі _ст ати буд мен \
+         нас іст _в_ _ви _йо _ме _мо _та _тр _як ала але али ас_ год доб же_ зна ив_ ки_ \
+         ло_ мо_ ни_ ня_ при ра_ роб ста тис ува іль _зр _ми _мі _ро _сп _це _ча ба_ ви_ \
+         він ди_ дом ді_ ере им_ ити йог ли_ ля_ мож но_ нов ов_ одн оді ок_ она оро сто \
+         то_ ці_ ьог ють ін_ _во _де _ди _ка _об _ос _па _ра _са _сь _те _хо _їх _її ам_ \
+         ано ато аю_ бра бул вій де_ див дні до_ еле ене за_ зав зі_ иви ий_ ими нам них \
+         об_ оби обо обр обі ове оди оло ою_ пов ран рат рок сьо та_ тод тре це_ час щоб \
+         ів_ ід_ іда іти її_ _а_ _ал _ба _бр _бі _ва _вв _вч _го _гр _дя _зі _лю _му _ни \
+         _но _св _су _тв _ус ав_ авд ай_ ало ами амо ане анн арк аці ают бер бре був бі_ \
+         біл ват вер во_ вон вор ві_ віт дал дві дем дин дов доп дяк еба емо ент ера ефо \
+         еш_ зал зро ись ким ле_ леф лив лиш лос люд мал мам мог мін міс над ння нок нув \
+         ньо ова ові оже оки оли ом_ опо ора ост ось пар пок пом поч про ре_ реб сам сте \
+         сту тел тоб том тос ту_ уде ула усі фон хал хоч чор чі_ ше_ шов яки яку їх_ _ан \
+         _бе _вд _га _дв _дл _др _ду _ді _зм _кі _лі _мн _ні _ох _рі _се _ск _ту _фі _хт"
+    ),
+    profile!(
+        "bel",
+        Cyrillic,
+        "_па _на не_ ць_ _з_ _не ла_ _да _за _та _і_ дзе _ты _я_ ала на_ _ка бра мі_ ны_ \
+         ра_ _га _дз _пр дзі та_ _шт _ў_ ся_ то_ ты_ што ыя_ _ма ай_ да_ му_ нав ня_ _ад \
+         _вы _до _у_ ава зе_ нас оў_ ста ца_ цца ці_ ім_ іх_ _бы _ва _ве _гэ _ра _са _сп \
+         _ча _як аб_ адз алі ама ана вед вы_ го_ гэт еда ка_ ку_ ль_ ля_ ма_ мал мян ння \
+         ора пра рад ыў_ іць _бу _мн _мы _мя _тр _яг _ён _іх ага ада ам_ амі ас_ аст ацы \
+         аць аю_ аў_ буд ва_ га_ доб дом ды_ за_ лі_ мы_ обр оль ран уль цы_ эта яго яе_ \
+         ён_ _аб _бо _вя _гл _го _мо _ст _ся _сё _яе аві адо ады ак_ аку але аму анн ары \
+         асл ачы ба_ бол вай дал дзя ем_ ера зем зі_ каб кул ле_ льм льш мож най нам ніц \
+         ой_ пад пай пак пар пач пры раб рат ры_ рэб спа сці сён там трэ цыя час чы_ ым_ \
+         яку які яне ённ іцы _а_ _ал _ап _ба _бр _зр _кр _мі _но _св _су _тэ _ус _ха _ян \
+         _ўв абе абі аве ад_ адн аду айс ака акі аль ара арк асу ата ача аш_ ая_ бе_ был \
+         вац ве_ вяр гад гля гор доў едз ель зас заў зец зра зяк зін йсц йшо кав кал кол \
+         куй кі_ кім лас леф льн ляд ліц маг мам мно нен ным ныя ожа пав пам пас раз рам \
+         ру_ са_ ска су_ таб тад тва тор тэл удз уй_ це_ чор шоў ыла ыне ыся ьш_ эба эле \
+         ядз яна ёй_ іла іль іст іў_ ўве _ам _ас _ах _бе _гр _гі _ед _жы _зд _зн _кл _ко"
+    ),
+    profile!(
+        "ara",
+        Arabic,
+        "_ال اً_ _من الم من_ _في _أن في_ رة_ لى_ ها_ _وا أن_ الح الب وال _إل إلى ين_ الس \
+         ان_ دة_ كان ني_ ون_ ية_ _أي _جد _كا _لي _مع _هذ _هل ات_ ارة ال_ الج داً قة_ لما \
+         ما_ نا_ هل_ هم_ ير_ _أع _أم _إن _بع _عل _لك _وأ الأ الو اً، تي_ عد_ قبل قد_ لا_ \
+         لمد ماً نت_ نك_ ً،_ _با _سي _قب _لأ _مت _يم إنه اء_ الش الص الق بعد بل_ بيت دك_ \
+         دين ذا_ ذه_ ذهب راً ريد لك_ لم_ لنا ليس مكن نني نها هذه ي،_ يرة يس_ يمك يوم _أص \
+         _إذ _ان _به _تع _حت _ست _عن _كل _لا _لم _وك _ول _يت _يج _يع أصد أي_ أين إذن اح_ \
+         الآ الت الث الخ الد الن الي اية اً؟ باح بال بز_ تري تى_ جب_ جمي حتى خبز خرج دقا \
+         دون ذلك ذن_ رت_ رج_ ساع شكر صبا صدق عرف على عن_ عود غير فيل قاً قري ك؟_ كرا كنك \
+         كنن كون لأن لاث لبي لحد لحر لسي لقد لكن لن_ ليل ليو م،_ مة_ منذ نذ_ نذه هذا ولا \
+         وم_ يت_ يته يجب يدة يدي يرا يف_ يقة يلم ً؟_ _أخ _أر _أك _بن _بي _تأ _تر _تش _تم \
+         _حس _دا _در _ذل _سأ _سا _سن _شك _شي _صب _عا _عد _عم _فق _قر _قل _قه _لق _لل _لن \
+         _ما _مر _مس _مش _مغ _نح _نخ _هن _وي _ير آن_ أتي أحد أخو أخي أسب أسو أعر أكث أم_ \
+         أمس أنك أنه ئي_ ا،_ ائي اتص اذا ارع اضي اعد اف_ الا الذ الع الف الل ام_ اما انت \
+         انظ بات باً بدا بنا بوع ة،_ تغي تفع تك_ تما ته_ تها ثة_ ثر_ ثلا ثير جدا جدي حدث \
+         حدي حرب حسن حو_ خي_ خير دت_ دث_ درج درس ديد ديق ر،_ رب_ رطة رف، رني ريب زار سار"
+    ),
+    profile!(
+        "per",
+        Arabic,
+        "می_ _می _بر _به به_ از_ ند_ _از _با _و_ ین_ _در _کن ان_ ده_ را_ _ای _ها در_ _خو \
+         _را ای_ این ها_ ید_ _آن _ما _من _هم _تا _دا _دو برا ست_ نه_ آن_ ار_ انه ما_ مان \
+         یم_ _دی _سا _شد است ام_ اید لی_ من_ نی_ های وز_ _اس _ام _کر _که ته_ خان خوا روز \
+         ور_ کرد که_ ی؟_ _بی _تو _خی _رو _صب تما د،_ دان رای ن،_ هر_ واه ون_ چه_ یش_ یک_ \
+         _بو _تر _خا _نی _گر ادر اری انی ایی با_ بای برو بود تر_ دار دم_ دید رد_ رفت روی \
+         زار شده صبح م،_ مام وان ود_ ویم یلی _ان _او _تم _شه _هس _پس _کا _کم _یا اشی ال_ \
+         اند انم باش بح_ بر_ تا_ تند توا خیل ستا ستن سه_ سی_ شهر فت_ مه_ ندا نم_ نگ_ هست \
+         وست ول_ پس_ کار کند کنی گرد یرو یند یی_ _آم _آی _جن _رف _زم _زن _سه _مو _نم _هر \
+         _وق _پا _پد _پی _کج _کس _کش _گو _یک آین ا،_ ات_ ارا ازه اما امر اهی ایش باز برگ \
+         ت،_ تاب تاز تان تی_ جا_ جنگ د؟_ درت درس دو_ دوس دی_ راد ران رت_ ردم رده رم_ رها \
+         رود ری_ زه_ سال ست، شته شد_ شی_ طور فته ماد مرو مین نان نجا نده نش_ نمی نید نیس \
+         هم_ هما همه همی وش_ وقت وه_ وی_ پدر کجا کر_ کسی کن، یر_ یس_ یست ینی _آب _بب _بخ \
+         _بد _بس _بع _بن _تغ _دق _ده _زو _سر _سو _شا _شن _شی _طو _فی _قب _قد _قه _مج _مر \
+         _مم _نا _ند _نز _نگ _هز _هف _هن _هو _ول _وی _پن _چط _چن _چه _گف _ی_ آب_ ارت اره \
+         ارک ازی اس_ اشت اعت امش ان، انت انش انو اه_ اها اهد او_ اور اول اً_ ای؟ ایت ببی"
+    ),
+    profile!(
+        "urd",
+        Arabic,
+        "یں_ _می سے_ میں ہیں نے_ _ہو _سے _کے _کی کے_ _ہے _ہی ور_ _او _تم _کہ ے۔_ اور _کر \
+         _ہم تم_ ں۔_ _کا _کو وں_ _دو _نے نہی یا_ یں۔ _اس _ان _تھ _دی _لی اس_ رے_ کا_ ہے۔ \
+         _نہ ئی_ کہ_ ے،_ _جا _سا _وہ ات_ ان_ ا۔_ تے_ لے_ وہ_ کیا ھی_ یہ_ _بھ _رہ _گی _یہ \
+         دہ_ ری_ میر نا_ کو_ کی_ ھر_ _زی _پا ادہ بھی تھا زیا کر_ ہم_ ہے، یاد یک_ یے_ _اب \
+         _با _ٹھ _پہ _گئ _گھ ال_ اں_ دیک لیے نی_ پہل گھر ہلے ہمی ہو_ ہوں یکھ _اپ _تو _لو \
+         _چا _گا ائی ار_ ارے ام_ اپن تمہ تی_ رہ_ مار وئی ون_ ٹھی کہا ں؟_ ھیک ہاں ہر_ ہی_ \
+         ے؟_ _آج _بع _بہ _تی _جن _دا _در _سب _سن _سک _فل _پر _گے آج_ ئیں ا،_ ارہ بعد بھا \
+         بہت تو_ جان جنگ را_ رت_ رنے روں زار سب_ سکت عد_ لوگ مت_ و،_ و؟_ وگ_ و۔_ پر_ چاہ \
+         کرن کری کل_ گئی گی۔ ں،_ ھا۔ ہا_ ہت_ ہو؟ ہو۔ ہے_ ی،_ یرے یسے یں، یں؟ ی۔_ _بج _بن \
+         _تع _را _سو _شک _شہ _صب _طر _فو _مع _نک _پھ _پی _چل _چھ _کل _گر ئے_ اتھ ارا ارت \
+         ارک اری انو انی اگل بح_ تھ_ جائ جے_ داد دوس رات رک_ ریہ سا_ سات سار شکر شہر صبح \
+         علو فلم فون لوم لی_ لیک معل مہا می_ ند_ نوں نکل نگل ورت پار پنے پھر چھو کتی کن_ \
+         کوئ کھو کیس گا۔ گیا گے؟ ھا_ ھائ ھوڑ ھے_ ہار یری یسا ین_ یکن یہا _آ_ _آؤ _آب _ام \
+         _اچ _اگ _ای _بد _بر _بڑ _بی _تا _تر _تق _تک _جو _خت _دھ _ذر _رو _سچ _سی _شا _ما \
+         _مت _مج _مد _مز _مل _نئ _وا _پو _پچ _پڑ _کن _کچ _کھ _ہز _ہف ؤں_ اؤں ائے اب_ ابو"
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::middleware::Http;
+    use tauri::async_runtime::block_on;
+
+    /// `GetSubLanguages` as the server answers it: `SubLanguageID`,
+    /// `ISO639` and `LanguageName` of every language open for upload.
+    const SUB_LANGUAGES: &[(&str, &str, &str)] = &[
+        ("afr", "af", "Afrikaans"),
+        ("alb", "sq", "Albanian"),
+        ("amh", "am", "Amharic"),
+        ("ara", "ar", "Arabic"),
+        ("arg", "an", "Aragonese"),
+        ("arm", "hy", "Armenian"),
+        ("asm", "as", "Assamese"),
+        ("ast", "at", "Asturian"),
+        ("aze", "az", "Azerbaijani"),
+        ("baq", "eu", "Basque"),
+        ("bel", "be", "Belarusian"),
+        ("ben", "bn", "Bengali"),
+        ("bos", "bs", "Bosnian"),
+        ("bre", "br", "Breton"),
+        ("bul", "bg", "Bulgarian"),
+        ("bur", "my", "Burmese"),
+        ("cat", "ca", "Catalan"),
+        ("zhc", "zc", "Chinese (Cantonese)"),
+        ("chi", "zh", "Chinese (simplified)"),
+        ("zht", "zt", "Chinese (traditional)"),
+        ("zhe", "ze", "Chinese bilingual"),
+        ("hrv", "hr", "Croatian"),
+        ("cze", "cs", "Czech"),
+        ("dan", "da", "Danish"),
+        ("prs", "pr", "Dari"),
+        ("dut", "nl", "Dutch"),
+        ("eng", "en", "English"),
+        ("epo", "eo", "Esperanto"),
+        ("est", "et", "Estonian"),
+        ("ext", "ex", "Extremaduran"),
+        ("fin", "fi", "Finnish"),
+        ("fre", "fr", "French"),
+        ("gla", "gd", "Gaelic"),
+        ("glg", "gl", "Galician"),
+        ("geo", "ka", "Georgian"),
+        ("ger", "de", "German"),
+        ("ell", "el", "Greek"),
+        ("heb", "he", "Hebrew"),
+        ("hin", "hi", "Hindi"),
+        ("hun", "hu", "Hungarian"),
+        ("ice", "is", "Icelandic"),
+        ("ibo", "ig", "Igbo"),
+        ("ind", "id", "Indonesian"),
+        ("ina", "ia", "Interlingua"),
+        ("gle", "ga", "Irish"),
+        ("ita", "it", "Italian"),
+        ("jpn", "ja", "Japanese"),
+        ("kan", "kn", "Kannada"),
+        ("kaz", "kk", "Kazakh"),
+        ("khm", "km", "Khmer"),
+        ("kor", "ko", "Korean"),
+        ("kur", "ku", "Kurdish"),
+        ("lav", "lv", "Latvian"),
+        ("lit", "lt", "Lithuanian"),
+        ("ltz", "lb", "Luxembourgish"),
+        ("mac", "mk", "Macedonian"),
+        ("may", "ms", "Malay"),
+        ("mal", "ml", "Malayalam"),
+        ("mni", "ma", "Manipuri"),
+        ("mar", "mr", "Marathi"),
+        ("mon", "mn", "Mongolian"),
+        ("mne", "me", "Montenegrin"),
+        ("nav", "nv", "Navajo"),
+        ("nep", "ne", "Nepali"),
+        ("sme", "se", "Northern Sami"),
+        ("nor", "no", "Norwegian"),
+        ("oci", "oc", "Occitan"),
+        ("ori", "or", "Odia"),
+        ("per", "fa", "Persian"),
+        ("pol", "pl", "Polish"),
+        ("por", "pt", "Portuguese"),
+        ("pob", "pb", "Portuguese (BR)"),
+        ("pom", "pm", "Portuguese (MZ)"),
+        ("pus", "ps", "Pushto"),
+        ("rum", "ro", "Romanian"),
+        ("rus", "ru", "Russian"),
+        ("sat", "sx", "Santali"),
+        ("scc", "sr", "Serbian"),
+        ("snd", "sd", "Sindhi"),
+        ("sin", "si", "Sinhalese"),
+        ("slo", "sk", "Slovak"),
+        ("slv", "sl", "Slovenian"),
+        ("som", "so", "Somali"),
+        ("spa", "es", "Spanish"),
+        ("spn", "sp", "Spanish (EU)"),
+        ("spl", "ea", "Spanish (LA)"),
+        ("swa", "sw", "Swahili"),
+        ("swe", "sv", "Swedish"),
+        ("syr", "sy", "Syriac"),
+        ("tgl", "tl", "Tagalog"),
+        ("tam", "ta", "Tamil"),
+        ("tat", "tt", "Tatar"),
+        ("tel", "te", "Telugu"),
+        ("tet", "tm", "Tetum"),
+        ("tha", "th", "Thai"),
+        ("tok", "tp", "Toki Pona"),
+        ("tur", "tr", "Turkish"),
+        ("tuk", "tk", "Turkmen"),
+        ("ukr", "uk", "Ukrainian"),
+        ("urd", "ur", "Urdu"),
+        ("uig", "ug", "Uyghur"),
+        ("vie", "vi", "Vietnamese"),
+        ("wel", "cy", "Welsh"),
+    ];
+
+    /// Read as another language; see the module documentation.
+    const REGIONAL: &[&str] = &["pob", "pom", "spn", "spl", "zhc", "zhe"];
+
+    /// Offered for upload but never identified here.
+    const UNPROFILED: &[&str] = &[
+        "amh", "arg", "asm", "ast", "bre", "ext", "gla", "gle", "ibo", "ina", "kaz", "kur", "ltz",
+        "mar", "mni", "mne", "mon", "nav", "nep", "oci", "ori", "prs", "pus", "sat", "sin", "sme",
+        "snd", "som", "syr", "tat", "tet", "tok", "tuk", "uig",
+    ];
+
+    #[test]
+    fn every_server_language_is_known() {
+        for &(code, iso639_1, name) in SUB_LANGUAGES {
+            let language = languages::lookup(code, false);
+            assert_eq!(
+                language.map(|l| (l.code, l.iso639_1, l.name)),
+                Some((code, iso639_1, name))
+            );
+        }
+        assert_eq!(languages::LANGUAGES.len(), SUB_LANGUAGES.len());
+    }
+
+    #[test]
+    fn every_language_can_be_identified_or_is_left_to_the_server() {
+        for &(code, ..) in SUB_LANGUAGES {
+            let identified = PROFILES.iter().any(|p| p.code == code)
+                || SCRIPT_LANGUAGES.iter().any(|(_, c)| *c == code)
+                || ["chi", "zht", "jpn"].contains(&code);
+            let left = REGIONAL.contains(&code) || UNPROFILED.contains(&code);
+            assert!(identified != left, "{code}");
+        }
+    }
+
+    #[test]
+    fn own_script_settles_the_language() {
+        let korean =
+            identify("안녕하세요, 오늘 날씨가 정말 좋네요. 같이 산책하러 갈까요?").unwrap();
+        assert_eq!(korean.language.code, "kor");
+        assert!(korean.confidence >= HIGH_CONFIDENCE);
+    }
+
+    #[test]
+    fn shared_script_is_left_to_the_server() {
+        let text = "नमस्ते, आप कैसे हैं? मैं ठीक हूँ, धन्यवाद। आज मौसम बहुत अच्छा है।";
+        let local = identify(text).unwrap();
+        assert_eq!(local.language.code, "hin");
+        assert!(local.confidence < HIGH_CONFIDENCE);
+
+        let path = std::env::temp_dir().join(format!("{}-hindi.srt", std::process::id()));
+        std::fs::write(&path, format!("1\n00:00:01,000 --> 00:00:02,000\n{text}\n")).unwrap();
+        let rest = RestClient::with_http(
+            Http::new(reqwest::Client::new()),
+            "http://127.0.0.1:9",
+            None,
+        );
+        let decision = block_on(decide(&rest, &path, Some(local)));
+        assert_eq!(decision.source, Some(Decision::LocalFallback));
+        assert!(decision.server_error.is_some());
+        assert_eq!(decision.language.as_deref(), Some("hin"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
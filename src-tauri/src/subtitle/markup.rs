@@ -0,0 +1,422 @@
+//! Inline formatting: HTML-like tags in SRT and WebVTT, ASS override
+//! blocks, and the line codes of MicroDVD and MPL2. Italics, bold and
+//! underline become [`Style`]s and back; everything else is dropped.
+
+use super::parse::Diagnostics;
+use super::{Span, Style};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Tag names for italics, bold and underline, in [`flags`] order.
+const TAGS: [&str; 3] = ["i", "b", "u"];
+
+fn flags(style: Style) -> [bool; 3] {
+    [style.italic, style.bold, style.underline]
+}
+
+/// Collects text into spans, starting a new one when the style changes.
+#[derive(Default)]
+pub(crate) struct SpanBuilder {
+    spans: Vec<Span>,
+    pub style: Style,
+}
+
+impl SpanBuilder {
+    pub fn push_str(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(last) if last.style == self.style => last.text.push_str(text),
+            _ => self.spans.push(Span {
+                text: text.into(),
+                style: self.style,
+            }),
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// The spans, without whitespace around the text as a whole.
+    pub fn finish(mut self) -> Vec<Span> {
+        while let Some(first) = self.spans.first_mut() {
+            let trimmed = first.text.trim_start();
+            if !trimmed.is_empty() {
+                first.text = trimmed.to_string();
+                break;
+            }
+            self.spans.remove(0);
+        }
+        while let Some(last) = self.spans.last_mut() {
+            let trimmed = last.text.trim_end();
+            if !trimmed.is_empty() {
+                last.text.truncate(trimmed.len());
+                break;
+            }
+            self.spans.pop();
+        }
+        self.spans
+    }
+}
+
+/// Text without formatting.
+pub(crate) fn plain(text: &str) -> Vec<Span> {
+    let mut out = SpanBuilder::default();
+    out.push_str(text);
+    out.finish()
+}
+
+/// Applies an ASS override block, without its braces, to `style`. `\r`
+/// resets to `reset(None)`, or `reset(Some(name))` for `\rName`. Returns
+/// whether a `\p` tag turned vector drawing on or off.
+pub(crate) fn apply_override(
+    block: &str,
+    style: &mut Style,
+    reset: impl Fn(Option<&str>) -> Style,
+) -> Option<bool> {
+    let mut drawing = None;
+    let base = reset(None);
+    for tag in block.split('\\').skip(1) {
+        let tag = tag.trim();
+        let Some(first) = tag.chars().next() else {
+            continue;
+        };
+        let value = &tag[first.len_utf8()..];
+        let numeric = value.bytes().all(|b| b.is_ascii_digit());
+        let on = value.parse::<u32>().ok();
+        match first {
+            'i' if numeric => style.italic = on.map_or(base.italic, |v| v != 0),
+            // Weights below 600 are regular.
+            'b' if numeric => style.bold = on.map_or(base.bold, |v| v == 1 || v >= 600),
+            'u' if numeric => style.underline = on.map_or(base.underline, |v| v != 0),
+            'p' if numeric => drawing = Some(on.is_some_and(|v| v != 0)),
+            'r' => *style = reset(Some(value).filter(|name| !name.is_empty())),
+            _ => {}
+        }
+    }
+    drawing
+}
+
+/// Reads ASS/SSA dialogue text starting in `base` style. Override blocks set
+/// the style and other braces are comments; `\N` breaks the line, `\n` is a
+/// soft break and `\h` a hard space. Vector drawings (`\p1`) are skipped.
+pub(crate) fn ass(text: &str, base: Style, styles: &HashMap<String, Style>) -> Vec<Span> {
+    let mut out = SpanBuilder {
+        style: base,
+        ..Default::default()
+    };
+    let reset = |name: Option<&str>| name.and_then(|n| styles.get(n)).copied().unwrap_or(base);
+    let mut drawing = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '{' {
+            if let Some(end) = rest.find('}') {
+                if let Some(on) = apply_override(&rest[1..end], &mut out.style, reset) {
+                    drawing = on;
+                }
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        let mut len = c.len_utf8();
+        let c = match (c, rest[len..].chars().next()) {
+            ('\\', Some(escape @ ('N' | 'n' | 'h'))) => {
+                len = 2;
+                match escape {
+                    'N' => '\n',
+                    'n' => ' ',
+                    _ => '\u{A0}',
+                }
+            }
+            _ => c,
+        };
+        if !drawing {
+            out.push(c);
+        }
+        rest = &rest[len..];
+    }
+    out.finish()
+}
+
+/// Reads SRT or WebVTT cue text. `<i>`, `<b>` and `<u>` set the style, and
+/// so do the ASS override blocks (`{\i1}`) many SRT files carry; other tags
+/// (`<font>`, `<c.yellow>`, `<v Bob>`, timestamps) are dropped. WebVTT
+/// escapes `&` and `<` as character references.
+pub(crate) fn tagged(
+    text: &str,
+    line: usize,
+    references: bool,
+    diagnostics: &mut Diagnostics,
+) -> Vec<Span> {
+    let mut out = SpanBuilder::default();
+    // Open tags of each kind, and the state set by override blocks.
+    let mut open = [0usize; 3];
+    let mut overridden = Style::default();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let tag = rest[1..].find(['>', '\n']).map(|end| &rest[1..end + 1]);
+            if let Some(tag) =
+                tag.filter(|tag| is_tag(tag) && rest[tag.len() + 1..].starts_with('>'))
+            {
+                let closing = tag.starts_with('/');
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c == '.' || c.is_whitespace())
+                    .next()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if let Some(i) = TAGS.iter().position(|n| *n == name) {
+                    if !closing {
+                        open[i] += 1;
+                    } else if open[i] > 0 {
+                        open[i] -= 1;
+                    } else {
+                        diagnostics.warning(
+                            line,
+                            "unmatchedTag",
+                            format!("</{name}> without an opening <{name}>"),
+                        );
+                    }
+                }
+                rest = &rest[tag.len() + 2..];
+                out.style = combined(open, overridden);
+                continue;
+            }
+        } else if c == '{' && rest[1..].starts_with('\\') {
+            if let Some(end) = rest.find('}') {
+                apply_override(&rest[1..end], &mut overridden, |_| Style::default());
+                rest = &rest[end + 1..];
+                out.style = combined(open, overridden);
+                continue;
+            }
+        } else if c == '&' && references {
+            if let Some((decoded, len)) = reference(rest) {
+                out.push(decoded);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    for (count, name) in open.iter().zip(TAGS) {
+        if *count > 0 {
+            diagnostics.warning(line, "unclosedTag", format!("<{name}> is never closed"));
+        }
+    }
+    out.finish()
+}
+
+fn combined(open: [usize; 3], overridden: Style) -> Style {
+    Style {
+        italic: open[0] > 0 || overridden.italic,
+        bold: open[1] > 0 || overridden.bold,
+        underline: open[2] > 0 || overridden.underline,
+    }
+}
+
+/// Whether the text between `<` and `>` is a tag rather than a literal
+/// `<`, as in `a < b > c`.
+fn is_tag(tag: &str) -> bool {
+    tag.trim_start_matches('/')
+        .starts_with(|c: char| c.is_ascii_alphanumeric())
+}
+
+/// The character a WebVTT character reference at the start of `text`
+/// stands for, and the reference's length.
+fn reference(text: &str) -> Option<(char, usize)> {
+    let (end, _) = text.char_indices().take(10).find(|(_, c)| *c == ';')?;
+    let c = match &text[1..end] {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{A0}',
+        "lrm" => '\u{200E}',
+        "rlm" => '\u{200F}',
+        numeric => {
+            let code = numeric.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)?
+        }
+    };
+    Some((c, end + 1))
+}
+
+/// Reads `|`-separated MicroDVD or MPL2 text. Control codes style the rest
+/// of their line, `{y:i}`, or every line after it too, `{Y:i}`; a `/` at
+/// the start of a line italicizes it. Codes for colours, fonts and sizes are
+/// dropped.
+pub(crate) fn piped(text: &str) -> Vec<Span> {
+    let mut out = SpanBuilder::default();
+    let mut every_line = Style::default();
+    for (i, line) in text.split('|').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.style = every_line;
+        let mut rest = line;
+        let mut start = true;
+        while let Some(c) = rest.chars().next() {
+            if c == '/' && start {
+                out.style.italic = true;
+                rest = &rest[1..];
+                continue;
+            }
+            if let Some((kind, value, len)) = control_code(rest) {
+                if kind.eq_ignore_ascii_case(&'y') {
+                    for flag in value.split(',') {
+                        let set = |style: &mut Style| match flag.trim() {
+                            "i" | "I" => style.italic = true,
+                            "b" | "B" => style.bold = true,
+                            "u" | "U" => style.underline = true,
+                            _ => {}
+                        };
+                        set(&mut out.style);
+                        if kind == 'Y' {
+                            set(&mut every_line);
+                        }
+                    }
+                }
+                rest = &rest[len..];
+                continue;
+            }
+            start = false;
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out.finish()
+}
+
+/// A `{k:value}` code at the start of `text`: its letter, value and length.
+fn control_code(text: &str) -> Option<(char, &str, usize)> {
+    let inner = text.strip_prefix('{')?;
+    let end = inner.find('}')?;
+    let (kind, value) = inner[..end].split_once(':')?;
+    let mut letters = kind.chars();
+    match (letters.next(), letters.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Some((letter, value, end + 2)),
+        _ => None,
+    }
+}
+
+/// Spans split at line breaks.
+fn lines(spans: &[Span]) -> Vec<Vec<Span>> {
+    let mut lines = vec![Vec::new()];
+    for span in spans {
+        for (i, part) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Vec::new());
+            }
+            if let Some(line) = lines.last_mut().filter(|_| !part.is_empty()) {
+                line.push(Span {
+                    text: part.into(),
+                    style: span.style,
+                });
+            }
+        }
+    }
+    lines
+}
+
+/// Writes SRT or WebVTT cue text, with `<i>`, `<b>` and `<u>` tags nested
+/// properly and closed at the end of each line. `references` escapes `&`,
+/// `<` and `>` as WebVTT requires.
+pub(crate) fn to_tagged(spans: &[Span], references: bool) -> String {
+    let mut out = String::new();
+    for (i, line) in lines(spans).iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut open: Vec<usize> = Vec::new();
+        for span in line {
+            let wanted = flags(span.style);
+            // Close down to the first tag that ends here; the ones above it
+            // that carry on are opened again.
+            if let Some(from) = open.iter().position(|&tag| !wanted[tag]) {
+                for tag in open.drain(from..).rev() {
+                    let _ = write!(out, "</{}>", TAGS[tag]);
+                }
+            }
+            for (tag, on) in wanted.into_iter().enumerate() {
+                if on && !open.contains(&tag) {
+                    let _ = write!(out, "<{}>", TAGS[tag]);
+                    open.push(tag);
+                }
+            }
+            for c in span.text.chars() {
+                match c {
+                    '&' if references => out.push_str("&amp;"),
+                    '<' if references => out.push_str("&lt;"),
+                    '>' if references => out.push_str("&gt;"),
+                    c => out.push(c),
+                }
+            }
+        }
+        for tag in open.into_iter().rev() {
+            let _ = write!(out, "</{}>", TAGS[tag]);
+        }
+    }
+    out
+}
+
+/// Writes ASS/SSA dialogue text for a line in `base` style: an override
+/// block wherever the style changes, and `\N` between lines.
+pub(crate) fn to_ass(spans: &[Span], base: Style) -> String {
+    let mut out = String::new();
+    let mut current = base;
+    for span in spans {
+        let changes = flags(current).into_iter().zip(flags(span.style));
+        let block: String = TAGS
+            .iter()
+            .zip(changes)
+            .filter(|(_, (was, now))| was != now)
+            .map(|(tag, (_, now))| format!("\\{tag}{}", u8::from(now)))
+            .collect();
+        if !block.is_empty() {
+            let _ = write!(out, "{{{block}}}");
+        }
+        current = span.style;
+        out.push_str(&span.text.replace('\n', "\\N"));
+    }
+    out
+}
+
+/// Writes `|`-separated MicroDVD text. Codes style whole lines, so a line
+/// takes only the formatting all of its text shares; the second value is
+/// whether any formatting was dropped for that reason.
+pub(crate) fn to_piped(spans: &[Span]) -> (String, bool) {
+    let mut partial = false;
+    let lines: Vec<String> = lines(spans)
+        .into_iter()
+        .map(|line| {
+            // Spaces between styled words carry no formatting worth keeping.
+            let visible = || line.iter().filter(|span| !span.text.trim().is_empty());
+            let shared = visible()
+                .map(|span| flags(span.style))
+                .reduce(|a, b| [a[0] && b[0], a[1] && b[1], a[2] && b[2]])
+                .unwrap_or_default();
+            partial |= visible().any(|span| flags(span.style) != shared);
+            let codes: Vec<&str> = TAGS
+                .iter()
+                .zip(shared)
+                .filter_map(|(tag, on)| on.then_some(*tag))
+                .collect();
+            let text: String = line.iter().map(|span| span.text.as_str()).collect();
+            if codes.is_empty() {
+                text
+            } else {
+                format!("{{y:{}}}{text}", codes.join(","))
+            }
+        })
+        .collect();
+    (lines.join("|"), partial)
+}
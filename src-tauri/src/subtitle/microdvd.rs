@@ -0,0 +1,111 @@
+//! MicroDVD: `{start}{end}text` in frame numbers, `|` between lines. A first
+//! cue of `{1}{1}23.976` declares the frame rate.
+
+use super::markup;
+use super::parse::Diagnostics;
+use super::{Cue, Format, Subtitle};
+use std::fmt::Write as _;
+
+/// Frame rate assumed when neither the file nor the caller gives one.
+pub const DEFAULT_FPS: f64 = 23.976;
+
+fn number(text: &str) -> Option<u64> {
+    (!text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| text.parse().ok())
+        .flatten()
+}
+
+/// The frames and text of a cue line; the end frame may be left out.
+pub(crate) fn split(line: &str) -> Option<(u64, Option<u64>, &str)> {
+    let (start, rest) = line.strip_prefix('{')?.split_once('}')?;
+    let (end, text) = rest.strip_prefix('{')?.split_once('}')?;
+    let end = match end {
+        "" => None,
+        end => Some(number(end)?),
+    };
+    Some((number(start)?, end, text))
+}
+
+pub(crate) fn parse(lines: &[&str], fps: Option<f64>, diagnostics: &mut Diagnostics) -> Subtitle {
+    let mut entries = Vec::new();
+    let mut declared = None;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((start, end, text)) = split(line) else {
+            if line.starts_with('{') {
+                diagnostics.error(
+                    i + 1,
+                    "badTimestamp",
+                    format!("cannot read the frames of {line:?}"),
+                );
+            } else {
+                diagnostics.warning(i + 1, "strayText", format!("text outside a cue: {line:?}"));
+            }
+            continue;
+        };
+        if entries.is_empty() && declared.is_none() && start <= 1 && end.is_some_and(|e| e <= 1) {
+            if let Some(rate) = text
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|r| *r > 0.0 && *r < 200.0)
+            {
+                declared = Some(rate);
+                continue;
+            }
+        }
+        entries.push((i + 1, start, end, text));
+    }
+
+    let fps = declared.or(fps).unwrap_or_else(|| {
+        diagnostics.warning(
+            0,
+            "assumedFrameRate",
+            format!("no frame rate declared or given; assuming {DEFAULT_FPS} fps"),
+        );
+        DEFAULT_FPS
+    });
+    let ms = |frame: u64| (frame as f64 * 1000.0 / fps).round() as u64;
+    let mut cues = Vec::with_capacity(entries.len());
+    for (k, &(line, start, end, text)) in entries.iter().enumerate() {
+        let end = end.or_else(|| {
+            diagnostics.warning(
+                line,
+                "missingEnd",
+                "cue has no end frame; it lasts until the next",
+            );
+            entries.get(k + 1).map(|next| next.1)
+        });
+        let end = end.unwrap_or(start);
+        cues.push(Cue::new(line, ms(start), ms(end), markup::piped(text)));
+    }
+    let mut subtitle = Subtitle::new(Format::MicroDvd, cues);
+    subtitle.fps = Some(fps);
+    subtitle
+}
+
+/// The frame a time falls on at `fps`.
+pub(crate) fn frame(ms: u64, fps: f64) -> u64 {
+    (ms as f64 * fps / 1000.0).round() as u64
+}
+
+/// Writes cues as MicroDVD at `fps`, declared in a first `{1}{1}` cue.
+/// Also returns how many cues lost formatting that covered part of a line.
+pub(crate) fn render(cues: &[Cue], fps: f64) -> (String, usize) {
+    let mut out = format!("{{1}}{{1}}{fps}\r\n");
+    let mut partial = 0;
+    for cue in cues {
+        let (text, dropped) = markup::to_piped(&cue.spans);
+        partial += usize::from(dropped);
+        let _ = write!(
+            out,
+            "{{{}}}{{{}}}{text}\r\n",
+            frame(cue.start_ms, fps),
+            frame(cue.end_ms, fps)
+        );
+    }
+    (out, partial)
+}
@@ -0,0 +1,250 @@
+//! Subtitle files as text: character encodings, and the formats read into a
+//! common cue model, converted and retimed.
+
+pub mod ass;
+pub mod convert;
+pub mod encoding;
+pub mod language;
+mod markup;
+pub mod microdvd;
+pub mod mpl2;
+pub mod parse;
+pub mod srt;
+pub mod subviewer;
+pub mod timing;
+pub mod tmplayer;
+pub mod vtt;
+
+use crate::error::impl_serialize_error;
+use crate::media::MediaError;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SubtitleError {
+    #[error("could not read {}: {source}", .path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("could not write {}: {source}", .path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("unknown character encoding {0:?}")]
+    UnknownEncoding(String),
+    #[error("{0} is not supported")]
+    UnsupportedEncoding(&'static str),
+    #[error("{} is not in a subtitle format we can read", .0.display())]
+    UnknownFormat(PathBuf),
+    #[error("{} files cannot be written", .0.name())]
+    Unwritable(Format),
+    #[error("{0}")]
+    InvalidTiming(String),
+    #[error(transparent)]
+    Media(#[from] MediaError),
+    #[error("background task failed: {0}")]
+    Task(String),
+}
+
+impl SubtitleError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Write { .. } => "write",
+            Self::UnknownEncoding(_) => "unknownEncoding",
+            Self::UnsupportedEncoding(_) => "unsupportedEncoding",
+            Self::UnknownFormat(_) => "unknownFormat",
+            Self::Unwritable(_) => "unwritable",
+            Self::InvalidTiming(_) => "invalidTiming",
+            Self::Media(e) => e.kind(),
+            Self::Task(_) => "task",
+        }
+    }
+
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl_serialize_error!(SubtitleError);
+
+/// Text subtitle formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Srt,
+    Vtt,
+    Ass,
+    Ssa,
+    /// `{start frame}{end frame}text`.
+    MicroDvd,
+    /// `[hh:mm:ss]` lines, each cue ending where the next starts.
+    SubViewer1,
+    /// `hh:mm:ss.cc,hh:mm:ss.cc` lines with `[br]` line breaks.
+    SubViewer2,
+    /// `[start][end]text` in tenths of a second.
+    Mpl2,
+    /// `hh:mm:ss:text`, each cue ending where the next starts.
+    TmPlayer,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Srt => "SubRip",
+            Self::Vtt => "WebVTT",
+            Self::Ass => "Advanced SubStation Alpha",
+            Self::Ssa => "SubStation Alpha",
+            Self::MicroDvd => "MicroDVD",
+            Self::SubViewer1 => "SubViewer 1",
+            Self::SubViewer2 => "SubViewer 2",
+            Self::Mpl2 => "MPL2",
+            Self::TmPlayer => "TMPlayer",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Ass => "ass",
+            Self::Ssa => "ssa",
+            Self::MicroDvd | Self::SubViewer1 | Self::SubViewer2 => "sub",
+            Self::Mpl2 | Self::TmPlayer => "txt",
+        }
+    }
+}
+
+/// Formatting that survives conversion between formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Style {
+    pub italic: bool,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+/// A run of cue text with one [`Style`]; line breaks are `\n`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+    pub text: String,
+    #[serde(flatten)]
+    pub style: Style,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cue {
+    /// 1-based line the cue starts on in the file.
+    pub line: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Text without markup, lines separated by `\n`.
+    pub text: String,
+    /// The same text, split where the formatting changes.
+    pub spans: Vec<Span>,
+    /// SRT counter or WebVTT cue identifier.
+    pub id: Option<String>,
+    /// ASS/SSA style name.
+    pub style: Option<String>,
+    /// WebVTT cue settings, e.g. `position:10% align:start`.
+    pub settings: Option<String>,
+}
+
+impl Cue {
+    pub fn new(line: usize, start_ms: u64, end_ms: u64, spans: Vec<Span>) -> Self {
+        Self {
+            line,
+            start_ms,
+            end_ms,
+            text: spans.iter().map(|span| span.text.as_str()).collect(),
+            spans,
+            id: None,
+            style: None,
+            settings: None,
+        }
+    }
+}
+
+/// A parsed subtitle file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subtitle {
+    pub format: Format,
+    pub cues: Vec<Cue>,
+    /// Frame rate MicroDVD frame numbers were converted with.
+    pub fps: Option<f64>,
+    /// What precedes the cues, kept for writing the same format again: the
+    /// WebVTT header blocks, or the ASS/SSA script info and styles.
+    #[serde(skip)]
+    pub header: Option<String>,
+}
+
+impl Subtitle {
+    fn new(format: Format, cues: Vec<Cue>) -> Self {
+        Self {
+            format,
+            cues,
+            fps: None,
+            header: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Cues were lost or are unusable; the server is likely to reject the
+    /// file.
+    Error,
+    Warning,
+}
+
+/// Something wrong with a subtitle file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// 1-based; 0 for the file as a whole.
+    pub line: usize,
+    pub severity: Severity,
+    /// Stable identifier for the UI, e.g. `badTimestamp`.
+    pub kind: &'static str,
+    pub message: String,
+}
+
+pub(crate) fn read(path: &Path) -> Result<Vec<u8>, SubtitleError> {
+    fs::read(path).map_err(|e| SubtitleError::io(path, e))
+}
+
+/// Writes `bytes` to `path` through a temporary file, so `path` may be the
+/// file the bytes were read from.
+pub(crate) fn write(path: &Path, bytes: &[u8]) -> Result<(), SubtitleError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let write = || {
+        let mut file = File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().map_err(|source| {
+        let _ = fs::remove_file(&tmp);
+        SubtitleError::Write {
+            path: path.to_path_buf(),
+            source,
+        }
+    })
+}
+
+/// Rejects a frame rate that would turn frames into nonsense times.
+pub(crate) fn check_fps(fps: Option<f64>) -> Result<(), SubtitleError> {
+    match fps {
+        Some(fps) if !(fps.is_finite() && fps > 0.0) => Err(SubtitleError::InvalidTiming(format!(
+            "{fps} is not a frame rate"
+        ))),
+        _ => Ok(()),
+    }
+}
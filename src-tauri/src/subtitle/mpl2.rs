@@ -0,0 +1,72 @@
+//! MPL2: `[start][end]text` in tenths of a second, `|` between lines and
+//! `/` italicizing a line.
+
+use super::markup;
+use super::parse::Diagnostics;
+use super::{Cue, Format, Subtitle};
+
+fn number(text: &str) -> Option<u64> {
+    (!text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| text.parse().ok())
+        .flatten()
+}
+
+/// The times, in deciseconds, and text of a cue line; the end may be left
+/// out.
+pub(crate) fn split(line: &str) -> Option<(u64, Option<u64>, &str)> {
+    let (start, rest) = line.strip_prefix('[')?.split_once(']')?;
+    let (end, text) = rest.strip_prefix('[')?.split_once(']')?;
+    let end = match end {
+        "" => None,
+        end => Some(number(end)?),
+    };
+    Some((number(start)?, end, text))
+}
+
+pub(crate) fn parse(lines: &[&str], diagnostics: &mut Diagnostics) -> Subtitle {
+    let mut entries = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match split(line) {
+            Some((start, end, text)) => {
+                let ms = |ds: u64| ds.checked_mul(100);
+                match (ms(start), end.map(ms)) {
+                    (Some(start), None) => entries.push((i + 1, start, None, text)),
+                    (Some(start), Some(Some(end))) => entries.push((i + 1, start, Some(end), text)),
+                    _ => diagnostics.error(
+                        i + 1,
+                        "badTimestamp",
+                        format!("the times of {line:?} are out of range"),
+                    ),
+                }
+            }
+            None if line.starts_with('[') => {
+                diagnostics.error(
+                    i + 1,
+                    "badTimestamp",
+                    format!("cannot read the times of {line:?}"),
+                );
+            }
+            None => {
+                diagnostics.warning(i + 1, "strayText", format!("text outside a cue: {line:?}"))
+            }
+        }
+    }
+    let mut cues = Vec::with_capacity(entries.len());
+    for (k, &(line, start, end, text)) in entries.iter().enumerate() {
+        let end = end.or_else(|| {
+            diagnostics.warning(
+                line,
+                "missingEnd",
+                "cue has no end time; it lasts until the next",
+            );
+            entries.get(k + 1).map(|next| next.1)
+        });
+        let end = end.unwrap_or(start);
+        cues.push(Cue::new(line, start, end, markup::piped(text)));
+    }
+    Subtitle::new(Format::Mpl2, cues)
+}
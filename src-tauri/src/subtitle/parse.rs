@@ -0,0 +1,555 @@
+//! Reading subtitle files into [`Subtitle`]s, with diagnostics for what is
+//! wrong with them.
+//!
+//! Parsers are lenient: they keep every cue they can make sense of and
+//! report the rest with its line number, so a file can be fixed before
+//! `TryUploadSubtitles` turns it down without saying why.
+
+use super::encoding;
+use super::{ass, check_fps, microdvd, mpl2, read, srt, subviewer, tmplayer, vtt};
+use super::{Diagnostic, Format, Severity, Subtitle, SubtitleError};
+use crate::media::timestamp;
+use serde::Serialize;
+use std::path::Path;
+
+/// Diagnostics listed individually; the rest are only counted.
+const MAX_LISTED: usize = 200;
+
+/// Lines looked at to recognise a format.
+const SNIFF_LINES: usize = 100;
+
+#[derive(Default)]
+pub(crate) struct Diagnostics {
+    list: Vec<Diagnostic>,
+    errors: usize,
+    warnings: usize,
+}
+
+impl Diagnostics {
+    pub fn error(&mut self, line: usize, kind: &'static str, message: impl Into<String>) {
+        self.errors += 1;
+        self.push(line, Severity::Error, kind, message.into());
+    }
+
+    pub fn warning(&mut self, line: usize, kind: &'static str, message: impl Into<String>) {
+        self.warnings += 1;
+        self.push(line, Severity::Warning, kind, message.into());
+    }
+
+    fn push(&mut self, line: usize, severity: Severity, kind: &'static str, message: String) {
+        if self.list.len() < MAX_LISTED {
+            self.list.push(Diagnostic {
+                line,
+                severity,
+                kind,
+                message,
+            });
+        }
+    }
+}
+
+/// Parses `[H:]MM:SS[.fff]` clock times, with `,` also accepted before the
+/// fraction as in SRT. The fraction is decimal, so `.5` is 500 ms and the
+/// centiseconds of ASS work as they are.
+pub(crate) fn clock(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (hms, fraction) = match text.find([',', '.']) {
+        Some(i) => (&text[..i], Some(&text[i + 1..])),
+        None => (text, None),
+    };
+    let number = |part: &str| {
+        (!part.is_empty() && part.len() <= 4 && part.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| part.parse::<u64>().ok())
+            .flatten()
+    };
+    let parts: Vec<&str> = hms.split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [h, m, s] => (number(h)?, number(m)?, number(s)?),
+        [m, s] => (0, number(m)?, number(s)?),
+        _ => return None,
+    };
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    let ms = match fraction {
+        None => 0,
+        Some(f) if !f.is_empty() && f.len() <= 9 && f.bytes().all(|b| b.is_ascii_digit()) => {
+            let digits = &f[..f.len().min(3)];
+            digits.parse::<u64>().ok()? * 10u64.pow(3 - digits.len() as u32)
+        }
+        Some(_) => return None,
+    };
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + ms)
+}
+
+/// Recognises the format from the first lines of the file.
+pub fn detect_format(text: &str) -> Option<Format> {
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(SNIFF_LINES);
+    for line in lines {
+        if line.starts_with("WEBVTT") {
+            return Some(Format::Vtt);
+        }
+        let lower = line.to_ascii_lowercase();
+        if ["[script info]", "[v4+ styles]", "[v4 styles]", "[events]"].contains(&lower.as_str()) {
+            return Some(ass::flavour(text));
+        }
+        if srt::timing(line).is_some() {
+            return Some(Format::Srt);
+        }
+        if microdvd::split(line).is_some() {
+            return Some(Format::MicroDvd);
+        }
+        if mpl2::split(line).is_some() {
+            return Some(Format::Mpl2);
+        }
+        if lower == "[information]" || subviewer::timing(line).is_some() {
+            return Some(Format::SubViewer2);
+        }
+        if line == "**START SCRIPT**" || subviewer::time(line).is_some() {
+            return Some(Format::SubViewer1);
+        }
+        if tmplayer::split(line).is_some() {
+            return Some(Format::TmPlayer);
+        }
+    }
+    None
+}
+
+/// Parses subtitle text, detecting its format unless `format` is given.
+/// `fps` converts MicroDVD frame numbers when the file does not declare a
+/// frame rate. `None` when the format is not recognised.
+pub(crate) fn parse_text(
+    text: &str,
+    format: Option<Format>,
+    fps: Option<f64>,
+    diagnostics: &mut Diagnostics,
+) -> Option<Subtitle> {
+    let format = format.or_else(|| detect_format(text))?;
+    // Classic Mac OS line endings.
+    let text = if text.contains('\r') && !text.contains('\n') {
+        std::borrow::Cow::Owned(text.replace('\r', "\n"))
+    } else {
+        std::borrow::Cow::Borrowed(text)
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let subtitle = match format {
+        Format::Srt => srt::parse(&lines, diagnostics),
+        Format::Vtt => vtt::parse(&lines, diagnostics),
+        Format::Ass | Format::Ssa => ass::parse(&lines, format, diagnostics),
+        Format::MicroDvd => microdvd::parse(&lines, fps, diagnostics),
+        Format::SubViewer1 => subviewer::parse_v1(&lines, diagnostics),
+        Format::SubViewer2 => subviewer::parse_v2(&lines, diagnostics),
+        Format::Mpl2 => mpl2::parse(&lines, diagnostics),
+        Format::TmPlayer => tmplayer::parse(&lines, diagnostics),
+    };
+    check(&subtitle, diagnostics);
+    Some(subtitle)
+}
+
+/// Timing problems common to every format.
+fn check(subtitle: &Subtitle, diagnostics: &mut Diagnostics) {
+    if subtitle.cues.is_empty() {
+        diagnostics.error(0, "noCues", "no cues found");
+        return;
+    }
+    // Styled ASS lines overlap on purpose, placed apart on screen.
+    let overlap_allowed = matches!(subtitle.format, Format::Ass | Format::Ssa);
+    let mut previous: Option<&super::Cue> = None;
+    for cue in &subtitle.cues {
+        let (start, end) = (timestamp(cue.start_ms, '.'), timestamp(cue.end_ms, '.'));
+        if cue.end_ms < cue.start_ms {
+            diagnostics.error(
+                cue.line,
+                "negativeDuration",
+                format!("cue ends at {end}, before it starts at {start}"),
+            );
+        } else if cue.end_ms == cue.start_ms {
+            diagnostics.warning(
+                cue.line,
+                "zeroDuration",
+                format!("cue at {start} is never shown"),
+            );
+        }
+        if cue.text.trim().is_empty() {
+            diagnostics.warning(cue.line, "emptyCue", format!("cue at {start} has no text"));
+        }
+        if let Some(previous) = previous {
+            if cue.start_ms < previous.start_ms {
+                diagnostics.warning(
+                    cue.line,
+                    "outOfOrder",
+                    format!(
+                        "cue at {start} comes after one at {}",
+                        timestamp(previous.start_ms, '.')
+                    ),
+                );
+            } else if cue.start_ms < previous.end_ms && !overlap_allowed {
+                diagnostics.warning(
+                    cue.line,
+                    "overlap",
+                    format!(
+                        "cue at {start} starts before the one on line {} ends at {}",
+                        previous.line,
+                        timestamp(previous.end_ms, '.')
+                    ),
+                );
+            }
+        }
+        previous = Some(cue);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Parsed {
+    #[serde(flatten)]
+    pub subtitle: Subtitle,
+    /// Character encoding the file was read as.
+    pub encoding: &'static str,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Totals, counting diagnostics past the listed ones.
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// Reads and parses a subtitle file. `language` is a hint for detecting the
+/// character encoding.
+pub fn parse_file(
+    path: &Path,
+    format: Option<Format>,
+    fps: Option<f64>,
+    language: Option<&str>,
+) -> Result<Parsed, SubtitleError> {
+    check_fps(fps)?;
+    let bytes = read(path)?;
+    let (detection, decoded) = encoding::detect_and_decode(&bytes, language)?;
+    let mut diagnostics = Diagnostics::default();
+    for bad in &decoded.undecodable {
+        diagnostics.warning(
+            bad.line,
+            "undecodable",
+            format!(
+                "bytes at offset {} are not valid {}",
+                bad.offset, detection.encoding
+            ),
+        );
+    }
+    let subtitle = parse_text(&decoded.text, format, fps, &mut diagnostics)
+        .ok_or_else(|| SubtitleError::UnknownFormat(path.to_path_buf()))?;
+    Ok(Parsed {
+        subtitle,
+        encoding: detection.encoding,
+        diagnostics: diagnostics.list,
+        errors: diagnostics.errors,
+        warnings: diagnostics.warnings,
+    })
+}
+
+/// Parses a subtitle file into cues, listing what is wrong with it. The
+/// format is detected from the content unless given.
+#[tauri::command]
+pub async fn parse_subtitle(
+    path: String,
+    format: Option<Format>,
+    fps: Option<f64>,
+    language: Option<String>,
+) -> Result<Parsed, SubtitleError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        parse_file(Path::new(&path), format, fps, language.as_deref())
+    })
+    .await
+    .map_err(|e| SubtitleError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::convert::convert;
+    use crate::subtitle::{Span, Style};
+
+    fn parse(text: &str) -> (Option<Subtitle>, Diagnostics) {
+        let mut diagnostics = Diagnostics::default();
+        (parse_text(text, None, None, &mut diagnostics), diagnostics)
+    }
+
+    #[test]
+    fn non_ascii_override_tag() {
+        let (subtitle, _) = parse("1\n00:00:01,000 --> 00:00:02,000\n{\\é}Café {\\ ü}au lait\n");
+        let subtitle = subtitle.unwrap();
+        assert_eq!(subtitle.cues.len(), 1);
+        assert_eq!(subtitle.cues[0].text, "Café au lait");
+    }
+
+    #[test]
+    fn mpl2_times_out_of_range() {
+        let (subtitle, diagnostics) =
+            parse("[10][20]Hello\n[184467440737095517][184467440737095518]Boom\n");
+        let subtitle = subtitle.unwrap();
+        assert_eq!(subtitle.format, Format::Mpl2);
+        assert_eq!(subtitle.cues.len(), 1);
+        assert_eq!(
+            (subtitle.cues[0].start_ms, subtitle.cues[0].end_ms),
+            (1000, 2000)
+        );
+        assert_eq!(diagnostics.errors, 1);
+        assert_eq!(diagnostics.list[0].kind, "badTimestamp");
+    }
+
+    #[test]
+    fn rejects_bad_frame_rates() {
+        let (subtitle, _) = parse("{10}{20}Hello\n");
+        let subtitle = subtitle.unwrap();
+        for fps in [0.0, -25.0, f64::NAN, f64::INFINITY] {
+            let result = convert(&subtitle, Format::MicroDvd, Some(fps));
+            assert!(
+                matches!(result, Err(SubtitleError::InvalidTiming(_))),
+                "{fps}"
+            );
+        }
+        assert!(convert(&subtitle, Format::MicroDvd, Some(23.976)).is_ok());
+    }
+
+    fn times(subtitle: &Subtitle) -> Vec<(u64, u64, &str)> {
+        let cues = subtitle.cues.iter();
+        cues.map(|cue| (cue.start_ms, cue.end_ms, cue.text.as_str()))
+            .collect()
+    }
+
+    fn kinds(diagnostics: &Diagnostics) -> Vec<(usize, &'static str)> {
+        let listed = diagnostics.list.iter();
+        listed.map(|d| (d.line, d.kind)).collect()
+    }
+
+    #[test]
+    fn parses_every_format() {
+        let two = [(1000, 2500, "Hello\nworld"), (3000, 4000, "Bye")];
+        let cases = [
+            (
+                "1\n00:00:01,000 --> 00:00:02,500\nHello\nworld\n\n\
+                 2\n00:00:03,000 --> 00:00:04,000\nBye\n",
+                Format::Srt,
+            ),
+            (
+                "WEBVTT\n\nNOTE made by hand\n\nintro\n00:01.000 --> 00:02.500 align:start\n\
+                 Hello\nworld\n\n00:00:03.000 --> 00:00:04.000\nBye\n",
+                Format::Vtt,
+            ),
+            (
+                "[Script Info]\nScriptType: v4.00+\n\n[Events]\n\
+                 Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                 Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,Bye\n\
+                 Comment: 0,0:00:00.00,0:00:05.00,Default,,0,0,0,,timed by hand\n\
+                 Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,Hello\\Nworld\n",
+                Format::Ass,
+            ),
+            (
+                "[Script Info]\nScriptType: v4.00\n\n[Events]\n\
+                 Format: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                 Dialogue: Marked=0,0:00:01.00,0:00:02.50,Default,,0,0,0,,Hello\\Nworld\n\
+                 Dialogue: Marked=0,0:00:03.00,0:00:04.00,Default,,0,0,0,,Bye\n",
+                Format::Ssa,
+            ),
+            (
+                "{1}{1}25\n{25}{62}Hello|world\n{75}{100}Bye\n",
+                Format::MicroDvd,
+            ),
+            (
+                "**START SCRIPT**\n[00:00:01]\nHello\nworld\n[00:00:03]\nBye\n[00:00:04]\n\
+                 **END SCRIPT**\n",
+                Format::SubViewer1,
+            ),
+            (
+                "[INFORMATION]\n[TITLE]Test\n[END INFORMATION]\n[SUBTITLE]\n\
+                 00:00:01.00,00:00:02.50\nHello[br]world\n\n00:00:03.00,00:00:04.00\nBye\n",
+                Format::SubViewer2,
+            ),
+            ("[10][25]Hello|world\n[30][40]Bye\n", Format::Mpl2),
+            (
+                "00:00:01:Hello|world\n00:00:03:Bye\n00:00:04:\n",
+                Format::TmPlayer,
+            ),
+            // The variant numbering the lines of a cue.
+            (
+                "00:00:01,1=Hello\n00:00:01,2=world\n00:00:03,1=Bye\n00:00:04,1=\n",
+                Format::TmPlayer,
+            ),
+        ];
+        for (text, format) in cases {
+            let (subtitle, diagnostics) = parse(text);
+            let subtitle = subtitle.unwrap();
+            assert_eq!(subtitle.format, format);
+            let mut expected = two.to_vec();
+            // One frame is 40 ms at 25 fps.
+            if format == Format::MicroDvd {
+                expected[0].1 = 2480;
+            }
+            // These end each cue where the next starts.
+            if matches!(format, Format::SubViewer1 | Format::TmPlayer) {
+                expected[0].1 = 3000;
+            }
+            assert_eq!(times(&subtitle), expected, "{format:?}");
+            assert_eq!(kinds(&diagnostics), [], "{format:?}");
+        }
+    }
+
+    #[test]
+    fn reads_inline_formatting() {
+        let span = |text: &str, flags: &str| Span {
+            text: text.into(),
+            style: Style {
+                italic: flags.contains('i'),
+                bold: flags.contains('b'),
+                underline: flags.contains('u'),
+            },
+        };
+        let cue = |timing: &str| format!("1\n{timing}\n");
+        let srt = cue("00:00:01,000 --> 00:00:02,000");
+        let vtt = format!("WEBVTT\n\n{}", cue("00:00:01.000 --> 00:00:02.000"));
+        let ass = "[Script Info]\nScriptType: v4.00+\n\n[V4+ Styles]\n\
+                   Format: Name, Fontname, Fontsize, Bold, Italic, Underline\n\
+                   Style: Default,Arial,20,0,0,0\nStyle: Loud,Arial,20,-1,0,0\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, \
+                   Text\nDialogue: 0,0:00:01.00,0:00:02.00,";
+        let cases = [
+            (
+                format!("{srt}<i>Hello</i> <b><u>world</u></b>\n"),
+                vec![span("Hello", "i"), span(" ", ""), span("world", "bu")],
+            ),
+            (
+                format!("{srt}<font color=\"red\">{{\\i1}}Hello{{\\i0}}</font> a < b\n"),
+                vec![span("Hello", "i"), span(" a < b", "")],
+            ),
+            (
+                format!("{vtt}<v Bob><c.yellow>Fish &amp; <i>chips</i></c> &lt;3\n"),
+                vec![span("Fish & ", ""), span("chips", "i"), span(" <3", "")],
+            ),
+            (
+                format!("{ass}Default,,0,0,0,,{{\\b1}}Hello{{\\b0}}\\Nworld{{\\p1}}m 0 0 l 1 1\n"),
+                vec![span("Hello", "b"), span("\nworld", "")],
+            ),
+            (
+                format!("{ass}Loud,,0,0,0,,Hello {{\\i1}}world{{\\r}} again\n"),
+                vec![
+                    span("Hello ", "b"),
+                    span("world", "bi"),
+                    span(" again", "b"),
+                ],
+            ),
+            (
+                "{1}{1}25\n{25}{50}{Y:i}Hello|{y:b}world|{c:$0000ff}again\n".to_string(),
+                vec![
+                    span("Hello\n", "i"),
+                    span("world\n", "ib"),
+                    span("again", "i"),
+                ],
+            ),
+            (
+                "[10][20]/Hello|world\n".to_string(),
+                vec![span("Hello\n", "i"), span("world", "")],
+            ),
+        ];
+        for (text, spans) in cases {
+            let (subtitle, diagnostics) = parse(&text);
+            let subtitle = subtitle.unwrap();
+            assert_eq!(subtitle.cues[0].spans, spans, "{text}");
+            assert_eq!(kinds(&diagnostics), [], "{text}");
+        }
+
+        let (_, diagnostics) = parse(&format!("{srt}<i>Hello</b>\n"));
+        assert_eq!(
+            kinds(&diagnostics),
+            [(1, "unmatchedTag"), (1, "unclosedTag")]
+        );
+    }
+
+    #[test]
+    fn reports_malformed_cues() {
+        let text = "1\n00:00:01,000 --> 00:00:03,000\nFirst\n\
+                    2\n00:00:02,000 --> 00:00:04,000\nOverlaps, with no blank line before\n\n\
+                    3\n00:00:0x,000 --> 00:00:06,000\nBad time\n\n\
+                    4\n00:00:01,500 --> 00:00:02,000\nOut of order\n\n\
+                    5\n00:00:09,000 --> 00:00:08,000\nBackwards\n\n\
+                    7\n00:00:10,000 --> 00:00:10,000\nNever shown\n";
+        let (subtitle, diagnostics) = parse(text);
+        assert_eq!(subtitle.unwrap().cues.len(), 5);
+        assert_eq!(
+            kinds(&diagnostics),
+            [
+                (4, "missingBlankLine"),
+                (9, "badTimestamp"),
+                (20, "counter"),
+                (4, "overlap"),
+                (12, "outOfOrder"),
+                (16, "negativeDuration"),
+                (20, "zeroDuration"),
+            ]
+        );
+        assert_eq!((diagnostics.errors, diagnostics.warnings), (2, 5));
+        assert_eq!(
+            diagnostics.list[3].message,
+            "cue at 00:00:02.000 starts before the one on line 1 ends at 00:00:03.000"
+        );
+    }
+
+    #[test]
+    fn reports_problems_particular_to_a_format() {
+        let cases = [
+            (
+                "00:00:01.000 --> 00:00:02,000\nHello\n\nstray\n",
+                Some(Format::Vtt),
+                vec![
+                    (1, "missingSignature"),
+                    (1, "commaInTimestamp"),
+                    (4, "strayText"),
+                ],
+            ),
+            (
+                "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nHello\n\nstray\n\nNOTE fine\n",
+                None,
+                vec![(6, "strayText")],
+            ),
+            (
+                "[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hello\n\
+                 Dialogue: 0,0:00:0x.00,0:00:03.00,Default,,0,0,0,,Bad\n\
+                 Dialogue: 0,0:00:04.00\nshouting\n",
+                None,
+                vec![
+                    (2, "missingFormat"),
+                    (3, "badTimestamp"),
+                    (4, "badEvent"),
+                    (5, "strayText"),
+                ],
+            ),
+            (
+                "{25}{50}Hello\n{x}{75}Bad\n{75}{}Open\n{100}{125}Bye\n",
+                None,
+                vec![
+                    (2, "badTimestamp"),
+                    (0, "assumedFrameRate"),
+                    (3, "missingEnd"),
+                ],
+            ),
+            (
+                "[10][20]Hello\n[3O][40]Bad\n",
+                None,
+                vec![(2, "badTimestamp")],
+            ),
+            (
+                "00:00:01:Hello\n0:1:2:Bad\n",
+                None,
+                vec![(2, "badTimestamp")],
+            ),
+            ("WEBVTT\n\nNOTE nothing yet\n", None, vec![(0, "noCues")]),
+        ];
+        for (text, format, expected) in cases {
+            let mut diagnostics = Diagnostics::default();
+            parse_text(text, format, None, &mut diagnostics).unwrap();
+            assert_eq!(kinds(&diagnostics), expected, "{text}");
+        }
+        assert!(parse("Just some notes.\n").0.is_none());
+    }
+}
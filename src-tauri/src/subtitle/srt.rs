@@ -0,0 +1,107 @@
+//! SubRip: numbered cues, each with a `HH:MM:SS,mmm --> HH:MM:SS,mmm` line
+//! and text up to a blank line.
+
+use super::markup;
+use super::parse::{clock, Diagnostics};
+use super::{Cue, Format, Subtitle};
+use crate::media::{self, TextCue};
+
+/// The start and end of a timing line. Coordinates after the end time
+/// (`X1:40 X2:600 ...`) are ignored.
+pub(crate) fn timing(line: &str) -> Option<(u64, u64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((clock(start)?, clock(end)?))
+}
+
+fn is_counter(line: &str) -> bool {
+    !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether a cue starts at `lines[i]`, with or without its counter.
+fn starts_cue(lines: &[&str], i: usize) -> bool {
+    let line = lines[i].trim();
+    line.contains("-->")
+        || (is_counter(line) && lines.get(i + 1).is_some_and(|next| next.contains("-->")))
+}
+
+pub(crate) fn parse(lines: &[&str], diagnostics: &mut Diagnostics) -> Subtitle {
+    let mut cues = Vec::new();
+    let mut expected = 1;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line.is_empty() {
+            i += 1;
+            continue;
+        }
+        if !starts_cue(lines, i) {
+            diagnostics.warning(i + 1, "strayText", format!("text outside a cue: {line:?}"));
+            i += 1;
+            continue;
+        }
+        let first = i;
+        let id = is_counter(line).then_some(line);
+        if id.is_none() {
+            diagnostics.warning(i + 1, "missingCounter", "cue has no number");
+        } else {
+            i += 1;
+        }
+        let timing_line = lines[i].trim();
+        let timing_at = i + 1;
+
+        // The text runs to a blank line, or to the next cue when the blank
+        // line is missing.
+        i += 1;
+        let text_start = i;
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            if starts_cue(lines, i) {
+                diagnostics.warning(i + 1, "missingBlankLine", "no blank line before this cue");
+                break;
+            }
+            i += 1;
+        }
+        // Counted before the timing is read, so a cue lost to a bad time
+        // does not throw off the numbers after it.
+        if let Some(number) = id.and_then(|id| id.parse::<u64>().ok()) {
+            if number != expected {
+                diagnostics.warning(
+                    first + 1,
+                    "counter",
+                    format!("cue number {number}, expected {expected}"),
+                );
+            }
+            expected = number;
+        }
+        expected += 1;
+        let Some((start, end)) = timing(timing_line) else {
+            diagnostics.error(
+                timing_at,
+                "badTimestamp",
+                format!("cannot read the timing {timing_line:?}"),
+            );
+            continue;
+        };
+        let text = lines[text_start..i].join("\n");
+        let spans = markup::tagged(&text, first + 1, false, diagnostics);
+        let mut cue = Cue::new(first + 1, start, end, spans);
+        cue.id = id.map(str::to_string);
+        cues.push(cue);
+    }
+    Subtitle::new(Format::Srt, cues)
+}
+
+/// Writes cues as SubRip, numbered from 1 with tags for their formatting.
+pub(crate) fn render(cues: &[Cue]) -> String {
+    let cues: Vec<TextCue> = cues
+        .iter()
+        .map(|cue| {
+            TextCue::new(
+                cue.start_ms,
+                cue.end_ms,
+                markup::to_tagged(&cue.spans, false),
+            )
+        })
+        .collect();
+    media::render_srt(&cues)
+}
@@ -0,0 +1,136 @@
+//! SubViewer 1 and 2.
+//!
+//! Version 1 puts `[hh:mm:ss]` on a line of its own before each cue's text,
+//! which lasts until the next time. Version 2 has `hh:mm:ss.cc,hh:mm:ss.cc`
+//! timing lines, text with `[br]` line breaks, and blank lines between cues.
+//! Both may start with a header of `[TAG]` lines.
+
+use super::markup;
+use super::parse::{clock, Diagnostics};
+use super::{Cue, Format, Subtitle};
+use std::fmt::Write as _;
+
+/// How long the last SubViewer 1 cue stays up.
+const LAST_CUE_MS: u64 = 3000;
+
+/// The header SubViewer 2 writes before the cues.
+const HEADER: &str = "[INFORMATION]\r
+[TITLE]\r
+[AUTHOR]\r
+[SOURCE]\r
+[PRG]\r
+[FILEPATH]\r
+[DELAY]0\r
+[CD TRACK]0\r
+[COMMENT]\r
+[END INFORMATION]\r
+[SUBTITLE]\r
+[COLF]&HFFFFFF,[STYLE]bd,[SIZE]18,[FONT]Arial\r
+";
+
+/// A SubViewer 1 time line, `[hh:mm:ss]`.
+pub(crate) fn time(line: &str) -> Option<u64> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    (inner.len() == 8 && inner.matches(':').count() == 2)
+        .then(|| clock(inner))
+        .flatten()
+}
+
+/// A SubViewer 2 timing line, `hh:mm:ss.cc,hh:mm:ss.cc`.
+pub(crate) fn timing(line: &str) -> Option<(u64, u64)> {
+    let (start, end) = line.split_once(',')?;
+    let valid = |time: &str| time.matches(':').count() == 2 && time.contains('.');
+    if !valid(start) || !valid(end) {
+        return None;
+    }
+    Some((clock(start)?, clock(end)?))
+}
+
+pub(crate) fn parse_v1(lines: &[&str], diagnostics: &mut Diagnostics) -> Subtitle {
+    let mut entries: Vec<(usize, u64, Vec<&str>)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if let Some(start) = time(line) {
+            entries.push((i + 1, start, Vec::new()));
+        } else if line.is_empty() || line.starts_with("**") {
+            continue;
+        } else if let Some(entry) = entries.last_mut() {
+            entry.2.push(line);
+        } else if !line.starts_with('[') {
+            diagnostics.warning(
+                i + 1,
+                "strayText",
+                format!("text before the first cue: {line:?}"),
+            );
+        }
+    }
+    let mut cues = Vec::new();
+    for (k, (line, start, text)) in entries.iter().enumerate() {
+        if text.is_empty() {
+            continue;
+        }
+        let end = entries
+            .get(k + 1)
+            .map_or(start + LAST_CUE_MS, |next| next.1);
+        cues.push(Cue::new(*line, *start, end, markup::piped(&text.join("|"))));
+    }
+    Subtitle::new(Format::SubViewer1, cues)
+}
+
+pub(crate) fn parse_v2(lines: &[&str], diagnostics: &mut Diagnostics) -> Subtitle {
+    let mut cues = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        i += 1;
+        if line.is_empty() {
+            continue;
+        }
+        let Some((start, end)) = timing(line) else {
+            // Header tags, e.g. `[TITLE]` or `[COLF]&HFFFFFF,[STYLE]bd`,
+            // and their values.
+            if !cues.is_empty() {
+                diagnostics.warning(i, "strayText", format!("text outside a cue: {line:?}"));
+            }
+            continue;
+        };
+        let first = i;
+        while i < lines.len() && !lines[i].trim().is_empty() && timing(lines[i].trim()).is_none() {
+            i += 1;
+        }
+        let text = lines[first..i]
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .replace("[br]", "\n");
+        cues.push(Cue::new(first, start, end, markup::plain(&text)));
+    }
+    Subtitle::new(Format::SubViewer2, cues)
+}
+
+/// `hh:mm:ss.cc`.
+fn timestamp(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:02}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000 / 10
+    )
+}
+
+/// Writes cues as SubViewer 2, which has no formatting.
+pub(crate) fn render_v2(cues: &[Cue]) -> String {
+    let mut out = HEADER.to_string();
+    for cue in cues {
+        let _ = write!(
+            out,
+            "{},{}\r\n{}\r\n\r\n",
+            timestamp(cue.start_ms),
+            timestamp(cue.end_ms),
+            cue.text.replace('\n', "[br]")
+        );
+    }
+    out
+}
@@ -0,0 +1,550 @@
+//! Fixing subtitle timing without leaving the app: shifting cues, stretching
+//! them between two anchor points, and converting between frame rates.
+//!
+//! Each operation writes a new file. [`Resyncs`] remembers what was done to
+//! it, across restarts, and uploads of that file say so in their comment.
+
+use super::convert::{write_parsed, Converted};
+use super::parse::parse_file;
+use super::{write, Cue, Format, SubtitleError};
+use crate::api::xmlrpc::UploadInfo;
+use crate::media::timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Cues `first` to `last`, inclusive, numbered from 0 as `parse_subtitle`
+/// lists them.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CueRange {
+    pub first: usize,
+    pub last: usize,
+}
+
+/// A time in the file and the time it should be at.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anchor {
+    pub from_ms: u64,
+    pub to_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operation {
+    Shift {
+        offset_ms: i64,
+        range: Option<CueRange>,
+    },
+    /// Moves times linearly so both anchors land where they should.
+    Stretch { first: Anchor, second: Anchor },
+    /// Retimes a subtitle made for a video at `from` frames per second for
+    /// the same video at `to`.
+    Fps { from: f64, to: f64 },
+}
+
+/// NTSC rates as the exact fractions `23.976` and `29.97` stand for.
+fn exact(fps: f64) -> f64 {
+    for (rounded, exact) in [(23.976, 24000.0 / 1001.0), (29.97, 30000.0 / 1001.0)] {
+        if (fps - rounded).abs() < 0.001 {
+            return exact;
+        }
+    }
+    fps
+}
+
+/// `+1.500 s`.
+fn seconds(ms: i64) -> String {
+    format!(
+        "{}{}.{:03} s",
+        if ms < 0 { '-' } else { '+' },
+        ms.unsigned_abs() / 1000,
+        ms.unsigned_abs() % 1000
+    )
+}
+
+impl Operation {
+    fn validate(&self, cue_count: usize) -> Result<(), SubtitleError> {
+        let invalid = |message: String| Err(SubtitleError::InvalidTiming(message));
+        match *self {
+            Self::Shift {
+                range: Some(range), ..
+            } if range.first > range.last || range.last >= cue_count => invalid(format!(
+                "cues {} to {} are not in a file of {cue_count} cues",
+                range.first + 1,
+                range.last + 1
+            )),
+            Self::Stretch { first, second } => {
+                let (from, to) = (
+                    second.from_ms as f64 - first.from_ms as f64,
+                    second.to_ms as f64 - first.to_ms as f64,
+                );
+                if from == 0.0 {
+                    invalid("the anchor points are at the same time".into())
+                } else if to / from <= 0.0 {
+                    invalid("the anchor points would put the cues in reverse order".into())
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Fps { from, to } if !(from > 0.0 && to > 0.0) => {
+                invalid(format!("cannot convert from {from} to {to} fps"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Where a time moves to, before clamping at zero.
+    fn map(&self, ms: u64) -> f64 {
+        let ms = ms as f64;
+        match *self {
+            Self::Shift { offset_ms, .. } => ms + offset_ms as f64,
+            Self::Stretch { first, second } => {
+                let scale = (second.to_ms as f64 - first.to_ms as f64)
+                    / (second.from_ms as f64 - first.from_ms as f64);
+                first.to_ms as f64 + (ms - first.from_ms as f64) * scale
+            }
+            Self::Fps { from, to } => ms * exact(from) / exact(to),
+        }
+    }
+
+    /// Retimes the cues it applies to; returns how many changed and how
+    /// many would have started before zero.
+    fn apply(&self, cues: &mut [Cue]) -> (usize, usize) {
+        let cues = match *self {
+            Self::Shift {
+                range: Some(range), ..
+            } => &mut cues[range.first..=range.last],
+            _ => cues,
+        };
+        let (mut changed, mut clamped) = (0, 0);
+        for cue in cues {
+            let (start, end) = (self.map(cue.start_ms), self.map(cue.end_ms));
+            clamped += usize::from(start < 0.0);
+            let (start, end) = (start.max(0.0).round() as u64, end.max(0.0).round() as u64);
+            changed += usize::from((start, end) != (cue.start_ms, cue.end_ms));
+            cue.start_ms = start;
+            cue.end_ms = end;
+        }
+        (changed, clamped)
+    }
+
+    /// What was done, for the upload comment.
+    fn describe(&self) -> String {
+        match *self {
+            Self::Shift {
+                offset_ms,
+                range: None,
+            } => format!("shifted by {}", seconds(offset_ms)),
+            Self::Shift {
+                offset_ms,
+                range: Some(range),
+            } => format!(
+                "shifted cues {} to {} by {}",
+                range.first + 1,
+                range.last + 1,
+                seconds(offset_ms)
+            ),
+            Self::Stretch { first, second } => format!(
+                "stretched to move {} to {} and {} to {}",
+                timestamp(first.from_ms, '.'),
+                timestamp(first.to_ms, '.'),
+                timestamp(second.from_ms, '.'),
+                timestamp(second.to_ms, '.')
+            ),
+            Self::Fps { from, to } => format!("converted from {from} to {to} fps"),
+        }
+    }
+}
+
+/// What retiming operations produced each file, so uploads can mention
+/// them. Saved as it changes, so a file retimed before a restart still says
+/// so when uploaded; the upload queue also stores the comment itself.
+#[derive(Clone, Default)]
+pub struct Resyncs {
+    notes: Arc<Mutex<HashMap<PathBuf, Vec<String>>>>,
+    /// Where the notes are saved; `None` keeps them in memory.
+    path: Option<Arc<PathBuf>>,
+}
+
+fn key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Resyncs {
+    /// Loads the notes saved at `path`, forgetting files that are gone. A
+    /// missing or unreadable file starts empty: at worst an upload comment
+    /// goes without its note.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut notes: HashMap<PathBuf, Vec<String>> = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        notes.retain(|file, _| file.exists());
+        Self {
+            notes: Arc::new(Mutex::new(notes)),
+            path: Some(Arc::new(path)),
+        }
+    }
+
+    /// Notes `operation` for `output`, after whatever produced `source`.
+    fn record(&self, source: &Path, output: &Path, operation: String) {
+        let mut notes = self.notes.lock().unwrap();
+        let mut done = notes.get(&key(source)).cloned().unwrap_or_default();
+        done.push(operation);
+        notes.insert(key(output), done);
+        // The retimed file is written either way; only its note is at stake.
+        if let (Some(path), Ok(data)) = (&self.path, serde_json::to_vec(&*notes)) {
+            let _ = write(path, &data);
+        }
+    }
+
+    /// Adds what was done to `path` to the upload comment, once.
+    pub fn annotate(&self, info: &mut UploadInfo, path: &Path) {
+        let Some(done) = self.notes.lock().unwrap().get(&key(path)).cloned() else {
+            return;
+        };
+        let note = format!("Resynced: {}.", done.join("; "));
+        match info.comment.as_mut() {
+            Some(comment) if comment.contains(&note) => {}
+            Some(comment) if !comment.trim().is_empty() => {
+                comment.push('\n');
+                comment.push_str(&note);
+            }
+            _ => info.comment = Some(note),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Retimed {
+    #[serde(flatten)]
+    pub converted: Converted,
+    /// Cues whose times changed.
+    pub changed: usize,
+    /// Cues moved before the start of the video, now starting at zero.
+    pub clamped: usize,
+    /// What the upload comment will say was done.
+    pub note: String,
+}
+
+/// Applies `operation` to a subtitle file and writes the result as `format`,
+/// by default the source's, next to it with `.resynced` before the
+/// extension.
+pub fn retime_file(
+    resyncs: &Resyncs,
+    path: &Path,
+    operation: Operation,
+    output: Option<&Path>,
+    format: Option<Format>,
+    language: Option<&str>,
+) -> Result<Retimed, SubtitleError> {
+    // MicroDVD frames stay put; only the rate they are counted at changes.
+    let (read_fps, write_fps) = match operation {
+        Operation::Fps { from, to } => (Some(from), Some(to)),
+        _ => (None, None),
+    };
+    let mut parsed = parse_file(path, None, read_fps, language)?;
+    operation.validate(parsed.subtitle.cues.len())?;
+    let (changed, clamped) = operation.apply(&mut parsed.subtitle.cues);
+    let to = format.unwrap_or(parsed.subtitle.format);
+    let output = output.map_or_else(
+        || path.with_extension(format!("resynced.{}", to.extension())),
+        Path::to_path_buf,
+    );
+    let converted = write_parsed(parsed, to, write_fps, output)?;
+    let note = operation.describe();
+    resyncs.record(path, &converted.output, note.clone());
+    Ok(Retimed {
+        converted,
+        changed,
+        clamped,
+        note,
+    })
+}
+
+async fn retime(
+    resyncs: &Resyncs,
+    path: String,
+    operation: Operation,
+    output: Option<String>,
+    format: Option<Format>,
+    language: Option<String>,
+) -> Result<Retimed, SubtitleError> {
+    let resyncs = resyncs.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        retime_file(
+            &resyncs,
+            Path::new(&path),
+            operation,
+            output.as_deref().map(Path::new),
+            format,
+            language.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| SubtitleError::Task(e.to_string()))?
+}
+
+/// Moves all cues, or cues `range`, by `offset_ms`; negative is earlier.
+#[tauri::command]
+pub async fn shift_subtitle(
+    resyncs: tauri::State<'_, Resyncs>,
+    path: String,
+    offset_ms: i64,
+    range: Option<CueRange>,
+    output: Option<String>,
+    format: Option<Format>,
+    language: Option<String>,
+) -> Result<Retimed, SubtitleError> {
+    let operation = Operation::Shift { offset_ms, range };
+    retime(&resyncs, path, operation, output, format, language).await
+}
+
+/// Moves cue times linearly so `first.fromMs` lands on `first.toMs` and
+/// `second.fromMs` on `second.toMs`, fixing drift as well as offset.
+#[tauri::command]
+pub async fn stretch_subtitle(
+    resyncs: tauri::State<'_, Resyncs>,
+    path: String,
+    first: Anchor,
+    second: Anchor,
+    output: Option<String>,
+    format: Option<Format>,
+    language: Option<String>,
+) -> Result<Retimed, SubtitleError> {
+    let operation = Operation::Stretch { first, second };
+    retime(&resyncs, path, operation, output, format, language).await
+}
+
+/// Retimes a subtitle made for a video at `from` fps, e.g. 25, for the same
+/// video at `to`, e.g. 23.976.
+#[tauri::command]
+pub async fn convert_subtitle_fps(
+    resyncs: tauri::State<'_, Resyncs>,
+    path: String,
+    from: f64,
+    to: f64,
+    output: Option<String>,
+    format: Option<Format>,
+    language: Option<String>,
+) -> Result<Retimed, SubtitleError> {
+    let operation = Operation::Fps { from, to };
+    retime(&resyncs, path, operation, output, format, language).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seconds_formats_any_offset() {
+        assert_eq!(seconds(1500), "+1.500 s");
+        assert_eq!(seconds(-250), "-0.250 s");
+        assert_eq!(seconds(i64::MIN), "-9223372036854775.808 s");
+    }
+
+    fn cues(times: &[(u64, u64)]) -> Vec<Cue> {
+        let cues = times.iter().enumerate();
+        cues.map(|(i, &(start, end))| Cue::new(i + 1, start, end, Vec::new()))
+            .collect()
+    }
+
+    fn applied(operation: Operation, times: &[(u64, u64)]) -> (Vec<(u64, u64)>, usize, usize) {
+        let mut cues = cues(times);
+        operation.validate(cues.len()).unwrap();
+        let (changed, clamped) = operation.apply(&mut cues);
+        let times = cues.iter().map(|cue| (cue.start_ms, cue.end_ms)).collect();
+        (times, changed, clamped)
+    }
+
+    const TIMES: [(u64, u64); 3] = [(1000, 2000), (3000, 4500), (6000, 7000)];
+
+    #[test]
+    fn shifts_every_cue_or_a_range() {
+        let shift = |offset_ms, range| Operation::Shift { offset_ms, range };
+        assert_eq!(
+            applied(shift(1500, None), &TIMES),
+            (vec![(2500, 3500), (4500, 6000), (7500, 8500)], 3, 0)
+        );
+        let middle = CueRange { first: 1, last: 1 };
+        assert_eq!(
+            applied(shift(-500, Some(middle)), &TIMES),
+            (vec![(1000, 2000), (2500, 4000), (6000, 7000)], 1, 0)
+        );
+        assert_eq!(applied(shift(0, None), &TIMES), (TIMES.to_vec(), 0, 0));
+    }
+
+    #[test]
+    fn clamps_cues_moved_before_zero() {
+        let operation = Operation::Shift {
+            offset_ms: -3500,
+            range: None,
+        };
+        assert_eq!(
+            applied(operation, &TIMES),
+            (vec![(0, 0), (0, 1000), (2500, 3500)], 3, 2)
+        );
+    }
+
+    #[test]
+    fn stretches_between_two_anchors() {
+        // Half a second late at ten seconds, a second and a half late at
+        // 110: both an offset and a drift.
+        let operation = Operation::Stretch {
+            first: Anchor {
+                from_ms: 10_000,
+                to_ms: 9_500,
+            },
+            second: Anchor {
+                from_ms: 110_000,
+                to_ms: 108_500,
+            },
+        };
+        assert_eq!(operation.map(10_000), 9_500.0);
+        assert_eq!(operation.map(110_000), 108_500.0);
+        assert_eq!(operation.map(60_000), 59_000.0);
+        // Outside the anchors, the same line carries on.
+        assert_eq!(operation.map(0), 9_500.0 - 9_900.0);
+        assert_eq!(operation.map(210_000), 207_500.0);
+        assert_eq!(
+            applied(operation, &[(0, 1000), (60_000, 61_000)]),
+            (vec![(0, 590), (59_000, 59_990)], 2, 1)
+        );
+    }
+
+    #[test]
+    fn converts_frame_rates_with_the_exact_ntsc_fraction() {
+        let hour = 3_600_000;
+        // 25 fps to 24000/1001 is a factor of exactly 25025/24000; taking
+        // 23.976 at face value would be almost 4 ms off after an hour.
+        let slower = Operation::Fps {
+            from: 25.0,
+            to: 23.976,
+        };
+        assert_eq!(applied(slower, &[(hour, hour)]).0, [(3_753_750, 3_753_750)]);
+        let faster = Operation::Fps {
+            from: 23.976,
+            to: 25.0,
+        };
+        assert_eq!(applied(faster, &[(3_753_750, 3_753_750)]).0, [(hour, hour)]);
+        let ntsc = Operation::Fps {
+            from: 29.97,
+            to: 23.976,
+        };
+        assert_eq!(applied(ntsc, &[(hour, hour)]).0, [(4_500_000, 4_500_000)]);
+        assert_eq!(applied(slower, &[(1000, 2000)]), (vec![(1043, 2085)], 1, 0));
+    }
+
+    #[test]
+    fn rejects_impossible_operations() {
+        let anchor = |from_ms, to_ms| Anchor { from_ms, to_ms };
+        let range = |first, last| Operation::Shift {
+            offset_ms: 1000,
+            range: Some(CueRange { first, last }),
+        };
+        let cases = [
+            (range(2, 1), "cues 3 to 2 are not in a file of 3 cues"),
+            (range(0, 3), "cues 1 to 4 are not in a file of 3 cues"),
+            (
+                Operation::Stretch {
+                    first: anchor(5000, 5000),
+                    second: anchor(5000, 9000),
+                },
+                "the anchor points are at the same time",
+            ),
+            (
+                Operation::Stretch {
+                    first: anchor(5000, 9000),
+                    second: anchor(9000, 5000),
+                },
+                "the anchor points would put the cues in reverse order",
+            ),
+            (
+                Operation::Stretch {
+                    first: anchor(5000, 5000),
+                    second: anchor(9000, 5000),
+                },
+                "the anchor points would put the cues in reverse order",
+            ),
+            (
+                Operation::Fps {
+                    from: 0.0,
+                    to: 25.0,
+                },
+                "cannot convert from 0 to 25 fps",
+            ),
+            (
+                Operation::Fps {
+                    from: 25.0,
+                    to: -23.976,
+                },
+                "cannot convert from 25 to -23.976 fps",
+            ),
+            (
+                Operation::Fps {
+                    from: f64::NAN,
+                    to: 25.0,
+                },
+                "cannot convert from NaN to 25 fps",
+            ),
+        ];
+        for (operation, message) in cases {
+            let error = operation.validate(3).unwrap_err();
+            assert_eq!(error.kind(), "invalidTiming");
+            assert_eq!(error.to_string(), message);
+        }
+        assert!(range(0, 2).validate(3).is_ok());
+    }
+
+    #[test]
+    fn remembers_resyncs_across_restarts() {
+        let dir = std::env::temp_dir().join(format!("{}-resyncs", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (source, shifted, stretched) = (
+            dir.join("a.srt"),
+            dir.join("a.resynced.srt"),
+            dir.join("a.resynced.resynced.srt"),
+        );
+        for file in [&source, &shifted, &stretched] {
+            fs::write(file, "").unwrap();
+        }
+        let notes = dir.join("resyncs.json");
+        let resyncs = Resyncs::open(&notes);
+        resyncs.record(&source, &shifted, "shifted by +1.000 s".into());
+        resyncs.record(
+            &shifted,
+            &stretched,
+            "converted from 25 to 23.976 fps".into(),
+        );
+
+        // A file that was deleted since is forgotten.
+        fs::remove_file(&shifted).unwrap();
+        let resyncs = Resyncs::open(&notes);
+        assert_eq!(resyncs.notes.lock().unwrap().len(), 1);
+
+        let mut info = UploadInfo {
+            comment: Some("Synced to the BluRay.".into()),
+            ..Default::default()
+        };
+        resyncs.annotate(&mut info, &stretched);
+        resyncs.annotate(&mut info, &stretched);
+        assert_eq!(
+            info.comment.unwrap(),
+            "Synced to the BluRay.\n\
+             Resynced: shifted by +1.000 s; converted from 25 to 23.976 fps."
+        );
+        let mut untouched = UploadInfo::default();
+        resyncs.annotate(&mut untouched, &source);
+        assert_eq!(untouched.comment, None);
+
+        // A broken file only loses the notes.
+        fs::write(&notes, "{").unwrap();
+        assert!(Resyncs::open(&notes).notes.lock().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
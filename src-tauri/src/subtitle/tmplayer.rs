@@ -0,0 +1,66 @@
+//! TMPlayer: `hh:mm:ss:text` lines, `|` between lines of text. Cues have no
+//! end time: each lasts until the next, and a line without text clears the
+//! screen.
+
+use super::markup;
+use super::parse::{clock, Diagnostics};
+use super::{Cue, Format, Subtitle};
+
+/// How long the last cue stays up when nothing clears it.
+const LAST_CUE_MS: u64 = 3000;
+
+/// The start and text of a cue line: `h:mm:ss:text`, `hh:mm:ss=text`, or
+/// `hh:mm:ss,1=text` in the variant that numbers the lines of a cue.
+pub(crate) fn split(line: &str) -> Option<(u64, &str)> {
+    let (hours, rest) = line.split_once(':')?;
+    let (minutes, rest) = rest.split_once(':')?;
+    if !(1..=2).contains(&hours.len()) || minutes.len() != 2 {
+        return None;
+    }
+    let seconds = rest.get(..2)?;
+    let mut rest = &rest[2..];
+    if let Some(numbered) = rest.strip_prefix(',') {
+        rest = numbered.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    let text = rest.strip_prefix([':', '='])?;
+    Some((clock(&format!("{hours}:{minutes}:{seconds}"))?, text))
+}
+
+pub(crate) fn parse(lines: &[&str], diagnostics: &mut Diagnostics) -> Subtitle {
+    // Lines of the numbered variant share a start time; join them.
+    let mut entries: Vec<(usize, u64, String)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((start, text)) = split(line) else {
+            diagnostics.error(
+                i + 1,
+                "badTimestamp",
+                format!("cannot read the time of {line:?}"),
+            );
+            continue;
+        };
+        match entries.last_mut() {
+            Some(last) if last.1 == start && !text.trim().is_empty() => {
+                if !last.2.is_empty() {
+                    last.2.push('|');
+                }
+                last.2.push_str(text);
+            }
+            _ => entries.push((i + 1, start, text.to_string())),
+        }
+    }
+    let mut cues = Vec::new();
+    for (k, (line, start, text)) in entries.iter().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let end = entries
+            .get(k + 1)
+            .map_or(start + LAST_CUE_MS, |next| next.1);
+        cues.push(Cue::new(*line, *start, end, markup::piped(text)));
+    }
+    Subtitle::new(Format::TmPlayer, cues)
+}
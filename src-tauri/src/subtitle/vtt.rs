@@ -0,0 +1,88 @@
+//! WebVTT: the `WEBVTT` signature and header blocks, then cues of an
+//! optional identifier, a timing line with settings, and text.
+
+use super::markup;
+use super::parse::{clock, Diagnostics};
+use super::{Cue, Format, Subtitle};
+use crate::media::{self, TextCue};
+
+pub(crate) fn parse(lines: &[&str], diagnostics: &mut Diagnostics) -> Subtitle {
+    if !lines.first().is_some_and(|line| line.starts_with("WEBVTT")) {
+        diagnostics.error(1, "missingSignature", "the file does not start with WEBVTT");
+    }
+    let mut cues = Vec::new();
+    let mut header = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim().is_empty() {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            i += 1;
+        }
+        let block = &lines[first..i];
+        // The timing line comes first, or second after an identifier.
+        let Some(at) = block.iter().take(2).position(|line| line.contains("-->")) else {
+            if cues.is_empty() {
+                // Signature, STYLE, REGION and NOTE blocks.
+                header.extend_from_slice(block);
+                header.push("");
+            } else if !block[0].starts_with("NOTE") {
+                diagnostics.warning(
+                    first + 1,
+                    "strayText",
+                    format!("text outside a cue: {:?}", block[0].trim()),
+                );
+            }
+            continue;
+        };
+        let line = first + at + 1;
+        let (start, rest) = block[at].split_once("-->").unwrap_or_default();
+        let mut words = rest.split_whitespace();
+        let end = words.next().unwrap_or_default();
+        let (Some(start_ms), Some(end_ms)) = (clock(start), clock(end)) else {
+            diagnostics.error(
+                line,
+                "badTimestamp",
+                format!("cannot read the timing {:?}", block[at].trim()),
+            );
+            continue;
+        };
+        if start.contains(',') || end.contains(',') {
+            diagnostics.warning(
+                line,
+                "commaInTimestamp",
+                "WebVTT times need a '.' before the milliseconds",
+            );
+        }
+        let settings = words.collect::<Vec<_>>().join(" ");
+        let spans = markup::tagged(&block[at + 1..].join("\n"), line, true, diagnostics);
+        let mut cue = Cue::new(first + 1, start_ms, end_ms, spans);
+        cue.id = (at == 1).then(|| block[0].trim().to_string());
+        cue.settings = Some(settings).filter(|s| !s.is_empty());
+        cues.push(cue);
+    }
+    let mut subtitle = Subtitle::new(Format::Vtt, cues);
+    subtitle.header = Some(header.join("\n").trim_end().to_string()).filter(|h| !h.is_empty());
+    subtitle
+}
+
+/// Writes cues as WebVTT, with their identifiers and settings. `header` is
+/// what [`parse`] kept before the cues.
+pub(crate) fn render(cues: &[Cue], header: Option<&str>) -> String {
+    let cues: Vec<TextCue> = cues
+        .iter()
+        .map(|cue| TextCue {
+            id: cue.id.clone(),
+            settings: cue.settings.clone(),
+            ..TextCue::new(
+                cue.start_ms,
+                cue.end_ms,
+                markup::to_tagged(&cue.spans, true),
+            )
+        })
+        .collect();
+    media::render_vtt(&cues, header)
+}
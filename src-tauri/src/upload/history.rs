@@ -0,0 +1,398 @@
+//! Record of every successful `UploadSubtitles` call.
+//!
+//! Records are appended to `upload-history.jsonl` in the app data dir and kept
+//! in memory for querying; the file is never rewritten, so an interrupted
+//! write can at worst lose its own line. Lines that do not parse, such as one
+//! cut short, are skipped on loading, and the next record starts after them
+//! on a line of its own.
+
+use crate::api::xmlrpc::{UploadFile, UploadInfo, UploadResult};
+use crate::error::impl_serialize_error;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("upload history I/O error on {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not encode history record: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("background task failed: {0}")]
+    Task(String),
+}
+
+impl HistoryError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Encode(_) => "encode",
+            Self::Task(_) => "task",
+        }
+    }
+
+    fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl_serialize_error!(HistoryError);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRecord {
+    /// Unix milliseconds.
+    pub uploaded_at: u64,
+    pub subtitle_md5: String,
+    pub subtitle_filename: String,
+    pub movie_hash: Option<String>,
+    pub movie_byte_size: Option<u64>,
+    pub imdb_id: String,
+    pub language: String,
+    pub release_name: Option<String>,
+    pub hearing_impaired: bool,
+    pub high_definition: bool,
+    pub automatic_translation: bool,
+    pub foreign_parts_only: bool,
+    pub url: String,
+    pub account: Option<String>,
+    /// Character encoding the subtitle was uploaded as.
+    pub encoding: Option<String>,
+}
+
+impl HistoryRecord {
+    pub fn new(
+        info: &UploadInfo,
+        file: &UploadFile,
+        result: &UploadResult,
+        account: Option<&str>,
+    ) -> Self {
+        Self {
+            uploaded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            subtitle_md5: result.subtitle_md5.clone(),
+            subtitle_filename: file.filename(),
+            movie_hash: file.movie_hash.clone(),
+            movie_byte_size: file.movie_byte_size,
+            imdb_id: info.imdb_id.trim_start_matches("tt").to_string(),
+            language: info.sub_language_id.clone(),
+            release_name: info.release_name.clone(),
+            hearing_impaired: info.hearing_impaired,
+            high_definition: info.high_definition,
+            automatic_translation: info.automatic_translation,
+            foreign_parts_only: info.foreign_parts_only,
+            url: result.url.clone(),
+            account: account.map(str::to_string),
+            encoding: result.subtitle_encoding.clone(),
+        }
+    }
+}
+
+/// Query over the history; unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryFilter {
+    /// Case-insensitive substring of the release name, file name or URL.
+    pub text: Option<String>,
+    pub imdb_id: Option<String>,
+    pub language: Option<String>,
+    pub account: Option<String>,
+    pub movie_hash: Option<String>,
+    pub subtitle_md5: Option<String>,
+    /// Unix milliseconds, inclusive.
+    pub since: Option<u64>,
+    /// Unix milliseconds, exclusive.
+    pub until: Option<u64>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, record: &HistoryRecord) -> bool {
+        let eq = |wanted: &Option<String>, actual: Option<&str>| {
+            wanted
+                .as_deref()
+                .is_none_or(|w| actual.is_some_and(|a| a.eq_ignore_ascii_case(w)))
+        };
+        let imdb_id = self
+            .imdb_id
+            .as_deref()
+            .map(|id| id.trim_start_matches("tt").to_string());
+        let text = self.text.as_deref().map(str::to_lowercase);
+        eq(&imdb_id, Some(&record.imdb_id))
+            && eq(&self.language, Some(&record.language))
+            && eq(&self.account, record.account.as_deref())
+            && eq(&self.movie_hash, record.movie_hash.as_deref())
+            && eq(&self.subtitle_md5, Some(&record.subtitle_md5))
+            && self.since.is_none_or(|since| record.uploaded_at >= since)
+            && self.until.is_none_or(|until| record.uploaded_at < until)
+            && text.is_none_or(|text| {
+                [
+                    record.release_name.as_deref().unwrap_or_default(),
+                    &record.subtitle_filename,
+                    &record.url,
+                ]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&text))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    /// Matches before `offset` / `limit` were applied.
+    pub total: usize,
+    pub records: Vec<HistoryRecord>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+const CSV_HEADER: &str = "uploaded_at,subtitle_md5,subtitle_filename,movie_hash,movie_byte_size,imdb_id,language,release_name,hearing_impaired,high_definition,automatic_translation,foreign_parts_only,url,account,encoding";
+
+fn csv_field(out: &mut String, value: &str) {
+    // Spreadsheets run cells starting with these as formulas.
+    let guard = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        "'"
+    } else {
+        ""
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(guard);
+        out.push_str(&value.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(guard);
+        out.push_str(value);
+    }
+}
+
+fn to_csv(records: &[HistoryRecord]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push_str("\r\n");
+    for r in records {
+        let fields = [
+            r.uploaded_at.to_string(),
+            r.subtitle_md5.clone(),
+            r.subtitle_filename.clone(),
+            r.movie_hash.clone().unwrap_or_default(),
+            r.movie_byte_size.map(|s| s.to_string()).unwrap_or_default(),
+            r.imdb_id.clone(),
+            r.language.clone(),
+            r.release_name.clone().unwrap_or_default(),
+            u8::from(r.hearing_impaired).to_string(),
+            u8::from(r.high_definition).to_string(),
+            u8::from(r.automatic_translation).to_string(),
+            u8::from(r.foreign_parts_only).to_string(),
+            r.url.clone(),
+            r.account.clone().unwrap_or_default(),
+            r.encoding.clone().unwrap_or_default(),
+        ];
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            csv_field(&mut out, field);
+        }
+        let _ = write!(out, "\r\n");
+    }
+    out
+}
+
+struct Store {
+    path: PathBuf,
+    file: File,
+    records: Vec<HistoryRecord>,
+}
+
+/// Shared history handle; cheap to clone.
+#[derive(Clone)]
+pub struct UploadHistory {
+    store: Arc<Mutex<Store>>,
+}
+
+impl UploadHistory {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, HistoryError> {
+        let path = path.into();
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(HistoryError::io(&path, e)),
+        };
+        let records = data
+            .split(|&b| b == b'\n')
+            .filter_map(|line| serde_json::from_slice(line).ok())
+            .collect();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| HistoryError::io(dir, e))?;
+        }
+        let mut file = File::options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| HistoryError::io(&path, e))?;
+        if !data.is_empty() && !data.ends_with(b"\n") {
+            file.write_all(b"\n")
+                .map_err(|e| HistoryError::io(&path, e))?;
+        }
+        Ok(Self {
+            store: Arc::new(Mutex::new(Store {
+                path,
+                file,
+                records,
+            })),
+        })
+    }
+
+    pub fn record(&self, record: HistoryRecord) -> Result<(), HistoryError> {
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        let mut store = self.store.lock().unwrap();
+        let Store {
+            path,
+            file,
+            records,
+        } = &mut *store;
+        file.write_all(&line)
+            .and_then(|()| file.sync_data())
+            .map_err(|e| HistoryError::io(path, e))?;
+        records.push(record);
+        Ok(())
+    }
+
+    /// Matching records, newest first.
+    pub fn query(&self, filter: &HistoryFilter) -> HistoryPage {
+        let store = self.store.lock().unwrap();
+        let matching: Vec<_> = store
+            .records
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .collect();
+        HistoryPage {
+            total: matching.len(),
+            records: matching
+                .into_iter()
+                .skip(filter.offset.unwrap_or(0))
+                .take(filter.limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Writes the matching records to `path`; returns how many were written.
+    pub fn export(
+        &self,
+        path: &Path,
+        format: ExportFormat,
+        filter: &HistoryFilter,
+    ) -> Result<usize, HistoryError> {
+        let records = self.query(filter).records;
+        let data = match format {
+            ExportFormat::Csv => to_csv(&records).into_bytes(),
+            ExportFormat::Json => serde_json::to_vec_pretty(&records)?,
+        };
+        fs::write(path, data).map_err(|e| HistoryError::io(path, e))?;
+        Ok(records.len())
+    }
+}
+
+#[tauri::command]
+pub fn upload_history_query(
+    history: tauri::State<'_, UploadHistory>,
+    filter: Option<HistoryFilter>,
+) -> HistoryPage {
+    history.query(&filter.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn upload_history_export(
+    history: tauri::State<'_, UploadHistory>,
+    path: String,
+    format: ExportFormat,
+    filter: Option<HistoryFilter>,
+) -> Result<usize, HistoryError> {
+    let history = history.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        history.export(Path::new(&path), format, &filter.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| HistoryError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(release_name: &str) -> HistoryRecord {
+        HistoryRecord {
+            uploaded_at: 1_700_000_000_000,
+            subtitle_md5: "0123456789abcdef0123456789abcdef".into(),
+            subtitle_filename: "Movie.srt".into(),
+            movie_hash: None,
+            movie_byte_size: None,
+            imdb_id: "133093".into(),
+            language: "eng".into(),
+            release_name: Some(release_name.into()),
+            hearing_impaired: false,
+            high_definition: false,
+            automatic_translation: false,
+            foreign_parts_only: false,
+            url: "https://www.opensubtitles.org/subtitles/1".into(),
+            account: None,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn skips_a_torn_last_line() {
+        let path = std::env::temp_dir().join(format!("{}-history.jsonl", std::process::id()));
+        let mut data = serde_json::to_vec(&record("first")).unwrap();
+        data.push(b'\n');
+        let torn = serde_json::to_vec(&record("torn")).unwrap();
+        data.extend_from_slice(&torn[..torn.len() / 2]);
+        data.extend_from_slice("é".as_bytes().split_at(1).0);
+        fs::write(&path, data).unwrap();
+
+        let history = UploadHistory::open(&path).unwrap();
+        assert_eq!(history.query(&HistoryFilter::default()).total, 1);
+        history.record(record("second")).unwrap();
+        drop(history);
+
+        let history = UploadHistory::open(&path).unwrap();
+        let names: Vec<_> = history
+            .query(&HistoryFilter::default())
+            .records
+            .into_iter()
+            .filter_map(|r| r.release_name)
+            .collect();
+        assert_eq!(names, ["second", "first"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn csv_cells_are_not_formulas() {
+        let csv = to_csv(&[record("=HYPERLINK(\"http://x\",\"a,b\")"), record("-1+2")]);
+        let rows: Vec<&str> = csv.split("\r\n").collect();
+        assert!(rows[1].contains(",\"'=HYPERLINK(\"\"http://x\"\",\"\"a,b\"\")\","));
+        assert!(rows[2].contains(",'-1+2,"));
+    }
+}
@@ -0,0 +1,5 @@
+//! Upload pipeline state that has to outlive the window: the queue of pending
+//! uploads and the history of completed ones.
+
+pub mod history;
+pub mod queue;
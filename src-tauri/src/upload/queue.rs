@@ -0,0 +1,1020 @@
+//! Persistent upload queue.
+//!
+//! Every change is appended to a JSON-lines journal (`upload-queue.jsonl` in
+//! the app data dir) and fsynced before it is acknowledged, so closing the app
+//! or losing power mid-batch loses nothing. On open the journal is replayed,
+//! items caught mid-upload go back to `pending`, and the file is compacted to
+//! one line per item.
+//!
+//! A single worker task uploads items in queue order. Transient failures
+//! (network, 429, 5xx) are retried with exponential backoff; a transport
+//! failure also marks the queue offline, and the worker then probes the
+//! server with `ServerInfo` until it answers and resumes immediately.
+//! Validation failures are final and leave the item `failed`.
+//!
+//! Each item is tagged with the account that sends it, the active one when
+//! it was queued. The queue holds a token per signed-in account; items of an
+//! account without one wait until it signs in again.
+
+use super::history::{HistoryRecord, UploadHistory};
+use crate::accounts::Accounts;
+use crate::api::xmlrpc::{UploadFile, UploadInfo, UploadResult, XmlRpcClient, XmlRpcError};
+use crate::error::impl_serialize_error;
+use crate::subtitle::timing::Resyncs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// Transient failures tolerated before an item is marked `failed`.
+const MAX_ATTEMPTS: u32 = 8;
+
+const BASE_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// How often the server is probed while the queue is offline.
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Journal lines appended before the file is rewritten as a snapshot.
+const COMPACT_AFTER: usize = 500;
+
+/// Name of the event carrying [`QueueSnapshot`]s to the UI.
+pub const QUEUE_EVENT: &str = "upload-queue";
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    #[error("upload queue I/O error on {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not encode queue entry: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("no queued upload with id {0}")]
+    NotFound(String),
+    #[error("upload {0} is in progress")]
+    Busy(String),
+}
+
+impl QueueError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Encode(_) => "encode",
+            Self::NotFound(_) => "notFound",
+            Self::Busy(_) => "busy",
+        }
+    }
+
+    fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl_serialize_error!(QueueError);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemState {
+    Pending,
+    Uploading,
+    Failed,
+    Done,
+    /// `TryUploadSubtitles` reported the subtitle as already in the database.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItem {
+    pub id: String,
+    pub state: ItemState,
+    /// Paused items are skipped by the worker until resumed.
+    #[serde(default)]
+    pub paused: bool,
+    pub info: UploadInfo,
+    pub file: UploadFile,
+    /// Account the item is uploaded with. Items queued without one, or
+    /// before accounts were tracked, go with the active session.
+    #[serde(default)]
+    pub account: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+    /// Unix milliseconds before which a retry is not attempted.
+    pub next_attempt_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub result: Option<UploadResult>,
+    /// `TryUploadSubtitles` data for duplicates.
+    pub duplicate: Option<serde_json::Value>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// An upload as submitted by the UI.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewUpload {
+    pub info: UploadInfo,
+    pub file: UploadFile,
+    /// Queue the item paused, for review before it is uploaded.
+    #[serde(default)]
+    pub paused: bool,
+    /// Account to upload with; the active one when not given.
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSnapshot {
+    pub paused: bool,
+    pub online: bool,
+    /// Whether the worker has a session token to upload with.
+    pub has_session: bool,
+    /// Account new items are tagged with.
+    pub active_account: Option<String>,
+    pub items: Vec<QueueItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum JournalEntry {
+    Put { item: Box<QueueItem> },
+    Remove { id: String },
+    Order { ids: Vec<String> },
+    Paused { paused: bool },
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Moves `ids` to the front in the given order; other items keep their
+/// relative order behind them. Unknown ids are ignored.
+fn reorder(items: &mut Vec<QueueItem>, ids: &[String]) {
+    let mut rest = std::mem::take(items);
+    for id in ids {
+        if let Some(pos) = rest.iter().position(|item| &item.id == id) {
+            items.push(rest.remove(pos));
+        }
+    }
+    items.append(&mut rest);
+}
+
+struct Store {
+    path: PathBuf,
+    journal: File,
+    items: Vec<QueueItem>,
+    paused: bool,
+    online: bool,
+    appended: usize,
+}
+
+impl Store {
+    fn open(path: PathBuf) -> Result<Self, QueueError> {
+        let mut items: Vec<QueueItem> = Vec::new();
+        let mut paused = false;
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(QueueError::io(&path, e)),
+        };
+        for line in data.split(|&b| b == b'\n') {
+            // A torn last line from a crash, even one cut inside a UTF-8
+            // sequence, is skipped, not fatal.
+            let Ok(entry) = serde_json::from_slice::<JournalEntry>(line) else {
+                continue;
+            };
+            match entry {
+                JournalEntry::Put { item } => match items.iter_mut().find(|i| i.id == item.id) {
+                    Some(existing) => *existing = *item,
+                    None => items.push(*item),
+                },
+                JournalEntry::Remove { id } => items.retain(|i| i.id != id),
+                JournalEntry::Order { ids } => reorder(&mut items, &ids),
+                JournalEntry::Paused { paused: p } => paused = p,
+            }
+        }
+        for item in &mut items {
+            if item.state == ItemState::Uploading {
+                // Interrupted; the server-side duplicate check makes a retry safe.
+                item.state = ItemState::Pending;
+            }
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| QueueError::io(dir, e))?;
+        }
+        let journal = File::options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| QueueError::io(&path, e))?;
+        let mut store = Self {
+            path,
+            journal,
+            items,
+            paused,
+            online: true,
+            appended: 0,
+        };
+        store.compact()?;
+        Ok(store)
+    }
+
+    fn append(&mut self, entry: &JournalEntry) -> Result<(), QueueError> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.journal
+            .write_all(&line)
+            .and_then(|()| self.journal.sync_data())
+            .map_err(|e| QueueError::io(&self.path, e))?;
+        self.appended += 1;
+        if self.appended >= COMPACT_AFTER {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the journal as a snapshot, atomically via a temporary file.
+    fn compact(&mut self) -> Result<(), QueueError> {
+        let tmp = self.path.with_extension("jsonl.tmp");
+        let mut data = Vec::new();
+        for entry in std::iter::once(JournalEntry::Paused {
+            paused: self.paused,
+        })
+        .chain(self.items.iter().map(|item| JournalEntry::Put {
+            item: Box::new(item.clone()),
+        })) {
+            serde_json::to_writer(&mut data, &entry)?;
+            data.push(b'\n');
+        }
+        let mut file = File::create(&tmp).map_err(|e| QueueError::io(&tmp, e))?;
+        file.write_all(&data)
+            .and_then(|()| file.sync_all())
+            .map_err(|e| QueueError::io(&tmp, e))?;
+        fs::rename(&tmp, &self.path).map_err(|e| QueueError::io(&self.path, e))?;
+        self.journal = File::options()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| QueueError::io(&self.path, e))?;
+        self.appended = 0;
+        Ok(())
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut QueueItem, QueueError> {
+        self.items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| QueueError::NotFound(id.to_string()))
+    }
+
+    /// Applies `f` to item `id` and journals the result.
+    fn update(&mut self, id: &str, f: impl FnOnce(&mut QueueItem)) -> Result<(), QueueError> {
+        let item = self.get_mut(id)?;
+        f(item);
+        item.updated_at = unix_millis();
+        let item = item.clone();
+        self.append(&JournalEntry::Put {
+            item: Box::new(item),
+        })
+    }
+}
+
+enum Job {
+    Upload(Box<QueueItem>),
+    Probe,
+    Wait(Option<Duration>),
+}
+
+enum Outcome {
+    Done(UploadResult),
+    Duplicate(serde_json::Value),
+}
+
+type Listener = Arc<dyn Fn(&QueueSnapshot) + Send + Sync>;
+
+/// Credentials the worker uploads with.
+#[derive(Clone)]
+struct Session {
+    token: String,
+    /// Account name recorded in the upload history.
+    account: Option<String>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    /// Token per signed-in account.
+    tokens: HashMap<String, String>,
+    /// Session of the active account, which untagged items are sent with.
+    active: Option<Session>,
+}
+
+impl Sessions {
+    fn for_item(&self, item: &QueueItem) -> Option<Session> {
+        match &item.account {
+            Some(account) => self.tokens.get(account).map(|token| Session {
+                token: token.clone(),
+                account: Some(account.clone()),
+            }),
+            None => self.active.clone(),
+        }
+    }
+
+    /// Replaces or, with `None`, drops the token of `account`.
+    fn set(&mut self, account: &str, token: Option<String>) {
+        match &token {
+            Some(token) => self.tokens.insert(account.into(), token.clone()),
+            None => self.tokens.remove(account),
+        };
+        if let Some(active) = self
+            .active
+            .as_mut()
+            .filter(|s| s.account.as_deref() == Some(account))
+        {
+            match token {
+                Some(token) => active.token = token,
+                None => self.active = None,
+            }
+        }
+    }
+}
+
+struct Shared {
+    store: Mutex<Store>,
+    client: XmlRpcClient,
+    sessions: Mutex<Sessions>,
+    wake: Notify,
+    next_id: AtomicU32,
+}
+
+/// Shared queue handle; cheap to clone.
+#[derive(Clone)]
+pub struct UploadQueue {
+    shared: Arc<Shared>,
+    on_change: Option<Listener>,
+    history: Option<UploadHistory>,
+    accounts: Option<Accounts>,
+}
+
+impl UploadQueue {
+    /// Opens the journal at `path`, creating it if needed.
+    pub fn open(path: impl Into<PathBuf>, client: XmlRpcClient) -> Result<Self, QueueError> {
+        Ok(Self {
+            shared: Arc::new(Shared {
+                store: Mutex::new(Store::open(path.into())?),
+                client,
+                sessions: Mutex::default(),
+                wake: Notify::new(),
+                next_id: AtomicU32::new(0),
+            }),
+            on_change: None,
+            history: None,
+            accounts: None,
+        })
+    }
+
+    /// Records every completed upload in `history`.
+    pub fn with_history(mut self, history: UploadHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Logs in again through `accounts` when a session expires, instead of
+    /// waiting for the UI to do it.
+    pub fn with_accounts(mut self, accounts: Accounts) -> Self {
+        self.accounts = Some(accounts);
+        self
+    }
+
+    /// Calls `listener` with a fresh snapshot after every change.
+    pub fn on_change(mut self, listener: impl Fn(&QueueSnapshot) + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(listener));
+        self
+    }
+
+    /// Spawns the worker; pending items resume as soon as a session is set.
+    pub fn start(&self) {
+        tauri::async_runtime::spawn(self.clone().run());
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let store = self.shared.store.lock().unwrap();
+        let sessions = self.shared.sessions.lock().unwrap();
+        QueueSnapshot {
+            paused: store.paused,
+            online: store.online,
+            has_session: sessions.active.is_some(),
+            active_account: sessions.active.as_ref().and_then(|s| s.account.clone()),
+            items: store.items.clone(),
+        }
+    }
+
+    fn changed(&self) {
+        if let Some(listener) = &self.on_change {
+            listener(&self.snapshot());
+        }
+    }
+
+    /// Applies `f` to the store, then notifies listeners and the worker.
+    fn mutate<T>(
+        &self,
+        f: impl FnOnce(&mut Store) -> Result<T, QueueError>,
+    ) -> Result<T, QueueError> {
+        let result = f(&mut self.shared.store.lock().unwrap());
+        self.changed();
+        self.shared.wake.notify_one();
+        result
+    }
+
+    fn sessions_changed(&self) {
+        self.changed();
+        self.shared.wake.notify_one();
+    }
+
+    /// Sets or clears the active XML-RPC session, and the account name new
+    /// uploads are tagged and recorded under.
+    pub fn set_session(&self, token: Option<String>, account: Option<String>) {
+        {
+            let mut sessions = self.shared.sessions.lock().unwrap();
+            let token = token.filter(|t| !t.is_empty());
+            if let Some(account) = &account {
+                sessions.set(account, token.clone());
+            }
+            sessions.active = token.map(|token| Session { token, account });
+        }
+        self.sessions_changed();
+    }
+
+    /// Replaces every account's token, making `active` the active session.
+    pub fn set_accounts(&self, tokens: HashMap<String, String>, active: Option<String>) {
+        {
+            let mut sessions = self.shared.sessions.lock().unwrap();
+            sessions.active = active.and_then(|account| {
+                tokens.get(&account).map(|token| Session {
+                    token: token.clone(),
+                    account: Some(account),
+                })
+            });
+            sessions.tokens = tokens;
+        }
+        self.sessions_changed();
+    }
+
+    /// The active session token.
+    pub fn token(&self) -> Option<String> {
+        self.shared
+            .sessions
+            .lock()
+            .unwrap()
+            .active
+            .as_ref()
+            .map(|session| session.token.clone())
+    }
+
+    /// Number of unfinished items per account.
+    pub fn queued_by_account(&self) -> HashMap<String, usize> {
+        let store = self.shared.store.lock().unwrap();
+        let active = self.shared.sessions.lock().unwrap().active.clone();
+        let mut counts = HashMap::new();
+        for item in &store.items {
+            if !matches!(item.state, ItemState::Pending | ItemState::Uploading) {
+                continue;
+            }
+            let account = item
+                .account
+                .clone()
+                .or_else(|| active.as_ref().and_then(|s| s.account.clone()));
+            if let Some(account) = account {
+                *counts.entry(account).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn add(&self, uploads: Vec<NewUpload>) -> Result<Vec<QueueItem>, QueueError> {
+        let active = self
+            .shared
+            .sessions
+            .lock()
+            .unwrap()
+            .active
+            .as_ref()
+            .and_then(|s| s.account.clone());
+        self.mutate(|store| {
+            let mut added = Vec::with_capacity(uploads.len());
+            for upload in uploads {
+                let now = unix_millis();
+                let seq = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+                let item = QueueItem {
+                    id: format!("{now:012x}{seq:04x}"),
+                    state: ItemState::Pending,
+                    paused: upload.paused,
+                    info: upload.info,
+                    file: upload.file,
+                    account: upload.account.or_else(|| active.clone()),
+                    attempts: 0,
+                    next_attempt_at: None,
+                    last_error: None,
+                    result: None,
+                    duplicate: None,
+                    created_at: now,
+                    updated_at: now,
+                };
+                store.items.push(item.clone());
+                store.append(&JournalEntry::Put {
+                    item: Box::new(item.clone()),
+                })?;
+                added.push(item);
+            }
+            Ok(added)
+        })
+    }
+
+    /// Pauses the given items, or the whole queue when `ids` is `None`. An
+    /// upload already in flight finishes.
+    pub fn pause(&self, ids: Option<Vec<String>>) -> Result<(), QueueError> {
+        self.mutate(|store| match ids {
+            None => {
+                store.paused = true;
+                store.append(&JournalEntry::Paused { paused: true })
+            }
+            Some(ids) => ids
+                .iter()
+                .try_for_each(|id| store.update(id, |item| item.paused = true)),
+        })
+    }
+
+    /// Resumes the given items, or the whole queue when `ids` is `None`.
+    /// Resuming a failed item queues it again with a fresh retry budget.
+    pub fn resume(&self, ids: Option<Vec<String>>) -> Result<(), QueueError> {
+        self.mutate(|store| match ids {
+            None => {
+                store.paused = false;
+                store.append(&JournalEntry::Paused { paused: false })
+            }
+            Some(ids) => ids.iter().try_for_each(|id| {
+                store.update(id, |item| {
+                    item.paused = false;
+                    if item.state == ItemState::Failed {
+                        item.state = ItemState::Pending;
+                        item.attempts = 0;
+                        item.next_attempt_at = None;
+                    }
+                })
+            }),
+        })
+    }
+
+    /// Moves `ids` to the front of the queue in the given order.
+    pub fn reorder(&self, ids: Vec<String>) -> Result<(), QueueError> {
+        self.mutate(|store| {
+            reorder(&mut store.items, &ids);
+            store.append(&JournalEntry::Order { ids })
+        })
+    }
+
+    /// Removes items from the queue; items being uploaded cannot be cancelled.
+    pub fn cancel(&self, ids: Vec<String>) -> Result<(), QueueError> {
+        self.mutate(|store| {
+            for id in &ids {
+                if store.get_mut(id)?.state == ItemState::Uploading {
+                    return Err(QueueError::Busy(id.clone()));
+                }
+            }
+            for id in ids {
+                store.items.retain(|item| item.id != id);
+                store.append(&JournalEntry::Remove { id })?;
+            }
+            Ok(())
+        })
+    }
+
+    async fn run(self) {
+        loop {
+            match self.next_job() {
+                Job::Upload(item) => self.upload(*item).await,
+                Job::Probe => {
+                    if self.shared.client.server_info().await.is_ok() {
+                        self.back_online();
+                    } else {
+                        self.sleep(Some(PROBE_INTERVAL)).await;
+                    }
+                }
+                Job::Wait(duration) => self.sleep(duration).await,
+            }
+        }
+    }
+
+    /// Waits for `duration`, or until woken by a change.
+    async fn sleep(&self, duration: Option<Duration>) {
+        let woken = self.shared.wake.notified();
+        match duration {
+            Some(duration) => {
+                let _ = tokio::time::timeout(duration, woken).await;
+            }
+            None => woken.await,
+        }
+    }
+
+    /// Picks the first runnable item whose account has a session and marks
+    /// it `uploading`.
+    fn next_job(&self) -> Job {
+        let mut store = self.shared.store.lock().unwrap();
+        if !store.online {
+            return Job::Probe;
+        }
+        if store.paused {
+            return Job::Wait(None);
+        }
+        let sessions = self.shared.sessions.lock().unwrap();
+        let now = unix_millis();
+        let mut wait: Option<u64> = None;
+        let mut due = None;
+        for item in &store.items {
+            if item.state != ItemState::Pending || item.paused {
+                continue;
+            }
+            if sessions.for_item(item).is_none() {
+                continue;
+            }
+            match item.next_attempt_at {
+                Some(at) if at > now => wait = Some(wait.map_or(at - now, |w| w.min(at - now))),
+                _ => {
+                    due = Some(item.id.clone());
+                    break;
+                }
+            }
+        }
+        drop(sessions);
+        let Some(id) = due else {
+            return Job::Wait(wait.map(Duration::from_millis));
+        };
+        match store.update(&id, |item| item.state = ItemState::Uploading) {
+            Ok(()) => {
+                let item = store.items.iter().find(|i| i.id == id).cloned();
+                drop(store);
+                self.changed();
+                item.map_or(Job::Wait(None), |item| Job::Upload(Box::new(item)))
+            }
+            // The journal is not writable; retry later rather than spin.
+            Err(_) => Job::Wait(Some(PROBE_INTERVAL)),
+        }
+    }
+
+    async fn upload(&self, item: QueueItem) {
+        let session = self.shared.sessions.lock().unwrap().for_item(&item);
+        let Some(Session { token, account }) = session else {
+            return self.finish(&item.id, None, Err(XmlRpcError::NoSession));
+        };
+        let client = &self.shared.client;
+        let outcome = async {
+            let check = client.try_upload_subtitles(&token, &item.file).await?;
+            if check.already_in_db {
+                return Ok(Outcome::Duplicate(check.data));
+            }
+            client
+                .upload_subtitles(&token, &item.info, &item.file)
+                .await
+                .map(Outcome::Done)
+        }
+        .await;
+        if let (Some(history), Ok(Outcome::Done(result))) = (&self.history, &outcome) {
+            // The upload itself succeeded; a history write failure must not
+            // turn it into a retry.
+            let _ = history.record(HistoryRecord::new(
+                &item.info,
+                &item.file,
+                result,
+                account.as_deref(),
+            ));
+        }
+        let expired = matches!(&outcome, Err(e) if e.is_session_error());
+        let was_active = self.token().as_deref() == Some(token.as_str());
+        self.finish(&item.id, Some(&token), outcome);
+        if expired {
+            self.relogin(account, token, was_active);
+        }
+    }
+
+    /// Replaces the rejected `stale` token of `account` by logging in again,
+    /// restoring it as the active session if it was.
+    fn relogin(&self, account: Option<String>, stale: String, active: bool) {
+        let Some(accounts) = self.accounts.clone() else {
+            return;
+        };
+        let queue = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let Ok((token, name)) = accounts.relogin(account.as_deref(), &stale).await else {
+                return;
+            };
+            {
+                let mut sessions = queue.shared.sessions.lock().unwrap();
+                sessions.set(&name, Some(token.clone()));
+                if active && sessions.active.is_none() {
+                    sessions.active = Some(Session {
+                        token,
+                        account: Some(name),
+                    });
+                }
+            }
+            queue.sessions_changed();
+        });
+    }
+
+    /// Records the outcome of uploading item `id` with the session `token`.
+    fn finish(&self, id: &str, token: Option<&str>, outcome: Result<Outcome, XmlRpcError>) {
+        if let (Err(error), Some(token)) = (&outcome, token) {
+            if error.is_session_error() {
+                // Hold that account's items until it logs in again instead of
+                // burning retries.
+                let mut sessions = self.shared.sessions.lock().unwrap();
+                sessions.tokens.retain(|_, t| t != token);
+                if sessions.active.as_ref().is_some_and(|s| s.token == token) {
+                    sessions.active = None;
+                }
+            }
+        }
+        let _ = self.mutate(|store| {
+            if matches!(outcome, Err(XmlRpcError::Http(_))) {
+                store.online = false;
+            }
+            store.update(id, |item| match outcome {
+                Ok(Outcome::Done(result)) => {
+                    item.state = ItemState::Done;
+                    item.result = Some(result);
+                    item.last_error = None;
+                }
+                Ok(Outcome::Duplicate(data)) => {
+                    item.state = ItemState::Duplicate;
+                    item.duplicate = Some(data);
+                    item.last_error = None;
+                }
+                Err(error) => {
+                    item.last_error = Some(error.to_string());
+                    if error.is_session_error() {
+                        item.state = ItemState::Pending;
+                    } else if error.is_transient() && item.attempts + 1 < MAX_ATTEMPTS {
+                        let backoff = (BASE_BACKOFF * 2u32.pow(item.attempts)).min(MAX_BACKOFF);
+                        item.attempts += 1;
+                        item.state = ItemState::Pending;
+                        item.next_attempt_at = Some(unix_millis() + backoff.as_millis() as u64);
+                    } else {
+                        item.attempts += 1;
+                        item.state = ItemState::Failed;
+                    }
+                }
+            })
+        });
+    }
+
+    /// Marks the queue online and makes every waiting retry due now.
+    fn back_online(&self) {
+        let _ = self.mutate(|store| {
+            store.online = true;
+            let waiting: Vec<_> = store
+                .items
+                .iter()
+                .filter(|item| item.state == ItemState::Pending && item.next_attempt_at.is_some())
+                .map(|item| item.id.clone())
+                .collect();
+            waiting
+                .iter()
+                .try_for_each(|id| store.update(id, |item| item.next_attempt_at = None))
+        });
+    }
+}
+
+#[tauri::command]
+pub fn upload_queue_list(queue: tauri::State<'_, UploadQueue>) -> QueueSnapshot {
+    queue.snapshot()
+}
+
+#[tauri::command]
+pub fn upload_queue_set_session(
+    queue: tauri::State<'_, UploadQueue>,
+    token: Option<String>,
+    account: Option<String>,
+) {
+    queue.set_session(token, account);
+}
+
+#[tauri::command]
+pub async fn upload_queue_add(
+    queue: tauri::State<'_, UploadQueue>,
+    resyncs: tauri::State<'_, Resyncs>,
+    mut uploads: Vec<NewUpload>,
+) -> Result<Vec<QueueItem>, QueueError> {
+    for upload in &mut uploads {
+        resyncs.annotate(&mut upload.info, &upload.file.subtitle_path);
+    }
+    queue.add(uploads)
+}
+
+#[tauri::command]
+pub async fn upload_queue_pause(
+    queue: tauri::State<'_, UploadQueue>,
+    ids: Option<Vec<String>>,
+) -> Result<(), QueueError> {
+    queue.pause(ids)
+}
+
+#[tauri::command]
+pub async fn upload_queue_resume(
+    queue: tauri::State<'_, UploadQueue>,
+    ids: Option<Vec<String>>,
+) -> Result<(), QueueError> {
+    queue.resume(ids)
+}
+
+#[tauri::command]
+pub async fn upload_queue_reorder(
+    queue: tauri::State<'_, UploadQueue>,
+    ids: Vec<String>,
+) -> Result<(), QueueError> {
+    queue.reorder(ids)
+}
+
+#[tauri::command]
+pub async fn upload_queue_cancel(
+    queue: tauri::State<'_, UploadQueue>,
+    ids: Vec<String>,
+) -> Result<(), QueueError> {
+    queue.cancel(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{name}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn open(path: &Path) -> UploadQueue {
+        UploadQueue::open(path, XmlRpcClient::new("http://127.0.0.1:9")).unwrap()
+    }
+
+    fn upload(name: &str) -> NewUpload {
+        NewUpload {
+            info: UploadInfo::default(),
+            file: UploadFile {
+                subtitle_path: PathBuf::from(name),
+                ..UploadFile::default()
+            },
+            paused: false,
+            account: None,
+        }
+    }
+
+    fn ids(queue: &UploadQueue) -> Vec<String> {
+        queue
+            .snapshot()
+            .items
+            .into_iter()
+            .map(|item| item.id)
+            .collect()
+    }
+
+    fn state(queue: &UploadQueue, id: &str) -> QueueItem {
+        let items = queue.snapshot().items;
+        items.into_iter().find(|item| item.id == id).unwrap()
+    }
+
+    #[test]
+    fn replays_and_compacts_a_torn_journal() {
+        let path = journal("torn");
+        let queue = open(&path);
+        let added = queue.add(vec![upload("a.srt"), upload("b.srt")]).unwrap();
+        queue.cancel(vec![added[0].id.clone()]).unwrap();
+        drop(queue);
+        let mut data = fs::read(&path).unwrap();
+        data.extend_from_slice("{\"op\":\"put\",\"item\":{\"id\":\"é".as_bytes());
+        data.truncate(data.len() - 1);
+        fs::write(&path, data).unwrap();
+
+        let queue = open(&path);
+        assert_eq!(ids(&queue), [added[1].id.clone()]);
+        let lines = fs::read_to_string(&path).unwrap();
+        assert_eq!(lines.lines().count(), 2, "{lines}");
+        queue.add(vec![upload("c.srt")]).unwrap();
+        drop(queue);
+        assert_eq!(ids(&open(&path)).len(), 2);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn uploads_in_flight_are_pending_again_after_a_restart() {
+        let path = journal("restart");
+        let queue = open(&path);
+        let id = queue.add(vec![upload("a.srt")]).unwrap()[0].id.clone();
+        queue
+            .mutate(|store| store.update(&id, |item| item.state = ItemState::Uploading))
+            .unwrap();
+        assert!(matches!(
+            queue.cancel(vec![id.clone()]),
+            Err(QueueError::Busy(_))
+        ));
+        drop(queue);
+        assert_eq!(state(&open(&path), &id).state, ItemState::Pending);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reorder_moves_items_to_the_front() {
+        let path = journal("reorder");
+        let queue = open(&path);
+        let added: Vec<_> = queue
+            .add(vec![upload("a"), upload("b"), upload("c"), upload("d")])
+            .unwrap()
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| added[i].clone());
+        queue
+            .reorder(vec![c.clone(), "unknown".into(), a.clone()])
+            .unwrap();
+        let order = [c, a, b, d];
+        assert_eq!(ids(&queue), order);
+        drop(queue);
+        assert_eq!(ids(&open(&path)), order);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pause_resume_and_cancel() {
+        let path = journal("states");
+        let queue = open(&path);
+        let added = queue.add(vec![upload("a"), upload("b")]).unwrap();
+        let (a, b) = (added[0].id.clone(), added[1].id.clone());
+
+        queue.pause(Some(vec![a.clone()])).unwrap();
+        assert!(state(&queue, &a).paused);
+        assert!(!state(&queue, &b).paused);
+        queue.pause(None).unwrap();
+        assert!(queue.snapshot().paused);
+
+        queue
+            .mutate(|store| {
+                store.update(&b, |item| {
+                    item.state = ItemState::Failed;
+                    item.attempts = MAX_ATTEMPTS;
+                })
+            })
+            .unwrap();
+        queue.resume(Some(vec![a.clone(), b.clone()])).unwrap();
+        assert!(!state(&queue, &a).paused);
+        let retried = state(&queue, &b);
+        assert_eq!((retried.state, retried.attempts), (ItemState::Pending, 0));
+        drop(queue);
+
+        let queue = open(&path);
+        assert!(queue.snapshot().paused);
+        queue.resume(None).unwrap();
+        assert!(!queue.snapshot().paused);
+        assert!(matches!(
+            queue.cancel(vec!["unknown".into()]),
+            Err(QueueError::NotFound(_))
+        ));
+        queue.cancel(vec![a.clone()]).unwrap();
+        assert_eq!(ids(&queue), [b]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn transient_failures_back_off_exponentially() {
+        let path = journal("backoff");
+        let queue = open(&path);
+        let id = queue.add(vec![upload("a")]).unwrap()[0].id.clone();
+        for attempt in 0..MAX_ATTEMPTS {
+            let before = unix_millis();
+            queue.finish(&id, None, Err(XmlRpcError::TooManyRequests));
+            let item = state(&queue, &id);
+            assert_eq!(item.attempts, attempt + 1);
+            if attempt + 1 == MAX_ATTEMPTS {
+                assert_eq!(item.state, ItemState::Failed);
+                break;
+            }
+            let expected = (BASE_BACKOFF * 2u32.pow(attempt)).min(MAX_BACKOFF);
+            let wait = item.next_attempt_at.unwrap() - before;
+            assert!(
+                wait.abs_diff(expected.as_millis() as u64) < 1000,
+                "attempt {attempt}: {wait} ms"
+            );
+            assert_eq!(item.state, ItemState::Pending);
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
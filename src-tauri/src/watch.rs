@@ -0,0 +1,560 @@
+//! Watch folders: subtitles dropped into configured folders are paired,
+//! identified and added to the upload queue without anyone opening the app's
+//! window.
+//!
+//! Folders are polled with the library scanner rather than subscribed to, so
+//! network shares and external drives behave like local disks. A file counts
+//! as finished once its size and modification time have stayed the same for
+//! `settleSeconds`; the video it pairs with has to be finished too.
+//!
+//! Results go into the queue paused, for review, unless `autoUpload` is on
+//! and their overall confidence reaches `minConfidence`. Subtitles that were
+//! queued or need review are remembered in `watch-state.json` by path, size
+//! and mtime, so a restart does not queue them again but an edited file is
+//! picked up anew; those needing review keep their report. Failures, such as
+//! an unreachable server, are tried again on the next poll, and files deleted
+//! from a folder are forgotten.
+
+use crate::api::rest::RestClient;
+use crate::api::xmlrpc::XmlRpcClient;
+use crate::cli::pipeline::{self, Candidate, Overrides};
+use crate::error::impl_serialize_error;
+use crate::pairing;
+use crate::scan::{self, FileKind, ScanEvent, ScanOptions};
+use crate::upload::queue::{NewUpload, UploadQueue};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Name of the event carrying [`WatchReport`]s to the UI.
+pub const WATCH_EVENT: &str = "watch-folder";
+
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    #[error("watch folder I/O error on {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not encode watch folder state: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("invalid watch folder settings: {0}")]
+    Invalid(String),
+}
+
+impl WatchError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Encode(_) => "encode",
+            Self::Invalid(_) => "invalid",
+        }
+    }
+
+    fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl_serialize_error!(WatchError);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchConfig {
+    pub folders: Vec<PathBuf>,
+    /// Queue confident results unpaused so they upload straight away.
+    pub auto_upload: bool,
+    /// Overall confidence (`0.0..=1.0`) needed for `auto_upload`.
+    pub min_confidence: f64,
+    pub poll_seconds: u64,
+    /// How long a file must stay unchanged before it is treated as finished.
+    pub settle_seconds: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            folders: Vec::new(),
+            auto_upload: false,
+            min_confidence: 0.9,
+            poll_seconds: 10,
+            settle_seconds: 15,
+        }
+    }
+}
+
+impl WatchConfig {
+    pub fn validate(&self) -> Result<(), WatchError> {
+        if !(0.0..=1.0).contains(&self.min_confidence) {
+            return Err(WatchError::Invalid(format!(
+                "minConfidence must be between 0 and 1, got {}",
+                self.min_confidence
+            )));
+        }
+        if self.poll_seconds == 0 {
+            return Err(WatchError::Invalid("pollSeconds must be at least 1".into()));
+        }
+        if let Some(folder) = self.folders.iter().find(|f| !f.is_absolute()) {
+            return Err(WatchError::Invalid(format!(
+                "{} is not an absolute path",
+                folder.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchStatus {
+    /// Queued paused, waiting for review.
+    Queued,
+    /// Queued to upload straight away.
+    AutoUpload,
+    /// Movie or language unknown; nothing was queued.
+    NeedsReview,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchReport {
+    pub subtitle: PathBuf,
+    pub video: Option<PathBuf>,
+    pub status: WatchStatus,
+    pub confidence: Option<f64>,
+    /// Id of the queue item, when one was added.
+    pub queue_id: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Size and modification time: a file is handled again if either changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    size: u64,
+    modified: Option<u64>,
+}
+
+/// A subtitle that was queued or needs review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Handled {
+    #[serde(flatten)]
+    stamp: Stamp,
+    /// The report of a subtitle left for review.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    review: Option<WatchReport>,
+}
+
+/// A file seen by the last poll, and since when it has looked the same.
+struct Tracked {
+    kind: FileKind,
+    stamp: Stamp,
+    since: Instant,
+}
+
+type Listener = Arc<dyn Fn(&WatchReport) + Send + Sync>;
+
+struct Shared {
+    config_path: PathBuf,
+    state_path: PathBuf,
+    config: Mutex<WatchConfig>,
+    /// Subtitles already handled.
+    handled: Mutex<HashMap<PathBuf, Handled>>,
+    wake: Notify,
+}
+
+/// Shared watcher handle; cheap to clone.
+#[derive(Clone)]
+pub struct Watcher {
+    shared: Arc<Shared>,
+    queue: UploadQueue,
+    xmlrpc: XmlRpcClient,
+    rest: RestClient,
+    on_report: Option<Listener>,
+}
+
+fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> Result<T, WatchError> {
+    match fs::read(path) {
+        Ok(data) => Ok(serde_json::from_slice(&data)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(WatchError::io(path, e)),
+    }
+}
+
+/// Writes `value` atomically via a temporary file.
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), WatchError> {
+    let tmp = path.with_extension("json.tmp");
+    let data = serde_json::to_vec_pretty(value)?;
+    let mut file = File::create(&tmp).map_err(|e| WatchError::io(&tmp, e))?;
+    file.write_all(&data)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| WatchError::io(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| WatchError::io(path, e))
+}
+
+impl Watcher {
+    /// Loads `watch.json` and `watch-state.json` from `dir`.
+    pub fn open(
+        dir: &Path,
+        queue: UploadQueue,
+        xmlrpc: XmlRpcClient,
+        rest: RestClient,
+    ) -> Result<Self, WatchError> {
+        let config_path = dir.join("watch.json");
+        let state_path = dir.join("watch-state.json");
+        let config: WatchConfig = read_json(&config_path)?;
+        let handled = read_json(&state_path)?;
+        Ok(Self {
+            shared: Arc::new(Shared {
+                config_path,
+                state_path,
+                config: Mutex::new(config),
+                handled: Mutex::new(handled),
+                wake: Notify::new(),
+            }),
+            queue,
+            xmlrpc,
+            rest,
+            on_report: None,
+        })
+    }
+
+    /// Calls `listener` for every subtitle handled.
+    pub fn on_report(mut self, listener: impl Fn(&WatchReport) + Send + Sync + 'static) -> Self {
+        self.on_report = Some(Arc::new(listener));
+        self
+    }
+
+    /// Spawns the polling task.
+    pub fn start(&self) {
+        tauri::async_runtime::spawn(self.clone().run());
+    }
+
+    pub fn config(&self) -> WatchConfig {
+        self.shared.config.lock().unwrap().clone()
+    }
+
+    /// Subtitles left for review, as last reported.
+    pub fn reviews(&self) -> Vec<WatchReport> {
+        let mut reviews: Vec<WatchReport> = self
+            .shared
+            .handled
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|handled| handled.review.clone())
+            .collect();
+        reviews.sort_by(|a, b| a.subtitle.cmp(&b.subtitle));
+        reviews
+    }
+
+    /// Validates, saves and applies `config`; the next poll starts now.
+    pub fn set_config(&self, config: WatchConfig) -> Result<(), WatchError> {
+        config.validate()?;
+        write_json(&self.shared.config_path, &config)?;
+        *self.shared.config.lock().unwrap() = config;
+        self.shared.wake.notify_one();
+        Ok(())
+    }
+
+    async fn run(self) {
+        let mut tracked = HashMap::new();
+        loop {
+            let config = self.config();
+            if !config.folders.is_empty() {
+                let folders = config.folders.clone();
+                if let Ok((files, complete)) =
+                    tauri::async_runtime::spawn_blocking(move || list(&folders)).await
+                {
+                    self.poll(&config, files, &complete, &mut tracked).await;
+                }
+            }
+            let woken = self.shared.wake.notified();
+            let _ = tokio::time::timeout(Duration::from_secs(config.poll_seconds), woken).await;
+        }
+    }
+
+    async fn poll(
+        &self,
+        config: &WatchConfig,
+        files: Vec<(PathBuf, FileKind, Stamp)>,
+        complete: &[PathBuf],
+        tracked: &mut HashMap<PathBuf, Tracked>,
+    ) {
+        let now = Instant::now();
+        let mut present = HashSet::with_capacity(files.len());
+        for (path, kind, stamp) in files {
+            let entry = tracked.entry(path.clone()).or_insert(Tracked {
+                kind,
+                stamp,
+                since: now,
+            });
+            if entry.stamp != stamp {
+                entry.stamp = stamp;
+                entry.since = now;
+            }
+            present.insert(path);
+        }
+        tracked.retain(|path, _| present.contains(path));
+        let forgotten =
+            forget_deleted(&mut self.shared.handled.lock().unwrap(), &present, complete);
+        if forgotten {
+            self.save_state();
+        }
+
+        let settle = Duration::from_secs(config.settle_seconds);
+        let settled = |t: &Tracked| t.stamp.size > 0 && now.duration_since(t.since) >= settle;
+        let waiting: Vec<PathBuf> = {
+            let handled = self.shared.handled.lock().unwrap();
+            tracked
+                .iter()
+                .filter(|(path, t)| {
+                    t.kind == FileKind::Subtitle
+                        && settled(t)
+                        && handled.get(*path).map(|h| h.stamp) != Some(t.stamp)
+                })
+                .map(|(path, _)| path.clone())
+                .collect()
+        };
+        if waiting.is_empty() {
+            return;
+        }
+        // Identification needs a session; try again on a later poll.
+        let Some(token) = self.queue.token() else {
+            return;
+        };
+
+        let paths: Vec<PathBuf> = tracked.keys().cloned().collect();
+        let pairs = pairing::pair(&paths).pairs;
+        for pair in pairs.into_iter().filter(|p| waiting.contains(&p.subtitle)) {
+            // A video still being copied would hash wrong.
+            if let Some(video) = &pair.video {
+                if !tracked.get(video).is_some_and(settled) {
+                    continue;
+                }
+            }
+            let stamp = tracked[&pair.subtitle].stamp;
+            let candidate = Candidate {
+                subtitle: pair.subtitle.clone(),
+                extracted: None,
+                pairing: if pair.video.is_some() {
+                    pair.confidence
+                } else {
+                    0.0
+                },
+                video: pair.video,
+                language: pair.language,
+            };
+            let report = self.handle(config, &candidate, &token).await;
+            // Failures are tried again on the next poll.
+            if report.status != WatchStatus::Failed {
+                let review = (report.status == WatchStatus::NeedsReview).then(|| report.clone());
+                self.shared
+                    .handled
+                    .lock()
+                    .unwrap()
+                    .insert(candidate.subtitle, Handled { stamp, review });
+            }
+            if let Some(listener) = &self.on_report {
+                listener(&report);
+            }
+        }
+        self.save_state();
+    }
+
+    fn save_state(&self) {
+        let handled = self.shared.handled.lock().unwrap().clone();
+        let _ = write_json(&self.shared.state_path, &handled);
+    }
+
+    async fn handle(
+        &self,
+        config: &WatchConfig,
+        candidate: &Candidate,
+        token: &str,
+    ) -> WatchReport {
+        let mut report = WatchReport {
+            subtitle: candidate.subtitle.clone(),
+            video: candidate.video.clone(),
+            status: WatchStatus::Failed,
+            confidence: None,
+            queue_id: None,
+            message: None,
+        };
+        let identified = match pipeline::identify(
+            candidate,
+            &Overrides::default(),
+            &self.xmlrpc,
+            token,
+            &self.rest,
+        )
+        .await
+        {
+            Ok(identified) => identified,
+            Err(e) => {
+                report.message = Some(e.to_string());
+                return report;
+            }
+        };
+        let confidence = identified.confidence.overall;
+        report.confidence = Some(confidence);
+        let Some(info) = identified.upload_info() else {
+            let missing = match identified.imdb_id {
+                None => "movie not identified",
+                Some(_) => "language not detected",
+            };
+            // Only a server that answered leaves the subtitle for review.
+            if identified.lookup_failed {
+                report.message = Some(format!("{missing}: {}", identified.warnings.join("; ")));
+            } else {
+                report.status = WatchStatus::NeedsReview;
+                report.message = Some(missing.into());
+            }
+            return report;
+        };
+        let auto = config.auto_upload && confidence >= config.min_confidence;
+        match self.queue.add(vec![NewUpload {
+            info,
+            file: identified.file,
+            paused: !auto,
+            account: None,
+        }]) {
+            Ok(added) => {
+                report.status = if auto {
+                    WatchStatus::AutoUpload
+                } else {
+                    WatchStatus::Queued
+                };
+                report.queue_id = added.into_iter().next().map(|item| item.id);
+            }
+            Err(e) => report.message = Some(e.to_string()),
+        }
+        report
+    }
+}
+
+/// Forgets handled subtitles missing from folders that were read in full, so
+/// a share that is offline for a poll keeps its files. Whether any were
+/// forgotten.
+fn forget_deleted(
+    handled: &mut HashMap<PathBuf, Handled>,
+    present: &HashSet<PathBuf>,
+    complete: &[PathBuf],
+) -> bool {
+    let before = handled.len();
+    handled.retain(|path, _| {
+        present.contains(path) || !complete.iter().any(|folder| path.starts_with(folder))
+    });
+    handled.len() != before
+}
+
+/// Videos and subtitles under `folders`, and the folders read without
+/// errors; unreadable folders are skipped.
+fn list(folders: &[PathBuf]) -> (Vec<(PathBuf, FileKind, Stamp)>, Vec<PathBuf>) {
+    let options = ScanOptions {
+        threads: 2,
+        ..Default::default()
+    };
+    let found = Mutex::new(Vec::new());
+    let mut complete = Vec::new();
+    for folder in folders {
+        let scanned = scan::scan(folder, &options, &|event| {
+            if let ScanEvent::Files(files) = event {
+                found.lock().unwrap().extend(files.into_iter().map(|f| {
+                    let stamp = Stamp {
+                        size: f.size,
+                        modified: f.modified,
+                    };
+                    (f.path, f.kind, stamp)
+                }));
+            }
+        });
+        if scanned.is_ok_and(|counts| counts.errors == 0) {
+            complete.push(folder.clone());
+        }
+    }
+    (found.into_inner().unwrap(), complete)
+}
+
+#[tauri::command]
+pub fn watch_get_config(watcher: tauri::State<'_, Watcher>) -> WatchConfig {
+    watcher.config()
+}
+
+#[tauri::command]
+pub fn watch_set_config(
+    watcher: tauri::State<'_, Watcher>,
+    config: WatchConfig,
+) -> Result<WatchConfig, WatchError> {
+    watcher.set_config(config)?;
+    Ok(watcher.config())
+}
+
+/// Subtitles the watcher left for review.
+#[tauri::command]
+pub fn watch_reviews(watcher: tauri::State<'_, Watcher>) -> Vec<WatchReport> {
+    watcher.reviews()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handled(review: Option<WatchReport>) -> Handled {
+        Handled {
+            stamp: Stamp {
+                size: 10,
+                modified: Some(1),
+            },
+            review,
+        }
+    }
+
+    #[test]
+    fn reads_state_without_reviews() {
+        let state: HashMap<PathBuf, Handled> =
+            serde_json::from_str(r#"{ "/w/a.srt": { "size": 10, "modified": 1 } }"#).unwrap();
+        let entry = &state[Path::new("/w/a.srt")];
+        assert_eq!(entry.stamp, handled(None).stamp);
+        assert!(entry.review.is_none());
+
+        let report = WatchReport {
+            subtitle: "/w/b.srt".into(),
+            video: None,
+            status: WatchStatus::NeedsReview,
+            confidence: Some(0.5),
+            queue_id: None,
+            message: Some("movie not identified".into()),
+        };
+        let state = HashMap::from([(report.subtitle.clone(), handled(Some(report)))]);
+        let json = serde_json::to_string(&state).unwrap();
+        let read: HashMap<PathBuf, Handled> = serde_json::from_str(&json).unwrap();
+        let review = read[Path::new("/w/b.srt")].review.as_ref().unwrap();
+        assert_eq!(review.status, WatchStatus::NeedsReview);
+        assert_eq!(review.message.as_deref(), Some("movie not identified"));
+    }
+
+    #[test]
+    fn forgets_deleted_files_in_complete_folders() {
+        let mut state = HashMap::from([
+            (PathBuf::from("/w/kept.srt"), handled(None)),
+            (PathBuf::from("/w/deleted.srt"), handled(None)),
+            (PathBuf::from("/offline/a.srt"), handled(None)),
+        ]);
+        let present = HashSet::from([PathBuf::from("/w/kept.srt")]);
+        let complete = [PathBuf::from("/w")];
+        assert!(forget_deleted(&mut state, &present, &complete));
+        let mut left: Vec<_> = state.keys().map(|path| path.to_str().unwrap()).collect();
+        left.sort();
+        assert_eq!(left, ["/offline/a.srt", "/w/kept.srt"]);
+        assert!(!forget_deleted(&mut state, &present, &complete));
+    }
+}